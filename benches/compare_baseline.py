@@ -0,0 +1,56 @@
+#!/usr/bin/env python3
+"""Compares Criterion results against benches/baseline.json.
+
+Reads the mean time of every benchmark of `cargo bench --bench crackers --
+--save-baseline current` from target/criterion and fails if one of them is
+more than 20% slower than its baseline. With --update the baseline is
+rewritten from the results instead.
+"""
+import json
+import sys
+from pathlib import Path
+
+ROOT = Path(__file__).resolve().parent.parent
+BASELINE = ROOT / "benches" / "baseline.json"
+CRITERION = ROOT / "target" / "criterion"
+RESULTS = "current"
+MAX_REGRESSION = 0.20
+
+
+def results():
+    """Mean time in nanoseconds of every benchmark, keyed by "group/id"."""
+    means = {}
+    for estimates in sorted(CRITERION.glob(f"*/*/{RESULTS}/estimates.json")):
+        benchmark = estimates.parent.parent
+        name = f"{benchmark.parent.name}/{benchmark.name}"
+        means[name] = json.loads(estimates.read_text())["mean"]["point_estimate"]
+    return means
+
+
+def main():
+    current = results()
+    if not current:
+        sys.exit(f"No results in {CRITERION}, run cargo bench first")
+
+    if "--update" in sys.argv[1:]:
+        BASELINE.write_text(json.dumps(current, indent=2, sort_keys=True) + "\n")
+        print(f"Wrote {len(current)} benchmarks to {BASELINE.relative_to(ROOT)}")
+        return
+
+    baseline = json.loads(BASELINE.read_text())
+    regressions = 0
+    for name, expected in sorted(baseline.items()):
+        if name not in current:
+            print(f"{name}: missing")
+            regressions += 1
+            continue
+        change = current[name] / expected - 1
+        status = "REGRESSED" if change > MAX_REGRESSION else "ok"
+        print(f"{name}: {current[name] / 1e6:.2f} ms ({change:+.1%}) {status}")
+        regressions += change > MAX_REGRESSION
+    if regressions:
+        sys.exit(f"{regressions} benchmark(s) regressed by more than {MAX_REGRESSION:.0%}")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,231 @@
+//! Criterion benchmarks of the three main cracker modes.
+//!
+//! Every benchmark tests a certificate generated when the benchmarks start,
+//! with a fixed password and a single key derivation and MAC iteration, so
+//! that the time is spent in candidate generation and scheduling rather than
+//! in OpenSSL's PBKDF2. The password is outside of every keyspace: a found
+//! password would end the run at a point that depends on how the chunks are
+//! spread over the threads, an exhausted keyspace is the same work for every
+//! thread count. Each benchmark runs with 1, 2, 4 and 8 threads.
+//!
+//! `pre_hash_2048iter` is the exception: it measures `--pre-hash` on a
+//! certificate with 2048 key derivation and MAC iterations, OpenSSL's
+//! default, where the time is spent in PBKDF2.
+//!
+//! `length_order_random_position` is the other exception: it finds the
+//! passwords of several certificates, at random lengths and positions, on a
+//! single thread so that the candidates are tried in a fixed order.
+//!
+//! Run with `cargo bench --bench crackers`, see BENCHMARKS.md for comparing
+//! against `benches/baseline.json`.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use openssl::asn1::Asn1Time;
+use openssl::hash::MessageDigest;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use openssl::x509::{X509NameBuilder, X509};
+use pkcs12cracker::crackers::{
+    bruteforce::{BruteforceCracker, LengthOrder},
+    dictionary::{DelimiterMode, DictionaryCracker},
+    pattern::PatternCracker,
+};
+use pkcs12cracker::session::CrackSession;
+use pkcs12cracker::types::{PasswordCracker, WorkUnit};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Thread counts every benchmark is parameterized over
+const THREADS: [usize; 4] = [1, 2, 4, 8];
+
+/// Password of the benchmark certificate, no benchmark generates it
+const PASSWORD: &str = "not in the keyspace";
+
+/// Creates a PKCS#12 certificate protected by [`PASSWORD`].
+fn certificate() -> Arc<Pkcs12> {
+    certificate_with_iterations(1)
+}
+
+/// Creates a PKCS#12 certificate protected by [`PASSWORD`] with a number of
+/// key derivation and MAC iterations.
+fn certificate_with_iterations(iterations: u32) -> Arc<Pkcs12> {
+    certificate_with_password(PASSWORD, iterations)
+}
+
+/// Creates a PKCS#12 certificate protected by `password` with a number of
+/// key derivation and MAC iterations.
+fn certificate_with_password(password: &str, iterations: u32) -> Arc<Pkcs12> {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "pkcs12cracker bench")
+        .unwrap();
+    let name = name.build();
+
+    let mut cert = X509::builder().unwrap();
+    cert.set_version(2).unwrap();
+    cert.set_subject_name(&name).unwrap();
+    cert.set_issuer_name(&name).unwrap();
+    cert.set_pubkey(&key).unwrap();
+    cert.set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    cert.set_not_after(&Asn1Time::days_from_now(3650).unwrap())
+        .unwrap();
+    cert.sign(&key, MessageDigest::sha256()).unwrap();
+    let cert = cert.build();
+
+    let der = Pkcs12::builder()
+        .pkey(&key)
+        .cert(&cert)
+        .key_iter(iterations)
+        .mac_iter(iterations)
+        .build2(password)
+        .unwrap()
+        .to_der()
+        .unwrap();
+    Arc::new(Pkcs12::from_der(&der).unwrap())
+}
+
+/// Benchmarks a cracker exhausting its keyspace with every thread count.
+fn bench_cracker(c: &mut Criterion, name: &str, cracker: &dyn PasswordCracker) {
+    let pkcs12 = certificate();
+    let mut group = c.benchmark_group(name);
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(10));
+    for threads in THREADS {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| {
+                b.iter_batched(
+                    || CrackSession::new(threads).unwrap(),
+                    |session| {
+                        session.run(cracker, &pkcs12).unwrap();
+                        assert!(session.result().lock().unwrap().password.is_none());
+                    },
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+/// All 17 576 three-character lowercase candidates.
+fn bench_bruteforce_3chars(c: &mut Criterion) {
+    let cracker = BruteforceCracker::new(3, 3, "abcdefghijklmnopqrstuvwxyz".to_string());
+    bench_cracker(c, "bruteforce_3chars", &cracker);
+}
+
+/// Five unknown positions over 36 characters, restricted to the first of
+/// 3000 work units, i.e. 20 156 candidates.
+fn bench_pattern_5unknowns(c: &mut Criterion) {
+    let cracker = PatternCracker::new(
+        "pw-@@@@@".to_string(),
+        "abcdefghijklmnopqrstuvwxyz0123456789".to_string(),
+        '@',
+    )
+    .with_work_unit(WorkUnit {
+        index: 0,
+        total: 3000,
+    });
+    bench_cracker(c, "pattern_5unknowns", &cracker);
+}
+
+/// A dictionary of 100 000 words.
+fn bench_dictionary_100k(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("words.txt");
+    let words: Vec<String> = (0..100_000).map(|i| format!("word{i:06}")).collect();
+    std::fs::write(&path, words.join("\n") + "\n").unwrap();
+
+    let cracker = DictionaryCracker::new(path, DelimiterMode::SingleByte(b'\n'));
+    bench_cracker(c, "dictionary_100k", &cracker);
+}
+
+/// 2000 wrong words against 2048 iterations, fully decrypted and with
+/// the MAC checked first, on a single thread.
+fn bench_pre_hash_2048iter(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("words.txt");
+    let words: Vec<String> = (0..2_000).map(|i| format!("word{i:04}")).collect();
+    std::fs::write(&path, words.join("\n") + "\n").unwrap();
+
+    let pkcs12 = certificate_with_iterations(2048);
+    let cracker = DictionaryCracker::new(path, DelimiterMode::SingleByte(b'\n'));
+    let mut group = c.benchmark_group("pre_hash_2048iter");
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(10));
+    for pre_hash in [false, true] {
+        let name = if pre_hash { "mac_check" } else { "parse2" };
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    let session = CrackSession::new(1).unwrap();
+                    if pre_hash {
+                        session.with_pre_hash()
+                    } else {
+                        session
+                    }
+                },
+                |session| {
+                    session.run(&cracker, &pkcs12).unwrap();
+                    assert!(session.result().lock().unwrap().password.is_none());
+                },
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// Ten passwords of 1 to 5 digits, every length equally likely and the
+/// password at a uniformly random position of its length, found in chunks of
+/// 100 candidates with ascending and interleaved lengths.
+fn bench_length_order_random_position(c: &mut Criterion) {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+    let certificates: Vec<(String, Arc<Pkcs12>)> = (0..10)
+        .map(|_| {
+            let len = rng.gen_range(1..=5);
+            let password: String = (0..len)
+                .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+                .collect();
+            let pkcs12 = certificate_with_password(&password, 1);
+            (password, pkcs12)
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("length_order_random_position");
+    group
+        .sample_size(10)
+        .measurement_time(Duration::from_secs(10));
+    for order in [LengthOrder::Ascending, LengthOrder::Interleaved] {
+        let cracker = BruteforceCracker::new(1, 5, "0123456789".to_string())
+            .with_chunk_size(100)
+            .with_length_order(order);
+        group.bench_function(order.to_string(), |b| {
+            b.iter(|| {
+                for (password, pkcs12) in &certificates {
+                    let session = CrackSession::new(1).unwrap();
+                    session.run(&cracker, pkcs12).unwrap();
+                    let result = session.result().lock().unwrap();
+                    assert_eq!(result.password.as_ref(), Some(password));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_bruteforce_3chars,
+    bench_pattern_5unknowns,
+    bench_dictionary_100k,
+    bench_pre_hash_2048iter,
+    bench_length_order_random_position
+);
+criterion_main!(benches);
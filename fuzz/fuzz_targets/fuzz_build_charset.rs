@@ -0,0 +1,30 @@
+//! Feeds arbitrary `--charset`, `--custom-chars` and `--charset-range` values
+//! to `build_charset`. Invalid specs must be rejected with an error and valid
+//! ones must never produce duplicate characters.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pkcs12cracker::charset::build_charset;
+use pkcs12cracker::Args;
+use std::collections::HashSet;
+
+fuzz_target!(
+    |input: (Option<String>, Option<String>, Vec<String>, bool)| {
+        let (char_sets, specific_chars, charset_range, no_space) = input;
+        // Huge ranges are valid but only slow the fuzzer down
+        if charset_range.len() > 4 {
+            return;
+        }
+        let args = Args {
+            char_sets,
+            specific_chars,
+            charset_range,
+            no_space,
+            ..Default::default()
+        };
+        if let Ok(charset) = build_charset(&args) {
+            let unique: HashSet<char> = charset.chars().collect();
+            assert_eq!(unique.len(), charset.chars().count(), "{charset:?}");
+        }
+    }
+);
@@ -0,0 +1,16 @@
+//! Feeds arbitrary bytes to `load_certificate`, which wraps OpenSSL's ASN.1
+//! parser. Malformed DER must surface as an error, never as a panic.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::io::Write;
+
+fuzz_target!(|data: &[u8]| {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(data).unwrap();
+
+    if pkcs12cracker::load_certificate(file.path()).is_ok() {
+        // Parsed certificates must also survive a password check
+        let _ = pkcs12cracker::verify_password(file.path(), "");
+    }
+});
@@ -0,0 +1,46 @@
+//! Feeds arbitrary bytes to `DictionaryCracker::process_chunk` as a chunk of
+//! a dictionary file. The first byte selects the delimiter and encoding, so
+//! chunks cut in the middle of a multibyte character are covered as well.
+#![no_main]
+
+use encoding_rs::{Encoding, UTF_16LE, UTF_8, WINDOWS_1252};
+use libfuzzer_sys::fuzz_target;
+use openssl::pkcs12::Pkcs12;
+use pkcs12cracker::crackers::dictionary::{DelimiterMode, DictionaryCracker};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+
+/// Every password check derives a key, longer chunks only slow the fuzzer down
+const MAX_CHUNK: usize = 256;
+
+const DELIMITERS: [&str; 7] = ["\n", ",", ";", "\t", ":", "||", "\r\n"];
+const ENCODINGS: [&Encoding; 3] = [UTF_8, WINDOWS_1252, UTF_16LE];
+
+fn certificate() -> &'static Arc<Pkcs12> {
+    static CERTIFICATE: OnceLock<Arc<Pkcs12>> = OnceLock::new();
+    CERTIFICATE.get_or_init(|| {
+        pkcs12cracker::load_certificate(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../tests/fixtures/umlaut.p12"
+        ))
+        .unwrap()
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, chunk)) = data.split_first() else {
+        return;
+    };
+    let chunk = &chunk[..chunk.len().min(MAX_CHUNK)];
+    let delimiter = match DELIMITERS[selector as usize % DELIMITERS.len()] {
+        "\r\n" => DelimiterMode::Crlf,
+        single if single.len() == 1 => DelimiterMode::SingleByte(single.as_bytes()[0]),
+        multi => DelimiterMode::MultiByteStr(multi.to_string()),
+    };
+    let encoding = ENCODINGS[selector as usize / DELIMITERS.len() % ENCODINGS.len()];
+
+    let cracker = DictionaryCracker::new(PathBuf::new(), delimiter)
+        .with_encoding(encoding)
+        .with_suffixes(vec!["1".to_string()]);
+    cracker.fuzz_process_chunk(chunk, certificate());
+});
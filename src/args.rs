@@ -1,6 +1,18 @@
+use crate::checkpoint::WriteCadence;
+use crate::crackers::bruteforce::LengthOrder;
+use crate::crackers::dictionary::DelimiterMode;
+use crate::crackers::john_rules;
+use crate::crackers::pattern::{CaseToggle, NumericOrder, PatternSymbol};
+use crate::crackers::policy::CharClass;
+use crate::preprocessing::SortOrder;
+use crate::types::{CaseSensitivity, PkcsError, WorkUnit};
+use crate::unicode::WordEncoding;
 use anyhow::{bail, Result};
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, ValueEnum};
+use encoding_rs::Encoding;
+use regex::Regex;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 
 /// PKCS#12 password cracker that supports dictionary, pattern-based, and brute force attacks
 #[derive(Debug, Parser, Clone)]
@@ -12,16 +24,38 @@ use std::path::PathBuf;
     long_about = "Cracks passwords for PKCS#12 files (.p12/.pfx) using multiple attack strategies: \
     dictionary-based, pattern-based, or brute force. Supports multi-threading for faster cracking."
 )]
+#[command(group(clap::ArgGroup::new("patterns").args(["pattern", "pattern_file"])))]
+#[command(group(
+    clap::ArgGroup::new("reversible")
+        .args(["dictionary_path", "pattern", "pattern_file"])
+        .multiple(true)
+))]
+#[command(group(
+    clap::ArgGroup::new("numbered")
+        .args(["bruteforce_flag", "pattern", "pattern_file"])
+        .multiple(true)
+))]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct Args {
-    /// Path to the PKCS#12 certificate file to crack
+    /// Paths to the PKCS#12 certificate files to crack
     #[arg(
-        required(true),
+        required_unless_present = "alphabet_test",
         num_args(1..),
         value_name = "FILE",
-        value_parser = validate_certificate_path,
-        help = "Path to the PKCS#12 (.p12/.pfx) file to crack"
+        help = "Path to the PKCS#12 (.p12/.pfx) file to crack, - reads it from stdin; several files are only supported with --dictionary"
     )]
-    pub certificate_path: PathBuf,
+    pub certificate_paths: Vec<PathBuf>,
+
+    /// Skip validation of the certificate paths
+    #[arg(
+        long = "force",
+        help = "Skip checking that the certificate files exist and have a .p12, .pfx or .pem extension"
+    )]
+    pub force: bool,
 
     /// Path to dictionary file for dictionary-based attack
     #[arg(
@@ -30,8 +64,251 @@ pub struct Args {
         value_name = "FILE",
         help = "Use dictionary-based attack with the specified wordlist file"
     )]
+    #[cfg_attr(feature = "config", serde(rename = "dictionary"))]
     pub dictionary_path: Option<PathBuf>,
 
+    /// Keep surrounding whitespace of dictionary entries
+    #[arg(
+        long = "no-trim",
+        help = "Try dictionary entries verbatim, without trimming surrounding whitespace",
+        requires = "dictionary_path"
+    )]
+    pub no_trim: bool,
+
+    /// Encoding of the dictionary file
+    #[arg(
+        long = "wordlist-encoding",
+        value_name = "ENCODING",
+        value_parser = crate::crackers::dictionary::parse_encoding,
+        default_value = "utf-8",
+        help = "Dictionary file encoding: utf-8, latin-1, windows-1252 or utf-16le [default: utf-8]",
+        long_help = "Dictionary file encoding: utf-8, latin-1, windows-1252 or utf-16le\n\
+                     [default: utf-8]. A byte order mark at the start of the file\n\
+                     overrides this setting. Invalid sequences are replaced with U+FFFD.",
+        requires = "dictionary_path"
+    )]
+    #[cfg_attr(feature = "config", serde(with = "config::encoding"))]
+    pub wordlist_encoding: &'static Encoding,
+
+    /// Conversion of the raw dictionary words into passwords
+    #[arg(
+        long = "word-encoding",
+        value_name = "ENCODING",
+        value_enum,
+        help = "Convert every dictionary word as ENCODING: latin1",
+        long_help = "Convert every dictionary word as ENCODING instead of decoding the file\n\
+                     with --wordlist-encoding. 'latin1' maps every byte to the code point of\n\
+                     the same value, as Java keytool reads passwords, so 0x80-0x9F stay\n\
+                     control characters where '--wordlist-encoding latin-1' reads them as\n\
+                     Windows-1252. A byte order mark overrides this setting.",
+        requires = "dictionary_path",
+        conflicts_with = "wordlist_encoding"
+    )]
+    pub word_encoding: Option<WordEncoding>,
+
+    /// Order to sort the dictionary in before cracking
+    #[arg(
+        long = "wordlist-sort",
+        value_name = "ORDER",
+        value_enum,
+        help = "Sort the dictionary before cracking: frequency, alphabetical or length",
+        long_help = "Sort the dictionary before cracking. 'frequency' removes duplicates and\n\
+                     tries the most frequent entries first, 'alphabetical' sorts entries\n\
+                     lexicographically and 'length' tries the shortest entries first. Ties\n\
+                     keep the order of the file.",
+        requires = "dictionary_path"
+    )]
+    pub wordlist_sort: Option<SortOrder>,
+
+    /// Sort the dictionary externally in runs of this many megabytes
+    #[arg(
+        long = "wordlist-sort-chunk",
+        value_name = "N_MB",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Sort dictionaries too large for memory in sorted runs of N_MB megabytes",
+        requires = "wordlist_sort"
+    )]
+    pub wordlist_sort_chunk: Option<u32>,
+
+    /// Append numeric suffixes to dictionary entries
+    #[arg(
+        long = "append-numbers",
+        value_name = "MAX_DIGITS",
+        value_parser = clap::value_parser!(u8).range(1..=4),
+        help = "Also try every dictionary entry with 1 to MAX_DIGITS (1-4) digits appended",
+        requires = "dictionary_path",
+        conflicts_with = "append_numbers_fixed"
+    )]
+    pub append_numbers: Option<u8>,
+
+    /// Append fixed-width numeric suffixes to dictionary entries
+    #[arg(
+        long = "append-numbers-fixed",
+        value_name = "DIGITS",
+        value_parser = clap::value_parser!(u8).range(1..=4),
+        help = "Also try every dictionary entry with exactly DIGITS (1-4) digits appended",
+        requires = "dictionary_path"
+    )]
+    pub append_numbers_fixed: Option<u8>,
+
+    /// Number of leading dictionary entries to skip
+    #[arg(
+        long = "skip-n",
+        value_name = "N",
+        help = "Skip the first N non-empty dictionary entries",
+        requires = "dictionary_path",
+        conflicts_with_all = ["work_unit", "progress_save_every"]
+    )]
+    pub skip_n: Option<usize>,
+
+    /// Number of dictionary entries to test
+    #[arg(
+        long = "first-n",
+        value_name = "N",
+        help = "Test only the first N non-empty dictionary entries, e.g. as a quick check",
+        long_help = "Test only the first N non-empty dictionary entries, e.g. as a quick\n\
+                     check of the setup. Counted after --skip-n, so '--skip-n 5000\n\
+                     --first-n 1000' tests the entries 5001 to 6000. Rules and appended\n\
+                     numbers still apply to every tested entry.",
+        requires = "dictionary_path",
+        conflicts_with_all = ["work_unit", "progress_save_every"]
+    )]
+    pub first_n: Option<usize>,
+
+    /// Hashcat rule file mangling dictionary entries
+    #[arg(
+        long = "rules",
+        value_name = "FILE",
+        help = "Mangle every dictionary entry with each rule of a Hashcat .rule FILE",
+        long_help = "Try every dictionary entry as mangled by each rule of a Hashcat .rule\n\
+                     FILE, one rule per line. Supported functions are : l u c C t TN r d pN\n\
+                     f { } $X ^X [ ] DN iNX oNX sXY @X zN ZN LN RN, rules with other\n\
+                     functions are skipped with a warning. The entry itself is only tried\n\
+                     by a ':' rule. Positions count characters instead of bytes.",
+        requires = "dictionary_path"
+    )]
+    pub rules: Option<PathBuf>,
+
+    /// John the Ripper config file with rules mangling dictionary entries
+    #[arg(
+        long = "john-rules",
+        value_name = "FILE",
+        help = "Mangle every dictionary entry with each rule of a John the Ripper config section",
+        long_help = "Try every dictionary entry as mangled by each rule of the\n\
+                     [List.Rules:SECTION] section of a John the Ripper config FILE, e.g.\n\
+                     john.conf. The preprocessor expands [0-9] lists, and length and\n\
+                     character class conditions like '>8' or '/?d' reject entries. Rules\n\
+                     with unsupported functions are skipped with a warning.",
+        requires = "dictionary_path",
+        conflicts_with = "rules"
+    )]
+    pub john_rules: Option<PathBuf>,
+
+    /// Section of the John the Ripper config file to read rules from
+    #[arg(
+        long = "john-rules-section",
+        value_name = "SECTION",
+        default_value = john_rules::DEFAULT_SECTION,
+        help = "Section of --john-rules to use, without 'List.Rules:'",
+        requires = "john_rules"
+    )]
+    pub john_rules_section: String,
+
+    /// Training corpus for Markov chain attack
+    #[arg(
+        long = "markov-model",
+        value_name = "CORPUS",
+        help = "Use Markov chain attack with a model trained on the specified password corpus",
+        conflicts_with = "markov_model_bin"
+    )]
+    pub markov_model: Option<PathBuf>,
+
+    /// Pre-trained binary model for Markov chain attack
+    #[arg(
+        long = "markov-model-bin",
+        value_name = "FILE",
+        help = "Use Markov chain attack with a model saved by --markov-save-bin"
+    )]
+    pub markov_model_bin: Option<PathBuf>,
+
+    /// Output path for the trained Markov model
+    #[arg(
+        long = "markov-save-bin",
+        value_name = "FILE",
+        help = "Save the model trained from --markov-model to a binary file",
+        requires = "markov_model"
+    )]
+    pub markov_save_bin: Option<PathBuf>,
+
+    /// Pruning threshold for Markov chain attack
+    #[arg(
+        long = "markov-min-prob",
+        value_name = "F64",
+        default_value = "1e-6",
+        help = "Skip Markov candidates less likely than this probability [default: 1e-6]"
+    )]
+    pub markov_min_prob: f64,
+
+    /// Base word for edit-distance attack
+    #[arg(
+        long = "mutate",
+        value_name = "WORD",
+        help = "Try every candidate within --distance edits of WORD, closest first",
+        long_help = "Try every candidate within --distance edits of WORD, closest first.\n\
+                     Edits are substitutions and insertions of charset characters,\n\
+                     deletions and swaps of adjacent characters. Every candidate is\n\
+                     tried once, at its smallest distance."
+    )]
+    pub mutate: Option<String>,
+
+    /// Largest edit distance for edit-distance attack
+    #[arg(
+        long = "distance",
+        value_name = "N",
+        default_value = "1",
+        value_parser = clap::value_parser!(u8).range(1..),
+        help = "Largest number of edits applied to the --mutate word [default: 1]",
+        long_help = "Largest number of edits applied to the --mutate word [default: 1].\n\
+                     Distances above 2 print a warning with the keyspace size, as\n\
+                     every distance is generated in memory before it is tested.",
+        requires = "mutate"
+    )]
+    pub distance: u8,
+
+    /// Words for permutation attack
+    #[arg(
+        long = "permutation-words",
+        value_name = "W1,W2,...",
+        value_delimiter = ',',
+        help = "Try every ordering of the comma-separated words, concatenated",
+        long_help = "Try every ordering of the comma-separated words, concatenated.\n\
+                     N words have N! orderings, more than 8 words print a warning\n\
+                     with the size of the keyspace. Repeated words are used once."
+    )]
+    pub permutation_words: Vec<String>,
+
+    /// Largest number of words for permutation attack
+    #[arg(
+        long = "permutation-max-n",
+        value_name = "N",
+        value_parser = clap::value_parser!(u8).range(1..),
+        help = "Only permute the first N of the --permutation-words",
+        requires = "permutation_words"
+    )]
+    pub permutation_max_n: Option<u8>,
+
+    /// Try candidates derived from the certificate metadata first
+    #[arg(
+        long = "words-from-certificate",
+        help = "First try candidates derived from the certificate subject, issuer and SAN fields",
+        long_help = "Before the main attack, try candidates derived from the subject and\n\
+                     issuer DN, the subject alternative names and the friendly names of\n\
+                     the certificate: the raw fields, their parts, case variants, digits\n\
+                     stripped and years appended. Only metadata stored without encryption\n\
+                     is readable, e.g. files exported with 'openssl pkcs12 -certpbe NONE'."
+    )]
+    pub words_from_certificate: bool,
+
     /// Pattern template for pattern-based attack
     #[arg(
         short = 'p',
@@ -40,11 +317,31 @@ pub struct Args {
         help = "Use pattern-based attack (e.g., 'Pass@@rd' where '@' marks variable positions)",
         long_help = "Enable pattern-based attack using the specified template. \
                      Variable positions are marked with a symbol (default: '@'). \
-                     Example: 'Pass@@rd' will try all combinations replacing '@' positions.",
+                     Example: 'Pass@@rd' will try all combinations replacing '@' positions. \
+                     A symbol followed by a quantifier like '@{1,4}' or '@{3}' stands for a \
+                     variable number of positions, shortest first, and is an error after a \
+                     fixed character. A group like '[-_.]' is a variable position trying only \
+                     the characters it lists; write a literal '[', ']', '{' or '\\' as '\\[', \
+                     '\\]', '\\{' or '\\\\', and a literal symbol as '\\@'. The token '?y' is one \
+                     variable position trying every year of --years, most recent first, and \
+                     '?Y' the last two digits of the years; write a literal '?' as '\\?'.",
         conflicts_with_all = ["minumum_length", "maximum_length", "bruteforce_flag"]
     )]
     pub pattern: Option<String>,
 
+    /// File with pattern templates, tried one after another
+    #[arg(
+        long = "pattern-file",
+        value_name = "FILE",
+        help = "Try the patterns of FILE in order, one per line",
+        long_help = "Try the patterns of FILE in order until one yields the password, one\n\
+                     pattern per line with the syntax of --pattern. Blank lines are skipped\n\
+                     and a '#' at the start of a line or after whitespace starts a comment.\n\
+                     Malformed patterns are reported with their line number and skipped.",
+        conflicts_with_all = ["minumum_length", "maximum_length", "bruteforce_flag"]
+    )]
+    pub pattern_file: Option<PathBuf>,
+
     /// Symbol used to mark variable positions in pattern
     #[arg(
         short = 's',
@@ -52,11 +349,118 @@ pub struct Args {
         value_name = "CHAR",
         default_value = "@",
         help = "Symbol to mark variable positions in pattern [default: @]",
-        requires = "pattern",
+        requires = "patterns",
         conflicts_with_all = ["minumum_length", "maximum_length", "bruteforce_flag"]
     )]
     pub pattern_symbol: char,
 
+    /// Further pattern symbols with their own charsets
+    #[arg(
+        long = "symbol",
+        value_name = "SYM=SPEC|SYM:CHARS",
+        value_parser = crate::crackers::pattern::parse_symbol,
+        help = "Declare a pattern symbol with its own charset, e.g. '#=n' or '%:!?' (repeatable)",
+        long_help = "Declare a further symbol marking variable positions in the pattern,\n\
+                     tried with its own charset. SYM=SPEC takes the charset from --charset\n\
+                     specifiers (e.g. '#=n' or '#=lower,digits'), SYM:CHARS lists the\n\
+                     characters like --custom-chars (e.g. '%:!?'). The pattern symbol keeps\n\
+                     the charset of --charset and --custom-chars unless it is declared too.\n\
+                     Example: -p 'Pa@@w#rd' --symbol '#=n' tries digits in the '#' position.\n\
+                     Every declared symbol must occur in the pattern.",
+        requires = "patterns",
+        conflicts_with_all = ["minumum_length", "maximum_length", "bruteforce_flag"]
+    )]
+    pub symbols: Vec<PatternSymbol>,
+
+    /// Pattern symbols whose positions all take the same character
+    #[arg(
+        long = "linked-symbol",
+        value_name = "SYM|SYM=SPEC|SYM:CHARS",
+        value_parser = crate::crackers::pattern::parse_linked_symbol,
+        help = "Declare a symbol whose positions all take the same character, e.g. '&' (repeatable)",
+        long_help = "Declare a symbol whose positions all take the same character, so\n\
+                     they count as one variable position in the keyspace. A bare symbol\n\
+                     tries the charset of --charset and --custom-chars, SYM=SPEC and\n\
+                     SYM:CHARS give it its own charset like --symbol. Several linked\n\
+                     symbols each take their own character.\n\
+                     Example: -p '&_secret_&' --linked-symbol '&' tries 'a_secret_a',\n\
+                     'b_secret_b', ... Every declared symbol must occur in the pattern.",
+        requires = "patterns",
+        conflicts_with_all = ["minumum_length", "maximum_length", "bruteforce_flag"]
+    )]
+    pub linked_symbols: Vec<PatternSymbol>,
+
+    /// Years of the `?y` and `?Y` pattern tokens
+    #[arg(
+        long = "years",
+        value_name = "FIRST-LAST",
+        value_parser = crate::crackers::pattern::parse_years,
+        default_value = "1950-2030",
+        help = "Years the '?y' (e.g. 2024) and '?Y' (e.g. 24) pattern tokens try, most recent first",
+        requires = "patterns"
+    )]
+    pub years: RangeInclusive<u16>,
+
+    /// Case variants of the fixed pattern letters to try
+    #[arg(
+        long = "toggle-case",
+        value_name = "MODE",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "common",
+        help = "Also try case variants of the fixed pattern letters: common (default) or =all",
+        long_help = "Also try case variants of the fixed letters of the pattern for every\n\
+                     combination before moving on. common tries the pattern as typed, all\n\
+                     lowercase, all uppercase and with only the first letter uppercase,\n\
+                     e.g. 'acme@@' also as 'ACME@@' and 'Acme@@'. --toggle-case=all tries every mix of\n\
+                     lowercase and uppercase letters, up to 16 letters. The candidates\n\
+                     multiply by the number of variants, work units still count combinations.",
+        requires = "patterns",
+        conflicts_with_all = ["minumum_length", "maximum_length", "bruteforce_flag"]
+    )]
+    pub toggle_case: Option<CaseToggle>,
+
+    /// Order of the runs of adjacent digit positions in pattern mode
+    #[arg(
+        long = "numeric-order",
+        value_name = "asc[:START]",
+        value_parser = crate::crackers::pattern::parse_numeric_order,
+        help = "Count runs of digit positions upward from START, e.g. asc:1999",
+        long_help = "Count every run of adjacent pattern positions whose charset is exactly\n\
+                     the digits upward like an integer, starting at START (0 by default) and\n\
+                     wrapping around to zero. With -c n -p 'Backup@@@@' --numeric-order asc:1999\n\
+                     the run tries 1999, 2000, ... 9999 and then 0000 to 1998, so years and\n\
+                     small counters near START come first.",
+        requires = "patterns",
+        conflicts_with_all = ["random_order", "probability_model"]
+    )]
+    pub numeric_order: Option<NumericOrder>,
+
+    /// Word list for the %W token of the patterns
+    #[arg(
+        long = "pattern-dict",
+        value_name = "FILE",
+        help = "Replace %W in the patterns by every word of FILE, one per line",
+        long_help = "Replace the %W token in the patterns by every word of FILE, one per\n\
+                     line, and try the variable positions for each word, e.g. --pattern\n\
+                     '%W@@!' tries every word followed by two characters and '!'. Words\n\
+                     are read in file order and taken as is, symbols in them stay fixed.\n\
+                     Every pattern needs the token. Work units take the words round-robin.",
+        requires = "patterns",
+        conflicts_with_all = [
+            "minumum_length",
+            "maximum_length",
+            "bruteforce_flag",
+            "probability_model",
+            "progress_save_every",
+            "session",
+            "skip",
+            "limit"
+        ]
+    )]
+    pub pattern_dict: Option<PathBuf>,
+
     /// Minimum password length for brute force attack
     #[arg(
         short = 'm',
@@ -66,8 +470,9 @@ pub struct Args {
         value_parser = clap::value_parser!(u8).range(1..=255),
         help = "Minimum password length for brute force attack [default: 1]",
         requires = "bruteforce_flag",
-        conflicts_with_all = ["pattern", "pattern_symbol"]
+        conflicts_with_all = ["pattern", "pattern_file", "pattern_symbol"]
     )]
+    #[cfg_attr(feature = "config", serde(rename = "min_length"))]
     pub minumum_length: u8,
 
     /// Maximum password length for brute force attack
@@ -78,10 +483,12 @@ pub struct Args {
         value_parser = clap::value_parser!(u8).range(1..=255),
         help = "Maximum password length for brute force attack [default: 6]",
         long_help = "Maximum password length for brute force attack [default: 6]\n\
-                     Note: Many PKCS#12 implementations limit passwords to 15 bytes.",
+                     Note: Many PKCS#12 implementations limit passwords to 15 bytes,\n\
+                     see --max-bytes.",
         requires = "bruteforce_flag",
-        conflicts_with_all = ["pattern", "pattern_symbol"]
+        conflicts_with_all = ["pattern", "pattern_file", "pattern_symbol"]
     )]
+    #[cfg_attr(feature = "config", serde(rename = "max_length"))]
     pub maximum_length: u8,
 
     /// Enable brute force attack mode
@@ -90,43 +497,623 @@ pub struct Args {
         long = "brute-force",
         help = "Enable brute force attack mode"
     )]
+    #[cfg_attr(feature = "config", serde(rename = "brute_force"))]
     pub bruteforce_flag: bool,
 
+    /// Restrict brute force to pronounceable candidates
+    #[arg(
+        long = "pronounceable",
+        help = "Only try pronounceable made-up words, alternating consonants and vowels",
+        long_help = "Only try pronounceable made-up words of lowercase letters: consonants,\n\
+                     including the digraphs ch, ph, sh, th and wh, alternate with vowels.\n\
+                     Replaces the charset, so charset and order options cannot be combined\n\
+                     with it. See --pronounceable-digits for trailing digits.",
+        requires = "bruteforce_flag",
+        conflicts_with_all = [
+            "char_sets", "charset_range", "specific_chars", "no_space", "random_order",
+            "smart_order", "reverse", "length_order", "interleave_lengths", "start_at",
+            "skip", "limit", "require", "require_count", "session"
+        ]
+    )]
+    pub pronounceable: bool,
+
+    /// Trailing digits of pronounceable candidates
+    #[arg(
+        long = "pronounceable-digits",
+        value_name = "N",
+        default_value = "0",
+        value_parser = clap::value_parser!(u8).range(0..=4),
+        help = "Also try pronounceable words followed by up to N (0-4) digits [default: 0]",
+        requires = "pronounceable"
+    )]
+    pub pronounceable_digits: u8,
+
     /// Character sets to use in brute force attack
     #[arg(
         short = 'c',
         long = "charset",
         value_name = "SETS",
         help = "Character sets to use in brute force attack",
-        long_help = "Specify one or more character sets for password generation:\n\
-                     a - lowercase letters (a-z)\n\
-                     A - uppercase letters (A-Z)\n\
-                     n - digits (0-9)\n\
-                     s - special chars (!@#$%^&*...)\n\
-                     x - all of the above\n\
-                     Example: 'aAn' for alphanumeric passwords"
+        long_help = crate::charset::charset_help()
     )]
+    #[cfg_attr(feature = "config", serde(rename = "charset"))]
     pub char_sets: Option<String>,
 
+    /// Unicode codepoint ranges to add to the character set
+    #[arg(
+        long = "charset-range",
+        value_name = "RANGES",
+        value_delimiter = ',',
+        help = "Unicode codepoint ranges to add to the character set (e.g., 'U+0400-U+04FF')",
+        long_help = "Add one or more Unicode codepoint ranges to the character set.\n\
+                     Ranges are comma separated or given with repeated flags, a single\n\
+                     codepoint is also accepted. Surrogates and noncharacters are skipped.\n\
+                     Example: 'U+0400-U+04FF,U+3040-U+309F' for Cyrillic and hiragana"
+    )]
+    pub charset_range: Vec<String>,
+
+    /// Exclude the space character from the presets
+    #[arg(
+        long = "no-space",
+        help = "Exclude the space character from the 's', 'p' and 'x' character sets"
+    )]
+    pub no_space: bool,
+
     /// Custom character set for brute force attack
     #[arg(
         long = "custom-chars",
         value_name = "CHARS",
         help = "Custom character set for brute force attack",
         long_help = "Define a custom set of characters to use in brute force attack.\n\
+                     Supports escapes: \\t, \\n, \\\\, \\xNN and \\u{XXXX}.\n\
                      Example: 'abcABC123!@#'"
     )]
+    #[cfg_attr(feature = "config", serde(rename = "custom_chars"))]
     pub specific_chars: Option<String>,
 
+    /// Try candidates in a random order
+    #[arg(
+        long = "random-order",
+        help = "Try brute force and pattern candidates in a seeded random order",
+        conflicts_with = "dictionary_path"
+    )]
+    pub random_order: bool,
+
+    /// Try simple brute force candidates first
+    #[arg(
+        long = "smart-order",
+        help = "Try repeats, sequences and palindromes before the exhaustive brute force pass",
+        long_help = "Before the exhaustive brute force pass, try a first wave of likely\n\
+                     candidates: single character repeats ('aaaa'), ascending and\n\
+                     descending charset runs ('1234', 'dcba') and palindromes ('abba').\n\
+                     The exhaustive pass skips them, so nothing is tried twice.",
+        requires = "bruteforce_flag",
+        conflicts_with = "work_unit"
+    )]
+    pub smart_order: bool,
+
+    /// Walk the keyspace backwards
+    #[arg(
+        long = "reverse",
+        help = "Try brute force and pattern candidates from the top of the keyspace downward",
+        long_help = "Try brute force and pattern candidates from the top of the keyspace\n\
+                     downward: longest lengths and highest indices first. --work-unit,\n\
+                     --start-at and --limit refer to this reversed order, and --restore\n\
+                     keeps the direction of the saved session.",
+        conflicts_with_all = ["dictionary_path", "random_order"]
+    )]
+    pub reverse: bool,
+
+    /// Order of the lengths for brute force
+    #[arg(
+        long = "length-order",
+        value_name = "ORDER",
+        value_enum,
+        help = "Order of the brute force lengths: ascending, descending or interleaved",
+        long_help = "Order of the brute force lengths [default: ascending, descending with --reverse].\n\
+                     interleaved tests one chunk of every length in turn, so long lengths\n\
+                     start right away instead of after all shorter ones. With --reverse\n\
+                     only the positions inside each length are reversed.",
+        requires = "bruteforce_flag"
+    )]
+    pub length_order: Option<LengthOrder>,
+
+    /// Shorthand for `--length-order interleaved`
+    #[arg(
+        long = "interleave-lengths",
+        help = "Test one chunk of every brute force length in turn, same as --length-order interleaved",
+        requires = "bruteforce_flag",
+        conflicts_with = "length_order"
+    )]
+    pub interleave_lengths: bool,
+
+    /// Only try brute force candidates without repeated characters
+    #[arg(
+        long = "no-repeats",
+        help = "Only try brute force candidates whose characters are all distinct",
+        long_help = "Only try brute force candidates whose characters are all distinct, for\n\
+                     generated passwords known to never repeat a character. Candidates are\n\
+                     generated directly, so the keyspace shrinks from n^len to\n\
+                     n·(n-1)·…·(n-len+1), e.g. 62^8 = 2.2e14 becomes 1.4e14, and --skip,\n\
+                     --limit, --start-at and --work-unit count only those candidates.",
+        requires = "bruteforce_flag",
+        conflicts_with_all = ["pronounceable", "require", "require_count", "probability_model"]
+    )]
+    pub no_repeats: bool,
+
+    /// Per-character weights ordering brute force and pattern candidates
+    #[arg(
+        long = "probability-model",
+        value_name = "JSON",
+        help = "Try brute force and pattern candidates in decreasing probability under per-character weights",
+        long_help = "Try brute force and pattern candidates in decreasing probability.\n\
+                     JSON is a file mapping characters to weights, e.g. English letter\n\
+                     frequencies {\"e\": 12.7, \"t\": 9.1, \"z\": 0.07}. Weights are normalized over\n\
+                     the charset, characters without a weight get the smallest one. Brute\n\
+                     force mixes the lengths, a likely long candidate can come before an\n\
+                     unlikely short one. With --random-order the order is shuffled but\n\
+                     still favors likely characters.",
+        conflicts_with_all = [
+            "dictionary_path", "pronounceable", "reverse", "length_order", "interleave_lengths",
+            "start_at", "smart_order", "session", "progress_save_every"
+        ]
+    )]
+    pub probability_model: Option<PathBuf>,
+
+    /// First brute force candidate to try
+    #[arg(
+        long = "start-at",
+        value_name = "CANDIDATE",
+        help = "Start brute force at CANDIDATE, skipping shorter and preceding candidates",
+        requires = "bruteforce_flag",
+        conflicts_with = "random_order"
+    )]
+    pub start_at: Option<String>,
+
+    /// Number of brute force candidates or pattern combinations to skip
+    #[arg(
+        long = "skip",
+        value_name = "NUM",
+        help = "Skip the first NUM brute force candidates or pattern combinations of the work unit",
+        long_help = "Skip the first NUM brute force candidates or pattern combinations of the\n\
+                     work unit. Candidates of all lengths and combinations of all patterns\n\
+                     are numbered consecutively in the order they are tried.",
+        requires = "numbered"
+    )]
+    pub skip: Option<u128>,
+
+    /// Maximum number of brute force candidates or pattern combinations
+    #[arg(
+        long = "limit",
+        value_name = "NUM",
+        help = "Stop brute force or pattern mode after trying NUM candidates or combinations",
+        requires = "numbered"
+    )]
+    pub limit: Option<u128>,
+
+    /// Character classes every brute force candidate must contain
+    #[arg(
+        long = "require",
+        value_name = "CLASSES",
+        value_delimiter = ',',
+        value_parser = crate::crackers::policy::parse_char_class,
+        help = "Only try brute force candidates containing each class (lower, upper, digit, special)",
+        long_help = "Only try brute force candidates containing at least one character of\n\
+                     each listed class: lower, upper, digit, special. Candidates that\n\
+                     violate the policy are skipped without being tested.\n\
+                     Example: --require upper,digit,special",
+        requires = "bruteforce_flag"
+    )]
+    pub require: Vec<CharClass>,
+
+    /// Minimum number of characters of a class in every brute force candidate
+    #[arg(
+        long = "require-count",
+        value_name = "CLASS=N",
+        value_delimiter = ',',
+        value_parser = crate::crackers::policy::parse_class_count,
+        help = "Only try brute force candidates with at least N characters of CLASS, e.g. digit=2",
+        long_help = "Only try brute force candidates with at least N characters of CLASS,\n\
+                     one of lower, upper, digit, special. A class named here and in\n\
+                     --require keeps the larger minimum. Candidates that violate the\n\
+                     policy are skipped without being tested.\n\
+                     Example: --require-count digit=2,special=1",
+        requires = "bruteforce_flag"
+    )]
+    pub require_count: Vec<(CharClass, usize)>,
+
+    /// Seed for the random candidate order
+    #[arg(
+        long = "seed",
+        value_name = "U64",
+        help = "Seed for --random-order [default: derived from the current time]",
+        requires = "random_order"
+    )]
+    pub seed: Option<u64>,
+
+    /// Share of the search space processed by this machine
+    #[arg(
+        long = "work-unit",
+        value_name = "START/TOTAL",
+        value_parser = validate_work_unit,
+        default_value = "0/1",
+        help = "Process only work unit START (0-based) of TOTAL equal units [default: 0/1]",
+        long_help = "Split the search space into TOTAL equal, non-overlapping units and\n\
+                     process only unit START (0-based). Running START = 0..TOTAL on\n\
+                     separate machines covers the whole search space exactly once.\n\
+                     With --random-order all machines must use the same --seed."
+    )]
+    pub work_unit: WorkUnit,
+
+    /// Session file for saving brute force or pattern progress
+    #[arg(
+        long = "session",
+        value_name = "FILE",
+        help = "Save brute force or pattern progress to FILE so the run can be resumed with --restore",
+        requires = "numbered"
+    )]
+    pub session: Option<PathBuf>,
+
+    /// Resume a saved brute force or pattern session
+    #[arg(
+        long = "restore",
+        help = "Resume the brute force or pattern run saved in the --session file",
+        long_help = "Resume the brute force or pattern run saved in the --session file.\n\
+                     The charset, lengths, --seed, --work-unit and certificate must\n\
+                     match the original run, for patterns also the patterns, the\n\
+                     charsets of their symbols and --reverse.",
+        requires = "session"
+    )]
+    pub restore: bool,
+
+    /// How often the --session file is written
+    #[arg(
+        long = "session-save-every",
+        value_name = "N|DURATION",
+        help = "Write the --session file every N attempts or every DURATION (30s, 5m, 1h) [default: 30s]",
+        requires = "session"
+    )]
+    pub session_save_every: Option<WriteCadence>,
+
+    /// Save the position to a progress file every N attempts or DURATION
+    #[arg(
+        long = "progress-save-every",
+        value_name = "N|DURATION",
+        help = "Save the position to a progress file every N attempts or DURATION (30s, 5m, 1h) and resume from it",
+        long_help = "Save the position of the run to a progress file every N attempts,\n\
+                     or every DURATION such as 30s, 5m or 1h:\n\
+                     the length and combination index for brute force, the combination\n\
+                     index for patterns and the byte offset for dictionaries. A later\n\
+                     run of the same certificate skips forward to the saved position.\n\
+                     The other settings must match the original run. The file is\n\
+                     written again when the run stops and removed once the password\n\
+                     is found."
+    )]
+    pub progress_save_every: Option<WriteCadence>,
+
+    /// Progress file used with --progress-save-every
+    #[arg(
+        long = "progress-file",
+        value_name = "PATH",
+        help = "Progress file for --progress-save-every [default: <cert>.progress]",
+        requires = "progress_save_every"
+    )]
+    pub progress_file: Option<PathBuf>,
+
+    /// Resume an identical earlier run without asking
+    #[arg(
+        long = "auto-resume",
+        help = "Resume an interrupted run of the same command without asking",
+        long_help = "Resume an interrupted run of the same command without asking.\n\
+                     Brute force, pattern and dictionary runs save their position to a\n\
+                     state directory, keyed by the certificate and every attack parameter.\n\
+                     On a terminal the tool asks whether to resume a matching run, otherwise\n\
+                     it is only resumed with this flag."
+    )]
+    pub auto_resume: bool,
+
+    /// File every tested candidate is written to
+    #[arg(
+        long = "log-candidates",
+        value_name = "FILE",
+        help = "Write every tested candidate to FILE, one per line (slow, can produce huge files)"
+    )]
+    pub log_candidates: Option<PathBuf>,
+
+    /// Print the search space without cracking
+    #[arg(
+        long = "dry-run",
+        help = "Print the search space and work unit breakdown without cracking"
+    )]
+    pub dry_run: bool,
+
+    /// Duration of the benchmark that estimates the time of the run
+    #[arg(
+        long = "estimate-time",
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Benchmark the certificate for SECS seconds and print the estimated run time first",
+        long_help = "Benchmark the certificate for SECS seconds and print the estimated\n\
+                     time of the run before starting it. The key derivation cost differs\n\
+                     between certificates, so the rate measured with the actual\n\
+                     certificate gives a realistic estimate, e.g. \"estimated 3 days\n\
+                     14 hours\". The run then starts from the beginning and covers the\n\
+                     whole search space. Supports a single certificate.",
+        conflicts_with_all = ["dry_run", "session"]
+    )]
+    pub estimate_time: Option<u64>,
+
+    /// Number of candidates to print without cracking
+    #[arg(
+        long = "show-candidates",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Print the first N candidates in traversal order and exit without loading the certificate",
+        long_help = "Print the mode, the keyspace and the first N candidates in traversal order,\n\
+                     after --filter-regex, --max-bytes, rules and appended numbers, and exit\n\
+                     without loading the certificate. Use it to check that a pattern, its\n\
+                     symbols and charsets or a rule set produce the intended candidates.",
+        conflicts_with_all = ["dry_run", "estimate_time", "session", "progress_save_every", "save_all_found"]
+    )]
+    pub show_candidates: Option<u64>,
+
+    /// Print the MAC and encryption algorithms of the certificates and exit
+    #[arg(
+        long = "show-cert-info",
+        help = "Print the MAC and encryption algorithms of the certificates and exit",
+        long_help = "Print the MAC algorithm, the encryption algorithm of the private key (or of\n\
+                     the certificates without an encrypted key), the key derivation iterations\n\
+                     and the salt of every certificate, and exit. No mode or password is needed.\n\
+                     The iterations are those of the MAC, which every candidate goes through\n\
+                     first, and warns when there are fewer than 1024.",
+        conflicts_with_all = ["dry_run", "show_candidates", "estimate_time", "quiet_found"]
+    )]
+    pub show_cert_info: bool,
+
+    /// Print the resolved character set and exit
+    #[arg(
+        long = "alphabet-test",
+        help = "Print the characters of -c, --charset-range and --custom-chars and exit",
+        long_help = "Print the character set that -c, --charset-range, --custom-chars and\n\
+                     --no-space resolve to, one character per line with its codepoint, sorted\n\
+                     and without duplicates, followed by their number, and exit. No\n\
+                     certificate is needed.",
+        conflicts_with_all = ["dry_run", "show_candidates", "estimate_time", "show_cert_info", "quiet_found"]
+    )]
+    pub alphabet_test: bool,
+
+    /// Number of machines for the dry-run work unit breakdown
+    #[arg(
+        long = "total-machines",
+        value_name = "NUM",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Show the --work-unit breakdown for NUM machines in --dry-run",
+        requires = "dry_run"
+    )]
+    pub total_machines: Option<u64>,
+
     /// Delimiter for dictionary entries
     #[arg(
         long = "delimiter",
-        value_name = "CHAR",
+        value_name = "DELIMITER",
         default_value = "\n",
+        value_parser = crate::crackers::dictionary::parse_delimiter,
         help = "Dictionary file entry delimiter [default: newline]",
-        requires = "pattern"
+        long_help = "Dictionary file entry delimiter [default: newline]\n\
+                     A single character or a string such as '||'. Escapes like '\\t', '\\n'\n\
+                     and '\\r\\n' are translated, '\\r\\n' also accepts lone newlines.\n\
+                     Use 'auto' to detect tab, comma, colon or newline from the file content.",
+        requires = "dictionary_path"
+    )]
+    pub delimiter: DelimiterMode,
+
+    /// Seconds between two status lines
+    #[arg(
+        long = "status-interval",
+        value_name = "SECS",
+        default_value = "10",
+        help = "Print progress every SECS seconds while cracking, 0 disables it [default: 10]"
+    )]
+    pub status_interval: u64,
+
+    /// Format of the final result
+    #[arg(
+        long = "output",
+        value_name = "FORMAT",
+        value_enum,
+        default_value = "text",
+        help = "Format of the final result: text or json [default: text]"
+    )]
+    pub output: OutputFormat,
+
+    /// Print only the found password
+    #[arg(
+        long = "quiet-found",
+        help = "Print only the found password, for scripts; exit with 1 and no output if not found",
+        long_help = "For scripts: print nothing but the found password followed by a newline,\n\
+                     e.g. to pipe it to `openssl pkcs12 -passin stdin`. All progress, warnings\n\
+                     and statistics are suppressed. When the password is not found, nothing is\n\
+                     printed and the exit code is 1. Only errors that stop the run are printed,\n\
+                     to stderr. Supports a single certificate.",
+        conflicts_with_all = ["output", "dry_run", "show_candidates", "estimate_time"]
+    )]
+    pub quiet_found: bool,
+
+    /// Only test candidates matching a regex
+    #[arg(
+        long = "filter-regex",
+        value_name = "REGEX",
+        value_parser = validate_filter_regex,
+        help = "Only test candidates matching REGEX, in every mode",
+        long_help = "Only test candidates matching REGEX, in every mode. The regex is\n\
+                     unanchored, use ^ and $ to match the whole candidate, e.g. '\\D$' for\n\
+                     candidates not ending in a digit. Backreferences and lookaround are not\n\
+                     supported. Filtered candidates are still generated and count as\n\
+                     attempts, the filter only saves their expensive decryption."
+    )]
+    #[cfg_attr(feature = "config", serde(with = "config::regex"))]
+    pub filter_regex: Option<Regex>,
+
+    /// Skip candidates longer than a number of bytes
+    #[arg(
+        long = "max-bytes",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Skip candidates longer than N bytes in UTF-8, in every mode",
+        long_help = "Skip candidates whose UTF-8 encoding is longer than N bytes, in every\n\
+                     mode. Many PKCS#12 implementations limit passwords to 15 bytes, and a\n\
+                     multibyte charset can exceed that even at short lengths. Skipped\n\
+                     candidates are not tested and do not count as attempts. Without a\n\
+                     limit, a warning is printed once a candidate longer than 15 bytes is\n\
+                     tested."
+    )]
+    pub max_bytes: Option<u32>,
+
+    /// Case folds of every candidate to test besides the candidate itself
+    #[arg(
+        long = "case-insensitive",
+        value_name = "FOLD",
+        value_enum,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "lower",
+        help = "Also test every candidate in lowercase (default), =upper or =both; 2x slower, 3x with both",
+        long_help = "Also test every candidate in lowercase, for certificates created by\n\
+                     libraries that normalize the password before deriving the key, e.g.\n\
+                     some .NET libraries. --case-insensitive=upper tests the uppercase form\n\
+                     instead and --case-insensitive=both tests both. Every fold is another\n\
+                     decryption: the run is 2x slower, 3x with both. Folds equal to the\n\
+                     candidate are skipped and folds do not count as attempts. The password\n\
+                     is reported in the form that opened the certificate."
+    )]
+    pub case_insensitive: Option<CaseSensitivity>,
+
+    /// Also test every candidate reversed
+    #[arg(
+        long = "also-reversed",
+        requires = "reversible",
+        help = "Also test every dictionary or pattern candidate reversed; 2x slower",
+        long_help = "Also test every dictionary or pattern candidate reversed character by\n\
+                     character, for passwords typed backwards. The reversed form counts as\n\
+                     another attempt, palindromes are only tested once. The result says\n\
+                     when the password matched a reversed candidate."
+    )]
+    pub also_reversed: bool,
+
+    /// Check the MAC of every candidate before decrypting it
+    #[arg(
+        long = "pre-hash",
+        help = "Check every candidate against the certificate's MAC before decrypting it",
+        long_help = "Check every candidate against the certificate's MAC before the full\n\
+                     decryption. The MAC uses the same key derivation as the encryption, so\n\
+                     the time per wrong candidate still depends on the iteration count; the\n\
+                     check skips OpenSSL's error reporting for rejected candidates. Files\n\
+                     without a MAC are decrypted for every candidate, as without the flag."
+    )]
+    pub pre_hash: bool,
+
+    /// Store the recovered password in the macOS Keychain
+    #[arg(
+        long = "save-to-keychain",
+        help = "Store a recovered password in the default macOS Keychain, named after the certificate file",
+        long_help = "Store a recovered password in the user's default macOS Keychain with\n\
+                     'security add-generic-password', using the certificate file name as\n\
+                     the service name. The password is passed on the command line of\n\
+                     'security', so other local users can see it with 'ps' while it runs.\n\
+                     Only available on macOS, elsewhere a warning is printed and the\n\
+                     password is not saved."
+    )]
+    pub save_to_keychain: bool,
+
+    /// CSV file every found password is written to
+    #[arg(
+        long = "save-all-found",
+        value_name = "FILE",
+        help = "Write every found password to a CSV file, e.g. when cracking several certificates",
+        long_help = "Write a row 'certificate_path,password,attempts,elapsed_secs' to the CSV\n\
+                     file for every certificate whose password is found. Each row is written\n\
+                     as soon as the certificate is cracked, so the rows are kept if the run\n\
+                     is killed. The file is overwritten unless --save-all-found-append is given."
+    )]
+    pub save_all_found: Option<PathBuf>,
+
+    /// Append to the `--save-all-found` file instead of overwriting it
+    #[arg(
+        long = "save-all-found-append",
+        requires = "save_all_found",
+        help = "Append to the --save-all-found file instead of overwriting it"
+    )]
+    pub save_all_found_append: bool,
+
+    /// Show a desktop notification when the password is found
+    #[arg(
+        long = "notify",
+        help = "Show a desktop notification when the password is found",
+        long_help = "Show a desktop notification with the recovered password after a\n\
+                     successful crack. Needs a build with the 'notifications' feature\n\
+                     (cargo install pkcs12cracker --features notifications); without it,\n\
+                     or when no notification service is running, the terminal bell rings."
+    )]
+    pub notify: bool,
+
+    /// URL to post the result to when the run completes
+    #[arg(
+        long = "webhook",
+        value_name = "URL",
+        help = "POST the result as JSON to URL when a password is found",
+        long_help = "POST the result of every certificate as JSON to URL when a password\n\
+                     is found, e.g. to a Slack incoming webhook. The body has the fields\n\
+                     found, password, cert, attempts and elapsed_secs. A failed request\n\
+                     only prints a warning."
+    )]
+    pub webhook: Option<String>,
+
+    /// Also post to the webhook when no password is found
+    #[arg(
+        long = "webhook-on-not-found",
+        help = "Also POST to the --webhook when no password is found",
+        requires = "webhook"
+    )]
+    pub webhook_on_not_found: bool,
+
+    /// Bearer token for the webhook
+    #[arg(
+        long = "webhook-token",
+        value_name = "TOKEN",
+        help = "Send TOKEN as a Bearer token in the Authorization header of the --webhook request",
+        requires = "webhook"
+    )]
+    pub webhook_token: Option<String>,
+
+    /// Windows system store to import a cracked certificate into
+    #[cfg_attr(
+        target_os = "windows",
+        arg(
+            long = "import-windows-store",
+            value_name = "STORE",
+            help = "Import a cracked certificate into a Windows system store, e.g. MY or ROOT",
+            long_help = "After a successful crack, import the certificate and its private key\n\
+                         into the given system store of the current user (e.g. MY or ROOT)\n\
+                         using the recovered password. Private keys are imported as\n\
+                         exportable and user protected. Only available on Windows."
+        )
+    )]
+    #[cfg_attr(not(target_os = "windows"), arg(skip))]
+    pub import_windows_store: Option<String>,
+
+    /// Number of candidates per parallel work item
+    #[arg(
+        long = "chunk-size",
+        value_name = "NUM",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Candidates per parallel work item [default: 16384]",
+        long_help = "Candidates per parallel work item for brute force, pattern, Markov,\n\
+                     edit distance and permutation attacks [default: 16384]. Larger\n\
+                     chunks mean less scheduling overhead for fast certificates, smaller\n\
+                     chunks stop sooner once the password is found on slow ones.\n\
+                     Dictionary files are always split into 16384 byte chunks."
     )]
-    pub delimiter: String,
+    pub chunk_size: Option<u32>,
 
     /// Number of threads to use
     #[arg(
@@ -138,6 +1125,37 @@ pub struct Args {
         help = "Number of cracking threads [default: number of CPU cores]"
     )]
     pub threads: u8,
+
+    /// Test candidates in traversal order for reproducible results
+    #[arg(
+        long = "deterministic",
+        help = "Test candidates strictly in order so the found password and attempt count are reproducible",
+        long_help = "Test candidates strictly in traversal order on a single worker thread,\n\
+                     so that the reported password is always the first match and the\n\
+                     attempt count is the same on every run. Useful for benchmarks and,\n\
+                     with --log-candidates, for auditable runs. Overrides --threads, so\n\
+                     cracking is about N times slower on N cores. --random-order needs\n\
+                     an explicit --seed."
+    )]
+    pub deterministic: bool,
+}
+
+/// Format of the final result printed by the command line tool.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OutputFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// A single JSON object with the run's statistics
+    Json,
+    /// Only the found password, set by `--quiet-found`
+    #[value(skip)]
+    BarePassword,
 }
 
 impl Default for Args {
@@ -145,15 +1163,93 @@ impl Default for Args {
         Self {
             char_sets: None,
             specific_chars: None,
-            certificate_path: PathBuf::new(),
+            charset_range: Vec::new(),
+            no_space: false,
+            certificate_paths: Vec::new(),
             dictionary_path: None,
+            no_trim: false,
+            wordlist_encoding: encoding_rs::UTF_8,
+            word_encoding: None,
+            wordlist_sort: None,
+            wordlist_sort_chunk: None,
+            append_numbers: None,
+            append_numbers_fixed: None,
+            skip_n: None,
+            first_n: None,
+            rules: None,
+            john_rules: None,
+            john_rules_section: john_rules::DEFAULT_SECTION.to_string(),
+            markov_model: None,
+            markov_model_bin: None,
+            markov_save_bin: None,
+            markov_min_prob: 1e-6,
+            mutate: None,
+            distance: 1,
+            permutation_words: Vec::new(),
+            permutation_max_n: None,
+            words_from_certificate: false,
             pattern: None,
+            pattern_file: None,
             pattern_symbol: '@',
+            symbols: Vec::new(),
+            linked_symbols: Vec::new(),
+            years: crate::crackers::pattern::DEFAULT_YEARS,
+            numeric_order: None,
+            toggle_case: None,
+            pattern_dict: None,
             minumum_length: 1,
             maximum_length: 8,
             bruteforce_flag: false,
-            delimiter: String::new(),
+            pronounceable: false,
+            pronounceable_digits: 0,
+            random_order: false,
+            seed: None,
+            smart_order: false,
+            reverse: false,
+            length_order: None,
+            interleave_lengths: false,
+            no_repeats: false,
+            probability_model: None,
+            start_at: None,
+            skip: None,
+            limit: None,
+            require: Vec::new(),
+            require_count: Vec::new(),
+            work_unit: WorkUnit::FULL,
+            session: None,
+            restore: false,
+            progress_save_every: None,
+            session_save_every: None,
+            progress_file: None,
+            auto_resume: false,
+            log_candidates: None,
+            dry_run: false,
+            estimate_time: None,
+            show_candidates: None,
+            show_cert_info: false,
+            alphabet_test: false,
+            case_insensitive: None,
+            also_reversed: false,
+            pre_hash: false,
+            force: false,
+            total_machines: None,
+            delimiter: DelimiterMode::SingleByte(b'\n'),
+            status_interval: 10,
+            output: OutputFormat::Text,
+            quiet_found: false,
+            filter_regex: None,
+            max_bytes: None,
+            save_to_keychain: false,
+            save_all_found: None,
+            save_all_found_append: false,
+            notify: false,
+            webhook: None,
+            webhook_on_not_found: false,
+            webhook_token: None,
+            import_windows_store: None,
+            chunk_size: None,
             threads: 1,
+            deterministic: false,
         }
     }
 }
@@ -167,12 +1263,154 @@ fn validate_threads_count(threads: &str) -> Result<u8> {
     }
 }
 
-fn validate_certificate_path(path: &str) -> Result<PathBuf> {
-    let path = PathBuf::from(path);
-    if path.extension().is_some() {
-        Ok(path)
-    } else {
-        bail!("Certificate file must have .p12 or .pfx extension");
+fn validate_work_unit(work_unit: &str) -> Result<WorkUnit> {
+    let Some((index, total)) = work_unit.split_once('/') else {
+        bail!("Work unit must have the form START/TOTAL, e.g. 0/4");
+    };
+    let index = index.trim().parse::<usize>()?;
+    let total = total.trim().parse::<usize>()?;
+    if total == 0 || index >= total {
+        bail!("Work unit START must be smaller than TOTAL, got {index}/{total}");
+    }
+    Ok(WorkUnit { index, total })
+}
+
+fn validate_filter_regex(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| anyhow::anyhow!("Invalid --filter-regex: {e}"))
+}
+
+impl Args {
+    /// Validates every certificate path, unless `--force` is given.
+    ///
+    /// `-` reads the certificate from stdin and is not checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `-` is given more than once or a certificate file
+    /// does not exist.
+    pub fn validate_certificate_paths(&self) -> Result<()> {
+        let stdin = Path::new("-");
+        if self
+            .certificate_paths
+            .iter()
+            .filter(|path| *path == stdin)
+            .count()
+            > 1
+        {
+            bail!("Stdin can only be read once, give '-' as a single certificate");
+        }
+        if self.force {
+            return Ok(());
+        }
+        self.certificate_paths
+            .iter()
+            .filter(|path| *path != stdin)
+            .try_for_each(|path| validate_certificate_path(path))
+    }
+}
+
+/// Checks that a certificate file exists.
+///
+/// Files without an extension are accepted, a note is logged at info level.
+/// An extension other than `p12`, `pfx` or `pem` only prints a warning.
+fn validate_certificate_path(path: &Path) -> Result<()> {
+    if !path.is_file() {
+        return Err(PkcsError::CertificateNotFound(path.to_path_buf()).into());
+    }
+    match path.extension().and_then(|extension| extension.to_str()) {
+        None => log::info!(
+            "Certificate file {} has no extension, assuming PKCS#12",
+            path.display()
+        ),
+        Some(extension)
+            if ["p12", "pfx", "pem"]
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known)) => {}
+        Some(_) => eprintln!(
+            "Warning: {} does not have a .p12, .pfx or .pem extension",
+            path.display()
+        ),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "config")]
+impl Args {
+    /// Reads arguments from a TOML config file's content.
+    ///
+    /// Keys are the field names of [`Args`], except for the fields named
+    /// after their flag like `dictionary` or `min_length`. Missing keys take
+    /// their default value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the key if the TOML is malformed, a key is
+    /// unknown or a value has the wrong type
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let deserializer = toml::Deserializer::new(text);
+        serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let path = e.path().to_string();
+            anyhow::anyhow!("Invalid config key '{path}': {}", e.into_inner().message())
+        })
+    }
+
+    /// Writes the arguments as a TOML config file that [`Args::from_toml`] reads back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a number does not fit into a TOML integer
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+}
+
+/// Serde helpers for the fields of [`Args`] without serde support.
+#[cfg(feature = "config")]
+mod config {
+    /// `&'static Encoding` as its name, e.g. `UTF-8`.
+    pub mod encoding {
+        use encoding_rs::Encoding;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            encoding: &&'static Encoding,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(encoding.name())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<&'static Encoding, D::Error> {
+            let label = String::deserialize(deserializer)?;
+            Encoding::for_label(label.as_bytes())
+                .ok_or_else(|| serde::de::Error::custom(format!("unknown encoding '{label}'")))
+        }
+    }
+
+    /// `Option<Regex>` as its pattern.
+    pub mod regex {
+        use regex::Regex;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(
+            regex: &Option<Regex>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match regex {
+                Some(regex) => serializer.serialize_some(regex.as_str()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Regex>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|pattern| super::super::validate_filter_regex(&pattern))
+                .transpose()
+                .map_err(serde::de::Error::custom)
+        }
     }
 }
 
@@ -182,13 +1420,106 @@ mod tests {
 
     #[test]
     fn test_validate_certificate_path() {
-        let path = String::from("test.p12");
-        assert!(validate_certificate_path(&path).is_ok());
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["test.p12", "test.PFX", "test", "test.txt"] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"").unwrap();
+            assert!(validate_certificate_path(&path).is_ok(), "{name}");
+        }
     }
 
     #[test]
     fn test_validate_certificate_path_invalid() {
-        let path = String::from("test");
-        assert!(validate_certificate_path(&path).is_err());
+        let dir = tempfile::tempdir().unwrap();
+        assert!(validate_certificate_path(&dir.path().join("test.p12")).is_err());
+        assert!(validate_certificate_path(&dir.path().join("test")).is_err());
+        // A directory is not a certificate file
+        assert!(validate_certificate_path(dir.path()).is_err());
+
+        let args = Args {
+            certificate_paths: vec![dir.path().join("test")],
+            force: true,
+            ..Default::default()
+        };
+        assert!(args.validate_certificate_paths().is_ok());
+
+        let args = Args {
+            certificate_paths: vec!["-".into()],
+            ..Default::default()
+        };
+        assert!(args.validate_certificate_paths().is_ok());
+        let args = Args {
+            certificate_paths: vec!["-".into(), "-".into()],
+            force: true,
+            ..Default::default()
+        };
+        assert!(args.validate_certificate_paths().is_err());
+    }
+
+    #[test]
+    fn test_validate_work_unit() {
+        assert_eq!(
+            validate_work_unit("2/4").unwrap(),
+            WorkUnit { index: 2, total: 4 }
+        );
+        assert!(validate_work_unit("4/4").is_err());
+        assert!(validate_work_unit("0/0").is_err());
+        assert!(validate_work_unit("1").is_err());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_config_round_trip() {
+        let defaults = Args::default();
+        let parsed = Args::try_parse_from([
+            "pkcs12cracker",
+            "-p",
+            "Acme#@{1,2}",
+            "--symbol",
+            "#=n",
+            "-c",
+            "a",
+            "--toggle-case",
+            "--filter-regex",
+            "^A",
+            "--require",
+            "digit",
+            "--work-unit",
+            "1/4",
+            "--session",
+            "run.json",
+            "--session-save-every",
+            "10s",
+            "cert.p12",
+        ])
+        .unwrap();
+        for args in [defaults, parsed] {
+            let text = args.to_toml().unwrap();
+            let read = Args::from_toml(&text).unwrap();
+            assert_eq!(format!("{read:?}"), format!("{args:?}"));
+            assert_eq!(read.to_toml().unwrap(), text);
+        }
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_config_errors_name_the_key() {
+        let args = Args::from_toml("min_length = 2\nmax_length = 4\n").unwrap();
+        assert_eq!((args.minumum_length, args.maximum_length), (2, 4));
+
+        let error = Args::from_toml("min_length = 2\nmax_lenght = 4\n").unwrap_err();
+        assert!(
+            error.to_string().contains("unknown field `max_lenght`"),
+            "{error}"
+        );
+        let error = Args::from_toml("threads = \"many\"").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Invalid config key 'threads'"),
+            "{error}"
+        );
+        let error = Args::from_toml("wordlist_encoding = \"klingon\"").unwrap_err();
+        assert!(error.to_string().contains("unknown encoding 'klingon'"));
     }
 }
@@ -0,0 +1,164 @@
+//! Record of every tested candidate for `--log-candidates`.
+//!
+//! Candidates are appended to per-thread buffers, so the workers do not
+//! contend for the file, and a buffer is only written out once it holds
+//! [`FLUSH_BYTES`]. [`CandidateLog::flush`] writes out what is left when a
+//! run ends or is interrupted.
+//!
+//! [`CandidatePreview`] keeps the first candidates of a run in memory
+//! instead, for `--show-candidates`.
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Size of a thread's buffer at which it is written to the file
+pub const FLUSH_BYTES: usize = 64 * 1024;
+
+/// Writes every tested candidate to a file, one per line.
+pub struct CandidateLog {
+    /// The log file
+    file: Mutex<File>,
+    /// One buffer per worker thread, plus one for threads outside the pool
+    buffers: Vec<Mutex<Vec<u8>>>,
+    /// Whether a write failure was reported, later failures are silent
+    failed: AtomicBool,
+}
+
+impl CandidateLog {
+    /// Creates the log file, truncating an existing one.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the log file
+    /// * `threads` - Number of worker threads of the session
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created
+    pub fn create(path: &Path, threads: usize) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create candidate log: {}", path.display()))?;
+        Ok(Self {
+            file: Mutex::new(file),
+            buffers: (0..=threads).map(|_| Mutex::new(Vec::new())).collect(),
+            failed: AtomicBool::new(false),
+        })
+    }
+
+    /// Records a tested candidate.
+    ///
+    /// Write failures are reported once and do not stop the run.
+    #[inline(always)]
+    pub fn record(&self, candidate: &str) {
+        let slot = rayon::current_thread_index()
+            .filter(|&index| index + 1 < self.buffers.len())
+            .unwrap_or(self.buffers.len() - 1);
+        let mut buffer = self.buffers[slot].lock().unwrap();
+        buffer.extend_from_slice(candidate.as_bytes());
+        buffer.push(b'\n');
+        if buffer.len() >= FLUSH_BYTES {
+            if let Err(e) = self.write(&mut buffer) {
+                if !self.failed.swap(true, Ordering::Relaxed) {
+                    eprintln!("{e:#}");
+                }
+            }
+        }
+    }
+
+    /// Writes the candidates buffered by every thread to the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn flush(&self) -> Result<()> {
+        for buffer in &self.buffers {
+            self.write(&mut buffer.lock().unwrap())?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>) -> Result<()> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        let result = self.file.lock().unwrap().write_all(buffer);
+        buffer.clear();
+        result.context("Failed to write candidate log")
+    }
+}
+
+/// The first candidates of a run, collected instead of being tested.
+pub struct CandidatePreview {
+    /// Number of candidates to collect
+    limit: usize,
+    /// Candidates collected so far, in the order they were generated
+    candidates: Mutex<Vec<String>>,
+}
+
+impl CandidatePreview {
+    /// Creates an empty preview.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Number of candidates to collect
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            candidates: Mutex::new(Vec::with_capacity(limit.min(1 << 16))),
+        }
+    }
+
+    /// Records a candidate, candidates beyond the limit are dropped.
+    pub fn record(&self, candidate: &str) {
+        let mut candidates = self.candidates.lock().unwrap();
+        if candidates.len() < self.limit {
+            candidates.push(candidate.to_string());
+        }
+    }
+
+    /// Returns `true` once the limit is reached.
+    pub fn is_full(&self) -> bool {
+        self.candidates.lock().unwrap().len() >= self.limit
+    }
+
+    /// Returns the collected candidates.
+    pub fn into_candidates(self) -> Vec<String> {
+        self.candidates.into_inner().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidate_log_threads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("candidates.txt");
+        let log = CandidateLog::create(&path, 4).unwrap();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        // Enough candidates for every thread to flush its buffer a few times
+        pool.install(|| {
+            use rayon::prelude::*;
+            (0..50_000)
+                .into_par_iter()
+                .for_each(|i| log.record(&format!("candidate{i}")));
+        });
+        log.record("outside the pool");
+        log.flush().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let mut lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 50_001);
+        lines.sort_unstable();
+        lines.dedup();
+        assert_eq!(lines.len(), 50_001);
+        assert!(lines.contains(&"outside the pool"));
+    }
+}
@@ -0,0 +1,385 @@
+//! Algorithms protecting a PKCS#12 file, for `--show-cert-info`.
+//!
+//! The MAC and the password-based encryption are declared unencrypted in the
+//! DER structure, together with their salt and key derivation iterations.
+//! The iterations decide how fast candidates can be tested: OpenSSL checks
+//! the MAC first, so every wrong candidate costs one key derivation.
+use crate::der::{
+    data_content, definite_lengths, expect, integer, octet_string, oid_string, Der, TAG_EXPLICIT_0,
+    TAG_INTEGER, TAG_OID, TAG_SEQUENCE,
+};
+use anyhow::{bail, Result};
+use std::fmt;
+
+/// Iteration counts below this make a weak key derivation.
+pub const WEAK_ITERATIONS: u32 = 1024;
+
+/// PKCS#7 `encryptedData` content, holding the certificates of most files
+const OID_ENCRYPTED_DATA: &str = "1.2.840.113549.1.7.6";
+/// PKCS#12 bag of an encrypted private key
+const OID_SHROUDED_KEY_BAG: &str = "1.2.840.113549.1.12.10.1.2";
+/// PBES2 encryption scheme of PKCS#5 v2
+const OID_PBES2: &str = "1.2.840.113549.1.5.13";
+/// PBKDF2 key derivation of PKCS#5 v2
+const OID_PBKDF2: &str = "1.2.840.113549.1.5.12";
+/// PBMAC1 message authentication of PKCS#5 v2, used by RFC 9579 MACs
+const OID_PBMAC1: &str = "1.2.840.113549.1.5.14";
+/// HMAC-SHA1, the default PRF of PBKDF2
+const OID_HMAC_SHA1: &str = "1.2.840.113549.2.7";
+
+/// Names of the digests, PRFs, ciphers and encryption schemes by OID.
+const ALGORITHMS: &[(&str, &str)] = &[
+    ("1.2.840.113549.2.5", "MD5"),
+    ("1.3.14.3.2.26", "SHA1"),
+    ("2.16.840.1.101.3.4.2.4", "SHA224"),
+    ("2.16.840.1.101.3.4.2.1", "SHA256"),
+    ("2.16.840.1.101.3.4.2.2", "SHA384"),
+    ("2.16.840.1.101.3.4.2.3", "SHA512"),
+    ("2.16.840.1.101.3.4.2.5", "SHA512-224"),
+    ("2.16.840.1.101.3.4.2.6", "SHA512-256"),
+    (OID_HMAC_SHA1, "HMAC-SHA1"),
+    ("1.2.840.113549.2.8", "HMAC-SHA224"),
+    ("1.2.840.113549.2.9", "HMAC-SHA256"),
+    ("1.2.840.113549.2.10", "HMAC-SHA384"),
+    ("1.2.840.113549.2.11", "HMAC-SHA512"),
+    ("1.2.840.113549.1.12.1.1", "pbeWithSHAAnd128BitRC4"),
+    ("1.2.840.113549.1.12.1.2", "pbeWithSHAAnd40BitRC4"),
+    ("1.2.840.113549.1.12.1.3", "pbeWithSHAAnd3-KeyTripleDES-CBC"),
+    ("1.2.840.113549.1.12.1.4", "pbeWithSHAAnd2-KeyTripleDES-CBC"),
+    ("1.2.840.113549.1.12.1.5", "pbeWithSHAAnd128BitRC2-CBC"),
+    ("1.2.840.113549.1.12.1.6", "pbeWithSHAAnd40BitRC2-CBC"),
+    ("1.2.840.113549.1.5.3", "pbeWithMD5AndDES-CBC"),
+    ("1.2.840.113549.1.5.10", "pbeWithSHA1AndDES-CBC"),
+    ("1.3.14.3.2.7", "DES-CBC"),
+    ("1.2.840.113549.3.2", "RC2-CBC"),
+    ("1.2.840.113549.3.7", "DES-EDE3-CBC"),
+    ("2.16.840.1.101.3.4.1.2", "AES-128-CBC"),
+    ("2.16.840.1.101.3.4.1.22", "AES-192-CBC"),
+    ("2.16.840.1.101.3.4.1.42", "AES-256-CBC"),
+];
+
+/// Returns the name of an algorithm, the OID itself for unknown ones.
+fn algorithm_name(oid: &str) -> String {
+    ALGORITHMS
+        .iter()
+        .find(|(known, _)| *known == oid)
+        .map_or_else(|| oid.to_string(), |(_, name)| name.to_string())
+}
+
+/// Algorithms and key derivation parameters of a PKCS#12 file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pkcs12Metadata {
+    /// MAC protecting the file, e.g. `HMAC-SHA256`, `none` without a MAC
+    pub mac_algorithm: String,
+    /// Encryption of the private key, or of the certificates if there is no
+    /// encrypted key, e.g. `PBES2 (PBKDF2 with HMAC-SHA256, AES-256-CBC)`;
+    /// `none` if nothing is encrypted
+    pub pbe_algorithm: String,
+    /// Key derivation iterations of the MAC, or of the encryption for files
+    /// without a MAC; 0 if neither is present
+    pub iteration_count: u32,
+    /// Key derivation salt, taken from the same place as `iteration_count`
+    pub salt: Vec<u8>,
+}
+
+impl Pkcs12Metadata {
+    /// Whether candidates are tested with fewer than [`WEAK_ITERATIONS`]
+    /// key derivation iterations.
+    pub fn is_weak(&self) -> bool {
+        self.iteration_count < WEAK_ITERATIONS
+    }
+}
+
+impl fmt::Display for Pkcs12Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let salt: String = self.salt.iter().map(|byte| format!("{byte:02x}")).collect();
+        writeln!(f, "MAC algorithm: {}", self.mac_algorithm)?;
+        writeln!(f, "Encryption algorithm: {}", self.pbe_algorithm)?;
+        writeln!(f, "Iterations: {}", self.iteration_count)?;
+        write!(f, "Salt: {salt} ({} bytes)", self.salt.len())
+    }
+}
+
+/// A password-based algorithm with its key derivation parameters.
+struct Kdf {
+    /// Name of the algorithm
+    algorithm: String,
+    /// Key derivation iterations
+    iterations: u32,
+    /// Key derivation salt
+    salt: Vec<u8>,
+}
+
+/// Reads the MAC and encryption algorithms of a DER encoded PKCS#12 file.
+///
+/// Works without the password: only the MAC data and the algorithm
+/// identifiers in front of the encrypted content are read.
+/// BER indefinite lengths, as in files exported by Windows and Java, are
+/// read as well.
+///
+/// # Arguments
+///
+/// * `der` - Contents of the certificate file
+///
+/// # Errors
+///
+/// Returns an error if the data is not a PKCS#12 file or its MAC data
+/// cannot be read
+pub fn parse_pkcs12_metadata(der: &[u8]) -> Result<Pkcs12Metadata> {
+    let der = definite_lengths(der).unwrap_or_default();
+    let mut fields = Der(expect(&der, TAG_SEQUENCE).unwrap_or_default());
+    let (Some((TAG_INTEGER, _)), Some((TAG_SEQUENCE, auth_safe))) = (fields.next(), fields.next())
+    else {
+        bail!("The data is not a DER encoded PKCS#12 file");
+    };
+    let mac = match fields.next() {
+        Some((TAG_SEQUENCE, mac_data)) => match mac(mac_data) {
+            Some(mac) => Some(mac),
+            None => bail!("The MAC data of the PKCS#12 file is malformed"),
+        },
+        _ => None,
+    };
+    let encryption = encryption(auth_safe);
+
+    let (iteration_count, salt) = match mac.as_ref().or(encryption.as_ref()) {
+        Some(kdf) => (kdf.iterations, kdf.salt.clone()),
+        None => (0, Vec::new()),
+    };
+    let name = |kdf: Option<Kdf>| kdf.map_or_else(|| "none".to_string(), |kdf| kdf.algorithm);
+    Ok(Pkcs12Metadata {
+        mac_algorithm: name(mac),
+        pbe_algorithm: name(encryption),
+        iteration_count,
+        salt,
+    })
+}
+
+/// Splits an AlgorithmIdentifier into its OID and the contents of its parameters.
+fn algorithm_identifier(algorithm: &[u8]) -> Option<(String, &[u8])> {
+    let mut fields = Der(algorithm);
+    let (TAG_OID, oid) = fields.next()? else {
+        return None;
+    };
+    let parameters = fields.next().map_or(&[][..], |(_, contents)| contents);
+    Some((oid_string(oid), parameters))
+}
+
+/// Reads the MacData of a PFX.
+fn mac(mac_data: &[u8]) -> Option<Kdf> {
+    let mut fields = Der(mac_data);
+    let (TAG_SEQUENCE, digest_info) = fields.next()? else {
+        return None;
+    };
+    let (tag, salt) = fields.next()?;
+    let salt = octet_string(tag, salt)?;
+    let iterations = match fields.next() {
+        Some((TAG_INTEGER, iterations)) => integer(iterations)?,
+        Some(_) => return None,
+        None => 1,
+    };
+    let (oid, parameters) = algorithm_identifier(expect(digest_info, TAG_SEQUENCE)?)?;
+    if oid == OID_PBMAC1 {
+        // The key derivation and the HMAC are declared in the parameters
+        let mut fields = Der(parameters);
+        let (TAG_SEQUENCE, kdf) = fields.next()? else {
+            return None;
+        };
+        let (TAG_SEQUENCE, scheme) = fields.next()? else {
+            return None;
+        };
+        let (scheme, _) = algorithm_identifier(scheme)?;
+        let pbkdf2 = pbkdf2(kdf)?;
+        return Some(Kdf {
+            algorithm: format!("PBMAC1 ({}, {})", pbkdf2.algorithm, algorithm_name(&scheme)),
+            ..pbkdf2
+        });
+    }
+    Some(Kdf {
+        algorithm: format!("HMAC-{}", algorithm_name(&oid)),
+        iterations,
+        salt,
+    })
+}
+
+/// Reads a PBKDF2 AlgorithmIdentifier, named after its PRF.
+fn pbkdf2(algorithm: &[u8]) -> Option<Kdf> {
+    let (oid, parameters) = algorithm_identifier(algorithm)?;
+    if oid != OID_PBKDF2 {
+        return None;
+    }
+    let mut fields = Der(parameters);
+    let (tag, salt) = fields.next()?;
+    let salt = octet_string(tag, salt)?;
+    let (TAG_INTEGER, iterations) = fields.next()? else {
+        return None;
+    };
+    // An optional key length precedes the PRF, which defaults to HMAC-SHA1
+    let prf = fields
+        .find(|&(tag, _)| tag == TAG_SEQUENCE)
+        .and_then(|(_, prf)| algorithm_identifier(prf))
+        .map_or_else(|| OID_HMAC_SHA1.to_string(), |(oid, _)| oid);
+    Some(Kdf {
+        algorithm: format!("PBKDF2 with {}", algorithm_name(&prf)),
+        iterations: integer(iterations)?,
+        salt,
+    })
+}
+
+/// Reads a password-based encryption AlgorithmIdentifier.
+///
+/// PBES2 is named after its key derivation and cipher, the PKCS#12 and
+/// PKCS#5 v1 schemes by their own name.
+fn pbe(algorithm: &[u8]) -> Option<Kdf> {
+    let (oid, parameters) = algorithm_identifier(algorithm)?;
+    let mut fields = Der(parameters);
+    if oid == OID_PBES2 {
+        let (TAG_SEQUENCE, kdf) = fields.next()? else {
+            return None;
+        };
+        let (TAG_SEQUENCE, cipher) = fields.next()? else {
+            return None;
+        };
+        let (cipher, _) = algorithm_identifier(cipher)?;
+        let pbkdf2 = pbkdf2(kdf)?;
+        return Some(Kdf {
+            algorithm: format!("PBES2 ({}, {})", pbkdf2.algorithm, algorithm_name(&cipher)),
+            ..pbkdf2
+        });
+    }
+    let (tag, salt) = fields.next()?;
+    let salt = octet_string(tag, salt)?;
+    let (TAG_INTEGER, iterations) = fields.next()? else {
+        return None;
+    };
+    Some(Kdf {
+        algorithm: algorithm_name(&oid),
+        iterations: integer(iterations)?,
+        salt,
+    })
+}
+
+/// Finds the encryption of the private key, or of the certificates if no
+/// unencrypted bag holds an encrypted key.
+fn encryption(auth_safe: &[u8]) -> Option<Kdf> {
+    let content_infos = definite_lengths(&data_content(auth_safe)?)?;
+    let mut certificates = None;
+    for (_, content_info) in Der(expect(&content_infos, TAG_SEQUENCE)?) {
+        if let Some(safe_contents) = data_content(content_info) {
+            let safe_contents = definite_lengths(&safe_contents).unwrap_or_default();
+            let key = Der(expect(&safe_contents, TAG_SEQUENCE).unwrap_or_default())
+                .find_map(|(_, bag)| shrouded_key(bag));
+            if key.is_some() {
+                return key;
+            }
+        } else if certificates.is_none() {
+            certificates = encrypted_data(content_info);
+        }
+    }
+    certificates
+}
+
+/// Reads the encryption of a PKCS#8 shrouded key bag.
+fn shrouded_key(bag: &[u8]) -> Option<Kdf> {
+    let mut fields = Der(bag);
+    let (TAG_OID, bag_id) = fields.next()? else {
+        return None;
+    };
+    let (TAG_EXPLICIT_0, value) = fields.next()? else {
+        return None;
+    };
+    if oid_string(bag_id) != OID_SHROUDED_KEY_BAG {
+        return None;
+    }
+    // EncryptedPrivateKeyInfo: encryptionAlgorithm, encryptedData
+    pbe(expect(expect(value, TAG_SEQUENCE)?, TAG_SEQUENCE)?)
+}
+
+/// Reads the encryption of a ContentInfo of type `encryptedData`.
+fn encrypted_data(content_info: &[u8]) -> Option<Kdf> {
+    let mut fields = Der(content_info);
+    let (TAG_OID, content_type) = fields.next()? else {
+        return None;
+    };
+    let (TAG_EXPLICIT_0, explicit) = fields.next()? else {
+        return None;
+    };
+    if oid_string(content_type) != OID_ENCRYPTED_DATA {
+        return None;
+    }
+    // EncryptedData: version, EncryptedContentInfo
+    let (_, encrypted_content_info) = Der(expect(explicit, TAG_SEQUENCE)?).nth(1)?;
+    // EncryptedContentInfo: contentType, contentEncryptionAlgorithm, encryptedContent
+    let (TAG_SEQUENCE, algorithm) = Der(encrypted_content_info).nth(1)? else {
+        return None;
+    };
+    pbe(algorithm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &str) -> Vec<u8> {
+        std::fs::read(format!("{}/{path}", env!("CARGO_MANIFEST_DIR"))).unwrap()
+    }
+
+    #[test]
+    fn test_fixtures_metadata() {
+        let fixtures = std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "p12"));
+        let mut count = 0;
+        for path in fixtures {
+            let metadata = parse_pkcs12_metadata(&std::fs::read(&path).unwrap()).unwrap();
+            assert_eq!(
+                metadata,
+                Pkcs12Metadata {
+                    mac_algorithm: "HMAC-SHA256".to_string(),
+                    pbe_algorithm: "PBES2 (PBKDF2 with HMAC-SHA256, AES-256-CBC)".to_string(),
+                    iteration_count: 2048,
+                    salt: metadata.salt.clone(),
+                },
+                "{}",
+                path.display()
+            );
+            assert_eq!(metadata.salt.len(), 8, "{}", path.display());
+            assert!(!metadata.is_weak());
+            count += 1;
+        }
+        assert_eq!(count, 20);
+    }
+
+    #[test]
+    fn test_legacy_metadata() {
+        let metadata = parse_pkcs12_metadata(&read("benchmarks/openwall.pfx")).unwrap();
+        assert_eq!(metadata.mac_algorithm, "HMAC-SHA1");
+        assert_eq!(metadata.pbe_algorithm, "pbeWithSHAAnd3-KeyTripleDES-CBC");
+        assert_eq!(metadata.iteration_count, 2048);
+
+        // Exported by Windows with indefinite lengths and a single MAC iteration
+        let metadata = parse_pkcs12_metadata(&read("benchmarks/exportado.p12")).unwrap();
+        assert_eq!(metadata.mac_algorithm, "HMAC-SHA1");
+        assert_eq!(metadata.pbe_algorithm, "pbeWithSHAAnd3-KeyTripleDES-CBC");
+        assert_eq!((metadata.iteration_count, metadata.salt.len()), (1, 16));
+        assert!(metadata.is_weak());
+
+        // Without a MAC the parameters of the key encryption are shown
+        let metadata = parse_pkcs12_metadata(&read("benchmarks/certificate.p12")).unwrap();
+        assert_eq!(metadata.mac_algorithm, "none");
+        assert_eq!(
+            metadata.pbe_algorithm,
+            "PBES2 (PBKDF2 with HMAC-SHA256, AES-256-CBC)"
+        );
+        assert_eq!(metadata.iteration_count, 2048);
+    }
+
+    #[test]
+    fn test_invalid_metadata() {
+        assert!(parse_pkcs12_metadata(b"not a certificate").is_err());
+        assert!(parse_pkcs12_metadata(&[]).is_err());
+        // SEQUENCE { INTEGER 3, SEQUENCE {}, SEQUENCE {} }: malformed MAC data
+        let pfx = [0x30, 0x07, 0x02, 0x01, 0x03, 0x30, 0x00, 0x30, 0x00];
+        assert!(parse_pkcs12_metadata(&pfx).is_err());
+    }
+}
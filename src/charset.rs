@@ -4,7 +4,9 @@
 //! custom character sets for password cracking.
 //!
 use crate::args::Args;
-use anyhow::Result;
+use crate::types::PkcsError;
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
 
 /// Lowercase letters from a to z
 pub static LOWER_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz";
@@ -15,6 +17,147 @@ pub static DIGITS: &str = "0123456789";
 /// Common special characters used in passwords
 pub static SPECIAL_CHARS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~ ";
 
+/// All printable ASCII characters (0x20-0x7E), including space
+pub fn printable_ascii() -> String {
+    (0x20u8..=0x7E).map(char::from).collect()
+}
+
+/// Lowercase hexadecimal digits
+pub static HEX_DIGITS: &str = "0123456789abcdef";
+
+/// A named character set accepted by `--charset`.
+#[derive(Debug)]
+pub struct CharsetPreset {
+    /// Single-letter specifier, if the preset has one
+    pub letter: Option<char>,
+    /// Long-form name
+    pub name: &'static str,
+    /// Description shown in the help text
+    pub description: &'static str,
+    /// Produces the characters of the preset
+    pub chars: fn() -> String,
+}
+
+/// All character sets accepted by `--charset`.
+pub static CHARSET_PRESETS: &[CharsetPreset] = &[
+    CharsetPreset {
+        letter: Some('a'),
+        name: "lower",
+        description: "lowercase letters (a-z)",
+        chars: || LOWER_ALPHABET.to_string(),
+    },
+    CharsetPreset {
+        letter: Some('A'),
+        name: "upper",
+        description: "uppercase letters (A-Z)",
+        chars: || UPPER_ALPHABET.to_string(),
+    },
+    CharsetPreset {
+        letter: Some('n'),
+        name: "digits",
+        description: "digits (0-9)",
+        chars: || DIGITS.to_string(),
+    },
+    CharsetPreset {
+        letter: Some('s'),
+        name: "special",
+        description: "special chars (!@#$%^&*...)",
+        chars: || SPECIAL_CHARS.to_string(),
+    },
+    CharsetPreset {
+        letter: Some('p'),
+        name: "printable",
+        description: "all printable ASCII (0x20-0x7E)",
+        chars: printable_ascii,
+    },
+    CharsetPreset {
+        letter: Some('x'),
+        name: "all",
+        description: "lower, upper, digits and special",
+        chars: || [LOWER_ALPHABET, UPPER_ALPHABET, DIGITS, SPECIAL_CHARS].concat(),
+    },
+    CharsetPreset {
+        letter: None,
+        name: "hex",
+        description: "lowercase hex digits (0-9a-f)",
+        chars: || HEX_DIGITS.to_string(),
+    },
+];
+
+/// Generates the `--charset` help text from [`CHARSET_PRESETS`].
+pub fn charset_help() -> String {
+    let mut help = String::from(
+        "Specify one or more character sets for password generation, either as\n\
+         single letters or as comma-separated names (unique prefixes are accepted):\n",
+    );
+    for preset in CHARSET_PRESETS {
+        let letter = preset.letter.map_or(" ".to_string(), |c| c.to_string());
+        help.push_str(&format!(
+            "{letter}  {:<10} - {}\n",
+            preset.name, preset.description
+        ));
+    }
+    help.push_str(
+        "'special', 'printable' and 'all' include space, use --no-space to exclude it\n\
+         Example: 'aAn' or 'lower,upper,digits' for alphanumeric passwords",
+    );
+    help
+}
+
+/// Resolves a `--charset` value into the presets it names.
+///
+/// Each comma-separated token is matched, in order, as an exact long name,
+/// a run of single-letter specifiers, or a unique prefix of a long name.
+///
+/// # Errors
+///
+/// Returns an error listing the valid names if a token is unknown or ambiguous.
+pub fn resolve_charset_spec(spec: &str) -> Result<Vec<&'static CharsetPreset>> {
+    let valid_names = || {
+        CHARSET_PRESETS
+            .iter()
+            .map(|p| match p.letter {
+                Some(letter) => format!("{} ({letter})", p.name),
+                None => p.name.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let by_letter = |c: char| CHARSET_PRESETS.iter().find(|p| p.letter == Some(c));
+
+    let mut presets = Vec::new();
+    for token in spec.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if let Some(preset) = CHARSET_PRESETS.iter().find(|p| p.name == token) {
+            presets.push(preset);
+        } else if let Some(letters) = token.chars().map(by_letter).collect::<Option<Vec<_>>>() {
+            presets.extend(letters);
+        } else {
+            let matches: Vec<_> = CHARSET_PRESETS
+                .iter()
+                .filter(|p| p.name.starts_with(token))
+                .collect();
+            match matches.as_slice() {
+                [preset] => presets.push(preset),
+                [] => {
+                    return Err(PkcsError::InvalidCharset(format!(
+                        "Unknown character set '{token}'. Valid names: {}",
+                        valid_names()
+                    ))
+                    .into())
+                }
+                _ => {
+                    return Err(PkcsError::InvalidCharset(format!(
+                        "Ambiguous character set '{token}'. Valid names: {}",
+                        valid_names()
+                    ))
+                    .into())
+                }
+            }
+        }
+    }
+    Ok(presets)
+}
+
 /// Builds a character set based on provided arguments.
 ///
 /// # Arguments
@@ -24,10 +167,13 @@ pub static SPECIAL_CHARS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~ ";
 /// # Returns
 ///
 /// Returns a `Result` containing the constructed character set string.
+/// Characters are kept in the order they first appear, so overlapping
+/// presets and custom characters never produce duplicate candidates.
 ///
 /// # Example
 ///
 /// ```no_run
+/// use pkcs12cracker::charset::build_charset;
 /// use pkcs12cracker::Args;
 /// let args = Args {
 ///     char_sets: Some("aA".to_string()), // lowercase and uppercase
@@ -40,33 +186,145 @@ pub static SPECIAL_CHARS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~ ";
 pub fn build_charset(args: &Args) -> Result<String> {
     let mut charset = if let Some(char_sets) = &args.char_sets {
         let mut chars = String::with_capacity(128);
-        for c in char_sets.chars() {
-            match c {
-                'a' => chars.push_str(LOWER_ALPHABET),
-                'A' => chars.push_str(UPPER_ALPHABET),
-                'n' => chars.push_str(DIGITS),
-                's' => chars.push_str(SPECIAL_CHARS),
-                'x' => {
-                    chars.push_str(LOWER_ALPHABET);
-                    chars.push_str(UPPER_ALPHABET);
-                    chars.push_str(DIGITS);
-                    chars.push_str(SPECIAL_CHARS);
-                }
-                _ => (),
-            }
+        for preset in resolve_charset_spec(char_sets)? {
+            chars.push_str(&(preset.chars)());
         }
         chars
-    } else {
+    } else if args.charset_range.is_empty() {
         LOWER_ALPHABET.to_string()
+    } else {
+        String::new()
     };
 
+    if args.no_space {
+        charset.retain(|c| c != ' ');
+    }
+
+    for range in &args.charset_range {
+        charset.extend(parse_codepoint_range(range)?);
+    }
+
     if let Some(specific_chars) = &args.specific_chars {
-        charset.push_str(specific_chars);
+        charset.push_str(&unescape_chars(specific_chars)?);
     }
 
+    let mut seen = HashSet::new();
+    charset.retain(|c| seen.insert(c));
     Ok(charset)
 }
 
+/// Expands a codepoint range like `U+0400-U+04FF` into its characters.
+///
+/// A single codepoint (`U+00E9`) is accepted as a range of one. Surrogates
+/// and Unicode noncharacters are skipped.
+///
+/// # Errors
+///
+/// Returns an error if the range is malformed, reversed, or out of the Unicode range.
+pub fn parse_codepoint_range(range: &str) -> Result<Vec<char>> {
+    let parse = |codepoint: &str| -> Result<u32> {
+        let hex = codepoint
+            .trim()
+            .strip_prefix("U+")
+            .or_else(|| codepoint.trim().strip_prefix("u+"))
+            .with_context(|| format!("Invalid codepoint '{codepoint}': expected 'U+XXXX'"))?;
+        let value = u32::from_str_radix(hex, 16)
+            .with_context(|| format!("Invalid codepoint '{codepoint}': not a hex number"))?;
+        if value > char::MAX as u32 {
+            bail!("Invalid codepoint '{codepoint}': beyond U+10FFFF");
+        }
+        Ok(value)
+    };
+
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => {
+            let codepoint = parse(range)?;
+            (codepoint, codepoint)
+        }
+    };
+    if start > end {
+        bail!("Invalid codepoint range '{range}': start is greater than end");
+    }
+
+    Ok((start..=end)
+        .filter(|&c| !is_noncharacter(c))
+        .filter_map(char::from_u32)
+        .collect())
+}
+
+/// Checks whether a codepoint is a Unicode noncharacter.
+fn is_noncharacter(codepoint: u32) -> bool {
+    (0xFDD0..=0xFDEF).contains(&codepoint) || codepoint & 0xFFFE == 0xFFFE
+}
+
+/// Expands escape sequences in a user-supplied character list.
+///
+/// Supported escapes are `\t`, `\n`, `\r`, `\\`, `\xNN` (two hex digits) and
+/// `\u{XXXX}` (one to six hex digits). Any other character is kept as is.
+///
+/// # Errors
+///
+/// Returns an error if an escape sequence is unknown, truncated, or does not
+/// describe a valid Unicode scalar value.
+pub fn unescape_chars(input: &str) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => output.push('\t'),
+            Some('n') => output.push('\n'),
+            Some('r') => output.push('\r'),
+            Some('\\') => output.push('\\'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if hex.len() != 2 {
+                    bail!("Invalid escape '\\x{hex}': expected two hex digits");
+                }
+                let code = u8::from_str_radix(&hex, 16)
+                    .with_context(|| format!("Invalid escape '\\x{hex}': not a hex number"))?;
+                output.push(char::from(code));
+            }
+            Some('u') => {
+                if chars.next() != Some('{') {
+                    bail!("Invalid escape '\\u': expected '{{' after '\\u'");
+                }
+                let mut hex = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    hex.push(c);
+                }
+                if !closed {
+                    bail!("Invalid escape '\\u{{{hex}': missing closing '}}'");
+                }
+                if hex.is_empty() || hex.len() > 6 {
+                    bail!("Invalid escape '\\u{{{hex}}}': expected 1 to 6 hex digits");
+                }
+                let code = u32::from_str_radix(&hex, 16)
+                    .with_context(|| format!("Invalid escape '\\u{{{hex}}}': not a hex number"))?;
+                match char::from_u32(code) {
+                    Some(c) => output.push(c),
+                    None => bail!("Invalid escape '\\u{{{hex}}}': not a valid Unicode character"),
+                }
+            }
+            Some(other) => bail!("Unknown escape sequence '\\{other}'"),
+            None => bail!("Trailing backslash in character list"),
+        }
+    }
+
+    Ok(output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,4 +385,232 @@ mod tests {
             "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZäöüß"
         );
     }
+
+    #[test]
+    fn test_build_charset_unicode_escape() {
+        let args = Args {
+            char_sets: Some("n".to_string()),
+            specific_chars: Some("\\u{00E9}".to_string()),
+            ..Default::default()
+        };
+        let charset = build_charset(&args).unwrap();
+        assert_eq!(charset, "0123456789é");
+    }
+
+    #[test]
+    fn test_unescape_chars() {
+        assert_eq!(unescape_chars("a\\\\b").unwrap(), "a\\b");
+        assert_eq!(unescape_chars("\\t\\n\\r").unwrap(), "\t\n\r");
+        assert_eq!(unescape_chars("\\xA0\\x41").unwrap(), "\u{a0}A");
+        assert_eq!(unescape_chars("\\u{1F600}").unwrap(), "😀");
+    }
+
+    #[test]
+    fn test_unescape_chars_malformed() {
+        assert!(unescape_chars("\\").is_err());
+        assert!(unescape_chars("\\q").is_err());
+        assert!(unescape_chars("\\x4").is_err());
+        assert!(unescape_chars("\\xZZ").is_err());
+        assert!(unescape_chars("\\u00E9").is_err());
+        assert!(unescape_chars("\\u{D800}").is_err());
+        assert!(unescape_chars("\\u{}").is_err());
+        assert!(unescape_chars("\\u{00E9").is_err());
+    }
+
+    #[test]
+    fn test_build_charset_printable_ascii() {
+        let args = Args {
+            char_sets: Some("p".to_string()),
+            ..Default::default()
+        };
+        let charset = build_charset(&args).unwrap();
+        assert_eq!(charset.chars().count(), 95);
+        assert!(charset.contains('`'));
+        assert!(charset.contains('~'));
+        assert!(charset.contains(' '));
+    }
+
+    #[test]
+    fn test_build_charset_no_space() {
+        let with_space = Args {
+            char_sets: Some("s".to_string()),
+            ..Default::default()
+        };
+        let without_space = Args {
+            no_space: true,
+            ..with_space.clone()
+        };
+        assert_eq!(build_charset(&with_space).unwrap().chars().count(), 33);
+        let charset = build_charset(&without_space).unwrap();
+        assert_eq!(charset.chars().count(), 32);
+        assert!(!charset.contains(' '));
+    }
+
+    #[test]
+    fn test_build_charset_no_space_keeps_custom_space() {
+        let args = Args {
+            char_sets: Some("p".to_string()),
+            specific_chars: Some(" ".to_string()),
+            no_space: true,
+            ..Default::default()
+        };
+        let charset = build_charset(&args).unwrap();
+        assert_eq!(charset.chars().count(), 95);
+        assert!(charset.ends_with(' '));
+    }
+
+    #[test]
+    fn test_build_charset_range_crossing_plane() {
+        let args = Args {
+            charset_range: vec!["U+FFF0-U+10010".to_string()],
+            ..Default::default()
+        };
+        let charset: Vec<char> = build_charset(&args).unwrap().chars().collect();
+        assert_eq!(charset.len(), 14 + 17);
+        assert_eq!(charset[0], '\u{FFF0}');
+        assert_eq!(charset[13], '\u{FFFD}');
+        assert_eq!(charset[14], '\u{10000}');
+        assert!(!charset.contains(&'\u{FFFE}'));
+        assert!(!charset.contains(&'\u{FFFF}'));
+    }
+
+    #[test]
+    fn test_build_charset_range_combined() {
+        let args = Args {
+            char_sets: Some("n".to_string()),
+            charset_range: vec!["U+0410-U+0412".to_string(), "U+3042".to_string()],
+            ..Default::default()
+        };
+        let charset = build_charset(&args).unwrap();
+        assert_eq!(charset, "0123456789АБВあ");
+    }
+
+    #[test]
+    fn test_parse_codepoint_range_skips_surrogates() {
+        let chars = parse_codepoint_range("U+D7FF-U+E000").unwrap();
+        assert_eq!(chars, vec!['\u{D7FF}', '\u{E000}']);
+    }
+
+    #[test]
+    fn test_parse_codepoint_range_invalid() {
+        assert!(parse_codepoint_range("0400-04FF").is_err());
+        assert!(parse_codepoint_range("U+04FF-U+0400").is_err());
+        assert!(parse_codepoint_range("U+110000").is_err());
+        assert!(parse_codepoint_range("U+ZZZZ").is_err());
+    }
+
+    #[test]
+    fn test_build_charset_long_names() {
+        let args = Args {
+            char_sets: Some("lower,upper,digits".to_string()),
+            ..Default::default()
+        };
+        let letters = Args {
+            char_sets: Some("aAn".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            build_charset(&args).unwrap(),
+            build_charset(&letters).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_build_charset_hex() {
+        let args = Args {
+            char_sets: Some("hex".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(build_charset(&args).unwrap(), HEX_DIGITS);
+    }
+
+    #[test]
+    fn test_resolve_charset_spec_aliases() {
+        let names = |spec: &str| -> Vec<&str> {
+            resolve_charset_spec(spec)
+                .unwrap()
+                .iter()
+                .map(|p| p.name)
+                .collect()
+        };
+        assert_eq!(names("all"), vec!["all"]);
+        assert_eq!(names("x"), vec!["all"]);
+        assert_eq!(names("d"), vec!["digits"]);
+        assert_eq!(names("aA, printable"), vec!["lower", "upper", "printable"]);
+        assert_eq!(names("spec,h"), vec!["special", "hex"]);
+    }
+
+    #[test]
+    fn test_resolve_charset_spec_unknown() {
+        let err = resolve_charset_spec("lower,cyrillic").unwrap_err();
+        assert!(err.to_string().contains("'cyrillic'"));
+        assert!(err.to_string().contains("digits (n)"));
+        assert!(matches!(
+            err.downcast_ref::<PkcsError>(),
+            Some(PkcsError::InvalidCharset(_))
+        ));
+        assert!(resolve_charset_spec("aq").is_err());
+    }
+
+    #[test]
+    fn test_charset_help_lists_all_presets() {
+        let help = charset_help();
+        for preset in CHARSET_PRESETS {
+            assert!(help.contains(preset.name));
+        }
+    }
+
+    #[test]
+    fn test_build_charset_drops_duplicates() {
+        let args = Args {
+            char_sets: Some("n,hex".to_string()),
+            specific_chars: Some("a0@@".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(build_charset(&args).unwrap(), "0123456789abcdef@");
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// `--charset` specs made of preset letters and long names
+        fn charset_spec() -> impl Strategy<Value = Option<String>> {
+            let token = prop_oneof![
+                "[aAnspx]{1,4}",
+                Just("lower".to_string()),
+                Just("digits".to_string()),
+                Just("hex".to_string()),
+                Just("all".to_string()),
+            ];
+            proptest::option::of(prop::collection::vec(token, 1..4).prop_map(|t| t.join(",")))
+        }
+
+        /// `--charset-range` values of up to 64 codepoints
+        fn codepoint_range() -> impl Strategy<Value = String> {
+            (0x20u32..0x3000, 0u32..64)
+                .prop_map(|(start, len)| format!("U+{start:04X}-U+{:04X}", start + len))
+        }
+
+        proptest! {
+            #[test]
+            fn build_charset_never_returns_duplicates(
+                char_sets in charset_spec(),
+                specific_chars in proptest::option::of("[^\\\\]{0,12}"),
+                charset_range in prop::collection::vec(codepoint_range(), 0..3),
+                no_space in any::<bool>(),
+            ) {
+                let args = Args {
+                    char_sets,
+                    specific_chars,
+                    charset_range,
+                    no_space,
+                    ..Default::default()
+                };
+                let charset = build_charset(&args).unwrap();
+                let unique: HashSet<char> = charset.chars().collect();
+                prop_assert_eq!(unique.len(), charset.chars().count());
+            }
+        }
+    }
 }
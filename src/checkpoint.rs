@@ -0,0 +1,756 @@
+//! Brute force and pattern checkpoints and crash-safe progress writes.
+//!
+//! A checkpoint records how far a brute force or pattern run got so that
+//! `--restore` can continue it later. Chunks are tested in parallel and finish out of order,
+//! so progress is tracked as a low-water mark below which every index of the
+//! current length has been tested, plus the ranges beyond it that already
+//! completed.
+//!
+//! Session and progress files are written with [`write_atomic`], which keeps
+//! the previous generation as a `.bak` file, and read with [`load_json`],
+//! which falls back to it if the file is missing or damaged.
+use crate::crackers::bruteforce::LengthOrder;
+use crate::types::{PkcsError, WorkUnit};
+use anyhow::{bail, Context, Result};
+use openssl::pkcs12::Pkcs12;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Minimum time between two checkpoint writes
+pub const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Version of the session and progress file format
+///
+/// Version 3 numbers pattern combinations with the first unknown position
+/// most significant, earlier versions used the reverse for 4 or more.
+pub const FORMAT_VERSION: u32 = 3;
+
+/// First format version whose pattern positions are in the current order
+pub const PATTERN_ORDER_VERSION: u32 = 3;
+
+/// Format version of files written before the version field existed
+fn legacy_version() -> u32 {
+    1
+}
+
+/// How often progress is written to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum WriteCadence {
+    /// Once this much time passed since the last write
+    Interval(Duration),
+    /// Once this many candidates were tested since the last write
+    Attempts(usize),
+}
+
+impl Default for WriteCadence {
+    fn default() -> Self {
+        Self::Interval(CHECKPOINT_INTERVAL)
+    }
+}
+
+impl FromStr for WriteCadence {
+    type Err = String;
+
+    /// Parses a number of attempts (`100000`) or a duration (`30s`, `5m`, `1h`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!("invalid cadence '{s}', expected a number of attempts or a duration like 30s, 5m or 1h")
+        };
+        let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+            Some(split) => s.split_at(split),
+            None => (s, ""),
+        };
+        let value: u64 = number.parse().map_err(|_| invalid())?;
+        if value == 0 {
+            return Err(format!("cadence '{s}' must be positive"));
+        }
+        let seconds = match unit {
+            "" => return Ok(Self::Attempts(value as usize)),
+            "s" => value,
+            "m" => value.saturating_mul(60),
+            "h" => value.saturating_mul(3600),
+            _ => return Err(invalid()),
+        };
+        Ok(Self::Interval(Duration::from_secs(seconds)))
+    }
+}
+
+impl fmt::Display for WriteCadence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Interval(interval) => write!(f, "{}s", interval.as_secs()),
+            Self::Attempts(attempts) => write!(f, "{attempts}"),
+        }
+    }
+}
+
+/// Returns the backup of a file written with [`write_atomic`], `<path>.bak`.
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    PathBuf::from(backup)
+}
+
+/// Writes a file so that a crash or power loss never corrupts it.
+///
+/// The contents are written to a temporary file next to `path` and synced
+/// to disk. The current file then becomes the `.bak` backup and the
+/// temporary file is renamed into its place, so at every moment either the
+/// new file or the previous generation is complete on disk.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    // Unique per process, so concurrent runs never rename each other's file
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    let temp = PathBuf::from(temp);
+
+    let mut file =
+        File::create(&temp).with_context(|| format!("Failed to write {}", temp.display()))?;
+    file.write_all(contents)
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("Failed to write {}", temp.display()))?;
+    match fs::rename(path, backup_path(path)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("Failed to back up {}", path.display()))
+        }
+        _ => {}
+    }
+    fs::rename(&temp, path).with_context(|| format!("Failed to write {}", path.display()))?;
+    // Persist the renames themselves
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        File::open(dir).and_then(|dir| dir.sync_all()).ok();
+    }
+    Ok(())
+}
+
+/// Reads a JSON file written with [`write_atomic`].
+///
+/// If the file is missing or cannot be parsed, e.g. because it was cut
+/// short, its `.bak` backup is read instead.
+///
+/// # Arguments
+///
+/// * `path` - Path to the file
+/// * `kind` - Description of the file for messages, e.g. "session file"
+///
+/// # Returns
+///
+/// Returns `None` if neither the file nor its backup exists.
+///
+/// # Errors
+///
+/// Returns an error if the file is damaged and has no usable backup, or was
+/// written by a newer format version
+pub fn load_json<T: DeserializeOwned>(path: &Path, kind: &str) -> Result<Option<T>> {
+    let primary = read_versioned(path, kind);
+    if matches!(primary, Ok(Some(_))) {
+        return primary;
+    }
+    let backup = backup_path(path);
+    match read_versioned(&backup, kind) {
+        Ok(Some(value)) => {
+            match &primary {
+                Err(e) => eprintln!("Warning: {e:#}, using the backup {}", backup.display()),
+                _ => eprintln!(
+                    "Warning: {kind} {} is missing, using the backup {}",
+                    path.display(),
+                    backup.display()
+                ),
+            }
+            Ok(Some(value))
+        }
+        _ => primary,
+    }
+}
+
+/// Reads and parses a JSON file, checking its format version.
+fn read_versioned<T: DeserializeOwned>(path: &Path, kind: &str) -> Result<Option<T>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read {kind}: {}", path.display()))
+        }
+    };
+    // Only the version is read first, the rest may not be in a format this build knows
+    #[derive(Deserialize)]
+    struct Versioned {
+        #[serde(default = "legacy_version")]
+        version: u32,
+    }
+    let Versioned { version } = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {kind}: {}", path.display()))?;
+    if version > FORMAT_VERSION {
+        bail!(
+            "{kind} {} has format version {version}, this build supports up to {FORMAT_VERSION}",
+            path.display()
+        );
+    }
+    serde_json::from_str(&content)
+        .map(Some)
+        .with_context(|| format!("Failed to parse {kind}: {}", path.display()))
+}
+
+/// Progress of a brute force or pattern run, as stored in the session file.
+///
+/// Pattern runs number the combinations of all patterns consecutively and
+/// leave the lengths at 0.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Format version, see [`FORMAT_VERSION`]
+    #[serde(default = "legacy_version")]
+    pub version: u32,
+    /// Characters used in combinations
+    pub charset: String,
+    /// Minimum password length
+    pub min_len: u8,
+    /// Maximum password length
+    pub max_len: u8,
+    /// Seed of `--random-order`, if any
+    pub random_seed: Option<u64>,
+    /// Share of the search space processed by the run
+    pub work_unit: WorkUnit,
+    /// Whether the run walks the keyspace backwards, restored with the session
+    #[serde(default)]
+    pub reverse: bool,
+    /// Order of the lengths, restored with the session, `None` follows `reverse`
+    #[serde(default)]
+    pub length_order: Option<LengthOrder>,
+    /// `--require` policy candidates were filtered with, empty without one
+    #[serde(default)]
+    pub policy: String,
+    /// Whether candidates never repeat a character (`--no-repeats`)
+    #[serde(default)]
+    pub no_repeats: bool,
+    /// Patterns of a pattern run in the order they are tried, empty for brute force
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// Characters of every symbol of a pattern run, the pattern symbol included
+    #[serde(default)]
+    pub symbols: BTreeMap<char, String>,
+    /// Symbols of a pattern run whose positions all take the same character
+    #[serde(default)]
+    pub linked: Vec<char>,
+    /// Years of the year tokens of a pattern run, `None` without year tokens
+    #[serde(default)]
+    pub years: Option<RangeInclusive<u16>>,
+    /// SHA-256 of the certificate, hex encoded
+    pub fingerprint: String,
+    /// Password length being tested, all lengths before it are done
+    pub length: u8,
+    /// Every position of `length` in traversal order below this one has been tested
+    pub low_water: u128,
+    /// Ranges of positions of `length` above `low_water` that have been tested
+    pub completed: Vec<Range<u128>>,
+}
+
+impl Checkpoint {
+    /// Loads a checkpoint saved with [`Checkpoint::save`].
+    ///
+    /// Falls back to the previous generation if the file is damaged, see [`load_json`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if neither the file nor its backup is a valid checkpoint
+    pub fn load(path: &Path) -> Result<Self> {
+        load_json(path, "session file")?
+            .with_context(|| format!("Session file not found: {}", path.display()))
+    }
+
+    /// Writes the checkpoint as JSON, see [`write_atomic`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize session")?;
+        write_atomic(path, json.as_bytes())
+            .with_context(|| format!("Failed to write session file: {}", path.display()))
+    }
+
+    /// Checks that a saved checkpoint belongs to the same run configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - Checkpoint describing the run about to start
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PkcsError::CheckpointMismatch`] with a context naming the
+    /// first setting that differs
+    pub fn ensure_matches(&self, current: &Checkpoint) -> Result<()> {
+        match self.mismatch(current) {
+            Some(reason) => Err(anyhow::Error::new(PkcsError::CheckpointMismatch).context(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Describes the first setting a saved checkpoint differs in, if any.
+    fn mismatch(&self, current: &Checkpoint) -> Option<String> {
+        if self.fingerprint != current.fingerprint {
+            return Some("Session was saved for a different certificate".to_string());
+        }
+        if self.patterns != current.patterns {
+            return Some(match self.patterns.as_slice() {
+                [] => "Session was saved for a brute force run".to_string(),
+                patterns => format!("Session was saved for the patterns {patterns:?}"),
+            });
+        }
+        if self.charset != current.charset {
+            return Some("Session was saved with a different charset".to_string());
+        }
+        if self.symbols != current.symbols {
+            return Some("Session was saved with different symbol charsets".to_string());
+        }
+        if self.linked != current.linked {
+            return Some("Session was saved with different linked symbols".to_string());
+        }
+        if self.years != current.years {
+            return Some("Session was saved with different --years".to_string());
+        }
+        // Brute force adopts the saved direction, pattern positions depend on it
+        if !self.patterns.is_empty() && self.reverse != current.reverse {
+            return Some(if self.reverse {
+                "Session was saved with --reverse".to_string()
+            } else {
+                "Session was saved without --reverse".to_string()
+            });
+        }
+        if (self.min_len, self.max_len) != (current.min_len, current.max_len) {
+            return Some(format!(
+                "Session was saved with lengths {}..={}, got {}..={}",
+                self.min_len, self.max_len, current.min_len, current.max_len
+            ));
+        }
+        if self.random_seed != current.random_seed {
+            return Some(match self.random_seed {
+                Some(seed) => format!("Session was saved with --random-order --seed {seed}"),
+                None => "Session was saved without --random-order".to_string(),
+            });
+        }
+        if self.policy != current.policy {
+            return Some(format!(
+                "Session was saved with policy '{}', got '{}'",
+                self.policy, current.policy
+            ));
+        }
+        if self.no_repeats != current.no_repeats {
+            if self.no_repeats {
+                return Some("Session was saved with --no-repeats".to_string());
+            }
+            return Some("Session was saved without --no-repeats".to_string());
+        }
+        if self.work_unit != current.work_unit {
+            return Some(format!(
+                "Session was saved for --work-unit {}/{}",
+                self.work_unit.index, self.work_unit.total
+            ));
+        }
+        None
+    }
+}
+
+/// Returns the hex encoded SHA-256 of the certificate's DER encoding.
+///
+/// # Errors
+///
+/// Returns an error if the certificate cannot be encoded
+pub fn certificate_fingerprint(pkcs12: &Pkcs12) -> Result<String> {
+    let der = pkcs12.to_der().context("Failed to encode certificate")?;
+    Ok(openssl::sha::sha256(&der)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Tracks which indices of a single length have been tested.
+#[derive(Debug, Default)]
+pub struct ProgressTracker {
+    /// Every index below this one has been tested
+    low_water: u128,
+    /// Completed ranges above the low-water mark: start -> end
+    ahead: BTreeMap<u128, u128>,
+}
+
+impl ProgressTracker {
+    /// Creates a tracker resuming from saved progress.
+    ///
+    /// # Arguments
+    ///
+    /// * `low_water` - Every index below this one has been tested
+    /// * `completed` - Ranges above `low_water` that have been tested
+    pub fn new(low_water: u128, completed: &[Range<u128>]) -> Self {
+        let mut tracker = Self {
+            low_water,
+            ahead: BTreeMap::new(),
+        };
+        for range in completed {
+            tracker.complete(range.clone());
+        }
+        tracker
+    }
+
+    /// Records a tested range and advances the low-water mark past every
+    /// range that is now contiguous with it.
+    pub fn complete(&mut self, range: Range<u128>) {
+        if range.end <= self.low_water {
+            return;
+        }
+        let end = self.ahead.entry(range.start).or_insert(range.end);
+        *end = (*end).max(range.end);
+
+        while let Some((&start, &end)) = self.ahead.first_key_value() {
+            if start > self.low_water {
+                break;
+            }
+            self.ahead.pop_first();
+            self.low_water = self.low_water.max(end);
+        }
+    }
+
+    /// Every index below the returned one has been tested.
+    pub fn low_water(&self) -> u128 {
+        self.low_water
+    }
+
+    /// Completed ranges above the low-water mark.
+    pub fn completed(&self) -> Vec<Range<u128>> {
+        self.ahead.iter().map(|(&start, &end)| start..end).collect()
+    }
+}
+
+/// Periodically writes the progress of a brute force or pattern run to the session file.
+pub struct CheckpointWriter {
+    /// Path to the session file
+    path: PathBuf,
+    /// Progress shared by the worker threads
+    state: Mutex<WriterState>,
+}
+
+/// Maps a tracked index to a password length and position of that length.
+type Locate = Box<dyn Fn(u128) -> (u8, u128) + Send>;
+
+struct WriterState {
+    checkpoint: Checkpoint,
+    tracker: ProgressTracker,
+    /// Maps tracked indices to the length and position saved, see [`CheckpointWriter::start_run`]
+    locate: Option<Locate>,
+    /// How often the session file is written
+    cadence: WriteCadence,
+    last_write: Instant,
+    /// Number of indices completed since the last write
+    since_write: u128,
+}
+
+impl CheckpointWriter {
+    /// Creates a writer for the given run.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the session file
+    /// * `checkpoint` - Run configuration and starting progress
+    pub fn new(path: PathBuf, checkpoint: Checkpoint) -> Self {
+        let tracker = ProgressTracker::new(checkpoint.low_water, &checkpoint.completed);
+        Self {
+            path,
+            state: Mutex::new(WriterState {
+                checkpoint,
+                tracker,
+                locate: None,
+                cadence: WriteCadence::default(),
+                last_write: Instant::now(),
+                since_write: 0,
+            }),
+        }
+    }
+
+    /// Sets how often the session file is written, every
+    /// [`CHECKPOINT_INTERVAL`] by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `cadence` - Time or number of tested candidates between two writes
+    pub fn with_cadence(self, cadence: WriteCadence) -> Self {
+        self.state.lock().unwrap().cadence = cadence;
+        self
+    }
+
+    /// Starts tracking a run whose lengths share a single index space.
+    ///
+    /// The session file keeps storing a length and positions of it: the
+    /// low-water mark is saved as the length and position it falls in, and
+    /// only the completed ranges of that length are kept. Ranges completed in
+    /// later lengths are tested again after a restore.
+    ///
+    /// # Arguments
+    ///
+    /// * `low_water` - Every index below this one is already tested
+    /// * `completed` - Ranges above `low_water` that are already tested
+    /// * `locate` - Maps an index to its password length and position
+    pub fn start_run(
+        &self,
+        low_water: u128,
+        completed: &[Range<u128>],
+        locate: impl Fn(u128) -> (u8, u128) + Send + 'static,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        state.tracker = ProgressTracker::new(low_water, completed);
+        state.locate = Some(Box::new(locate));
+    }
+
+    /// Starts tracking a run whose positions are saved as they are, e.g. the
+    /// combinations of a pattern run.
+    ///
+    /// # Arguments
+    ///
+    /// * `low_water` - Every index below this one is already tested
+    /// * `completed` - Ranges above `low_water` that are already tested
+    pub fn start_at(&self, low_water: u128, completed: &[Range<u128>]) {
+        let mut state = self.state.lock().unwrap();
+        state.tracker = ProgressTracker::new(low_water, completed);
+        state.locate = None;
+    }
+
+    /// Records a tested range and writes the session file once the write
+    /// cadence is due.
+    ///
+    /// Write failures are reported but do not stop the run.
+    pub fn complete(&self, range: Range<u128>) {
+        let mut state = self.state.lock().unwrap();
+        state.since_write += range.end - range.start;
+        state.tracker.complete(range);
+        let due = match state.cadence {
+            WriteCadence::Interval(interval) => state.last_write.elapsed() >= interval,
+            WriteCadence::Attempts(attempts) => state.since_write >= attempts as u128,
+        };
+        if due {
+            if let Err(e) = Self::write(&self.path, &mut state) {
+                eprintln!("{e:#}");
+            }
+        }
+    }
+
+    /// Writes the session file immediately.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn save(&self) -> Result<()> {
+        Self::write(&self.path, &mut self.state.lock().unwrap())
+    }
+
+    fn write(path: &Path, state: &mut WriterState) -> Result<()> {
+        let low_water = state.tracker.low_water();
+        match &state.locate {
+            Some(locate) => {
+                let (length, position) = locate(low_water);
+                let offset = low_water - position;
+                state.checkpoint.length = length;
+                state.checkpoint.low_water = position;
+                state.checkpoint.completed = state
+                    .tracker
+                    .completed()
+                    .into_iter()
+                    .filter(|range| locate(range.end - 1).0 == length && range.start >= offset)
+                    .map(|range| range.start - offset..range.end - offset)
+                    .collect();
+            }
+            None => {
+                state.checkpoint.low_water = low_water;
+                state.checkpoint.completed = state.tracker.completed();
+            }
+        }
+        state.last_write = Instant::now();
+        state.since_write = 0;
+        state.checkpoint.save(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint() -> Checkpoint {
+        Checkpoint {
+            version: FORMAT_VERSION,
+            charset: "abc".to_string(),
+            min_len: 1,
+            max_len: 4,
+            random_seed: None,
+            work_unit: WorkUnit::FULL,
+            reverse: false,
+            length_order: None,
+            policy: String::new(),
+            no_repeats: false,
+            patterns: Vec::new(),
+            symbols: BTreeMap::new(),
+            linked: Vec::new(),
+            years: None,
+            fingerprint: "00".to_string(),
+            length: 3,
+            low_water: 10,
+            completed: vec![20..30, 40..50],
+        }
+    }
+
+    #[test]
+    fn test_tracker_out_of_order() {
+        let mut tracker = ProgressTracker::default();
+        tracker.complete(20..30);
+        tracker.complete(30..40);
+        assert_eq!(tracker.low_water(), 0);
+        assert_eq!(tracker.completed(), vec![20..30, 30..40]);
+
+        tracker.complete(0..10);
+        assert_eq!(tracker.low_water(), 10);
+
+        tracker.complete(10..20);
+        assert_eq!(tracker.low_water(), 40);
+        assert!(tracker.completed().is_empty());
+    }
+
+    #[test]
+    fn test_tracker_overlapping_ranges() {
+        // After a restore, chunks are aligned to the saved low-water mark and
+        // may straddle previously completed ranges.
+        let mut tracker = ProgressTracker::new(50, &[100..200, 250..300]);
+        tracker.complete(50..150);
+        assert_eq!(tracker.low_water(), 200);
+        tracker.complete(150..250);
+        assert_eq!(tracker.low_water(), 300);
+    }
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        checkpoint().save(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint());
+    }
+
+    #[test]
+    fn test_truncated_checkpoint_falls_back_to_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        checkpoint().save(&path).unwrap();
+        let mut newer = checkpoint();
+        newer.low_water = 20;
+        newer.save(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), newer);
+
+        // Power loss halfway through writing the file
+        let json = fs::read(&path).unwrap();
+        fs::write(&path, &json[..json.len() / 2]).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint());
+
+        // Crash between moving the file to the backup and renaming the new one
+        fs::remove_file(&path).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint());
+
+        fs::write(backup_path(&path), "{").unwrap();
+        assert!(Checkpoint::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_format_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        let mut json = serde_json::to_value(checkpoint()).unwrap();
+
+        // Files written before the version field existed still load
+        json.as_object_mut().unwrap().remove("version");
+        fs::write(&path, json.to_string()).unwrap();
+        assert_eq!(Checkpoint::load(&path).unwrap().version, 1);
+
+        json["version"] = (FORMAT_VERSION + 1).into();
+        fs::write(&path, json.to_string()).unwrap();
+        let error = Checkpoint::load(&path).unwrap_err();
+        assert!(format!("{error:#}").contains("format version"));
+    }
+
+    #[test]
+    fn test_write_cadence_parse() {
+        assert_eq!("1000".parse(), Ok(WriteCadence::Attempts(1000)));
+        assert_eq!(
+            "30s".parse(),
+            Ok(WriteCadence::Interval(Duration::from_secs(30)))
+        );
+        assert_eq!(
+            "5m".parse(),
+            Ok(WriteCadence::Interval(Duration::from_secs(300)))
+        );
+        assert_eq!(
+            "1h".parse(),
+            Ok(WriteCadence::Interval(Duration::from_secs(3600)))
+        );
+        assert!("0".parse::<WriteCadence>().is_err());
+        assert!("0s".parse::<WriteCadence>().is_err());
+        assert!("5d".parse::<WriteCadence>().is_err());
+        assert!("s".parse::<WriteCadence>().is_err());
+        assert_eq!(WriteCadence::default().to_string(), "30s");
+    }
+
+    #[test]
+    fn test_writer_attempts_cadence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        let mut start = checkpoint();
+        start.low_water = 0;
+        start.completed.clear();
+        let writer =
+            CheckpointWriter::new(path.clone(), start).with_cadence(WriteCadence::Attempts(10));
+
+        writer.complete(0..5);
+        assert!(!path.exists());
+        writer.complete(5..10);
+        assert_eq!(Checkpoint::load(&path).unwrap().low_water, 10);
+    }
+
+    #[test]
+    fn test_checkpoint_mismatch() {
+        let saved = checkpoint();
+        assert!(saved.ensure_matches(&checkpoint()).is_ok());
+
+        let mut other = checkpoint();
+        other.charset = "abcd".to_string();
+        let error = saved.ensure_matches(&other).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Session was saved with a different charset"
+        );
+        assert_eq!(
+            error.downcast_ref::<PkcsError>(),
+            Some(&PkcsError::CheckpointMismatch)
+        );
+
+        let mut other = checkpoint();
+        other.max_len = 5;
+        assert!(saved.ensure_matches(&other).is_err());
+
+        let mut other = checkpoint();
+        other.fingerprint = "01".to_string();
+        assert!(saved.ensure_matches(&other).is_err());
+
+        let mut other = checkpoint();
+        other.no_repeats = true;
+        assert!(saved.ensure_matches(&other).is_err());
+    }
+}
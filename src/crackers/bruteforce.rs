@@ -2,12 +2,290 @@
 //!
 //! This module provides functionality for testing all possible combinations
 //! within a given charset and length range.
-use crate::types::{CrackResult, PasswordCracker};
-use anyhow::Result;
+use super::permutation::IndexPermutation;
+use super::policy::CandidatePolicy;
+use super::{ProbabilityModel, WeightedCombinationIter};
+use crate::checkpoint::{
+    certificate_fingerprint, Checkpoint, CheckpointWriter, WriteCadence, FORMAT_VERSION,
+};
+use crate::progress_file::{Position, ProgressSaver};
+use crate::types::{
+    CombinationCount, CrackMode, CrackResult, PasswordCracker, PkcsError, WorkUnit,
+};
+use anyhow::{bail, Result};
+use clap::ValueEnum;
 use openssl::pkcs12::Pkcs12;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+/// Largest number of palindromes of a single length tried in the `--smart-order` first wave
+const MAX_PALINDROMES_PER_LENGTH: u128 = 10_000;
+
+/// Number of attempts a worker counts locally before adding them to the shared counter
+const ATTEMPT_BATCH: usize = 1024;
+
+/// Order in which the candidate lengths are tested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LengthOrder {
+    /// Shortest length first
+    Ascending,
+    /// Longest length first
+    Descending,
+    /// One chunk of every length in turn, shortest first
+    Interleaved,
+}
+
+impl fmt::Display for LengthOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ascending => "ascending",
+            Self::Descending => "descending",
+            Self::Interleaved => "interleaved",
+        })
+    }
+}
+
+/// Takes one item from each of several iterators in turn.
+///
+/// Exhausted iterators are dropped from the rotation, so the remaining ones
+/// share it.
+struct RoundRobin<I> {
+    /// Iterators in rotation, the next item is taken from the front one
+    queues: VecDeque<I>,
+}
+
+impl<I: Iterator> Iterator for RoundRobin<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        while let Some(mut queue) = self.queues.pop_front() {
+            if let Some(item) = queue.next() {
+                self.queues.push_back(queue);
+                return Some(item);
+            }
+        }
+        None
+    }
+}
+
+/// Cuts the part of the range in every length into chunks, in the order
+/// they are tested.
+///
+/// # Arguments
+///
+/// * `stages` - Slot of every length with its part of the range
+/// * `chunk_size` - Number of indices per chunk
+/// * `interleaved` - Whether to take one chunk of every length in turn
+///   instead of all chunks of a length before the next one
+fn chunk_queue(
+    stages: &[(usize, Range<u128>)],
+    chunk_size: u128,
+    interleaved: bool,
+) -> Box<dyn Iterator<Item = (usize, Range<u128>)> + Send> {
+    let queues: VecDeque<_> = stages
+        .iter()
+        .map(|(slot, range)| {
+            let (slot, range) = (*slot, range.clone());
+            (0..(range.end - range.start).div_ceil(chunk_size)).map(move |chunk| {
+                let start = range.start + chunk * chunk_size;
+                (slot, start..(start + chunk_size).min(range.end))
+            })
+        })
+        .collect();
+    if interleaved {
+        Box::new(RoundRobin { queues })
+    } else {
+        Box::new(queues.into_iter().flatten())
+    }
+}
+
+/// Builds the `--smart-order` first wave of likely candidates.
+///
+/// The wave holds, for every length between `min_len` and `max_len`:
+/// - single character repeats, e.g. `aaaa`
+/// - ascending and descending runs of consecutive charset characters, e.g. `1234` and `dcba`
+/// - palindromes, e.g. `abba`, unless a length has more than
+///   `MAX_PALINDROMES_PER_LENGTH` of them
+///
+/// # Arguments
+///
+/// * `charset` - Characters to use in combinations
+/// * `min_len` - Shortest candidate length
+/// * `max_len` - Longest candidate length
+///
+/// # Returns
+///
+/// Returns the candidates in the order they should be tried, without duplicates.
+pub fn simple_candidates(charset: &[char], min_len: u8, max_len: u8) -> Vec<String> {
+    let lengths = min_len as usize..=max_len as usize;
+    let mut wave = Vec::new();
+
+    for len in lengths.clone() {
+        wave.extend(charset.iter().map(|&c| c.to_string().repeat(len)));
+    }
+    for len in lengths.clone() {
+        for run in charset.windows(len) {
+            wave.push(run.iter().collect());
+            wave.push(run.iter().rev().collect());
+        }
+    }
+    for len in lengths {
+        let half = len.div_ceil(2);
+        let count = (charset.len() as u128).checked_pow(half as u32);
+        if count.is_none_or(|count| count > MAX_PALINDROMES_PER_LENGTH) {
+            continue;
+        }
+
+        let mut buffer = Vec::with_capacity(half);
+        for index in 0..count.unwrap_or(0) {
+            let head = super::index_to_combination(index, charset, half, &mut buffer);
+            let tail: String = head.chars().rev().skip(len % 2).collect();
+            wave.push(head + &tail);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    wave.retain(|candidate| seen.insert(candidate.clone()));
+    wave
+}
+
+/// Counts the candidates of a single length.
+///
+/// # Arguments
+///
+/// * `charset` - Characters to use in combinations
+/// * `len` - Password length
+/// * `no_repeats` - Whether candidates never repeat a character
+fn length_count(charset: &[char], len: u8, no_repeats: bool) -> CombinationCount {
+    if no_repeats {
+        super::estimate_permutations(charset, len as usize)
+    } else {
+        super::estimate_combinations(charset, len as usize)
+    }
+}
+
+/// Global numbering of the positions of all lengths in traversal order.
+///
+/// Position `p` of a length is numbered `offset + p`, where `offset` is the
+/// number of positions of all lengths before it in traversal order. A single
+/// index thus describes how far a run got across length boundaries, which
+/// work units, `--skip`, `--limit` and resumption all refer to.
+struct Layout {
+    /// Length, number of combinations and index of its first position, in traversal order
+    lengths: Vec<(u8, u128, u128)>,
+    /// Number of positions of all lengths
+    total: u128,
+}
+
+impl Layout {
+    /// Numbers the positions of the lengths in traversal order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the positions of all lengths do not fit into `u128`.
+    fn new(traversal: &[u8], charset: &[char], no_repeats: bool) -> Result<Self> {
+        let mut lengths = Vec::with_capacity(traversal.len());
+        let mut total: u128 = 0;
+        for &len in traversal {
+            let CombinationCount::Exact(count) = length_count(charset, len, no_repeats) else {
+                bail!(
+                    "Keyspace of length {len} exceeds {} combinations",
+                    u128::MAX
+                );
+            };
+            lengths.push((len, count, total));
+            let Some(next) = total.checked_add(count) else {
+                return Err(PkcsError::SearchSpaceExceeded.into());
+            };
+            total = next;
+        }
+        Ok(Self { lengths, total })
+    }
+
+    /// Returns the index of a position of a length, `None` if the length
+    /// is not part of the run.
+    fn index(&self, len: u8, position: u128) -> Option<u128> {
+        self.lengths
+            .iter()
+            .find(|&&(l, ..)| l == len)
+            .map(|&(_, _, offset)| offset + position)
+    }
+
+    /// Returns the number of combinations of a length, `None` if the length
+    /// is not part of the run.
+    fn count(&self, len: u8) -> Option<u128> {
+        self.lengths
+            .iter()
+            .find(|&&(l, ..)| l == len)
+            .map(|&(_, count, _)| count)
+    }
+
+    /// Returns the slot in traversal order of the length an index falls in
+    /// and its position in that length.
+    ///
+    /// `total` maps to the end of the last length.
+    fn locate(&self, index: u128) -> (usize, u128) {
+        let slot = self
+            .lengths
+            .partition_point(|&(_, _, offset)| offset <= index)
+            .saturating_sub(1);
+        (slot, index - self.lengths[slot].2)
+    }
+}
+
+/// State shared by all chunks of a brute force run.
+struct RunState<'a> {
+    /// Numbering of the positions of all lengths
+    layout: &'a Layout,
+    /// Whether positions are walked from the top of each length downward
+    reverse: bool,
+    /// Whether chunks of all lengths are tested in turn
+    interleaved: bool,
+    /// Ranges of indices a restored run already tested
+    skip: &'a [Range<u128>],
+    /// Checkpoint writer recording tested chunks, if any
+    session: Option<&'a CheckpointWriter>,
+    /// Progress file saver recording tested chunks, if any
+    progress: Option<&'a ProgressSaver>,
+    /// Candidates already tried in the `--smart-order` first wave
+    tried: &'a HashSet<String>,
+}
+
+/// Start positions of the chunks being tested, to report the lowest one.
+#[derive(Default)]
+struct InFlight(Mutex<BTreeSet<u128>>);
+
+impl InFlight {
+    /// Records a chunk as started.
+    ///
+    /// Returns the chunk's position if it is the lowest one in flight.
+    fn start(&self, position: u128) -> Option<u128> {
+        let mut chunks = self.0.lock().unwrap();
+        chunks.insert(position);
+        (chunks.first() == Some(&position)).then_some(position)
+    }
+
+    /// Records a chunk as finished.
+    ///
+    /// Returns the new lowest position in flight if the finished chunk was the lowest.
+    fn finish(&self, position: u128) -> Option<u128> {
+        let mut chunks = self.0.lock().unwrap();
+        let was_lowest = chunks.first() == Some(&position);
+        chunks.remove(&position);
+        if was_lowest {
+            chunks.first().copied()
+        } else {
+            None
+        }
+    }
+}
+
 /// Implements brute force password cracking.
 pub struct BruteforceCracker {
     /// Minimum password length to try
@@ -16,6 +294,36 @@ pub struct BruteforceCracker {
     max_len: u8,
     /// String containing all characters to use in combinations
     charset: String,
+    /// Seed for shuffling the candidates, `None` keeps the sequential order
+    random_seed: Option<u64>,
+    /// Share of the search space processed by this cracker
+    work_unit: WorkUnit,
+    /// Session file progress is saved to, if any
+    session_path: Option<PathBuf>,
+    /// Whether to resume the run saved in the session file
+    restore: bool,
+    /// How often the session file is written
+    session_cadence: WriteCadence,
+    /// Whether to try simple candidates before the exhaustive pass
+    smart_order: bool,
+    /// Candidate to start the search at, skipping everything before it
+    start_at: Option<String>,
+    /// Number of candidates at the start of the run to skip
+    skip: u128,
+    /// Maximum number of candidates to try
+    limit: Option<u128>,
+    /// Whether to walk the keyspace from the top downward
+    reverse: bool,
+    /// Order of the lengths, `None` follows `reverse`
+    length_order: Option<LengthOrder>,
+    /// Number of candidates per parallel work item
+    chunk_size: usize,
+    /// Character classes every candidate must contain
+    policy: CandidatePolicy,
+    /// Character weights ordering the candidates, most likely first
+    probability_model: Option<ProbabilityModel>,
+    /// Whether to only try candidates without repeated characters
+    no_repeats: bool,
 }
 
 impl BruteforceCracker {
@@ -31,14 +339,499 @@ impl BruteforceCracker {
             min_len,
             max_len,
             charset,
+            random_seed: None,
+            work_unit: WorkUnit::FULL,
+            session_path: None,
+            restore: false,
+            session_cadence: WriteCadence::default(),
+            smart_order: false,
+            start_at: None,
+            skip: 0,
+            limit: None,
+            reverse: false,
+            length_order: None,
+            chunk_size: super::CHUNK_SIZE,
+            policy: CandidatePolicy::default(),
+            probability_model: None,
+            no_repeats: false,
         }
     }
 
+    /// Makes the cracker try candidates of each length in a seeded random order.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed for the PRNG shuffling the candidates
+    pub fn with_random_order(mut self, seed: u64) -> Self {
+        self.random_seed = Some(seed);
+        self
+    }
+
+    /// Restricts the cracker to a share of the search space.
+    ///
+    /// Combinations of all lengths are numbered consecutively in traversal
+    /// order, shortest first unless reversed or the length order is descending.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_unit` - Share of the search space to process
+    pub fn with_work_unit(mut self, work_unit: WorkUnit) -> Self {
+        self.work_unit = work_unit;
+        self
+    }
+
+    /// Saves progress to a session file while cracking.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the session file
+    /// * `restore` - Whether to resume the run already saved in the file
+    pub fn with_session(mut self, path: PathBuf, restore: bool) -> Self {
+        self.session_path = Some(path);
+        self.restore = restore;
+        self
+    }
+
+    /// Sets how often the session file is written, every 30 seconds by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `cadence` - Time or number of tested candidates between two writes
+    pub fn with_session_cadence(mut self, cadence: WriteCadence) -> Self {
+        self.session_cadence = cadence;
+        self
+    }
+
+    /// Tries repeats, sequences and palindromes before the exhaustive pass.
+    ///
+    /// The exhaustive pass skips the candidates of this first wave, so every
+    /// candidate is still tried exactly once. See [`simple_candidates`].
+    pub fn with_smart_order(mut self) -> Self {
+        self.smart_order = true;
+        self
+    }
+
+    /// Starts the search at a given candidate.
+    ///
+    /// All shorter candidates and the candidates of the same length before
+    /// it are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `candidate` - First candidate to try, made of charset characters only
+    pub fn with_start_at(mut self, candidate: String) -> Self {
+        self.start_at = Some(candidate);
+        self
+    }
+
+    /// Skips a number of candidates at the start of the run.
+    ///
+    /// Candidates of all lengths are numbered consecutively in traversal
+    /// order, the skipped ones are counted from the start of the work unit.
+    ///
+    /// # Arguments
+    ///
+    /// * `skip` - Number of candidates to skip
+    pub fn with_skip(mut self, skip: u128) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    /// Stops the search after a number of candidates.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum number of candidates to try
+    pub fn with_limit(mut self, limit: u128) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Walks the keyspace backwards, longest candidates and highest indices first.
+    ///
+    /// Work units, `with_start_at`, `with_limit` and session progress all
+    /// refer to positions in this reversed traversal order.
+    pub fn with_reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    /// Sets the order in which the lengths are tested.
+    ///
+    /// Without it, lengths are tested shortest first, or longest first with
+    /// [`BruteforceCracker::with_reverse`]. An explicit order takes precedence
+    /// over the reverse flag, which then only reverses the positions inside
+    /// each length. Interleaved runs still number the combinations of all
+    /// lengths consecutively, so work units, `with_skip`, `with_limit` and
+    /// saved progress refer to the same candidates as an ascending run.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - Order of the lengths
+    pub fn with_length_order(mut self, order: LengthOrder) -> Self {
+        self.length_order = Some(order);
+        self
+    }
+
+    /// Sets the number of candidates per parallel work item.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - Candidates per chunk, at least 1
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Skips candidates that violate a password policy.
+    ///
+    /// Skipped candidates are not tested and not counted as attempts.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Character classes every candidate must contain
+    pub fn with_policy(mut self, policy: CandidatePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Only tries candidates whose characters are all distinct.
+    ///
+    /// Candidates are generated directly, not filtered: a length has the
+    /// falling factorial `n·(n-1)·…` of candidates instead of `n^len`, and
+    /// work units, `--skip`, `--start-at` and sessions number only those.
+    pub fn with_no_repeats(mut self) -> Self {
+        self.no_repeats = true;
+        self
+    }
+
+    /// Tries candidates in decreasing probability under per-character weights.
+    ///
+    /// Lengths are mixed, a short unlikely candidate can come after a long
+    /// likely one. Combined with `with_random_order` the order is randomized
+    /// but still favors likely characters. Sessions, `--start-at`, reverse and
+    /// length orders refer to the index order and are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Weights of the charset characters
+    pub fn with_probability_model(mut self, model: ProbabilityModel) -> Self {
+        self.probability_model = Some(model);
+        self
+    }
+
+    /// Tests the candidates most likely under the probability model first.
+    ///
+    /// Units of `--work-unit` take the candidates round-robin, `skip` and
+    /// `limit` count candidates in the weighted order.
+    fn crack_weighted(
+        &self,
+        charset: &[char],
+        model: &ProbabilityModel,
+        pkcs12: &Pkcs12,
+        result: &Arc<Mutex<CrackResult>>,
+    ) {
+        println!("Trying candidates in decreasing probability");
+        let WorkUnit { index, total } = self.work_unit;
+        let share = self.estimate_count().map_or(u128::MAX, |count| {
+            count.saturating_sub(index as u128).div_ceil(total as u128)
+        });
+        let share = share.saturating_sub(self.skip);
+        result.lock().unwrap().begin_stage(
+            "weighted candidates".to_string(),
+            self.limit.map_or(share, |limit| limit.min(share)),
+        );
+
+        let lengths = self.min_len as usize..=self.max_len as usize;
+        let candidates = WeightedCombinationIter::new(charset, model, lengths, self.random_seed)
+            .filter(|candidate| self.policy.allows(candidate))
+            .skip(index)
+            .step_by(total);
+        let mut candidates = candidates
+            .skip(usize::try_from(self.skip).unwrap_or(usize::MAX))
+            .take(self.limit.map_or(usize::MAX, |limit| {
+                usize::try_from(limit).unwrap_or(usize::MAX)
+            }));
+        let chunks = std::iter::from_fn(|| {
+            let chunk: Vec<String> = candidates.by_ref().take(self.chunk_size).collect();
+            (!chunk.is_empty()).then_some(chunk)
+        });
+        chunks
+            .par_bridge()
+            .find_any(|chunk| Self::process_chunk(chunk, pkcs12, result));
+    }
+
+    /// Resolves `--start-at` into a password length and linear index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the candidate has a length outside the length range
+    /// or contains characters missing from the charset.
+    fn start_position(&self, charset: &[char]) -> Result<Option<(u8, u128)>> {
+        let Some(candidate) = &self.start_at else {
+            return Ok(None);
+        };
+        let len = candidate.chars().count();
+        if len < self.min_len as usize || len > self.max_len as usize {
+            bail!(
+                "--start-at {candidate:?} has length {len}, outside of {}..={}",
+                self.min_len,
+                self.max_len
+            );
+        }
+        let index = if self.no_repeats {
+            super::permutation_to_index(candidate, charset)?
+        } else {
+            super::combination_to_index(candidate, charset)?
+        };
+        Ok(Some((len as u8, index)))
+    }
+
+    /// Creates the checkpoint writer for `--session`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the writer and, with `--restore`, the saved checkpoint to resume from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the saved session cannot be read or belongs to a
+    /// different certificate, charset, length range, seed or work unit.
+    fn open_session(
+        &self,
+        path: &Path,
+        pkcs12: &Pkcs12,
+    ) -> Result<(CheckpointWriter, Option<Checkpoint>)> {
+        let current = Checkpoint {
+            version: FORMAT_VERSION,
+            charset: self.charset.clone(),
+            min_len: self.min_len,
+            max_len: self.max_len,
+            random_seed: self.random_seed,
+            work_unit: self.work_unit,
+            reverse: self.reverse,
+            length_order: self.length_order,
+            policy: self.policy.to_string(),
+            no_repeats: self.no_repeats,
+            patterns: Vec::new(),
+            symbols: BTreeMap::new(),
+            linked: Vec::new(),
+            years: None,
+            fingerprint: certificate_fingerprint(pkcs12)?,
+            length: self.min_len,
+            low_water: 0,
+            completed: Vec::new(),
+        };
+        if !self.restore {
+            let writer = CheckpointWriter::new(path.to_path_buf(), current)
+                .with_cadence(self.session_cadence);
+            return Ok((writer, None));
+        }
+
+        let saved = Checkpoint::load(path)?;
+        saved.ensure_matches(&current)?;
+        println!(
+            "Resuming session at length {}, position {}{}{}",
+            saved.length,
+            saved.low_water,
+            if saved.reverse {
+                " in reverse order"
+            } else {
+                ""
+            },
+            saved
+                .length_order
+                .map_or_else(String::new, |order| format!(", {order} lengths"))
+        );
+        // Rewritten in the current format from now on
+        let upgraded = Checkpoint {
+            version: FORMAT_VERSION,
+            ..saved.clone()
+        };
+        let writer =
+            CheckpointWriter::new(path.to_path_buf(), upgraded).with_cadence(self.session_cadence);
+        Ok((writer, Some(saved)))
+    }
+
+    /// Prints how much of the keyspace is left after `--require` filtering.
+    fn print_policy_keyspace(&self, charset: &[char]) {
+        let raw = crate::keyspace::keyspace_table(charset.len(), self.min_len, self.max_len, false)
+            .last()
+            .map_or(0, |row| row.cumulative);
+        let allowed = (self.min_len..=self.max_len)
+            .map(|len| self.policy.count_allowed(charset, len as usize))
+            .fold(0u128, u128::saturating_add);
+        println!(
+            "Policy {}: {} of {} candidates ({:.2}%), {} at {}M/s",
+            self.policy,
+            crate::keyspace::format_magnitude(allowed),
+            crate::keyspace::format_magnitude(raw),
+            allowed as f64 * 100.0 / raw.max(1) as f64,
+            crate::keyspace::format_duration(allowed, crate::keyspace::REFERENCE_RATE),
+            crate::keyspace::REFERENCE_RATE / 1_000_000
+        );
+    }
+
+    /// Counts the combinations of all lengths between `min_len` and `max_len`.
+    fn total_combinations(&self, charset: &[char]) -> CombinationCount {
+        let mut total: u128 = 0;
+        for len in self.min_len..=self.max_len {
+            let count = match length_count(charset, len, self.no_repeats) {
+                CombinationCount::Exact(count) => count,
+                CombinationCount::TooLarge => return CombinationCount::TooLarge,
+            };
+            match total.checked_add(count) {
+                Some(next) => total = next,
+                None => return CombinationCount::TooLarge,
+            }
+        }
+        CombinationCount::Exact(total)
+    }
+
+    /// Tests a range of indices of the global numbering in parallel.
+    ///
+    /// The range is cut into chunks that never cross a length boundary and
+    /// handed out from a single queue, so workers move on to the next length
+    /// while the last chunks of the previous one are still being tested.
+    /// Workers pull a chunk only when they are idle, so the look-ahead into
+    /// later lengths is bounded by the number of threads and the length
+    /// order stays a priority rather than a barrier. Once the password is
+    /// found, chunks in flight stop at their next candidate and no further
+    /// chunk is taken from the queue.
+    /// Interleaved runs take one chunk of every length in turn instead.
+    /// Candidates are decoded from their index inside each chunk, so memory
+    /// use is bounded by the chunk size rather than the keyspace.
+    ///
+    /// # Arguments
+    ///
+    /// * `charset` - Characters to use in combinations
+    /// * `stages` - Slot of every length with candidates in the range, with
+    ///   its part of the range and the number of candidates the policy allows in it
+    /// * `run` - Numbering, checkpoint writer and first wave candidates of the run
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    /// * `result` - Shared result tracking structure
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the password was found, `false` otherwise.
+    fn process_range(
+        &self,
+        charset: &[char],
+        stages: &[(usize, Range<u128>, u128)],
+        run: &RunState,
+        pkcs12: &Pkcs12,
+        result: &Arc<Mutex<CrackResult>>,
+    ) -> bool {
+        let orders: Vec<Option<IndexPermutation>> = run
+            .layout
+            .lengths
+            .iter()
+            .map(|&(_, count, _)| {
+                self.random_seed
+                    .map(|seed| IndexPermutation::new(count, seed))
+            })
+            .collect();
+        let candidate_at = |slot: usize, index: u128, buffer: &mut Vec<char>| {
+            let (len, count, offset) = run.layout.lengths[slot];
+            let position = index - offset;
+            let position = if run.reverse {
+                count - 1 - position
+            } else {
+                position
+            };
+            let position = orders[slot].as_ref().map_or(position, |o| o.get(position));
+            if self.no_repeats {
+                super::index_to_permutation(position, charset, len as usize, buffer)
+            } else {
+                super::index_to_combination(position, charset, len as usize, buffer)
+            }
+        };
+        // The status line shows a candidate of the lowest chunk in flight
+        let in_flight = InFlight::default();
+        let publish_region = |index: Option<u128>| {
+            if let Some(index) = index {
+                let (slot, _) = run.layout.locate(index);
+                let region = candidate_at(slot, index, &mut Vec::new());
+                result.lock().unwrap().progress.region = Some(region);
+            }
+        };
+        // Slot of the length the last stage was published for
+        let stage = Mutex::new(None::<usize>);
+        let begin_stage = |slot: usize| {
+            let mut stage = stage.lock().unwrap();
+            if run.interleaved || stage.is_some_and(|current| current >= slot) {
+                return;
+            }
+            *stage = Some(slot);
+            if let Some((_, _, remaining)) = stages.iter().find(|(s, ..)| *s == slot) {
+                let len = run.layout.lengths[slot].0;
+                let tried = run
+                    .tried
+                    .iter()
+                    .filter(|c| c.chars().count() == len as usize)
+                    .count() as u128;
+                result
+                    .lock()
+                    .unwrap()
+                    .begin_stage(format!("length {len}"), remaining.saturating_sub(tried));
+            }
+        };
+
+        if run.interleaved {
+            let remaining = stages
+                .iter()
+                .map(|(_, _, remaining)| *remaining)
+                .fold(0u128, u128::saturating_add);
+            result.lock().unwrap().begin_stage(
+                format!("lengths {}-{} interleaved", self.min_len, self.max_len),
+                remaining.saturating_sub(run.tried.len() as u128),
+            );
+        }
+
+        let parts: Vec<(usize, Range<u128>)> = stages
+            .iter()
+            .map(|(slot, range, _)| (*slot, range.clone()))
+            .collect();
+        chunk_queue(&parts, self.chunk_size as u128, run.interleaved)
+            .par_bridge()
+            .find_any(|(slot, range)| {
+                begin_stage(*slot);
+                publish_region(in_flight.start(range.start));
+
+                let mut buffer = Vec::new();
+                let chunk: Vec<String> = range
+                    .clone()
+                    .filter(|index| !run.skip.iter().any(|done| done.contains(index)))
+                    .map(|index| candidate_at(*slot, index, &mut buffer))
+                    .filter(|candidate| !run.tried.contains(candidate))
+                    .filter(|candidate| self.policy.allows(candidate))
+                    .collect();
+
+                let found = Self::process_chunk(&chunk, pkcs12, result);
+                publish_region(in_flight.finish(range.start));
+                if found {
+                    return true;
+                }
+                if let Some(session) = run.session {
+                    session.complete(range.clone());
+                }
+                if let Some(progress) = run.progress {
+                    progress.complete(range.clone());
+                }
+                false
+            })
+            .is_some()
+    }
+
     /// Processes a chunk of generated password combinations.
     ///
+    /// Attempts are counted locally and added to the shared counter in batches
+    /// of [`ATTEMPT_BATCH`] and at the end of the chunk, the status reporter
+    /// samples the counter in the background.
+    ///
     /// # Arguments
     ///
-    /// * `chunk` - Bytes from the memory-mapped file
+    /// * `chunk` - Candidates to test
     /// * `pkcs12` - The PKCS#12 certificate to crack
     /// * `result` - Shared result tracking structure
     ///
@@ -46,20 +839,30 @@ impl BruteforceCracker {
     ///
     /// Returns `true` if the correct password is found in this chunk.
     fn process_chunk(chunk: &[String], pkcs12: &Pkcs12, result: &Arc<Mutex<CrackResult>>) -> bool {
+        let run = result.lock().unwrap().run();
+        let mut attempts = 0;
+        let mut found = false;
         for password in chunk {
-            {
-                let result_guard = result.lock().unwrap();
-                if result_guard.password.is_some() {
-                    return true;
-                }
-                result_guard.increment_attempts();
+            if run.is_stopped() {
+                found = true;
+                break;
+            }
+            if run.skips_oversized(password) {
+                continue;
             }
 
-            if super::check_password(pkcs12, password, result) {
-                return true;
+            attempts += 1;
+            if attempts == ATTEMPT_BATCH {
+                run.add_attempts(attempts);
+                attempts = 0;
+            }
+            if super::check_password(pkcs12, password, &run, result) {
+                found = true;
+                break;
             }
         }
-        false
+        run.add_attempts(attempts);
+        found
     }
 }
 
@@ -75,7 +878,8 @@ impl PasswordCracker for BruteforceCracker {
     /// - n is the size of the character set
     /// - l is the password length
     ///
-    /// Memory usage grows with the number of combinations being tested in parallel.
+    /// Candidates are generated on the fly, memory usage is bounded by the
+    /// number of chunks being tested in parallel.
     fn crack(&self, pkcs12: &Arc<Pkcs12>, result: &Arc<Mutex<CrackResult>>) -> Result<()> {
         println!(
             "Generating passwords with length between {} and {}",
@@ -83,25 +887,586 @@ impl PasswordCracker for BruteforceCracker {
         );
         let charset: Vec<char> = self.charset.chars().collect();
         println!("Charset: {:?}", charset);
+        crate::keyspace::print_keyspace_table(
+            charset.len(),
+            self.min_len,
+            self.max_len,
+            self.no_repeats,
+        );
+        if !self.policy.is_empty() {
+            self.print_policy_keyspace(&charset);
+        }
+        if let Some(model) = &self.probability_model {
+            self.crack_weighted(&charset, model, pkcs12, result);
+            return Ok(());
+        }
 
-        for len in self.min_len..=self.max_len {
-            let mut combinations = Vec::new();
-            super::generate_combinations(
-                &charset,
-                len,
-                &String::with_capacity(len as usize),
-                &mut combinations,
-            );
+        let unit_range = if self.work_unit.is_full() {
+            0..u128::MAX
+        } else {
+            match self.total_combinations(&charset) {
+                CombinationCount::Exact(total) => self.work_unit.range(total),
+                CombinationCount::TooLarge => {
+                    return Err(anyhow::Error::new(PkcsError::SearchSpaceExceeded).context(
+                        format!(
+                            "Search space exceeds {} combinations, cannot split it into work units",
+                            u128::MAX
+                        ),
+                    ))
+                }
+            }
+        };
 
-            if combinations
-                .par_chunks(super::CHUNK_SIZE)
+        let (session, resume_from) = match &self.session_path {
+            Some(path) => {
+                let (writer, saved) = self.open_session(path, pkcs12)?;
+                (Some(writer), saved)
+            }
+            None => (None, None),
+        };
+        let (reverse, length_order) = resume_from
+            .as_ref()
+            .map_or((self.reverse, self.length_order), |saved| {
+                (saved.reverse, saved.length_order)
+            });
+        let progress = result.lock().unwrap().progress_saver();
+        let mut traversal: Vec<u8> = (self.min_len..=self.max_len).collect();
+        let descending = match length_order {
+            Some(LengthOrder::Ascending) => false,
+            Some(LengthOrder::Descending) => true,
+            Some(LengthOrder::Interleaved) | None => reverse,
+        };
+        if descending {
+            traversal.reverse();
+        }
+        let layout = Arc::new(Layout::new(&traversal, &charset, self.no_repeats)?);
+
+        // Indices left to test, with the ranges a restored run already tested
+        let mut start = unit_range.start.saturating_add(self.skip).min(layout.total);
+        let mut end = unit_range.end.min(layout.total);
+        if let Some((len, index)) = self.start_position(&charset)? {
+            // Reversed runs count the positions of a length down from its last one
+            let position = if reverse {
+                layout
+                    .count(len)
+                    .and_then(|count| count.checked_sub(index.checked_add(1)?))
+            } else {
+                Some(index)
+            };
+            if let Some(index) = position.and_then(|position| layout.index(len, position)) {
+                start = start.max(index);
+            }
+        }
+        let mut completed = Vec::new();
+        if let Some(saved) = &resume_from {
+            // The session matches the length range, so its length is part of the run
+            let offset = layout.index(saved.length, 0).unwrap_or(0);
+            start = start.max(offset + saved.low_water);
+            completed = saved
+                .completed
+                .iter()
+                .map(|range| offset + range.start..offset + range.end)
+                .collect();
+        }
+        if let Some(Position::BruteForce { length, index }) =
+            progress.as_ref().and_then(|p| p.saved())
+        {
+            start = start.max(layout.index(length, index).unwrap_or(start));
+        }
+        if let Some(limit) = self.limit {
+            end = end.min(start.saturating_add(limit));
+        }
+        let range = start..end.max(start);
+
+        let first_wave = if self.smart_order {
+            let mut wave = simple_candidates(&charset, self.min_len, self.max_len);
+            wave.retain(|candidate| self.policy.allows(candidate));
+            if self.no_repeats {
+                wave.retain(|candidate| {
+                    let distinct: HashSet<char> = candidate.chars().collect();
+                    distinct.len() == candidate.chars().count()
+                });
+            }
+            wave
+        } else {
+            Vec::new()
+        };
+
+        // Part of the range in every length, with the candidates left to test
+        // after the policy skipped its share
+        let stages: Vec<(usize, Range<u128>, u128)> = layout
+            .lengths
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, &(len, count, offset))| {
+                let part = range.start.max(offset)..range.end.min(offset + count);
+                if part.is_empty() {
+                    return None;
+                }
+                let done: u128 = completed
+                    .iter()
+                    .map(|done: &Range<u128>| {
+                        done.end
+                            .min(part.end)
+                            .saturating_sub(done.start.max(part.start))
+                    })
+                    .sum();
+                let raw = (part.end - part.start).saturating_sub(done);
+                let remaining = self.policy.estimate_allowed(&charset, len as usize, raw);
+                Some((slot, part, remaining))
+            })
+            .collect();
+        result.lock().unwrap().progress.total = Some(
+            stages
+                .iter()
+                .map(|(_, _, remaining)| *remaining)
+                .fold(0u128, u128::saturating_add),
+        );
+
+        let found = if first_wave.is_empty() {
+            false
+        } else {
+            println!(
+                "Trying {} repeats, sequences and palindromes first",
+                first_wave.len()
+            );
+            result
+                .lock()
+                .unwrap()
+                .begin_stage("simple candidates".to_string(), first_wave.len() as u128);
+            first_wave
+                .par_chunks(self.chunk_size)
                 .find_any(|chunk| Self::process_chunk(chunk, pkcs12, result))
                 .is_some()
-            {
-                break;
+        };
+
+        if !layout.lengths.is_empty() {
+            if let Some(session) = &session {
+                let layout = Arc::clone(&layout);
+                session.start_run(range.start, &completed, move |index| {
+                    let (slot, position) = layout.locate(index);
+                    (layout.lengths[slot].0, position)
+                });
             }
+            if let Some(progress) = &progress {
+                let layout = Arc::clone(&layout);
+                progress.begin_mapped(range.start, move |index| {
+                    let (slot, position) = layout.locate(index);
+                    Position::BruteForce {
+                        length: layout.lengths[slot].0,
+                        index: position,
+                    }
+                });
+            }
+        }
+
+        let run = RunState {
+            layout: &layout,
+            reverse,
+            interleaved: length_order == Some(LengthOrder::Interleaved),
+            skip: &completed,
+            session: session.as_ref(),
+            progress: progress.as_deref(),
+            tried: &first_wave.iter().cloned().collect(),
+        };
+        if !found {
+            self.process_range(&charset, &stages, &run, pkcs12, result);
+        }
+
+        if let Some(session) = &session {
+            session.save()?;
         }
 
         Ok(())
     }
+
+    fn estimate_count(&self) -> Option<u128> {
+        let charset_len = self.charset.chars().count() as u128;
+        if !self.policy.is_empty() {
+            let charset: Vec<char> = self.charset.chars().collect();
+            return (self.min_len..=self.max_len).try_fold(0u128, |total, len| {
+                match self.policy.count_allowed(&charset, len as usize) {
+                    u128::MAX => None,
+                    count => total.checked_add(count),
+                }
+            });
+        }
+        if self.no_repeats {
+            let charset: Vec<char> = self.charset.chars().collect();
+            return match self.total_combinations(&charset) {
+                CombinationCount::Exact(total) => Some(total),
+                CombinationCount::TooLarge => None,
+            };
+        }
+        (self.min_len..=self.max_len).try_fold(0u128, |total, len| {
+            total.checked_add(charset_len.checked_pow(len as u32)?)
+        })
+    }
+
+    fn work_items(&self) -> Result<Option<CombinationCount>> {
+        if self.probability_model.is_some() {
+            return Ok(None);
+        }
+        let charset: Vec<char> = self.charset.chars().collect();
+        Ok(Some(self.total_combinations(&charset)))
+    }
+
+    fn mode(&self) -> CrackMode {
+        CrackMode::BruteForce
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crackers::policy::CharClass;
+    use crate::test_fixtures::fixture;
+
+    #[test]
+    fn test_in_flight_lowest() {
+        let in_flight = InFlight::default();
+        assert_eq!(in_flight.start(100), Some(100));
+        assert_eq!(in_flight.start(200), None);
+        assert_eq!(in_flight.start(50), Some(50));
+        assert_eq!(in_flight.finish(100), None);
+        assert_eq!(in_flight.finish(50), Some(200));
+        assert_eq!(in_flight.finish(200), None);
+    }
+
+    #[test]
+    fn test_layout_numbers_lengths_consecutively() {
+        let digits: Vec<char> = ('0'..='9').collect();
+        let layout = Layout::new(&[1, 2, 3], &digits, false).unwrap();
+        assert_eq!(layout.total, 1110);
+        assert_eq!(layout.index(2, 5), Some(15));
+        assert_eq!(layout.index(4, 0), None);
+        assert_eq!(layout.locate(9), (0, 9));
+        assert_eq!(layout.locate(10), (1, 0));
+        assert_eq!(layout.locate(1109), (2, 999));
+        // The end of the run maps to the end of the last length
+        assert_eq!(layout.locate(1110), (2, 1000));
+
+        let reversed = Layout::new(&[3, 2, 1], &digits, false).unwrap();
+        assert_eq!(reversed.index(1, 0), Some(1100));
+        assert!(Layout::new(&[255], &digits, false).is_err());
+    }
+
+    #[test]
+    fn test_chunk_queue_orders() {
+        let stages = [(0, 0..10), (1, 10..110), (2, 110..250)];
+        let slots = |interleaved| {
+            chunk_queue(&stages, 50, interleaved)
+                .map(|(slot, range)| (slot, range.start))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            slots(false),
+            [(0, 0), (1, 10), (1, 60), (2, 110), (2, 160), (2, 210)]
+        );
+        // Exhausted lengths leave the rotation to the remaining ones
+        assert_eq!(
+            slots(true),
+            [(0, 0), (1, 10), (2, 110), (1, 60), (2, 160), (2, 210)]
+        );
+        // Every chunk of a length stays within it
+        assert!(chunk_queue(&stages, 50, true).all(|(slot, range)| {
+            range.start >= stages[slot].1.start && range.end <= stages[slot].1.end
+        }));
+    }
+
+    #[test]
+    fn test_skip_and_limit_cross_lengths() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+
+        // Candidates 5 to 19: the last five of length 1, the first ten of length 2
+        let cracker = BruteforceCracker::new(1, 2, "0123456789".to_string())
+            .with_skip(5)
+            .with_limit(15)
+            .with_session(path.clone(), false);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 15);
+
+        let saved = Checkpoint::load(&path).unwrap();
+        assert_eq!((saved.length, saved.low_water), (2, 10));
+
+        // Resuming continues at the same global position
+        let cracker =
+            BruteforceCracker::new(1, 2, "0123456789".to_string()).with_session(path, true);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 90);
+    }
+
+    #[test]
+    fn test_estimate_count_matches_attempts() {
+        let cracker = BruteforceCracker::new(1, 2, "0123456789".to_string());
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+
+        assert_eq!(cracker.estimate_count(), Some(110));
+        assert_eq!(result.lock().unwrap().get_attempts() as u128, 110);
+    }
+
+    #[test]
+    fn test_simple_candidates() {
+        let charset: Vec<char> = "abc".chars().collect();
+        assert_eq!(
+            simple_candidates(&charset, 3, 3),
+            vec!["aaa", "bbb", "ccc", "abc", "cba", "aba", "aca", "bab", "bcb", "cac", "cbc"]
+        );
+        assert_eq!(simple_candidates(&charset, 1, 1), vec!["a", "b", "c"]);
+
+        let printable: Vec<char> = crate::charset::printable_ascii().chars().collect();
+        let wave = simple_candidates(&printable, 9, 9);
+        assert!(wave.contains(&"123456789".to_string()));
+        assert!(wave.contains(&"ZZZZZZZZZ".to_string()));
+    }
+
+    #[test]
+    fn test_smart_order_counts_every_candidate_once() {
+        let cracker = BruteforceCracker::new(1, 3, "0123456789".to_string()).with_smart_order();
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+
+        assert_eq!(cracker.estimate_count(), Some(1110));
+        assert_eq!(result.lock().unwrap().get_attempts(), 1110);
+    }
+
+    #[test]
+    fn test_start_at_and_limit() {
+        let cracker =
+            BruteforceCracker::new(1, 2, "0123456789".to_string()).with_start_at("5".to_string());
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 105);
+
+        let cracker =
+            BruteforceCracker::new(1, 2, "0123456789".to_string()).with_start_at("99".to_string());
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 1);
+
+        let cracker = BruteforceCracker::new(1, 2, "0123456789".to_string())
+            .with_start_at("5".to_string())
+            .with_limit(20);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 20);
+
+        let cracker =
+            BruteforceCracker::new(1, 2, "0123456789".to_string()).with_start_at("5a".to_string());
+        assert!(cracker
+            .crack(
+                &fixture("umlaut.p12"),
+                &Arc::new(Mutex::new(CrackResult::new()))
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_reverse_order() {
+        // The password "pä55" is near the top of the length 4 keyspace, so
+        // walking backwards finds it before most candidates are tried
+        let cracker = BruteforceCracker::new(3, 4, "5äp".to_string()).with_reverse();
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().password.as_deref(), Some("pä55"));
+        assert!(result.lock().unwrap().get_attempts() < 81);
+
+        // --start-at and --limit follow the traversal order
+        let cracker = BruteforceCracker::new(1, 2, "0123456789".to_string())
+            .with_reverse()
+            .with_start_at("90".to_string())
+            .with_limit(15);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 15);
+    }
+
+    #[test]
+    fn test_restore_keeps_direction() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        let pkcs12 = fixture("umlaut.p12");
+
+        let cracker = BruteforceCracker::new(1, 2, "0123456789".to_string())
+            .with_reverse()
+            .with_session(path.clone(), false);
+        cracker
+            .crack(&pkcs12, &Arc::new(Mutex::new(CrackResult::new())))
+            .unwrap();
+        let mut saved = Checkpoint::load(&path).unwrap();
+        assert!(saved.reverse);
+        assert_eq!((saved.length, saved.low_water), (1, 10));
+
+        // Interrupted after the first 30 positions of length 2, i.e. 99 down to 70
+        saved.length = 2;
+        saved.low_water = 30;
+        saved.save(&path).unwrap();
+
+        let cracker =
+            BruteforceCracker::new(1, 2, "0123456789".to_string()).with_session(path.clone(), true);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&pkcs12, &result).unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 80);
+        assert!(Checkpoint::load(&path).unwrap().reverse);
+    }
+
+    #[test]
+    fn test_progress_stages() {
+        let cracker = BruteforceCracker::new(1, 2, "0123456789".to_string());
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+
+        let progress = result.lock().unwrap().progress.clone();
+        assert_eq!(progress.total, Some(110));
+        let stage = progress.stage.unwrap();
+        assert_eq!(
+            (stage.label.as_str(), stage.total, stage.start_attempts),
+            ("length 2", 100, 10)
+        );
+    }
+
+    #[test]
+    fn test_restore_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        let pkcs12 = fixture("umlaut.p12");
+
+        let cracker = BruteforceCracker::new(1, 2, "0123456789".to_string())
+            .with_session(path.clone(), false);
+        cracker
+            .crack(&pkcs12, &Arc::new(Mutex::new(CrackResult::new())))
+            .unwrap();
+        let mut saved = Checkpoint::load(&path).unwrap();
+        assert_eq!((saved.length, saved.low_water), (2, 100));
+
+        // Pretend the run was interrupted halfway through length 2
+        saved.low_water = 50;
+        saved.completed = vec![60..70, 80..90];
+        saved.save(&path).unwrap();
+
+        let cracker =
+            BruteforceCracker::new(1, 2, "0123456789".to_string()).with_session(path.clone(), true);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&pkcs12, &result).unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 30);
+
+        let cracker = BruteforceCracker::new(1, 2, "0123".to_string()).with_session(path, true);
+        assert!(cracker
+            .crack(&pkcs12, &Arc::new(Mutex::new(CrackResult::new())))
+            .is_err());
+    }
+
+    #[test]
+    fn test_policy_skips_candidates() {
+        let policy = CandidatePolicy::new(&[CharClass::Upper], &[(CharClass::Digit, 2)]);
+        let cracker = BruteforceCracker::new(1, 4, "aB12".to_string()).with_policy(policy);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+
+        // Length 3: "B" in one of 3 places, 2^2 digit choices
+        // Length 4: two digits and "B" or "a" + "B" (72), or three digits and "B" (32)
+        assert_eq!(cracker.estimate_count(), Some(12 + 104));
+        let result = result.lock().unwrap();
+        assert_eq!(result.get_attempts(), 116);
+        assert_eq!(result.progress.total, Some(116));
+    }
+
+    #[test]
+    fn test_keyspace_beyond_usize() {
+        // 2^65 candidates of length 65, the password is the very last one
+        let pkcs12 = fixture("last_candidate.p12");
+        let last = "b".repeat(65);
+
+        let cracker = BruteforceCracker::new(65, 65, "ab".to_string())
+            .with_work_unit(WorkUnit { index: 1, total: 2 })
+            .with_start_at(format!("{}a", &last[1..]));
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&pkcs12, &result).unwrap();
+        assert_eq!(
+            result.lock().unwrap().password.as_deref(),
+            Some(last.as_str())
+        );
+        assert_eq!(result.lock().unwrap().get_attempts(), 2);
+        assert_eq!(
+            result.lock().unwrap().progress.total,
+            Some(2),
+            "the unit ends at the end of the keyspace"
+        );
+    }
+
+    #[test]
+    fn test_estimate_count_overflow() {
+        let cracker = BruteforceCracker::new(1, 255, "0123456789".to_string());
+        assert_eq!(cracker.estimate_count(), None);
+    }
+
+    #[test]
+    fn test_probability_model_order() {
+        let model = ProbabilityModel::from_weights(
+            [("p", 0.5), ("5", 0.3), ("ä", 0.2)]
+                .iter()
+                .map(|&(key, weight)| (key.to_string(), weight))
+                .collect(),
+        )
+        .unwrap();
+        let cracker =
+            BruteforceCracker::new(4, 4, "5äp".to_string()).with_probability_model(model.clone());
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+
+        // 37 candidates are more likely than "pä55", 12 are as likely
+        let result = result.lock().unwrap();
+        assert_eq!(result.password.as_deref(), Some("pä55"));
+        assert!((38..=49).contains(&result.get_attempts()));
+
+        let cracker = BruteforceCracker::new(4, 4, "5äp".to_string())
+            .with_probability_model(model)
+            .with_limit(37);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        let result = result.lock().unwrap();
+        assert_eq!(result.password, None);
+        assert_eq!(result.get_attempts(), 37);
+    }
+
+    #[test]
+    fn test_no_repeats() {
+        let cracker = BruteforceCracker::new(1, 5, "abcd".to_string()).with_no_repeats();
+        // 4 + 4·3 + 4·3·2 + 4·3·2·1, length 5 has no candidates
+        assert_eq!(cracker.estimate_count(), Some(64));
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 64);
+
+        let cracker = BruteforceCracker::new(4, 4, "5äp".to_string())
+            .with_no_repeats()
+            .with_start_at("p5ä".to_string());
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        assert!(cracker.crack(&fixture("umlaut.p12"), &result).is_err());
+
+        let cracker = BruteforceCracker::new(3, 3, "5äp".to_string())
+            .with_no_repeats()
+            .with_start_at("p55".to_string());
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        let error = cracker.crack(&fixture("umlaut.p12"), &result).unwrap_err();
+        assert!(error.to_string().contains("repeats"));
+    }
+
+    #[test]
+    fn test_found_short_length_cancels_longer_lengths() {
+        // "pä55" is candidate 103 of the 9840 of lengths 1 to 8
+        let cracker = BruteforceCracker::new(1, 8, "5äp".to_string()).with_chunk_size(1);
+        let session = crate::session::CrackSession::new(4).unwrap();
+        session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+
+        let result = session.result().lock().unwrap();
+        assert_eq!(result.password.as_deref(), Some("pä55"));
+        // Workers may test a few later chunks before the one holding the
+        // password, but no chunk of lengths 7 and 8 (from 1092 on) is taken
+        assert!(result.get_attempts() < 1092);
+    }
 }
@@ -2,23 +2,302 @@
 //!
 //! This module provides functionality for cracking PKCS#12 passwords
 //! using a dictionary file with memory-mapped parallel processing.
-use crate::types::{CrackResult, PasswordCracker};
-use anyhow::{Context, Result};
+use super::rules::{self, RuleOp};
+use crate::preprocessing::{self, SortOrder};
+use crate::progress_file::Position;
+use crate::types::{
+    CombinationCount, CrackMode, CrackResult, PasswordCracker, PkcsError, RunState, WorkUnit,
+};
+use crate::unicode::{self, WordEncoding};
+use anyhow::{bail, Context, Result};
+use encoding_rs::{Encoding, UTF_16LE, UTF_8, WINDOWS_1252};
 use memmap2::Mmap;
 use openssl::pkcs12::Pkcs12;
 use rayon::prelude::*;
+use std::borrow::Cow;
 use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::ops::Range;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use tempfile::NamedTempFile;
+
+/// `--delimiter` value that enables delimiter auto-detection
+pub const AUTO_DELIMITER: &str = "auto";
+
+/// Number of leading bytes inspected by delimiter auto-detection
+const DELIMITER_SAMPLE_SIZE: usize = 4096;
+
+/// Number of entries tested in parallel with `--skip-n` and `--first-n`
+const ENTRY_BATCH_SIZE: usize = 4096;
+
+/// Guesses the entry delimiter of a dictionary from a sample of its content.
+///
+/// Tab- and comma-separated files are recognized by having more separators
+/// than newlines, colon-separated files by having several colons on a single
+/// line. Everything else is treated as newline-separated.
+///
+/// # Arguments
+///
+/// * `sample` - Leading bytes of the dictionary file
+///
+/// # Returns
+///
+/// Returns `None` if the sample is empty.
+pub fn detect_delimiter(sample: &[u8]) -> Option<char> {
+    let sample = &sample[..sample.len().min(DELIMITER_SAMPLE_SIZE)];
+    if sample.is_empty() {
+        return None;
+    }
+
+    let count = |byte: u8| sample.iter().filter(|&&b| b == byte).count();
+    let newlines = count(b'\n');
+
+    if count(b'\t') > newlines {
+        Some('\t')
+    } else if count(b',') > newlines {
+        Some(',')
+    } else if count(b':') > 1 && !sample.trim_ascii_end().contains(&b'\n') {
+        Some(':')
+    } else {
+        Some('\n')
+    }
+}
+
+/// How the entries of a dictionary are separated, from `--delimiter`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DelimiterMode {
+    /// A single byte, split at as the character `byte as char`
+    SingleByte(u8),
+    /// A string of several bytes, e.g. `||` or a non-ASCII character
+    MultiByteStr(String),
+    /// Lines ending in `\r\n` or a lone `\n`
+    Crlf,
+    /// Detected from the content of the dictionary, see [`detect_delimiter`]
+    Auto,
+}
+
+impl DelimiterMode {
+    /// Returns the string between two entries, a newline for
+    /// [`DelimiterMode::Crlf`] and an unresolved [`DelimiterMode::Auto`].
+    fn separator(&self) -> Cow<'_, str> {
+        match self {
+            Self::SingleByte(byte) => Cow::Owned(char::from(*byte).to_string()),
+            Self::MultiByteStr(delimiter) => Cow::Borrowed(delimiter),
+            Self::Crlf | Self::Auto => Cow::Borrowed("\n"),
+        }
+    }
+
+    /// Splits decoded dictionary content into its raw entries.
+    ///
+    /// [`DelimiterMode::Crlf`] also strips the carriage return of every line.
+    fn split<'a>(&'a self, text: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        match self {
+            Self::SingleByte(byte) => Box::new(text.split(char::from(*byte))),
+            Self::MultiByteStr(delimiter) => Box::new(text.split(delimiter.as_str())),
+            Self::Crlf | Self::Auto => Box::new(
+                text.split('\n')
+                    .map(|line| line.strip_suffix('\r').unwrap_or(line)),
+            ),
+        }
+    }
+
+    /// Returns a printable name for the delimiter.
+    fn name(&self) -> String {
+        match self {
+            Self::SingleByte(b'\t') => "TAB".to_string(),
+            Self::SingleByte(b'\n') => "NEWLINE".to_string(),
+            Self::SingleByte(b',') => "COMMA".to_string(),
+            Self::SingleByte(b':') => "COLON".to_string(),
+            Self::Crlf => "CRLF".to_string(),
+            Self::Auto => "auto".to_string(),
+            other => format!("'{}'", other.separator()),
+        }
+    }
+}
+
+/// Parses a `--delimiter` value.
+///
+/// Escape sequences such as `\n`, `\r\n` and `\t` are translated as in
+/// `--custom-chars`. A single ASCII character becomes
+/// [`DelimiterMode::SingleByte`], a CRLF line ending [`DelimiterMode::Crlf`]
+/// and [`AUTO_DELIMITER`] [`DelimiterMode::Auto`], anything else is split at
+/// as a whole.
+///
+/// # Errors
+///
+/// Returns an error if an escape sequence is invalid or the delimiter is empty
+pub fn parse_delimiter(value: &str) -> Result<DelimiterMode> {
+    if value == AUTO_DELIMITER {
+        return Ok(DelimiterMode::Auto);
+    }
+    let delimiter = crate::charset::unescape_chars(value)
+        .with_context(|| format!("Invalid delimiter '{value}'"))?;
+    Ok(match delimiter.as_str() {
+        "" => bail!("The delimiter is empty"),
+        "\r\n" => DelimiterMode::Crlf,
+        single if single.len() == 1 => DelimiterMode::SingleByte(single.as_bytes()[0]),
+        _ => DelimiterMode::MultiByteStr(delimiter),
+    })
+}
+
+/// Resolves a `--wordlist-encoding` name.
+///
+/// `latin-1` is decoded as Windows-1252, its superset used by virtually all
+/// "Latin-1" wordlists in practice.
+///
+/// # Errors
+///
+/// Returns an error for encodings other than UTF-8, Latin-1, Windows-1252 and UTF-16LE
+pub fn parse_encoding(name: &str) -> Result<&'static Encoding> {
+    match name.to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" => Ok(UTF_8),
+        "latin-1" | "latin1" | "iso-8859-1" | "windows-1252" | "cp1252" => Ok(WINDOWS_1252),
+        "utf-16le" | "utf16le" => Ok(UTF_16LE),
+        _ => bail!(
+            "Unsupported wordlist encoding '{name}', expected utf-8, latin-1, windows-1252 \
+             or utf-16le"
+        ),
+    }
+}
+
+/// Encodes a delimiter in the encoding of a dictionary.
+///
+/// # Returns
+///
+/// The encoded delimiter and the size of a code unit of the encoding, the
+/// delimiter can only start at multiples of it.
+fn encoded_delimiter(delimiter: &DelimiterMode, encoding: &'static Encoding) -> (Vec<u8>, usize) {
+    let separator = delimiter.separator();
+    if encoding == UTF_16LE {
+        let units: Vec<u8> = separator
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        (units, 2)
+    } else {
+        let (bytes, _, _) = encoding.encode(&separator);
+        (bytes.into_owned(), 1)
+    }
+}
+
+/// Splits dictionary content into pieces of about `piece_len` bytes that
+/// end at an entry boundary.
+///
+/// # Arguments
+///
+/// * `content` - Dictionary content without its byte order mark
+/// * `delimiter` - Encoded delimiter, from [`encoded_delimiter`]
+/// * `code_unit` - Size of a code unit of the encoding
+/// * `piece_len` - Minimum length of every piece but the last
+fn pieces<'a>(
+    content: &'a [u8],
+    delimiter: &'a [u8],
+    code_unit: usize,
+    piece_len: usize,
+) -> impl Iterator<Item = &'a [u8]> + 'a {
+    let mut rest = content;
+    let mut done = false;
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let start = piece_len.min(rest.len()) / code_unit * code_unit;
+        let split = (start..=rest.len().saturating_sub(delimiter.len()))
+            .step_by(code_unit)
+            .find(|&i| rest[i..].starts_with(delimiter));
+        let piece = match split {
+            Some(i) => {
+                let piece = &rest[..i];
+                rest = &rest[i + delimiter.len()..];
+                piece
+            }
+            None => {
+                done = true;
+                rest
+            }
+        };
+        Some(piece)
+    })
+}
+
+/// Returns the bytes of the entries that start in a chunk of dictionary content.
+///
+/// Chunks are cut at fixed offsets, so an entry may straddle two of them. It
+/// belongs to the chunk it starts in, which reads on to the delimiter ending
+/// it, so every entry is tested exactly once. The last chunk also owns the
+/// empty entry after a trailing delimiter.
+///
+/// # Arguments
+///
+/// * `content` - Dictionary content without its byte order mark
+/// * `chunk` - Byte range of the chunk, starting at a multiple of `code_unit`
+/// * `delimiter` - Encoded delimiter, from [`encoded_delimiter`]
+/// * `code_unit` - Size of a code unit of the encoding
+///
+/// # Returns
+///
+/// Returns `None` if no entry starts in the chunk.
+fn chunk_entries(
+    content: &[u8],
+    chunk: Range<usize>,
+    delimiter: &[u8],
+    code_unit: usize,
+) -> Option<Range<usize>> {
+    // First delimiter at or after `from`
+    let find = |from: usize| {
+        (from..=content.len().saturating_sub(delimiter.len()))
+            .step_by(code_unit)
+            .find(|&i| content[i..].starts_with(delimiter))
+    };
+    // An entry starts right after a delimiter ending at or after the chunk start
+    let start = match chunk.start {
+        0 => 0,
+        chunk_start => find(chunk_start.saturating_sub(delimiter.len()))? + delimiter.len(),
+    };
+    if chunk.end == content.len() {
+        return (start <= chunk.end).then_some(start..chunk.end);
+    }
+    if start >= chunk.end {
+        return None;
+    }
+    let end = find(chunk.end.saturating_sub(delimiter.len()).max(start)).unwrap_or(content.len());
+    Some(start..end)
+}
 
 /// Implements dictionary-based password cracking.
 ///
 /// Uses memory mapping and parallel processing to efficiently test passwords.
+#[derive(Clone)]
 pub struct DictionaryCracker {
     /// Path to the dictionary file
     dictionary_path: PathBuf,
     /// Delimiter used to separate entries in the dictionary file
-    delimiter: String,
+    delimiter: DelimiterMode,
+    /// Whether surrounding whitespace is stripped from every entry
+    trim: bool,
+    /// Share of the dictionary processed by this cracker
+    work_unit: WorkUnit,
+    /// Suffixes additionally appended to every entry
+    suffixes: Vec<String>,
+    /// Encoding of the dictionary file, overridden by a byte order mark
+    encoding: &'static Encoding,
+    /// Conversion of the words replacing `encoding`, if any
+    word_encoding: Option<WordEncoding>,
+    /// Order to sort the entries in before cracking, with the memory budget
+    /// of an external sort in bytes
+    sort: Option<(SortOrder, Option<usize>)>,
+    /// Rules mangling every entry, the entry itself is only tried by a `:` rule
+    rules: Vec<Vec<RuleOp>>,
+    /// Number of leading non-empty entries to skip
+    skip_n: usize,
+    /// Maximum number of non-empty entries to test, `None` for all
+    first_n: Option<usize>,
 }
 
 impl DictionaryCracker {
@@ -27,51 +306,511 @@ impl DictionaryCracker {
     /// # Arguments
     ///
     /// * `dictionary_path` - Path to the dictionary file
-    /// * `delimiter` - Character used to separate entries in the file
-    pub fn new(dictionary_path: PathBuf, delimiter: String) -> Self {
+    /// * `delimiter` - How entries are separated in the file
+    pub fn new(dictionary_path: PathBuf, delimiter: DelimiterMode) -> Self {
         Self {
             dictionary_path,
             delimiter,
+            trim: true,
+            work_unit: WorkUnit::FULL,
+            suffixes: Vec::new(),
+            encoding: UTF_8,
+            word_encoding: None,
+            sort: None,
+            rules: Vec::new(),
+            skip_n: 0,
+            first_n: None,
+        }
+    }
+
+    /// Keeps leading and trailing whitespace of every entry.
+    ///
+    /// Only the carriage return of CRLF line endings is still stripped.
+    pub fn without_trim(mut self) -> Self {
+        self.trim = false;
+        self
+    }
+
+    /// Restricts the cracker to a share of the dictionary.
+    ///
+    /// The dictionary is split by chunks of `CHUNK_SIZE` bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_unit` - Share of the dictionary to process
+    pub fn with_work_unit(mut self, work_unit: WorkUnit) -> Self {
+        self.work_unit = work_unit;
+        self
+    }
+
+    /// Additionally tries every entry with each of the suffixes appended.
+    ///
+    /// The entry itself is always tried first.
+    ///
+    /// # Arguments
+    ///
+    /// * `suffixes` - Suffixes to append, e.g. from [`super::mutations::numeric_suffixes`]
+    pub fn with_suffixes(mut self, suffixes: Vec<String>) -> Self {
+        self.suffixes = suffixes;
+        self
+    }
+
+    /// Sets the encoding of the dictionary file.
+    ///
+    /// A byte order mark at the start of the file takes precedence.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - Encoding from [`parse_encoding`]
+    pub fn with_encoding(mut self, encoding: &'static Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Converts the words with a [`WordEncoding`] instead of an encoding.
+    ///
+    /// [`WordEncoding::Latin1`] maps every byte to the code point of the same
+    /// value, as Java keytool does, where Windows-1252 maps 0x80–0x9F to other
+    /// characters. A byte order mark still takes precedence.
+    ///
+    /// # Arguments
+    ///
+    /// * `word_encoding` - Conversion of the raw words
+    pub fn with_word_encoding(mut self, word_encoding: WordEncoding) -> Self {
+        // Single-byte delimiters are encoded as in Windows-1252
+        self.encoding = WINDOWS_1252;
+        self.word_encoding = Some(word_encoding);
+        self
+    }
+
+    /// Tries every entry as mangled by each of the rules instead of as is.
+    ///
+    /// Suffixes are appended to every mangled entry. Entries a rule rejects
+    /// are skipped for that rule.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - Rules from [`rules::parse_rules_file`], in order
+    pub fn with_rules(mut self, rules: Vec<Vec<RuleOp>>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Skips the first non-empty entries of the dictionary.
+    ///
+    /// Entries are counted in file order, after sorting with `with_sort`.
+    /// Skipping or limiting the entries reads the dictionary sequentially,
+    /// `with_work_unit` and progress files do not apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Number of entries to skip
+    pub fn with_skip_n(mut self, count: usize) -> Self {
+        self.skip_n = count;
+        self
+    }
+
+    /// Stops after testing a number of non-empty entries.
+    ///
+    /// Counted from the first entry after `with_skip_n`, so that consecutive
+    /// runs can test a dictionary batch by batch. Rules and suffixes still
+    /// apply to every tested entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - Maximum number of entries to test
+    pub fn with_first_n(mut self, count: usize) -> Self {
+        self.first_n = Some(count);
+        self
+    }
+
+    /// Sorts the dictionary entries before cracking.
+    ///
+    /// The sorted entries are written to a temporary UTF-8 file, which is
+    /// cracked instead of the dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - Order to sort the entries in
+    /// * `chunk_bytes` - Sort externally in runs of about this many bytes
+    ///   instead of in memory
+    pub fn with_sort(mut self, order: SortOrder, chunk_bytes: Option<usize>) -> Self {
+        self.sort = Some((order, chunk_bytes));
+        self
+    }
+
+    /// Writes the sorted entries of the dictionary to a temporary file.
+    ///
+    /// # Returns
+    ///
+    /// A cracker for the sorted file and the file, which is deleted when dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dictionary cannot be read or the sorted file
+    /// cannot be written.
+    fn sorted(
+        &self,
+        order: SortOrder,
+        chunk_bytes: Option<usize>,
+    ) -> Result<(Self, NamedTempFile)> {
+        let dict_file = self.open()?;
+        let mmap = unsafe { Mmap::map(&dict_file)? };
+        let delimiter = self.resolve_delimiter(&mmap);
+        let (encoding, bom_len) = self.resolve_encoding(&mmap);
+        let (encoded_delimiter, code_unit) = encoded_delimiter(&delimiter, encoding);
+
+        let piece_len = chunk_bytes.unwrap_or(usize::MAX);
+        let pieces = pieces(&mmap[bom_len..], &encoded_delimiter, code_unit, piece_len);
+        let words = pieces.flat_map(|piece| {
+            let (text, _) = self.decode(piece, encoding);
+            delimiter
+                .split(&text)
+                .map(|entry| Self::entry_password(entry, self.trim).to_string())
+                .collect::<Vec<_>>()
+        });
+
+        let mut file = NamedTempFile::new().context("Failed to create sorted dictionary")?;
+        match chunk_bytes {
+            Some(chunk_bytes) => {
+                preprocessing::sort_wordlist_external(
+                    words,
+                    order,
+                    chunk_bytes,
+                    &delimiter.separator(),
+                    file.as_file_mut(),
+                )?;
+            }
+            None => {
+                let sorted = preprocessing::sort_wordlist(words.collect(), order);
+                file.write_all(sorted.join(&delimiter.separator()).as_bytes())
+                    .context("Failed to write sorted dictionary")?;
+            }
+        }
+
+        let cracker = Self {
+            dictionary_path: file.path().to_path_buf(),
+            delimiter,
+            encoding: UTF_8,
+            word_encoding: None,
+            sort: None,
+            ..self.clone()
+        };
+        Ok((cracker, file))
+    }
+
+    /// Streams the non-empty entries of the dictionary, in file order.
+    ///
+    /// The file is decoded piece by piece, so only a piece of about
+    /// `CHUNK_SIZE` bytes is held in memory at a time.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - Called with every entry, stops the iteration by returning `true`
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if `f` stopped the iteration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dictionary cannot be read.
+    pub(crate) fn for_each_word(&self, mut f: impl FnMut(&str) -> bool) -> Result<bool> {
+        let dict_file = self.open()?;
+        let mmap = unsafe { Mmap::map(&dict_file)? };
+        let delimiter = self.resolve_delimiter(&mmap);
+        let (encoding, bom_len) = self.resolve_encoding(&mmap);
+        let (encoded_delimiter, code_unit) = encoded_delimiter(&delimiter, encoding);
+
+        for piece in pieces(
+            &mmap[bom_len..],
+            &encoded_delimiter,
+            code_unit,
+            super::CHUNK_SIZE,
+        ) {
+            let (text, _) = self.decode(piece, encoding);
+            for entry in delimiter.split(&text) {
+                let word = Self::entry_password(entry, self.trim);
+                if !word.is_empty() && f(word) {
+                    return Ok(true);
+                }
+            }
         }
+        Ok(false)
     }
 
     /// Processes a chunk of the dictionary file.
     ///
     /// # Safety(!)
     ///
-    /// Assumes the chunk is valid UTF-8. Invalid UTF-8 sequences are skipped.
+    /// Invalid sequences in the chunk are replaced with U+FFFD.
     ///
     /// # Arguments
     ///
-    /// * `chunk` - Bytes from the memory-mapped file
-    /// * `delimiter` - Character separating passwords in the file
-    /// * `pkcs12` - The PKCS#12 certificate to crack
-    /// * `result` - Shared result tracking structure
+    /// * `chunk` - Whole entries from the memory-mapped file, see [`chunk_entries`]
+    /// * `delimiter` - Resolved delimiter separating passwords in the file
+    /// * `encoding` - Encoding of the dictionary file
+    /// * `pkcs12s` - The PKCS#12 certificates to crack
+    /// * `results` - Result tracking structure of every certificate
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` once all certificates are cracked.
     #[inline(always)]
     fn process_chunk(
+        &self,
         chunk: &[u8],
-        delimiter: char,
-        pkcs12: &Pkcs12,
-        result: &Arc<Mutex<CrackResult>>,
+        delimiter: &DelimiterMode,
+        encoding: &'static Encoding,
+        pkcs12s: &[Arc<Pkcs12>],
+        results: &[Arc<Mutex<CrackResult>>],
     ) -> bool {
-        if let Ok(text) = std::str::from_utf8(chunk) {
-            for line in text.split(delimiter) {
-                {
-                    let result_guard = result.lock().unwrap();
-                    if result_guard.password.is_some() {
-                        return true;
+        // Decode lossily so that an invalid sequence in the file only damages
+        // the affected entry instead of the whole chunk.
+        let (text, had_errors) = self.decode(chunk, encoding);
+        if had_errors {
+            log::debug!(
+                "Replaced invalid {} sequences in a dictionary chunk with U+FFFD",
+                encoding.name()
+            );
+        }
+        let runs = super::runs(results);
+        let mut candidate = String::new();
+        for line in delimiter.split(&text) {
+            let word = Self::entry_password(line, self.trim);
+            if self.process_entry(word, &mut candidate, pkcs12s, &runs, results) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Tests an entry as mangled by each of the rules, or as is without rules.
+    ///
+    /// # Arguments
+    ///
+    /// * `word` - Dictionary entry
+    /// * `candidate` - Reusable buffer for the candidates
+    /// * `pkcs12s` - The PKCS#12 certificates to crack
+    /// * `runs` - Run state of every certificate, see [`super::runs`]
+    /// * `results` - Result tracking structure of every certificate
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` once all certificates are cracked.
+    #[inline(always)]
+    fn process_entry(
+        &self,
+        word: &str,
+        candidate: &mut String,
+        pkcs12s: &[Arc<Pkcs12>],
+        runs: &[Arc<RunState>],
+        results: &[Arc<Mutex<CrackResult>>],
+    ) -> bool {
+        if self.rules.is_empty() {
+            return self.process_word(word, candidate, pkcs12s, runs, results);
+        }
+        self.rules.iter().any(|ops| {
+            rules::apply_ruleset(ops, word).is_some_and(|mangled| {
+                self.process_word(&mangled, candidate, pkcs12s, runs, results)
+            })
+        })
+    }
+
+    /// Tests the entries selected by `with_skip_n` and `with_first_n`.
+    ///
+    /// The entries are streamed in file order and tested in parallel
+    /// batches, so exactly the selected entries are tried.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dictionary cannot be read.
+    fn crack_entries(
+        &self,
+        pkcs12s: &[Arc<Pkcs12>],
+        results: &[Arc<Mutex<CrackResult>>],
+    ) -> Result<()> {
+        let end = self
+            .first_n
+            .map_or(usize::MAX, |count| self.skip_n.saturating_add(count));
+        let runs = super::runs(results);
+        let test_batch = |batch: &mut Vec<String>| {
+            let found = batch
+                .par_iter()
+                .any(|word| self.process_entry(word, &mut String::new(), pkcs12s, &runs, results));
+            batch.clear();
+            found
+        };
+
+        let mut seen = 0usize;
+        let mut found = false;
+        let mut batch = Vec::new();
+        self.for_each_word(|word| {
+            if seen >= end {
+                return true;
+            }
+            seen += 1;
+            if seen > self.skip_n {
+                batch.push(word.to_string());
+                found = batch.len() == ENTRY_BATCH_SIZE && test_batch(&mut batch);
+            }
+            found
+        })?;
+        if !found && !batch.is_empty() {
+            found = test_batch(&mut batch);
+        }
+        if !found && seen > self.skip_n {
+            println!("Tested dictionary entries {} to {seen}", self.skip_n + 1);
+        } else if !found {
+            println!("No dictionary entries left after skipping {seen}");
+        }
+        Ok(())
+    }
+
+    /// Tests a word and the word with each of the suffixes appended.
+    ///
+    /// # Arguments
+    ///
+    /// * `word` - Dictionary entry, mangled by a rule if any
+    /// * `candidate` - Reusable buffer for the candidates
+    /// * `pkcs12s` - The PKCS#12 certificates to crack
+    /// * `runs` - Run state of every certificate, see [`super::runs`]
+    /// * `results` - Result tracking structure of every certificate
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` once all certificates are cracked or the run is stopped.
+    #[inline(always)]
+    fn process_word(
+        &self,
+        word: &str,
+        candidate: &mut String,
+        pkcs12s: &[Arc<Pkcs12>],
+        runs: &[Arc<RunState>],
+        results: &[Arc<Mutex<CrackResult>>],
+    ) -> bool {
+        for suffix in std::iter::once("").chain(self.suffixes.iter().map(String::as_str)) {
+            candidate.clear();
+            candidate.push_str(word);
+            candidate.push_str(suffix);
+
+            let mut all_cracked = true;
+            let mut oversized = false;
+            for run in runs {
+                if !run.is_stopped() {
+                    all_cracked = false;
+                    if run.skips_oversized(candidate) {
+                        oversized = true;
+                    } else {
+                        run.increment_attempts();
                     }
-                    result_guard.increment_attempts();
                 }
+            }
+            if all_cracked {
+                return true;
+            }
+            if oversized {
+                continue;
+            }
 
-                let password = line.trim().to_string();
-                if super::check_password(pkcs12, &password, result) {
-                    return true;
-                }
+            if super::check_password_multi(pkcs12s, candidate, runs, results) {
+                return true;
             }
         }
         false
     }
+
+    /// Tests the entries of a raw dictionary chunk against a certificate,
+    /// for the `fuzz_process_chunk` fuzz target.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the password was found.
+    #[cfg(fuzzing)]
+    #[doc(hidden)]
+    pub fn fuzz_process_chunk(&self, chunk: &[u8], pkcs12: &Arc<Pkcs12>) -> bool {
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        self.process_chunk(
+            chunk,
+            &self.delimiter,
+            self.encoding,
+            std::slice::from_ref(pkcs12),
+            std::slice::from_ref(&result),
+        )
+    }
+
+    /// Opens the dictionary file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PkcsError::DictionaryNotFound`] if the file does not exist,
+    /// or the I/O error if it cannot be opened.
+    fn open(&self) -> Result<File> {
+        File::open(&self.dictionary_path).map_err(|error| match error.kind() {
+            ErrorKind::NotFound => {
+                PkcsError::DictionaryNotFound(self.dictionary_path.clone()).into()
+            }
+            _ => anyhow::Error::new(error).context("Failed to open dictionary file"),
+        })
+    }
+
+    /// Returns the delimiter to split the dictionary by.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Content of the dictionary file, sniffed for `--delimiter auto`
+    fn resolve_delimiter(&self, content: &[u8]) -> DelimiterMode {
+        match &self.delimiter {
+            DelimiterMode::Auto => {
+                DelimiterMode::SingleByte(detect_delimiter(content).unwrap_or('\n') as u8)
+            }
+            delimiter => delimiter.clone(),
+        }
+    }
+
+    /// Decodes a piece of the dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - Bytes of the dictionary file
+    /// * `encoding` - Encoding from [`Self::resolve_encoding`]
+    ///
+    /// # Returns
+    ///
+    /// The text and whether invalid sequences were replaced with U+FFFD.
+    fn decode<'a>(&self, bytes: &'a [u8], encoding: &'static Encoding) -> (Cow<'a, str>, bool) {
+        match self.word_encoding {
+            // Another encoding comes from a byte order mark
+            Some(WordEncoding::Latin1) if encoding == self.encoding => {
+                (Cow::Owned(unicode::latin1_to_pkcs12_password(bytes)), false)
+            }
+            _ => encoding.decode_without_bom_handling(bytes),
+        }
+    }
+
+    /// Returns the encoding of the dictionary and the length of its byte order mark.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Content of the dictionary file
+    fn resolve_encoding(&self, content: &[u8]) -> (&'static Encoding, usize) {
+        Encoding::for_bom(content).unwrap_or((self.encoding, 0))
+    }
+
+    /// Turns a dictionary entry into the password to try.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - Raw entry between two delimiters
+    /// * `trim` - Whether to strip surrounding whitespace
+    #[inline(always)]
+    fn entry_password(entry: &str, trim: bool) -> &str {
+        if trim {
+            entry.trim()
+        } else {
+            entry.strip_suffix('\r').unwrap_or(entry)
+        }
+    }
 }
 
 impl PasswordCracker for DictionaryCracker {
@@ -83,20 +822,500 @@ impl PasswordCracker for DictionaryCracker {
     /// - The dictionary file cannot be opened or read
     /// - Memory mapping fails
     fn crack(&self, pkcs12: &Arc<Pkcs12>, result: &Arc<Mutex<CrackResult>>) -> Result<()> {
+        self.crack_many(std::slice::from_ref(pkcs12), std::slice::from_ref(result))
+    }
+
+    /// Attempts to crack several certificates in a single pass over the dictionary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The dictionary file cannot be opened or read
+    /// - Memory mapping fails
+    fn crack_many(
+        &self,
+        pkcs12s: &[Arc<Pkcs12>],
+        results: &[Arc<Mutex<CrackResult>>],
+    ) -> Result<()> {
+        if let Some((order, chunk_bytes)) = self.sort {
+            let (cracker, _sorted_file) = self.sorted(order, chunk_bytes)?;
+            println!("Sorted dictionary by {order}");
+            return cracker.crack_many(pkcs12s, results);
+        }
+
         println!(
             "Starting dictionary attack with {} threads",
             rayon::current_num_threads()
         );
+        if self.skip_n > 0 || self.first_n.is_some() {
+            return self.crack_entries(pkcs12s, results);
+        }
 
-        let dict_file =
-            File::open(&self.dictionary_path).context("Failed to open dictionary file")?;
+        let dict_file = self.open()?;
 
         let mmap = unsafe { Mmap::map(&dict_file)? };
-        let delimiter = self.delimiter.as_bytes()[0] as char;
+        let delimiter = self.resolve_delimiter(&mmap);
+        if self.delimiter == DelimiterMode::Auto && delimiter != DelimiterMode::SingleByte(b'\n') {
+            println!("Auto-detected delimiter: {}", delimiter.name());
+        }
+
+        let (encoding, bom_len) = self.resolve_encoding(&mmap);
+        if bom_len > 0 && encoding != self.encoding {
+            println!("Detected {} byte order mark", encoding.name());
+        }
+        // Skipping the BOM keeps UTF-16 chunks aligned to code units
+        let content = &mmap[bom_len..];
+        let (encoded_delimiter, code_unit) = encoded_delimiter(&delimiter, encoding);
+
+        let unit_range = self
+            .work_unit
+            .range(content.len().div_ceil(super::CHUNK_SIZE) as u128);
+        let mut start = unit_range.start as usize * super::CHUNK_SIZE;
+        let end = (unit_range.end as usize * super::CHUNK_SIZE).min(content.len());
+
+        // Progress files are only supported for a single certificate
+        let saver = match results {
+            [result] => result.lock().unwrap().progress_saver(),
+            _ => None,
+        };
+        if let Some(saver) = &saver {
+            if let Some(Position::Dictionary { offset }) = saver.saved() {
+                // Saved offsets are chunk boundaries, keep the chunks aligned
+                // so that entries are split exactly as in the saved run
+                let offset = (offset as usize).saturating_sub(bom_len);
+                start = start.max(offset - offset % super::CHUNK_SIZE).min(end);
+            }
+            saver.begin(Position::Dictionary {
+                offset: (bom_len + start) as u64,
+            });
+        }
 
-        mmap.par_chunks(super::CHUNK_SIZE)
-            .find_any(|chunk| Self::process_chunk(chunk, delimiter, pkcs12, result));
+        content[start..end]
+            .par_chunks(super::CHUNK_SIZE)
+            .enumerate()
+            .find_any(|(chunk_idx, chunk)| {
+                let chunk_start = start + chunk_idx * super::CHUNK_SIZE;
+                let entries = chunk_entries(
+                    content,
+                    chunk_start..chunk_start + chunk.len(),
+                    &encoded_delimiter,
+                    code_unit,
+                );
+                if entries.is_some_and(|entries| {
+                    self.process_chunk(&content[entries], &delimiter, encoding, pkcs12s, results)
+                }) {
+                    return true;
+                }
+                if let Some(saver) = &saver {
+                    let chunk_start = (bom_len + chunk_start) as u128;
+                    saver.complete(chunk_start..chunk_start + chunk.len() as u128);
+                }
+                false
+            });
 
         Ok(())
     }
+
+    /// Counts the dictionary entries, i.e. the non-overlapping delimiters
+    /// plus one, times the number of variants tried per entry.
+    ///
+    /// Entries rejected by rules are counted as well.
+    ///
+    /// Returns `None` if the dictionary cannot be read.
+    fn estimate_count(&self) -> Option<u128> {
+        let dict_file = File::open(&self.dictionary_path).ok()?;
+        let mmap = unsafe { Mmap::map(&dict_file).ok()? };
+        let delimiter = self.resolve_delimiter(&mmap);
+        let (encoding, bom_len) = self.resolve_encoding(&mmap);
+
+        let (delimiter, code_unit) = encoded_delimiter(&delimiter, encoding);
+        let content = &mmap[bom_len..];
+        let mut delimiters = 0usize;
+        let mut i = 0;
+        while i + delimiter.len() <= content.len() {
+            if content[i..].starts_with(&delimiter) {
+                delimiters += 1;
+                i += delimiter.len();
+            } else {
+                i += code_unit;
+            }
+        }
+        let entries = (delimiters as u128 + 1)
+            .saturating_sub(self.skip_n as u128)
+            .min(self.first_n.map_or(u128::MAX, |count| count as u128));
+        Some(entries * self.rules.len().max(1) as u128 * (self.suffixes.len() as u128 + 1))
+    }
+
+    fn work_items(&self) -> Result<Option<CombinationCount>> {
+        let len = self.open()?.metadata()?.len() as u128;
+        Ok(Some(CombinationCount::Exact(
+            len.div_ceil(super::CHUNK_SIZE as u128),
+        )))
+    }
+
+    fn mode(&self) -> CrackMode {
+        CrackMode::Dictionary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crackers::mutations::{fixed_numeric_suffixes, numeric_suffixes};
+    use crate::test_fixtures::fixture;
+    use std::io::Write;
+
+    #[test]
+    fn test_estimate_count_matches_attempts() {
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        write!(wordlist, "alpha\nbeta\ngamma\ndelta").unwrap();
+
+        let cracker = DictionaryCracker::new(
+            wordlist.path().to_path_buf(),
+            DelimiterMode::SingleByte(b'\n'),
+        );
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+
+        assert_eq!(cracker.estimate_count(), Some(4));
+        assert_eq!(result.lock().unwrap().get_attempts() as u128, 4);
+    }
+
+    #[test]
+    fn test_skip_n_and_first_n() {
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        let words: Vec<String> = (0..10_000).map(|i| format!("word{i}")).collect();
+        write!(wordlist, "\n{}\npä55\n", words.join("\n\n")).unwrap();
+        let cracker = || {
+            DictionaryCracker::new(
+                wordlist.path().to_path_buf(),
+                DelimiterMode::SingleByte(b'\n'),
+            )
+        };
+        let run = |cracker: DictionaryCracker| {
+            let result = Arc::new(Mutex::new(CrackResult::new()));
+            cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+            let result = result.lock().unwrap();
+            (result.password.clone(), result.get_attempts())
+        };
+
+        // Empty entries are not counted, the window spans several batches
+        assert_eq!(run(cracker().with_first_n(5000)), (None, 5000));
+        assert_eq!(
+            run(cracker().with_skip_n(9000).with_first_n(2000)),
+            (Some("pä55".to_string()), 1001)
+        );
+        assert_eq!(run(cracker().with_skip_n(9999).with_first_n(1)), (None, 1));
+        assert_eq!(run(cracker().with_skip_n(20_000)), (None, 0));
+        assert_eq!(cracker().with_first_n(3).estimate_count(), Some(3));
+        assert_eq!(
+            cracker()
+                .with_suffixes(numeric_suffixes(1))
+                .with_skip_n(2)
+                .with_first_n(3)
+                .estimate_count(),
+            Some(33)
+        );
+    }
+
+    #[test]
+    fn test_parse_delimiter() {
+        assert_eq!(
+            parse_delimiter("\n").unwrap(),
+            DelimiterMode::SingleByte(b'\n')
+        );
+        assert_eq!(
+            parse_delimiter("\\n").unwrap(),
+            DelimiterMode::SingleByte(b'\n')
+        );
+        assert_eq!(
+            parse_delimiter("\\t").unwrap(),
+            DelimiterMode::SingleByte(b'\t')
+        );
+        assert_eq!(
+            parse_delimiter(";").unwrap(),
+            DelimiterMode::SingleByte(b';')
+        );
+        assert_eq!(parse_delimiter("\\r\\n").unwrap(), DelimiterMode::Crlf);
+        assert_eq!(parse_delimiter("\r\n").unwrap(), DelimiterMode::Crlf);
+        assert_eq!(parse_delimiter("auto").unwrap(), DelimiterMode::Auto);
+        assert_eq!(
+            parse_delimiter("||").unwrap(),
+            DelimiterMode::MultiByteStr("||".to_string())
+        );
+        assert_eq!(
+            parse_delimiter("§").unwrap(),
+            DelimiterMode::MultiByteStr("§".to_string())
+        );
+
+        assert!(parse_delimiter("").is_err());
+        assert!(parse_delimiter("\\q").is_err());
+    }
+
+    #[test]
+    fn test_multi_byte_delimiter() {
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        // A lone "|" belongs to the entry, "|||" ends one and starts the next with "|"
+        write!(wordlist, "al|pha||be|||ta||pä55").unwrap();
+
+        let cracker = DictionaryCracker::new(
+            wordlist.path().to_path_buf(),
+            DelimiterMode::MultiByteStr("||".to_string()),
+        )
+        .without_trim();
+        let mut words = Vec::new();
+        cracker
+            .for_each_word(|word| {
+                words.push(word.to_string());
+                false
+            })
+            .unwrap();
+        assert_eq!(words, ["al|pha", "be", "|ta", "pä55"]);
+
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        let result = result.lock().unwrap();
+        assert_eq!(result.password.as_deref(), Some("pä55"));
+        assert_eq!(result.get_attempts(), 4);
+        assert_eq!(cracker.estimate_count(), Some(4));
+    }
+
+    #[test]
+    fn test_crlf_delimiter() {
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        write!(wordlist, "alpha \r\nbeta\npä55\r\n").unwrap();
+
+        let cracker = DictionaryCracker::new(wordlist.path().to_path_buf(), DelimiterMode::Crlf)
+            .without_trim();
+        let mut words = Vec::new();
+        cracker
+            .for_each_word(|word| {
+                words.push(word.to_string());
+                false
+            })
+            .unwrap();
+        assert_eq!(words, ["alpha ", "beta", "pä55"]);
+
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().password.as_deref(), Some("pä55"));
+    }
+
+    #[test]
+    fn test_rules_mangle_entries() {
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        write!(wordlist, "alpha\nPÄ\nbeta").unwrap();
+        let pkcs12 = fixture("word42.p12");
+
+        // "l $5 $5" turns "PÄ" into "pä55"
+        let rules = ["d", "l $5 $5", "@a"]
+            .iter()
+            .map(|rule| rules::parse_rule(rule).unwrap())
+            .collect();
+        let cracker = DictionaryCracker::new(
+            wordlist.path().to_path_buf(),
+            DelimiterMode::SingleByte(b'\n'),
+        )
+        .with_rules(rules);
+        assert_eq!(cracker.estimate_count(), Some(3 * 3));
+
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().password.as_deref(), Some("pä55"));
+
+        // Suffixes are appended to the mangled entries, not the entries
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        write!(wordlist, "swim\nwant").unwrap();
+        let cracker = DictionaryCracker::new(
+            wordlist.path().to_path_buf(),
+            DelimiterMode::SingleByte(b'\n'),
+        )
+        .with_rules(vec![rules::parse_rule("]]]").unwrap()])
+        .with_suffixes(vec!["ord42".to_string()]);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&pkcs12, &result).unwrap();
+        let result = result.lock().unwrap();
+        assert_eq!(result.password.as_deref(), Some("word42"));
+        assert_eq!(result.get_attempts(), 4);
+    }
+
+    #[test]
+    fn test_append_numbers() {
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        write!(wordlist, "pass\nword\n").unwrap();
+        let pkcs12 = fixture("word42.p12");
+
+        let cracker = DictionaryCracker::new(
+            wordlist.path().to_path_buf(),
+            DelimiterMode::SingleByte(b'\n'),
+        )
+        .with_suffixes(numeric_suffixes(2));
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&pkcs12, &result).unwrap();
+        assert_eq!(result.lock().unwrap().password.as_deref(), Some("word42"));
+
+        let cracker = DictionaryCracker::new(
+            wordlist.path().to_path_buf(),
+            DelimiterMode::SingleByte(b'\n'),
+        )
+        .with_suffixes(fixed_numeric_suffixes(3));
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&pkcs12, &result).unwrap();
+        assert_eq!(result.lock().unwrap().password, None);
+    }
+
+    #[test]
+    fn test_utf16_estimate_count() {
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        let bytes: Vec<u8> = "\u{feff}alpha\nbeta\ngamma"
+            .encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect();
+        wordlist.write_all(&bytes).unwrap();
+
+        let cracker = DictionaryCracker::new(
+            wordlist.path().to_path_buf(),
+            DelimiterMode::SingleByte(b'\n'),
+        );
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+
+        assert_eq!(cracker.estimate_count(), Some(3));
+        assert_eq!(result.lock().unwrap().get_attempts(), 3);
+    }
+
+    #[test]
+    fn test_parse_encoding() {
+        assert_eq!(parse_encoding("UTF-8").unwrap(), UTF_8);
+        assert_eq!(parse_encoding("latin-1").unwrap(), WINDOWS_1252);
+        assert_eq!(parse_encoding("utf-16le").unwrap(), UTF_16LE);
+        assert!(parse_encoding("koi8-r").is_err());
+    }
+
+    #[test]
+    fn test_detect_delimiter_tsv() {
+        assert_eq!(
+            detect_delimiter(b"alpha\tbeta\tgamma\ndelta\tepsilon\n"),
+            Some('\t')
+        );
+    }
+
+    #[test]
+    fn test_detect_delimiter_csv() {
+        assert_eq!(
+            detect_delimiter(b"alpha,beta,gamma\ndelta,epsilon\n"),
+            Some(',')
+        );
+    }
+
+    #[test]
+    fn test_detect_delimiter_colon() {
+        assert_eq!(detect_delimiter(b"alpha:beta:gamma:delta\n"), Some(':'));
+        assert_eq!(detect_delimiter(b"user:alpha\nuser:beta\n"), Some('\n'));
+    }
+
+    #[test]
+    fn test_detect_delimiter_newline() {
+        assert_eq!(detect_delimiter(b"alpha\nbeta\ngamma\n"), Some('\n'));
+        assert_eq!(detect_delimiter(b""), None);
+    }
+
+    #[test]
+    fn test_entry_password_trim() {
+        let entries: Vec<&str> = "first\n secret \nlast\r"
+            .split('\n')
+            .map(|e| DictionaryCracker::entry_password(e, true))
+            .collect();
+        assert_eq!(entries, vec!["first", "secret", "last"]);
+    }
+
+    #[test]
+    fn test_entry_password_no_trim() {
+        let entries: Vec<&str> = "first\n secret \nlast\r"
+            .split('\n')
+            .map(|e| DictionaryCracker::entry_password(e, false))
+            .collect();
+        assert_eq!(entries, vec!["first", " secret ", "last"]);
+    }
+
+    #[test]
+    fn test_chunk_entries_cover_every_entry_once() {
+        let entries = |content: &[u8], delimiter: &[u8], chunk_size: usize| {
+            let text = std::str::from_utf8(content).unwrap();
+            let delimiter_text = std::str::from_utf8(delimiter).unwrap();
+            (0..content.len().max(1))
+                .step_by(chunk_size)
+                .filter_map(|start| {
+                    let end = (start + chunk_size).min(content.len());
+                    chunk_entries(content, start..end, delimiter, 1)
+                })
+                .flat_map(|range| text[range].split(delimiter_text).map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+        for (content, delimiter) in [
+            ("alpha\nbeta\n\ngamma\npä55", "\n"),
+            ("alpha\nbeta\n", "\n"),
+            ("", "\n"),
+            ("al|pha||be||pä55||", "||"),
+            ("alphaäbetaääpä55", "ä"),
+        ] {
+            let expected: Vec<_> = content.split(delimiter).collect();
+            for chunk_size in 1..=content.len() + 1 {
+                assert_eq!(
+                    entries(content.as_bytes(), delimiter.as_bytes(), chunk_size),
+                    expected,
+                    "{content:?} in chunks of {chunk_size}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_entry_across_chunk_boundary() {
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        // The password starts 2 bytes before the end of the first chunk
+        let filler = "a".repeat(super::super::CHUNK_SIZE - 3);
+        write!(wordlist, "{filler}\npä55\nbeta").unwrap();
+
+        let cracker = DictionaryCracker::new(
+            wordlist.path().to_path_buf(),
+            DelimiterMode::SingleByte(b'\n'),
+        );
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+        let result = result.lock().unwrap();
+        assert_eq!(result.password.as_deref(), Some("pä55"));
+        assert!(result.get_attempts() <= 3);
+    }
+
+    #[test]
+    fn test_for_each_word_streams_pieces() {
+        let mut wordlist = tempfile::NamedTempFile::new().unwrap();
+        let entries: Vec<String> = (0..5000).map(|i| format!("word{i}")).collect();
+        write!(wordlist, "{}\r\n\r\n", entries.join("\r\n")).unwrap();
+
+        let cracker = DictionaryCracker::new(
+            wordlist.path().to_path_buf(),
+            DelimiterMode::SingleByte(b'\n'),
+        );
+        let mut words = Vec::new();
+        let stopped = cracker
+            .for_each_word(|word| {
+                words.push(word.to_string());
+                false
+            })
+            .unwrap();
+        assert!(!stopped);
+        assert_eq!(words, entries);
+
+        let mut seen = 0;
+        let stopped = cracker
+            .for_each_word(|word| {
+                seen += 1;
+                word == "word42"
+            })
+            .unwrap();
+        assert!(stopped);
+        assert_eq!(seen, 43);
+    }
 }
@@ -0,0 +1,396 @@
+//! Edit-distance password cracking implementation.
+//!
+//! This module provides functionality for cracking passwords that are close
+//! to a remembered base word: every candidate within a Damerau-Levenshtein
+//! distance of it, reached by substitutions from the charset, insertions,
+//! deletions and adjacent transpositions. Candidates are tested in increasing
+//! distance order and every candidate is tested once.
+use crate::keyspace;
+use crate::types::{CrackMode, CrackResult, PasswordCracker, WorkUnit};
+use anyhow::Result;
+use openssl::pkcs12::Pkcs12;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// Largest distance that is searched without a keyspace warning.
+pub const FULLY_SUPPORTED_DISTANCE: u8 = 2;
+
+/// Candidates around a base word, one distance at a time.
+///
+/// Yields the base word itself first, then every candidate one edit away
+/// from it, then every candidate two edits away and so on. A candidate is
+/// only yielded at its smallest distance.
+pub struct Neighbourhood {
+    /// Distinct characters used for substitutions and insertions
+    charset: Vec<char>,
+    /// Every candidate yielded so far
+    seen: HashSet<String>,
+    /// Candidates of the last yielded distance
+    frontier: Vec<String>,
+    /// Whether the base word itself has been yielded
+    started: bool,
+}
+
+impl Neighbourhood {
+    /// Creates the neighbourhood of a base word.
+    ///
+    /// # Arguments
+    ///
+    /// * `word` - The base word
+    /// * `charset` - Characters to substitute and insert, duplicates are ignored
+    pub fn new(word: &str, charset: &[char]) -> Self {
+        let mut distinct = Vec::with_capacity(charset.len());
+        for &c in charset {
+            if !distinct.contains(&c) {
+                distinct.push(c);
+            }
+        }
+        Self {
+            charset: distinct,
+            seen: HashSet::from([word.to_string()]),
+            frontier: vec![word.to_string()],
+            started: false,
+        }
+    }
+
+    /// Pushes every string one edit away from `word` that was not seen yet.
+    fn single_edits(&mut self, word: &str, next: &mut Vec<String>) {
+        let chars: Vec<char> = word.chars().collect();
+        let mut emit = |candidate: Vec<char>| {
+            let candidate: String = candidate.into_iter().collect();
+            if self.seen.insert(candidate.clone()) {
+                next.push(candidate);
+            }
+        };
+
+        for i in 0..=chars.len() {
+            if i < chars.len() {
+                let mut deleted = chars.clone();
+                deleted.remove(i);
+                emit(deleted);
+            }
+            if i + 1 < chars.len() && chars[i] != chars[i + 1] {
+                let mut transposed = chars.clone();
+                transposed.swap(i, i + 1);
+                emit(transposed);
+            }
+            for &c in &self.charset {
+                if i < chars.len() && chars[i] != c {
+                    let mut substituted = chars.clone();
+                    substituted[i] = c;
+                    emit(substituted);
+                }
+                let mut inserted = chars.clone();
+                inserted.insert(i, c);
+                emit(inserted);
+            }
+        }
+    }
+}
+
+impl Iterator for Neighbourhood {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            return Some(self.frontier.clone());
+        }
+        if self.frontier.is_empty() {
+            return None;
+        }
+
+        let mut next = Vec::new();
+        for word in std::mem::take(&mut self.frontier) {
+            self.single_edits(&word, &mut next);
+        }
+        self.frontier = next.clone();
+        Some(next)
+    }
+}
+
+/// Computes an upper bound on the number of candidates within a distance.
+///
+/// A word of `length` characters has at most `length` deletions,
+/// `length - 1` transpositions, `length * (charset_len - 1)` substitutions
+/// and `(length + 1) * charset_len` insertions, and every edit lengthens
+/// the word by at most one character.
+///
+/// # Arguments
+///
+/// * `length` - Number of characters of the base word
+/// * `charset_len` - Number of characters to substitute and insert
+/// * `distance` - Largest number of edits
+///
+/// # Returns
+///
+/// The bound, saturating at `u128::MAX`.
+pub fn candidates_upper_bound(length: usize, charset_len: usize, distance: u8) -> u128 {
+    let charset_len = charset_len as u128;
+    let mut total: u128 = 1;
+    let mut at_distance: u128 = 1;
+    for step in 0..distance as u128 {
+        let length = length as u128 + step;
+        let edits = length
+            + length.saturating_sub(1)
+            + length * charset_len.saturating_sub(1)
+            + (length + 1) * charset_len;
+        at_distance = at_distance.saturating_mul(edits);
+        total = total.saturating_add(at_distance);
+    }
+    total
+}
+
+/// Implements edit-distance password cracking around a base word.
+pub struct EditDistanceCracker {
+    /// The word the password is believed to be close to
+    word: String,
+    /// Largest number of edits to apply
+    distance: u8,
+    /// Characters to substitute and insert
+    charset: Vec<char>,
+    /// Share of the candidates processed by this cracker
+    work_unit: WorkUnit,
+    /// Number of candidates per parallel work item
+    chunk_size: usize,
+}
+
+impl EditDistanceCracker {
+    /// Creates a new EditDistanceCracker instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `word` - The word the password is believed to be close to
+    /// * `distance` - Largest number of edits to apply
+    /// * `charset` - Characters to substitute and insert
+    pub fn new(word: String, distance: u8, charset: String) -> Self {
+        Self {
+            word,
+            distance,
+            charset: charset.chars().collect(),
+            work_unit: WorkUnit::FULL,
+            chunk_size: super::CHUNK_SIZE,
+        }
+    }
+
+    /// Restricts the cracker to a share of the candidates.
+    ///
+    /// Candidates of every distance are dealt out round-robin: unit `i` of
+    /// `n` tries every `n`-th candidate starting at the `i`-th.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_unit` - Share of the candidates to process
+    pub fn with_work_unit(mut self, work_unit: WorkUnit) -> Self {
+        self.work_unit = work_unit;
+        self
+    }
+
+    /// Sets the number of candidates per parallel work item.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - Candidates per chunk, at least 1
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Processes a chunk of password candidates.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - Password candidates to test
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    /// * `result` - Shared result tracking structure
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the correct password is found in this chunk.
+    fn process_chunk(chunk: &[String], pkcs12: &Pkcs12, result: &Arc<Mutex<CrackResult>>) -> bool {
+        let run = result.lock().unwrap().run();
+        for password in chunk {
+            if run.is_stopped() {
+                return true;
+            }
+            if run.skips_oversized(password) {
+                continue;
+            }
+            run.increment_attempts();
+
+            if super::check_password(pkcs12, password, &run, result) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl PasswordCracker for EditDistanceCracker {
+    /// Attempts to crack the PKCS#12 password with candidates around the base word.
+    ///
+    /// The candidates of each distance are generated before they are tested
+    /// in parallel, so memory grows with the keyspace of the largest distance.
+    fn crack(&self, pkcs12: &Arc<Pkcs12>, result: &Arc<Mutex<CrackResult>>) -> Result<()> {
+        println!(
+            "Starting edit distance attack around {:?} with distance up to {}",
+            self.word, self.distance
+        );
+        if self.distance > FULLY_SUPPORTED_DISTANCE {
+            let bound = candidates_upper_bound(
+                self.word.chars().count(),
+                self.charset.len(),
+                self.distance,
+            );
+            eprintln!(
+                "Warning: distance {} can produce up to {} candidates, each distance is held in memory",
+                self.distance,
+                keyspace::format_magnitude(bound)
+            );
+        }
+
+        let WorkUnit { index, total } = self.work_unit;
+        let levels = Neighbourhood::new(&self.word, &self.charset).take(self.distance as usize + 1);
+        for (distance, level) in levels.enumerate() {
+            let candidates: Vec<String> = level.into_iter().skip(index).step_by(total).collect();
+            result
+                .lock()
+                .unwrap()
+                .begin_stage(format!("distance {distance}"), candidates.len() as u128);
+
+            if candidates
+                .par_chunks(self.chunk_size)
+                .find_any(|chunk| Self::process_chunk(chunk, pkcs12, result))
+                .is_some()
+            {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn mode(&self) -> CrackMode {
+        CrackMode::EditDistance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Unrestricted Damerau-Levenshtein distance (Lowrance-Wagner).
+    fn damerau_levenshtein(a: &[char], b: &[char]) -> usize {
+        let infinity = a.len() + b.len();
+        let mut last_row: HashMap<char, usize> = HashMap::new();
+        let mut d = vec![vec![0; b.len() + 2]; a.len() + 2];
+        d[0][0] = infinity;
+        for i in 0..=a.len() {
+            d[i + 1][0] = infinity;
+            d[i + 1][1] = i;
+        }
+        for j in 0..=b.len() {
+            d[0][j + 1] = infinity;
+            d[1][j + 1] = j;
+        }
+        for i in 1..=a.len() {
+            let mut last_match_column = 0;
+            for j in 1..=b.len() {
+                let k = last_row.get(&b[j - 1]).copied().unwrap_or(0);
+                let l = last_match_column;
+                let cost = if a[i - 1] == b[j - 1] {
+                    last_match_column = j;
+                    0
+                } else {
+                    1
+                };
+                d[i + 1][j + 1] = (d[i][j] + cost)
+                    .min(d[i + 1][j] + 1)
+                    .min(d[i][j + 1] + 1)
+                    .min(d[k][l] + (i - k - 1) + 1 + (j - l - 1));
+            }
+            last_row.insert(a[i - 1], i);
+        }
+        d[a.len() + 1][b.len() + 1]
+    }
+
+    /// Every string over `alphabet` of at most `max_length` characters.
+    fn all_strings(alphabet: &[char], max_length: usize) -> Vec<String> {
+        let mut strings = vec![String::new()];
+        let mut previous = vec![String::new()];
+        for _ in 0..max_length {
+            let next: Vec<String> = previous
+                .iter()
+                .flat_map(|s| alphabet.iter().map(move |&c| format!("{s}{c}")))
+                .collect();
+            strings.extend(next.iter().cloned());
+            previous = next;
+        }
+        strings
+    }
+
+    fn sorted(mut strings: Vec<String>) -> Vec<String> {
+        strings.sort();
+        strings
+    }
+
+    #[test]
+    fn test_neighbourhood_distance_one() {
+        let levels: Vec<Vec<String>> = Neighbourhood::new("ab", &['a', 'b']).take(2).collect();
+        assert_eq!(levels[0], vec!["ab"]);
+        // Deletions, the transposition, substitutions and insertions
+        assert_eq!(
+            sorted(levels[1].clone()),
+            vec!["a", "aa", "aab", "aba", "abb", "b", "ba", "bab", "bb"]
+        );
+    }
+
+    #[test]
+    fn test_neighbourhood_matches_damerau_levenshtein() {
+        // Every string within distance 3 of "abc" over a 4 letter alphabet is
+        // at most 6 characters long, compare against a brute force check
+        let charset = ['a', 'b', 'c', 'd'];
+        let word: Vec<char> = "abc".chars().collect();
+        let levels: Vec<Vec<String>> = Neighbourhood::new("abc", &charset).take(4).collect();
+
+        let mut expected: Vec<Vec<String>> = vec![Vec::new(); 4];
+        for candidate in all_strings(&charset, 6) {
+            let chars: Vec<char> = candidate.chars().collect();
+            let distance = damerau_levenshtein(&word, &chars);
+            if distance < expected.len() {
+                expected[distance].push(candidate);
+            }
+        }
+
+        for (distance, (level, expected)) in levels.into_iter().zip(expected).enumerate() {
+            assert_eq!(sorted(level.clone()), sorted(expected), "{distance}");
+            let distinct: HashSet<&String> = level.iter().collect();
+            assert_eq!(distinct.len(), level.len(), "duplicates at {distance}");
+        }
+    }
+
+    #[test]
+    fn test_neighbourhood_ends_with_empty_levels() {
+        // The empty alphabet only allows deleting and transposing
+        let levels: Vec<Vec<String>> = Neighbourhood::new("ab", &[]).take(4).collect();
+        assert_eq!(sorted(levels[1].clone()), vec!["a", "b", "ba"]);
+        assert_eq!(levels[2], vec![""]);
+        assert!(levels[3].is_empty());
+    }
+
+    #[test]
+    fn test_candidates_upper_bound() {
+        let charset = ['a', 'b', 'c', 'd'];
+        for distance in 0..=3u8 {
+            let actual: usize = Neighbourhood::new("abc", &charset)
+                .take(distance as usize + 1)
+                .map(|level| level.len())
+                .sum();
+            assert!(candidates_upper_bound(3, 4, distance) >= actual as u128);
+        }
+        assert_eq!(candidates_upper_bound(3, 4, 0), 1);
+        assert_eq!(candidates_upper_bound(40, 1000, 255), u128::MAX);
+    }
+}
@@ -0,0 +1,390 @@
+//! John the Ripper word mangling rules.
+//!
+//! This module parses the rules of a section of a John the Ripper `.conf`
+//! file, e.g. `[List.Rules:Wordlist]`, into the [`RuleOp`]s of
+//! [`super::rules`], so both syntaxes share one execution engine. Most
+//! single-letter functions are the same in both tools. The differences to
+//! Hashcat `.rule` files:
+//!
+//! - Rules live in named sections; `#` and `;` start comment lines.
+//! - A preprocessor expands `[...]` lists and ranges into one rule per
+//!   character, e.g. `$[0-9]` into `$0` through `$9`, the last list
+//!   changing fastest. `\` escapes the next character, so deleting the
+//!   first character is written `\[`.
+//! - `AN"STR"` inserts a string at N, with `z` for the end of the word.
+//!   Any character may delimit the string instead of `"`.
+//! - Rejection functions drop a word: `>N`, `<N` and `_N` by length, `!X`,
+//!   `/X`, `(X`, `)X` and `=NX` by its characters.
+//! - Wherever a function takes a character to match, `s`, `@` and the
+//!   rejection functions, a class like `?v` for vowels matches several. An
+//!   uppercase class letter negates it, `??` matches `?`.
+//! - `p`, `R` and `L` pluralize and shift on the keyboard in John the
+//!   Ripper, Hashcat's `z` and `Z` do not exist. They are not supported,
+//!   nor are memory functions, numeric variables and the positions `*`,
+//!   `-`, `+`, `l`, `m` and `p`.
+//! - Rule flags `-:`, `-c` and `-8` at the start of a rule are accepted,
+//!   rules with other flags are skipped.
+use super::rules::{position, CharClass, ClassKind, RuleOp};
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Section read by default, the rules of John the Ripper's wordlist mode
+pub const DEFAULT_SECTION: &str = "Wordlist";
+
+/// Most rules a single line may expand into
+const MAX_EXPANSIONS: usize = 65536;
+
+/// Reads the rules of a section of a John the Ripper config file.
+///
+/// The section name is matched case-insensitively, like John the Ripper
+/// does. Rules with unsupported functions are skipped with a warning.
+///
+/// # Arguments
+///
+/// * `path` - The config file, e.g. `john.conf`
+/// * `section` - Name of the section without `List.Rules:`, e.g. `Wordlist`
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, has no such section or the
+/// section has no supported rule
+pub fn parse_john_rules_file(path: &Path, section: &str) -> Result<Vec<Vec<RuleOp>>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read John the Ripper rules: {}", path.display()))?;
+    let Some(lines) = section_lines(&content, section) else {
+        bail!("No section [List.Rules:{section}] in {}", path.display());
+    };
+
+    let mut rules = Vec::new();
+    let mut skipped = 0;
+    let mut first_error = None;
+    for (line_number, line) in lines {
+        let parsed = expand_rule(line).and_then(|expanded| {
+            expanded
+                .iter()
+                .map(|rule| parse_rule(rule))
+                .collect::<Result<Vec<_>>>()
+        });
+        match parsed {
+            Ok(parsed) => rules.extend(parsed),
+            Err(e) => {
+                skipped += 1;
+                first_error.get_or_insert((line_number, e));
+            }
+        }
+    }
+    if let Some((line, e)) = first_error {
+        eprintln!(
+            "Warning: skipped {skipped} unsupported rules of {}, first on line {line}: {e:#}",
+            path.display()
+        );
+    }
+    if rules.is_empty() {
+        bail!(
+            "No supported rules in section [List.Rules:{section}] of {}",
+            path.display()
+        );
+    }
+    Ok(rules)
+}
+
+/// Returns the rule lines of a section with their line numbers.
+///
+/// # Returns
+///
+/// `None` if the file has no `[List.Rules:SECTION]` header.
+fn section_lines<'a>(content: &'a str, section: &str) -> Option<Vec<(usize, &'a str)>> {
+    let header = format!("[List.Rules:{section}]");
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.strip_suffix('\r').unwrap_or(line)));
+    lines.find(|(_, line)| line.trim().eq_ignore_ascii_case(&header))?;
+    Some(
+        lines
+            .take_while(|(_, line)| !line.starts_with("[List."))
+            .filter(|(_, line)| {
+                !line.trim().is_empty() && !line.starts_with('#') && !line.starts_with(';')
+            })
+            .collect(),
+    )
+}
+
+/// Expands the `[...]` lists of the preprocessor into individual rules.
+///
+/// # Errors
+///
+/// Returns an error if a list is not closed or the line expands into more
+/// than [`MAX_EXPANSIONS`] rules
+pub fn expand_rule(line: &str) -> Result<Vec<String>> {
+    let mut choices: Vec<Vec<char>> = Vec::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => choices.push(vec![chars.next().unwrap_or('\\')]),
+            '[' => {
+                let mut list = Vec::new();
+                loop {
+                    match chars.next() {
+                        None => bail!("Unclosed '[' in rule '{line}'"),
+                        Some(']') => break,
+                        Some('\\') => list.extend(chars.next()),
+                        Some('-') if !list.is_empty() && !chars.as_str().starts_with(']') => {
+                            let start = *list.last().expect("list is not empty");
+                            let Some(end) = chars.next() else {
+                                bail!("Unclosed '[' in rule '{line}'");
+                            };
+                            list.extend((start..=end).skip(1));
+                        }
+                        Some(c) => list.push(c),
+                    }
+                }
+                if list.is_empty() {
+                    bail!("Empty '[]' in rule '{line}'");
+                }
+                choices.push(list);
+            }
+            c => choices.push(vec![c]),
+        }
+    }
+
+    let count = choices
+        .iter()
+        .try_fold(1usize, |count, list| count.checked_mul(list.len()))
+        .filter(|&count| count <= MAX_EXPANSIONS);
+    if count.is_none() {
+        bail!("Rule '{line}' expands into more than {MAX_EXPANSIONS} rules");
+    }
+    let mut rules = vec![String::new()];
+    for list in choices {
+        rules = rules
+            .iter()
+            .flat_map(|rule| {
+                list.iter().map(move |&c| {
+                    let mut rule = rule.clone();
+                    rule.push(c);
+                    rule
+                })
+            })
+            .collect();
+    }
+    Ok(rules)
+}
+
+/// Parses a character or a `?X` class to match.
+fn char_class(chars: &mut std::str::Chars, function: char) -> Result<CharClass> {
+    let missing = || format!("Rule function '{function}' is missing an argument");
+    let c = chars.next().with_context(missing)?;
+    if c != '?' {
+        return Ok(CharClass::Char(c));
+    }
+    let class = chars.next().with_context(missing)?;
+    let kind = match class.to_ascii_lowercase() {
+        '?' => return Ok(CharClass::Char('?')),
+        'v' => ClassKind::Vowel,
+        'c' => ClassKind::Consonant,
+        'w' => ClassKind::Whitespace,
+        'p' => ClassKind::Punctuation,
+        's' => ClassKind::Symbol,
+        'l' => ClassKind::Lower,
+        'u' => ClassKind::Upper,
+        'd' => ClassKind::Digit,
+        'a' => ClassKind::Letter,
+        'x' => ClassKind::Alphanumeric,
+        'z' => ClassKind::Any,
+        _ => bail!("Unsupported character class '?{class}'"),
+    };
+    Ok(if class.is_ascii_uppercase() {
+        CharClass::NotClass(kind)
+    } else {
+        CharClass::Class(kind)
+    })
+}
+
+/// Parses a rule after preprocessing into its rule functions.
+///
+/// Spaces between functions are ignored.
+///
+/// # Errors
+///
+/// Returns an error for unsupported functions, flags, classes or
+/// positions, and for missing arguments
+pub fn parse_rule(rule: &str) -> Result<Vec<RuleOp>> {
+    let mut chars = rule.chars();
+    if let Some(flags) = rule.strip_prefix('-') {
+        match flags.chars().next() {
+            Some(':' | 'c' | '8') => {
+                chars.nth(1);
+            }
+            flag => bail!(
+                "Unsupported rule flag '-{}'",
+                flag.map(String::from).unwrap_or_default()
+            ),
+        }
+    }
+
+    let mut ops = Vec::new();
+    while let Some(function) = chars.next() {
+        let missing = || format!("Rule function '{function}' is missing an argument");
+        let position_argument =
+            |chars: &mut std::str::Chars| position(chars.next().with_context(missing)?);
+        let op = match function {
+            ' ' => continue,
+            ':' => RuleOp::Noop,
+            'l' => RuleOp::Lowercase,
+            'u' => RuleOp::Uppercase,
+            'c' => RuleOp::Capitalize,
+            'C' => RuleOp::InvertCapitalize,
+            't' => RuleOp::ToggleCase,
+            'T' => RuleOp::ToggleAt(position_argument(&mut chars)?),
+            'r' => RuleOp::Reverse,
+            'd' => RuleOp::Duplicate,
+            'f' => RuleOp::Reflect,
+            '{' => RuleOp::RotateLeft,
+            '}' => RuleOp::RotateRight,
+            '$' => RuleOp::Append(chars.next().with_context(missing)?),
+            '^' => RuleOp::Prepend(chars.next().with_context(missing)?),
+            '[' => RuleOp::TruncateLeft,
+            ']' => RuleOp::TruncateRight,
+            'D' => RuleOp::DeleteAt(position_argument(&mut chars)?),
+            'i' => RuleOp::InsertAt(
+                position_argument(&mut chars)?,
+                chars.next().with_context(missing)?,
+            ),
+            'o' => RuleOp::OverwriteAt(
+                position_argument(&mut chars)?,
+                chars.next().with_context(missing)?,
+            ),
+            's' => RuleOp::Replace(
+                char_class(&mut chars, function)?,
+                chars.next().with_context(missing)?,
+            ),
+            '@' => RuleOp::Purge(char_class(&mut chars, function)?),
+            '>' => RuleOp::RejectUnlessLonger(position_argument(&mut chars)?),
+            '<' => RuleOp::RejectUnlessShorter(position_argument(&mut chars)?),
+            '_' => RuleOp::RejectUnlessLength(position_argument(&mut chars)?),
+            '!' => RuleOp::RejectIfContains(char_class(&mut chars, function)?),
+            '/' => RuleOp::RejectUnlessContains(char_class(&mut chars, function)?),
+            '(' => RuleOp::RejectUnlessFirst(char_class(&mut chars, function)?),
+            ')' => RuleOp::RejectUnlessLast(char_class(&mut chars, function)?),
+            '=' => RuleOp::RejectUnlessAt(
+                position_argument(&mut chars)?,
+                char_class(&mut chars, function)?,
+            ),
+            'A' => {
+                let at = chars.next().with_context(missing)?;
+                let at = if at == 'z' { None } else { Some(position(at)?) };
+                let delimiter = chars.next().with_context(missing)?;
+                let Some((string, rest)) = chars.as_str().split_once(delimiter) else {
+                    bail!("Unterminated string of rule function 'A'");
+                };
+                // One insertion per character, the string keeps its order
+                ops.extend(string.chars().enumerate().map(|(i, c)| match at {
+                    Some(at) => RuleOp::InsertAt(at + i, c),
+                    None => RuleOp::Append(c),
+                }));
+                chars = rest.chars();
+                continue;
+            }
+            _ => bail!("Unsupported rule function '{function}'"),
+        };
+        ops.push(op);
+    }
+    Ok(ops)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crackers::rules::apply_ruleset;
+    use std::io::Write;
+
+    fn apply(rule: &str, word: &str) -> Option<String> {
+        let [rule] = expand_rule(rule).unwrap().try_into().unwrap();
+        apply_ruleset(&parse_rule(&rule).unwrap(), word)
+    }
+
+    #[test]
+    fn test_shared_functions() {
+        assert_eq!(apply("c $1", "password").as_deref(), Some("Password1"));
+        assert_eq!(apply("\\[ ]", "p@ssW0rd").as_deref(), Some("@ssW0r"));
+        assert_eq!(apply("-c T0 sa4", "banana").as_deref(), Some("B4n4n4"));
+        assert_eq!(apply("-: r", "abc").as_deref(), Some("cba"));
+    }
+
+    #[test]
+    fn test_insert_string() {
+        assert_eq!(apply("Az\"123\"", "pass").as_deref(), Some("pass123"));
+        assert_eq!(apply("A0\"the\"", "cat").as_deref(), Some("thecat"));
+        assert_eq!(apply("A1/-/ u", "ab").as_deref(), Some("A-B"));
+        assert!(parse_rule("Az\"12").is_err());
+    }
+
+    #[test]
+    fn test_length_conditions() {
+        // Only words longer than 8 characters are lowercased
+        assert_eq!(apply(">8 l", "PASSWORD").as_deref(), None);
+        assert_eq!(apply(">8 l", "PASSWORDS").as_deref(), Some("passwords"));
+        assert_eq!(apply("<6", "secret").as_deref(), None);
+        assert_eq!(apply("<6", "short").as_deref(), Some("short"));
+        assert_eq!(apply("_4 d", "word").as_deref(), Some("wordword"));
+        assert_eq!(apply("_4 d", "words").as_deref(), None);
+    }
+
+    #[test]
+    fn test_character_classes() {
+        assert_eq!(apply("@?v", "password").as_deref(), Some("psswrd"));
+        assert_eq!(apply("s?v*", "password").as_deref(), Some("p*ssw*rd"));
+        assert_eq!(apply("@?V", "pa55word").as_deref(), Some("ao"));
+        assert_eq!(apply("/?d", "password").as_deref(), None);
+        assert_eq!(apply("!?d", "pa55word").as_deref(), None);
+        assert_eq!(apply("(?u )?d", "Word1").as_deref(), Some("Word1"));
+        assert_eq!(apply("=1?v", "cat").as_deref(), Some("cat"));
+        assert_eq!(apply("=1?v", "cry").as_deref(), None);
+        assert_eq!(apply("s??!", "why?").as_deref(), Some("why!"));
+        assert!(parse_rule("@?q").is_err());
+    }
+
+    #[test]
+    fn test_expand_rule() {
+        assert_eq!(
+            expand_rule("$[0-2]$[ab]").unwrap(),
+            vec!["$0$a", "$0$b", "$1$a", "$1$b", "$2$a", "$2$b"]
+        );
+        assert_eq!(expand_rule("\\[ $[-x]").unwrap(), vec!["[ $-", "[ $x"]);
+        assert_eq!(expand_rule("Az\"[0-9]\"").unwrap().len(), 10);
+        assert!(expand_rule("$[0-9").is_err());
+        assert!(expand_rule("$[0-9]$[0-9]$[0-9]$[0-9]$[0-9]").is_err());
+    }
+
+    #[test]
+    fn test_unsupported_functions() {
+        for rule in ["p", "R", "L", "z2", "M", "'8", "-s r", "<*"] {
+            assert!(parse_rule(rule).is_err(), "{rule}");
+        }
+    }
+
+    #[test]
+    fn test_parse_john_rules_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            "[Options]\nWordlist = words.lst\n\n\
+             [List.Rules:Wordlist]\n# Try words as is\n:\n; and capitalized with a digit\n\
+             c $[0-9]\nM Q\n\n\
+             [List.Rules:Single]\nr\n"
+        )
+        .unwrap();
+
+        let rules = parse_john_rules_file(file.path(), DEFAULT_SECTION).unwrap();
+        assert_eq!(rules.len(), 1 + 10);
+        assert_eq!(rules[0], vec![RuleOp::Noop]);
+        assert_eq!(rules[10], vec![RuleOp::Capitalize, RuleOp::Append('9')]);
+
+        let rules = parse_john_rules_file(file.path(), "single").unwrap();
+        assert_eq!(rules, vec![vec![RuleOp::Reverse]]);
+
+        let error = parse_john_rules_file(file.path(), "Jumbo").unwrap_err();
+        assert!(error.to_string().contains("No section [List.Rules:Jumbo]"));
+    }
+}
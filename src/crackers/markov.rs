@@ -0,0 +1,367 @@
+//! Markov chain password cracking implementation.
+//!
+//! This module provides functionality for cracking passwords with candidates
+//! generated from a 2nd-order Markov model trained on a password corpus.
+//! Candidates are produced in decreasing probability order.
+use crate::types::{CrackMode, CrackResult, PasswordCracker, WorkUnit};
+use anyhow::{Context, Result};
+use openssl::pkcs12::Pkcs12;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Marker padding the start of every training word
+const START: char = '\u{2}';
+/// Marker terminating every training word
+const END: char = '\u{3}';
+/// Longest candidate the generator will ever emit
+const MAX_CANDIDATE_LEN: usize = 32;
+
+/// A 2nd-order Markov model of password character sequences.
+///
+/// Stores how often each character follows a pair of preceding characters.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MarkovModel {
+    /// Transition counts: (second to last, last) -> next -> count
+    transitions: HashMap<(char, char), HashMap<char, u32>>,
+}
+
+impl MarkovModel {
+    /// Trains a model from a newline-separated corpus.
+    ///
+    /// # Arguments
+    ///
+    /// * `corpus` - Training passwords, one per line
+    pub fn from_corpus(corpus: &str) -> Self {
+        let mut model = Self::default();
+        for word in corpus.lines().map(str::trim).filter(|w| !w.is_empty()) {
+            let (mut prev2, mut prev1) = (START, START);
+            for c in word.chars().chain(std::iter::once(END)) {
+                *model
+                    .transitions
+                    .entry((prev2, prev1))
+                    .or_default()
+                    .entry(c)
+                    .or_insert(0) += 1;
+                prev2 = prev1;
+                prev1 = c;
+            }
+        }
+        model
+    }
+
+    /// Trains a model from a corpus file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the corpus cannot be read
+    pub fn train(corpus_path: &Path) -> Result<Self> {
+        let corpus = fs::read_to_string(corpus_path)
+            .with_context(|| format!("Failed to read Markov corpus: {}", corpus_path.display()))?;
+        Ok(Self::from_corpus(&corpus))
+    }
+
+    /// Loads a model serialized with [`MarkovModel::save`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or is not a valid model
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open Markov model: {}", path.display()))?;
+        bincode::deserialize_from(BufReader::new(file)).context("Failed to parse Markov model")
+    }
+
+    /// Serializes the model to a binary file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created or written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create Markov model: {}", path.display()))?;
+        bincode::serialize_into(BufWriter::new(file), self).context("Failed to write Markov model")
+    }
+
+    /// Returns an iterator over candidates in decreasing probability order.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_prob` - Candidates (and prefixes) less likely than this are pruned
+    pub fn candidates(&self, min_prob: f64) -> Candidates {
+        let log_probs = self
+            .transitions
+            .iter()
+            .map(|(&state, next)| {
+                let total: u32 = next.values().sum();
+                let next = next
+                    .iter()
+                    .map(|(&c, &count)| (c, (count as f64 / total as f64).ln()))
+                    .collect();
+                (state, next)
+            })
+            .collect();
+
+        let mut heap = BinaryHeap::new();
+        heap.push(Node {
+            log_prob: 0.0,
+            text: String::new(),
+            state: (START, START),
+        });
+
+        Candidates {
+            log_probs,
+            heap,
+            min_log_prob: min_prob.ln(),
+        }
+    }
+}
+
+/// A partially generated candidate on the search frontier.
+struct Node {
+    /// Log-probability of the prefix
+    log_prob: f64,
+    /// Characters generated so far
+    text: String,
+    /// The last two characters, or `END` once the candidate is complete
+    state: (char, char),
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.log_prob.total_cmp(&other.log_prob)
+    }
+}
+
+/// Best-first generator of Markov candidates.
+///
+/// Every transition can only lower the log-probability, so popping the most
+/// likely node from a max-heap yields complete candidates in decreasing
+/// probability order.
+pub struct Candidates {
+    /// Transition log-probabilities: state -> [(next, ln p)]
+    log_probs: HashMap<(char, char), Vec<(char, f64)>>,
+    /// Search frontier ordered by log-probability
+    heap: BinaryHeap<Node>,
+    /// Natural log of the pruning threshold
+    min_log_prob: f64,
+}
+
+impl Iterator for Candidates {
+    type Item = (String, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.heap.pop() {
+            if node.state.1 == END {
+                return Some((node.text, node.log_prob));
+            }
+
+            let Some(next) = self.log_probs.get(&node.state) else {
+                continue;
+            };
+            for &(c, log_prob) in next {
+                let log_prob = node.log_prob + log_prob;
+                if log_prob < self.min_log_prob {
+                    continue;
+                }
+                let mut text = node.text.clone();
+                if c != END {
+                    if text.chars().count() >= MAX_CANDIDATE_LEN {
+                        continue;
+                    }
+                    text.push(c);
+                }
+                self.heap.push(Node {
+                    log_prob,
+                    text,
+                    state: (node.state.1, c),
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Implements Markov chain password cracking.
+pub struct MarkovCracker {
+    /// Trained model generating the candidates
+    model: MarkovModel,
+    /// Probability below which candidates are pruned
+    min_prob: f64,
+    /// Share of the candidates processed by this cracker
+    work_unit: WorkUnit,
+    /// Number of candidates per parallel work item
+    chunk_size: usize,
+}
+
+impl MarkovCracker {
+    /// Creates a new MarkovCracker instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Trained Markov model
+    /// * `min_prob` - Probability below which candidates are pruned
+    pub fn new(model: MarkovModel, min_prob: f64) -> Self {
+        Self {
+            model,
+            min_prob,
+            work_unit: WorkUnit::FULL,
+            chunk_size: super::CHUNK_SIZE,
+        }
+    }
+
+    /// Restricts the cracker to a share of the candidates.
+    ///
+    /// The number of candidates is not known up front, so candidates are
+    /// dealt out round-robin: unit `i` of `n` tries every `n`-th candidate
+    /// starting at the `i`-th.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_unit` - Share of the candidates to process
+    pub fn with_work_unit(mut self, work_unit: WorkUnit) -> Self {
+        self.work_unit = work_unit;
+        self
+    }
+
+    /// Sets the number of candidates per parallel work item.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - Candidates per chunk, at least 1
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Processes a chunk of generated password candidates.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - Password candidates to test
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    /// * `result` - Shared result tracking structure
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the correct password is found in this chunk.
+    fn process_chunk(chunk: &[String], pkcs12: &Pkcs12, result: &Arc<Mutex<CrackResult>>) -> bool {
+        let run = result.lock().unwrap().run();
+        for password in chunk {
+            if run.is_stopped() {
+                return true;
+            }
+            if run.skips_oversized(password) {
+                continue;
+            }
+            run.increment_attempts();
+
+            if super::check_password(pkcs12, password, &run, result) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl PasswordCracker for MarkovCracker {
+    /// Attempts to crack the PKCS#12 password with Markov-generated candidates.
+    ///
+    /// Candidates are generated sequentially in batches, each batch is tested
+    /// in parallel before the next one is generated.
+    fn crack(&self, pkcs12: &Arc<Pkcs12>, result: &Arc<Mutex<CrackResult>>) -> Result<()> {
+        println!(
+            "Starting Markov attack with minimum probability {}",
+            self.min_prob
+        );
+
+        let batch_size = self.chunk_size * rayon::current_num_threads();
+        let WorkUnit { index, total } = self.work_unit;
+        let mut candidates = self
+            .model
+            .candidates(self.min_prob)
+            .skip(index)
+            .step_by(total)
+            .map(|(c, _)| c);
+
+        loop {
+            let batch: Vec<String> = candidates.by_ref().take(batch_size).collect();
+            if batch.is_empty() {
+                break;
+            }
+
+            if batch
+                .par_chunks(self.chunk_size)
+                .find_any(|chunk| Self::process_chunk(chunk, pkcs12, result))
+                .is_some()
+            {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn mode(&self) -> CrackMode {
+        CrackMode::Markov
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markov_frequent_sequences_first() {
+        let model = MarkovModel::from_corpus("abc\nabc\nabc\nabd\nxyz\n");
+        let candidates: Vec<String> = model.candidates(1e-9).map(|(c, _)| c).collect();
+        assert_eq!(candidates[0], "abc");
+        assert!(candidates.contains(&"abd".to_string()));
+        assert!(candidates.contains(&"xyz".to_string()));
+    }
+
+    #[test]
+    fn test_markov_decreasing_probability() {
+        let model = MarkovModel::from_corpus("pass\npass1\npassword\nletmein\n");
+        let probs: Vec<f64> = model.candidates(1e-6).map(|(_, p)| p).collect();
+        assert!(!probs.is_empty());
+        assert!(probs.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_markov_min_prob_prunes() {
+        let model = MarkovModel::from_corpus("abc\nabc\nabc\nabd\n");
+        let candidates: Vec<String> = model.candidates(0.5).map(|(c, _)| c).collect();
+        assert_eq!(candidates, vec!["abc".to_string()]);
+    }
+
+    #[test]
+    fn test_markov_model_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("model.bin");
+        MarkovModel::from_corpus("abc\nabd\n").save(&path).unwrap();
+
+        let loaded = MarkovModel::load(&path).unwrap();
+        let candidates: Vec<String> = loaded.candidates(1e-9).map(|(c, _)| c).collect();
+        assert_eq!(candidates.len(), 2);
+    }
+}
@@ -1,23 +1,150 @@
 //! Password cracking strategies and utilities.
 pub mod bruteforce;
 pub mod dictionary;
+pub mod edit_distance;
+pub mod john_rules;
+pub mod markov;
+pub mod mutations;
 pub mod pattern;
+pub(crate) mod permutation;
+pub mod permutation_attack;
+pub mod policy;
+pub mod pronounceable;
+pub mod rules;
 
-use openssl::pkcs12::Pkcs12;
+use crate::types::{CombinationCount, CrackResult, RunState};
+use anyhow::{bail, Context, Result};
+use foreign_types::ForeignTypeRef;
+use openssl::pkcs12::{Pkcs12, Pkcs12Ref};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs;
+use std::ops::{Range, RangeInclusive};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-/// Optimal chunk size for parallel processing, tuned for modern CPU cache sizes.
+/// Default chunk size for parallel processing, tuned for modern CPU cache sizes.
 /// 16KB is chosen as a compromise between cache efficiency and parallelism.
-const CHUNK_SIZE: usize = 16384;
+/// Overridden per run with `--chunk-size`.
+pub const CHUNK_SIZE: usize = 16384;
 
 /// Attempts to decrypt a PKCS#12 certificate with a given password.
 ///
-/// This function is used internally by all cracking strategies.
+/// Non-ASCII passwords are tried both as UTF-8 (OpenSSL 1.1.0 and later)
+/// and in the legacy byte-wise encoding of older OpenSSL versions.
 ///
 /// # Arguments
 ///
 /// * `pkcs12` - The PKCS#12 certificate to test
 /// * `password` - The password to try
+///
+/// # Returns
+///
+/// Returns `true` if the password was correct, `false` otherwise. Passwords
+/// containing a NUL character cannot be passed to OpenSSL and never match.
+#[inline(always)]
+pub(crate) fn try_password(pkcs12: &Pkcs12, password: &str) -> bool {
+    if password.contains('\0') {
+        return false;
+    }
+    pkcs12.parse2(password).is_ok() || {
+        // Files created before OpenSSL 1.1.0 encode each byte of a non-ASCII
+        // password as a separate BMP character, i.e. as if it were Latin-1.
+        !password.is_ascii() && {
+            let legacy: String = password.bytes().map(char::from).collect();
+            pkcs12.parse2(&legacy).is_ok()
+        }
+    }
+}
+
+// Part of libcrypto since OpenSSL 1.0, but not declared by openssl-sys.
+extern "C" {
+    fn PKCS12_mac_present(p12: *const openssl_sys::PKCS12) -> c_int;
+    fn PKCS12_verify_mac(
+        p12: *mut openssl_sys::PKCS12,
+        pass: *const c_char,
+        passlen: c_int,
+    ) -> c_int;
+}
+
+/// Checks a password against the MAC of a DER encoded PKCS#12 certificate.
+///
+/// The MAC uses the same key derivation as the encryption, but verifying it
+/// skips decrypting the bags, the first step [`try_password`] does anyway.
+/// Used as a pre-filter before the full decryption with `--pre-hash`.
+///
+/// # Arguments
+///
+/// * `pkcs12_der` - The DER encoded PKCS#12 certificate
+/// * `password` - The password to check
+///
+/// # Returns
+///
+/// Returns `false` if the data is not a certificate or the MAC does not
+/// match the password, `true` otherwise. Certificates without a MAC cannot
+/// be checked and always return `true`.
+pub fn fast_mac_check(pkcs12_der: &[u8], password: &str) -> bool {
+    Pkcs12::from_der(pkcs12_der).is_ok_and(|pkcs12| mac_matches(&pkcs12, password))
+}
+
+/// Checks a password against the MAC of a parsed certificate.
+///
+/// Tries the same password forms as [`try_password`]: the legacy byte-wise
+/// encoding of non-ASCII passwords, and both a missing and an empty password.
+pub(crate) fn mac_matches(pkcs12: &Pkcs12Ref, password: &str) -> bool {
+    let p12 = pkcs12.as_ptr();
+    // SAFETY: `p12` points to the certificate borrowed for this call
+    if unsafe { PKCS12_mac_present(p12) } == 0 {
+        return true;
+    }
+    if password.contains('\0') {
+        return false;
+    }
+    let verify = |password: Option<&str>| {
+        let (pass, passlen) = match password {
+            Some(password) => (password.as_ptr().cast::<c_char>(), password.len() as c_int),
+            None => (std::ptr::null(), 0),
+        };
+        // SAFETY: OpenSSL reads `passlen` bytes of the password and only
+        // reads the certificate, which may be shared between threads
+        let verified = unsafe { PKCS12_verify_mac(p12, pass, passlen) } == 1;
+        if !verified {
+            // SAFETY: clears the failure from this thread's error queue
+            unsafe { openssl_sys::ERR_clear_error() };
+        }
+        verified
+    };
+    if password.is_empty() {
+        // OpenSSL accepts an empty password for a MAC computed without one
+        return verify(None) || verify(Some(""));
+    }
+    verify(Some(password))
+        || !password.is_ascii() && {
+            let legacy: String = password.bytes().map(char::from).collect();
+            verify(Some(&legacy))
+        }
+}
+
+/// Attempts to decrypt a PKCS#12 certificate and records the password on success.
+///
+/// This function is used internally by all cracking strategies. Candidates
+/// rejected by the run's filter are skipped before the expensive decryption.
+/// The options are read from the run state without locking, the result is
+/// only locked to record the password.
+/// The case folds of the run's [`CaseSensitivity`](crate::types::CaseSensitivity)
+/// are tested after the candidate, the password recorded is the one that matched.
+/// With `also_reversed`, the candidate reversed character by character and its
+/// folds follow as another attempt, unless the candidate is a palindrome.
+/// With `pre_hash`, a candidate is only decrypted once it matches the MAC.
+///
+/// # Arguments
+///
+/// * `pkcs12` - The PKCS#12 certificate to test
+/// * `password` - The password to try
+/// * `run` - State of the run, from [`CrackResult::run`]
 /// * `result` - Shared result object to store the password if successful
 ///
 /// # Returns
@@ -27,54 +154,1218 @@ const CHUNK_SIZE: usize = 16384;
 pub(crate) fn check_password(
     pkcs12: &Pkcs12,
     password: &str,
-    result: &Arc<Mutex<crate::types::CrackResult>>,
+    run: &RunState,
+    result: &Arc<Mutex<CrackResult>>,
+) -> bool {
+    check_password_logged(pkcs12, password, run, result, true)
+}
+
+/// Tests a password, recording it in the candidate log if `log` is set.
+fn check_password_logged(
+    pkcs12: &Pkcs12,
+    password: &str,
+    run: &RunState,
+    result: &Arc<Mutex<CrackResult>>,
+    log: bool,
 ) -> bool {
-    match pkcs12.parse2(password) {
-        Ok(_) => {
-            let mut result_guard = result.lock().unwrap();
-            result_guard.password = Some(password.to_string());
-            println!("\nFound correct password: {password}");
-            true
+    let config = &run.config;
+    if !run.accepts(password) {
+        return false;
+    }
+    if let Some(preview) = &config.preview {
+        preview.record(password);
+        return false;
+    }
+    let candidate_log = config.candidate_log.as_ref().filter(|_| log);
+    let reversed = config
+        .also_reversed
+        .then(|| password.chars().rev().collect::<String>())
+        .filter(|reversed| reversed != password);
+    if reversed.is_some() {
+        run.increment_attempts();
+    }
+    let (case_sensitivity, pre_hash) = (config.case_sensitivity, config.pre_hash);
+    if let Some(candidate_log) = candidate_log {
+        candidate_log.record(password);
+        if let Some(reversed) = &reversed {
+            candidate_log.record(reversed);
+        }
+    }
+    let decrypts = |candidate: &str| {
+        (!pre_hash || mac_matches(pkcs12, candidate)) && try_password(pkcs12, candidate)
+    };
+    let matches = |candidate: &str| {
+        if decrypts(candidate) {
+            Some(candidate.to_string())
+        } else {
+            case_sensitivity
+                .folds(candidate)
+                .into_iter()
+                .find(|fold| decrypts(fold))
         }
-        Err(_) => false,
+    };
+    let (password, is_reversed) = match matches(password) {
+        Some(password) => (password, false),
+        None => match reversed.as_deref().and_then(matches) {
+            Some(password) => (password, true),
+            None => return false,
+        },
+    };
+    let mut result_guard = result.lock().unwrap();
+    result_guard.found_at = Some(result_guard.start_time.elapsed());
+    if is_reversed {
+        println!("\nFound correct password: {password} (reversed candidate)");
+    } else {
+        println!("\nFound correct password: {password}");
     }
+    result_guard.password = Some(password);
+    result_guard.found_reversed = is_reversed;
+    run.mark_found();
+    true
 }
 
-/// Recursively generates all possible combinations of characters.
+/// Takes the run state of every certificate, see [`CrackResult::run`].
+pub(crate) fn runs(results: &[Arc<Mutex<CrackResult>>]) -> Vec<Arc<RunState>> {
+    results
+        .iter()
+        .map(|result| result.lock().unwrap().run())
+        .collect()
+}
+
+/// Tests a password against every certificate that is not cracked yet.
 ///
-/// Used by bruteforce and pattern-based cracking strategies to generate
-/// password candidates.
+/// # Arguments
+///
+/// * `pkcs12s` - The PKCS#12 certificates to test
+/// * `password` - The password to try
+/// * `runs` - State of the run of every certificate, from [`CrackResult::run`]
+/// * `results` - Result tracking structure of every certificate
+///
+/// # Returns
+///
+/// Returns `true` if all certificates are cracked, `false` otherwise.
+pub(crate) fn check_password_multi(
+    pkcs12s: &[Arc<Pkcs12>],
+    password: &str,
+    runs: &[Arc<RunState>],
+    results: &[Arc<Mutex<CrackResult>>],
+) -> bool {
+    let mut all_cracked = true;
+    // The certificates share the candidate log, record the candidate only once
+    let mut log = true;
+    for ((pkcs12, run), result) in pkcs12s.iter().zip(runs).zip(results) {
+        if run.is_found() {
+            continue;
+        }
+        if !check_password_logged(pkcs12, password, run, result, std::mem::take(&mut log)) {
+            all_cracked = false;
+        }
+    }
+    all_cracked
+}
+
+/// Generates all possible combinations of characters.
+///
+/// The reference enumeration the index mappings are tested against.
+/// Combinations are counted up by a [`Combinations`] digit counter, so the
+/// stack use does not grow with `length`.
 ///
 /// # Arguments
 ///
 /// * `charset` - Set of characters to use for combinations
 /// * `length` - Length of combinations to generate
-/// * `current` - Current combination being built
+/// * `current` - Prefix of every generated combination
 /// * `result` - Vector to store generated combinations
 ///
 /// # Example
 ///
-/// ```no_run
+/// ```ignore
 /// let mut combinations = Vec::new();
 /// let charset = vec!['a', 'b', 'c'];
 /// generate_combinations(&charset, 2, &String::new(), &mut combinations);
 /// // combinations will contain: ["aa", "ab", "ac", "ba", "bb", "bc", "ca", "cb", "cc"]
 /// ```
+#[cfg(test)]
 pub(crate) fn generate_combinations(
     charset: &[char],
     length: u8,
     current: &str,
     result: &mut Vec<String>,
 ) {
-    if length == 0 {
-        result.push(current.to_owned());
-        return;
+    result.extend(Combinations::new(charset, length as usize).with_prefix(current));
+}
+
+/// Iterator over all combinations of `length` characters in lexicographic order.
+///
+/// Works like an odometer: the digits are charset indices, the last one is
+/// incremented and carries into the ones before it when it wraps around.
+/// Iterating from the back decrements a second counter the same way, so the
+/// last combinations of a huge keyspace are as cheap as the first ones.
+/// Every position may have its own charset, the digits are then mixed radix.
+pub(crate) struct Combinations<'a> {
+    /// Characters of every position
+    charsets: Vec<Vec<char>>,
+    /// Prefix of every combination
+    prefix: &'a str,
+    /// Capacity of a combination in bytes
+    capacity: usize,
+    /// Digits of the next combination from the front
+    front: Vec<usize>,
+    /// Digits of the next combination from the back
+    back: Vec<usize>,
+    /// Whether the two counters have met
+    finished: bool,
+}
+
+impl<'a> Combinations<'a> {
+    /// Creates an iterator over all combinations of `length` characters.
+    ///
+    /// # Arguments
+    ///
+    /// * `charset` - Set of characters to use for combinations
+    /// * `length` - Length of the combinations
+    #[cfg(test)]
+    pub(crate) fn new(charset: &'a [char], length: usize) -> Self {
+        Self::from_charsets(vec![charset.to_vec(); length])
+    }
+
+    /// Creates an iterator over all combinations with their own charset per
+    /// position, in the order of [`index_to_positional`].
+    ///
+    /// # Arguments
+    ///
+    /// * `charsets` - Characters of every position
+    pub(crate) fn positional(charsets: &[Vec<char>]) -> Self {
+        Self::from_charsets(charsets.to_vec())
+    }
+
+    /// Creates an iterator over the combinations whose index in the order of
+    /// [`index_to_positional`] lies in `range`.
+    ///
+    /// # Arguments
+    ///
+    /// * `charsets` - Characters of every position
+    /// * `range` - Indices of the combinations, within the number of combinations
+    pub(crate) fn positional_range(charsets: &[Vec<char>], range: Range<u128>) -> Self {
+        let mut combinations = Self::positional(charsets);
+        if range.is_empty() {
+            combinations.finished = true;
+            return combinations;
+        }
+        let digits = |mut index: u128| {
+            let mut digits = vec![0; charsets.len()];
+            for (digit, charset) in digits.iter_mut().zip(charsets).rev() {
+                let base = charset.len() as u128;
+                *digit = (index % base) as usize;
+                index /= base;
+            }
+            digits
+        };
+        combinations.front = digits(range.start);
+        combinations.back = digits(range.end - 1);
+        combinations
+    }
+
+    fn from_charsets(charsets: Vec<Vec<char>>) -> Self {
+        let capacity = charsets
+            .iter()
+            .map(|charset| charset.iter().map(|c| c.len_utf8()).max().unwrap_or(0))
+            .sum();
+        Self {
+            prefix: "",
+            capacity,
+            front: vec![0; charsets.len()],
+            back: charsets
+                .iter()
+                .map(|charset| charset.len().saturating_sub(1))
+                .collect(),
+            finished: charsets.iter().any(|charset| charset.is_empty()),
+            charsets,
+        }
+    }
+
+    /// Puts `prefix` in front of every combination.
+    #[cfg(test)]
+    pub(crate) fn with_prefix(mut self, prefix: &'a str) -> Self {
+        self.capacity += prefix.len() - self.prefix.len();
+        self.prefix = prefix;
+        self
+    }
+
+    /// Returns the combination of a set of digits.
+    fn combination(&self, digits: &[usize]) -> String {
+        let mut combination = String::with_capacity(self.capacity);
+        combination.push_str(self.prefix);
+        combination.extend(
+            digits
+                .iter()
+                .zip(&self.charsets)
+                .map(|(&digit, charset)| charset[digit]),
+        );
+        combination
+    }
+}
+
+impl Iterator for Combinations<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if self.finished {
+            return None;
+        }
+        let combination = self.combination(&self.front);
+        if self.front == self.back {
+            self.finished = true;
+        } else {
+            for (digit, charset) in self.front.iter_mut().zip(&self.charsets).rev() {
+                *digit += 1;
+                if *digit < charset.len() {
+                    break;
+                }
+                *digit = 0;
+            }
+        }
+        Some(combination)
+    }
+}
+
+impl DoubleEndedIterator for Combinations<'_> {
+    fn next_back(&mut self) -> Option<String> {
+        if self.finished {
+            return None;
+        }
+        let combination = self.combination(&self.back);
+        if self.front == self.back {
+            self.finished = true;
+        } else {
+            for (digit, charset) in self.back.iter_mut().zip(&self.charsets).rev() {
+                if *digit > 0 {
+                    *digit -= 1;
+                    break;
+                }
+                *digit = charset.len() - 1;
+            }
+        }
+        Some(combination)
+    }
+}
+
+/// Decodes a linear index into a combination of `length` characters.
+///
+/// Combinations are numbered in the same lexicographic order as
+/// `generate_combinations` produces them: the first character is the most
+/// significant one, so for the charset "abc" index 1 is "aab" and index 3
+/// is "aba" (for `length` 3).
+///
+/// This is the one ordering of the keyspace: brute force, pattern mode and
+/// `--start-at` number combinations with this function and
+/// [`combination_to_index`], so positions in progress files and work units
+/// mean the same candidate whichever path generates it.
+///
+/// # Arguments
+///
+/// * `index` - Linear index, must be smaller than `charset.len().pow(length)`
+/// * `charset` - Set of characters to use for combinations
+/// * `length` - Length of the combination
+/// * `buffer` - Reusable buffer for the combination characters
+#[inline(always)]
+pub(crate) fn index_to_combination(
+    mut index: u128,
+    charset: &[char],
+    length: usize,
+    buffer: &mut Vec<char>,
+) -> String {
+    let base = charset.len() as u128;
+    buffer.clear();
+    buffer.resize(length, '\0');
+    for slot in buffer.iter_mut().rev() {
+        *slot = charset[(index % base) as usize];
+        index /= base;
+    }
+    buffer.iter().collect()
+}
+
+/// Encodes a combination into its linear index, the inverse of [`index_to_combination`].
+///
+/// # Arguments
+///
+/// * `combination` - Combination to encode
+/// * `charset` - Set of characters the combination is drawn from
+///
+/// # Errors
+///
+/// Returns an error if a character of the combination is not in the charset
+/// or the index does not fit into `u128`.
+pub(crate) fn combination_to_index(combination: &str, charset: &[char]) -> Result<u128> {
+    let base = charset.len() as u128;
+    combination.chars().try_fold(0u128, |index, c| {
+        let Some(digit) = charset.iter().position(|&x| x == c) else {
+            bail!("Character {c:?} of {combination:?} is not in the charset");
+        };
+        index
+            .checked_mul(base)
+            .and_then(|index| index.checked_add(digit as u128))
+            .with_context(|| format!("Index of {combination:?} exceeds {}", u128::MAX))
+    })
+}
+
+/// Computes the number of combinations of `positions` characters drawn from `charset`.
+///
+/// # Arguments
+///
+/// * `charset` - Set of characters to use for combinations
+/// * `positions` - Number of positions to fill
+///
+/// # Returns
+///
+/// Returns `CombinationCount::TooLarge` if the count does not fit into `u128`.
+pub(crate) fn estimate_combinations(charset: &[char], positions: usize) -> CombinationCount {
+    let mut total: u128 = 1;
+    for _ in 0..positions {
+        match total.checked_mul(charset.len() as u128) {
+            Some(next) => total = next,
+            None => return CombinationCount::TooLarge,
+        }
+    }
+    CombinationCount::Exact(total)
+}
+
+/// Decodes a linear index into a combination with its own charset per position.
+///
+/// The mixed-radix form of [`index_to_combination`]: the first position is
+/// the most significant one, so with the same charset at every position both
+/// functions return the same combination.
+///
+/// # Arguments
+///
+/// * `index` - Linear index, must be smaller than the product of the charset sizes
+/// * `charsets` - Characters of every position
+/// * `buffer` - Reusable buffer for the combination characters
+#[inline(always)]
+pub(crate) fn index_to_positional(
+    mut index: u128,
+    charsets: &[Vec<char>],
+    buffer: &mut Vec<char>,
+) -> String {
+    buffer.clear();
+    buffer.resize(charsets.len(), '\0');
+    for (slot, charset) in buffer.iter_mut().zip(charsets).rev() {
+        let base = charset.len() as u128;
+        *slot = charset[(index % base) as usize];
+        index /= base;
+    }
+    buffer.iter().collect()
+}
+
+/// Encodes a combination into its linear index, the inverse of [`index_to_positional`].
+///
+/// # Arguments
+///
+/// * `combination` - Combination to encode
+/// * `charsets` - Characters of every position
+///
+/// # Errors
+///
+/// Returns an error if the combination has another length than `charsets`,
+/// a character is not in the charset of its position or the index does not
+/// fit into `u128`.
+#[cfg(test)]
+pub(crate) fn positional_to_index(combination: &str, charsets: &[Vec<char>]) -> Result<u128> {
+    if combination.chars().count() != charsets.len() {
+        bail!(
+            "{combination:?} does not have {} characters",
+            charsets.len()
+        );
+    }
+    combination
+        .chars()
+        .zip(charsets)
+        .try_fold(0u128, |index, (c, charset)| {
+            let Some(digit) = charset.iter().position(|&x| x == c) else {
+                bail!("Character {c:?} of {combination:?} is not in the charset of its position");
+            };
+            index
+                .checked_mul(charset.len() as u128)
+                .and_then(|index| index.checked_add(digit as u128))
+                .with_context(|| format!("Index of {combination:?} exceeds {}", u128::MAX))
+        })
+}
+
+/// Computes the number of combinations with their own charset per position,
+/// the product of the charset sizes.
+///
+/// # Arguments
+///
+/// * `charsets` - Characters of every position
+///
+/// # Returns
+///
+/// Returns `CombinationCount::TooLarge` if the count does not fit into `u128`.
+pub(crate) fn estimate_positional(charsets: &[Vec<char>]) -> CombinationCount {
+    let mut total: u128 = 1;
+    for charset in charsets {
+        match total.checked_mul(charset.len() as u128) {
+            Some(next) => total = next,
+            None => return CombinationCount::TooLarge,
+        }
+    }
+    CombinationCount::Exact(total)
+}
+
+/// Decodes a linear index into a combination of distinct characters.
+///
+/// Combinations are numbered in lexicographic order of the charset like
+/// [`index_to_combination`], skipping every one that repeats a character.
+/// The index is a mixed-radix number: the first character has `n` choices,
+/// the second `n - 1` of the characters left and so on, so `length`
+/// characters have the falling factorial `n·(n-1)·…·(n-length+1)` indices.
+///
+/// # Arguments
+///
+/// * `index` - Linear index, must be smaller than the falling factorial
+/// * `charset` - Set of distinct characters to use for combinations
+/// * `length` - Length of the combination, at most `charset.len()`
+/// * `buffer` - Reusable buffer for the characters not used yet
+#[inline(always)]
+pub(crate) fn index_to_permutation(
+    mut index: u128,
+    charset: &[char],
+    length: usize,
+    buffer: &mut Vec<char>,
+) -> String {
+    let n = charset.len();
+    buffer.clear();
+    buffer.extend_from_slice(charset);
+    // Place value of the first character, the falling factorial of the others
+    let mut weight: u128 = (1..length).map(|position| (n - position) as u128).product();
+    let mut combination = String::with_capacity(length);
+    for position in 0..length {
+        combination.push(buffer.remove((index / weight) as usize));
+        index %= weight;
+        if position + 1 < length {
+            weight /= (n - position - 1) as u128;
+        }
+    }
+    combination
+}
+
+/// Encodes a combination of distinct characters into its linear index, the
+/// inverse of [`index_to_permutation`].
+///
+/// # Arguments
+///
+/// * `combination` - Combination to encode
+/// * `charset` - Set of distinct characters the combination is drawn from
+///
+/// # Errors
+///
+/// Returns an error if a character of the combination is not in the
+/// charset or repeats an earlier one.
+pub(crate) fn permutation_to_index(combination: &str, charset: &[char]) -> Result<u128> {
+    let mut left = charset.to_vec();
+    combination.chars().try_fold(0u128, |index, c| {
+        let Some(digit) = left.iter().position(|&x| x == c) else {
+            if charset.contains(&c) {
+                bail!("Character {c:?} repeats in {combination:?}");
+            }
+            bail!("Character {c:?} of {combination:?} is not in the charset");
+        };
+        let radix = left.len() as u128;
+        left.remove(digit);
+        // Mixed radix, never exceeds the falling factorial of the charset
+        Ok(index * radix + digit as u128)
+    })
+}
+
+/// Computes the number of combinations of `positions` distinct characters
+/// drawn from `charset`, the falling factorial `n·(n-1)·…·(n-positions+1)`.
+///
+/// # Arguments
+///
+/// * `charset` - Set of distinct characters to use for combinations
+/// * `positions` - Number of positions to fill
+///
+/// # Returns
+///
+/// Returns `CombinationCount::Exact(0)` if there are more positions than
+/// characters and `CombinationCount::TooLarge` if the count does not fit
+/// into `u128`.
+pub(crate) fn estimate_permutations(charset: &[char], positions: usize) -> CombinationCount {
+    let mut total: u128 = 1;
+    for position in 0..positions {
+        let choices = charset.len().saturating_sub(position) as u128;
+        match total.checked_mul(choices) {
+            Some(next) => total = next,
+            None => return CombinationCount::TooLarge,
+        }
+    }
+    CombinationCount::Exact(total)
+}
+
+/// Per-character weights loaded from a `--probability-model` file.
+///
+/// The file is a JSON object mapping single characters to positive weights,
+/// e.g. English letter frequencies `{"e": 0.127, "t": 0.091, ...}`. Weights
+/// need not sum to 1, they are normalized over the charset of a run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbabilityModel {
+    /// Weight of every character in the model
+    weights: HashMap<char, f64>,
+}
+
+impl ProbabilityModel {
+    /// Reads a probability model from a JSON file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the JSON file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not a JSON object of
+    /// numbers, has keys other than single characters or non-positive weights
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read probability model: {}", path.display()))?;
+        let raw: HashMap<String, f64> = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse probability model: {}", path.display()))?;
+        Self::from_weights(raw)
+    }
+
+    /// Builds a probability model from string keyed weights.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for keys other than single characters, non-positive
+    /// or non-finite weights and empty models
+    pub fn from_weights(raw: HashMap<String, f64>) -> Result<Self> {
+        let mut weights = HashMap::with_capacity(raw.len());
+        for (key, weight) in raw {
+            let mut chars = key.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                bail!("Probability model key {key:?} is not a single character");
+            };
+            if !weight.is_finite() || weight <= 0.0 {
+                bail!("Probability model weight of {key:?} must be positive, got {weight}");
+            }
+            weights.insert(c, weight);
+        }
+        if weights.is_empty() {
+            bail!("Probability model has no characters");
+        }
+        Ok(Self { weights })
+    }
+
+    /// Returns the characters of `charset` the model has no weight for.
+    ///
+    /// They are tried with the smallest weight of the model.
+    pub fn missing(&self, charset: &[char]) -> Vec<char> {
+        charset
+            .iter()
+            .copied()
+            .filter(|c| !self.weights.contains_key(c))
+            .collect()
+    }
+
+    /// Returns the natural log of the probability of every charset character.
+    fn log_probabilities(&self, charset: &[char]) -> Vec<f64> {
+        let fallback = self.weights.values().copied().fold(f64::INFINITY, f64::min);
+        let weights: Vec<f64> = charset
+            .iter()
+            .map(|c| self.weights.get(c).copied().unwrap_or(fallback))
+            .collect();
+        let total: f64 = weights.iter().sum();
+        weights.iter().map(|weight| (weight / total).ln()).collect()
+    }
+}
+
+/// A combination on the search frontier of [`WeightedCombinationIter`].
+struct WeightedState {
+    /// Sum of the log-probabilities of the chosen characters
+    score: f64,
+    /// Rank of the character at every position, 0 being the most likely one
+    ranks: Vec<usize>,
+}
+
+impl PartialEq for WeightedState {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for WeightedState {}
+
+impl PartialOrd for WeightedState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for WeightedState {
+    /// Higher scores first, ties in lexicographic order of the ranks.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.ranks.cmp(&self.ranks))
+    }
+}
+
+/// Best-first generator of combinations in decreasing probability order.
+///
+/// Every position draws independently from the charset, so the probability
+/// of a combination is the product of its character probabilities. Each
+/// combination has a single parent, the one with its rightmost non-zero rank
+/// lowered by one, and a child can never be more likely than its parent.
+/// Popping the most likely combination from a max-heap and pushing its
+/// children therefore yields every combination exactly once, most likely
+/// first, while the heap only holds the frontier.
+pub(crate) struct WeightedCombinationIter {
+    /// Characters of every position with their scores, most likely first
+    ranked: Vec<Vec<(char, f64)>>,
+    /// Search frontier ordered by score
+    heap: BinaryHeap<WeightedState>,
+}
+
+impl WeightedCombinationIter {
+    /// Creates an iterator over all combinations with lengths in `lengths`.
+    ///
+    /// With a seed every position gets its own random order that still
+    /// follows the weights: the log-probabilities are perturbed with Gumbel
+    /// noise, so likely characters tend to come first without always doing so.
+    ///
+    /// # Arguments
+    ///
+    /// * `charset` - Set of characters to use for combinations
+    /// * `model` - Weights of the characters
+    /// * `lengths` - Lengths of the combinations
+    /// * `seed` - Seed for the random order, `None` keeps the weighted order
+    pub(crate) fn new(
+        charset: &[char],
+        model: &ProbabilityModel,
+        lengths: RangeInclusive<usize>,
+        seed: Option<u64>,
+    ) -> Self {
+        let charsets = vec![charset.to_vec(); *lengths.end()];
+        Self::with_charsets(&charsets, model, lengths, seed)
+    }
+
+    /// Creates an iterator over all combinations with their own charset per position.
+    ///
+    /// The probabilities are normalized over the charset of every position.
+    ///
+    /// # Arguments
+    ///
+    /// * `charsets` - Characters of every position
+    /// * `model` - Weights of the characters
+    /// * `seed` - Seed for the random order, `None` keeps the weighted order
+    pub(crate) fn positional(
+        charsets: &[Vec<char>],
+        model: &ProbabilityModel,
+        seed: Option<u64>,
+    ) -> Self {
+        Self::with_charsets(charsets, model, charsets.len()..=charsets.len(), seed)
+    }
+
+    /// Ranks the characters of every position, see [`WeightedCombinationIter::new`].
+    fn with_charsets(
+        charsets: &[Vec<char>],
+        model: &ProbabilityModel,
+        lengths: RangeInclusive<usize>,
+        seed: Option<u64>,
+    ) -> Self {
+        let mut rng = seed.map(Xoshiro256PlusPlus::seed_from_u64);
+        let ranked = charsets
+            .iter()
+            .map(|charset| {
+                let log_probabilities = model.log_probabilities(charset);
+                let mut position: Vec<(char, f64)> = charset
+                    .iter()
+                    .zip(&log_probabilities)
+                    .map(|(&c, &score)| match rng.as_mut() {
+                        Some(rng) => {
+                            let uniform: f64 = rng.gen_range(f64::EPSILON..1.0);
+                            (c, score - (-uniform.ln()).ln())
+                        }
+                        None => (c, score),
+                    })
+                    .collect();
+                // Stable, so equally likely characters keep the charset order
+                position.sort_by(|a, b| b.1.total_cmp(&a.1));
+                position
+            })
+            .collect::<Vec<_>>();
+
+        let mut heap = BinaryHeap::new();
+        if ranked.iter().all(|position| !position.is_empty()) {
+            for len in lengths {
+                heap.push(WeightedState {
+                    score: ranked[..len].iter().map(|position| position[0].1).sum(),
+                    ranks: vec![0; len],
+                });
+            }
+        }
+        Self { ranked, heap }
+    }
+
+    /// Sums the scores of the ranked characters.
+    ///
+    /// Always adds the positions from left to right, so equally likely
+    /// combinations get bit-identical scores and keep their tie order.
+    fn sum_scores(&self, ranks: &[usize]) -> f64 {
+        ranks
+            .iter()
+            .enumerate()
+            .map(|(position, &rank)| self.ranked[position][rank].1)
+            .sum()
     }
 
-    let mut new_str = current.to_owned();
-    for &c in charset {
-        new_str.push(c);
-        generate_combinations(charset, length - 1, &new_str, result);
-        new_str.pop();
+    /// Returns the score of a combination, its log-probability without a seed.
+    ///
+    /// Returns `None` if the combination is too long or has characters
+    /// outside the charset.
+    #[cfg(test)]
+    pub(crate) fn score(&self, combination: &str) -> Option<f64> {
+        combination
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let position = self.ranked.get(i)?;
+                position
+                    .iter()
+                    .find(|&&(x, _)| x == c)
+                    .map(|&(_, score)| score)
+            })
+            .sum()
+    }
+}
+
+impl Iterator for WeightedCombinationIter {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let state = self.heap.pop()?;
+        let first_child = state.ranks.iter().rposition(|&rank| rank > 0).unwrap_or(0);
+        for position in first_child..state.ranks.len() {
+            if state.ranks[position] + 1 == self.ranked[position].len() {
+                continue;
+            }
+            let mut ranks = state.ranks.clone();
+            ranks[position] += 1;
+            self.heap.push(WeightedState {
+                score: self.sum_scores(&ranks),
+                ranks,
+            });
+        }
+        Some(
+            state
+                .ranks
+                .iter()
+                .enumerate()
+                .map(|(position, &rank)| self.ranked[position][rank].0)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{fixture, fixture_bytes};
+
+    #[test]
+    fn test_combination_index_roundtrip() {
+        let charset: Vec<char> = "abc".chars().collect();
+        let mut buffer = Vec::new();
+        for index in 0..27 {
+            let combination = index_to_combination(index, &charset, 3, &mut buffer);
+            assert_eq!(combination_to_index(&combination, &charset).unwrap(), index);
+        }
+        assert_eq!(combination_to_index("ccc", &charset).unwrap(), 26);
+
+        let printable: Vec<char> = crate::charset::printable_ascii().chars().collect();
+        let last = 95u128.pow(12) - 1;
+        let combination = index_to_combination(last, &printable, 12, &mut buffer);
+        assert_eq!(combination, "~".repeat(12));
+        assert_eq!(
+            combination_to_index(&combination, &printable).unwrap(),
+            last
+        );
+    }
+
+    #[test]
+    fn test_combination_to_index_rejects_unknown_characters() {
+        let charset: Vec<char> = "abc".chars().collect();
+        assert!(combination_to_index("abd", &charset).is_err());
+    }
+
+    #[test]
+    fn test_index_to_combination_matches_generated_order() {
+        let charset: Vec<char> = "abc".chars().collect();
+        let mut generated = Vec::new();
+        generate_combinations(&charset, 3, "", &mut generated);
+
+        let mut buffer = Vec::new();
+        let decoded: Vec<String> = (0..generated.len() as u128)
+            .map(|i| index_to_combination(i, &charset, 3, &mut buffer))
+            .collect();
+        assert_eq!(decoded, generated);
+    }
+
+    #[test]
+    fn test_estimate_combinations() {
+        let charset: Vec<char> = "abc".chars().collect();
+        assert_eq!(
+            estimate_combinations(&charset, 0),
+            CombinationCount::Exact(1)
+        );
+        assert_eq!(
+            estimate_combinations(&charset, 4),
+            CombinationCount::Exact(81)
+        );
+
+        let binary: Vec<char> = "ab".chars().collect();
+        assert_eq!(
+            estimate_combinations(&binary, 65),
+            CombinationCount::Exact(1 << 65)
+        );
+    }
+
+    #[test]
+    fn test_estimate_combinations_too_large() {
+        let charset: Vec<char> = crate::charset::LOWER_ALPHABET.chars().collect();
+        assert_eq!(
+            estimate_combinations(&charset, 64),
+            CombinationCount::TooLarge
+        );
+    }
+
+    #[test]
+    fn test_combinations_long_length() {
+        let charset: Vec<char> = "ab".chars().collect();
+        let first: Vec<String> = Combinations::new(&charset, 255).take(5).collect();
+        let prefix = "a".repeat(252);
+        assert_eq!(
+            first,
+            ["aaa", "aab", "aba", "abb", "baa"].map(|tail| format!("{prefix}{tail}"))
+        );
+
+        let last: Vec<String> = Combinations::new(&charset, 255).rev().take(5).collect();
+        let prefix = "b".repeat(252);
+        assert_eq!(
+            last,
+            ["bbb", "bba", "bab", "baa", "abb"].map(|tail| format!("{prefix}{tail}"))
+        );
+    }
+
+    #[test]
+    fn test_combinations_front_and_back_meet() {
+        let charset: Vec<char> = "abc".chars().collect();
+        let mut generated = Vec::new();
+        generate_combinations(&charset, 3, "x", &mut generated);
+        assert_eq!(generated.len(), 27);
+        assert_eq!(generated[5], "xabc");
+
+        let mut combinations = Combinations::new(&charset, 2);
+        let mut both = Vec::new();
+        while let (Some(front), back) = (combinations.next(), combinations.next_back()) {
+            both.push(front);
+            both.extend(back);
+        }
+        both.sort();
+        let mut expected = Vec::new();
+        generate_combinations(&charset, 2, "", &mut expected);
+        assert_eq!(both, expected);
+
+        assert_eq!(Combinations::new(&charset, 0).collect::<Vec<_>>(), [""]);
+        assert_eq!(Combinations::new(&[], 0).count(), 1);
+        assert_eq!(Combinations::new(&[], 2).count(), 0);
+    }
+
+    #[test]
+    fn test_positional_combinations() {
+        let charsets: Vec<Vec<char>> = vec!["ab".chars().collect(), "0123".chars().collect()];
+        let combinations: Vec<String> = Combinations::positional(&charsets).collect();
+        assert_eq!(
+            combinations,
+            ["a0", "a1", "a2", "a3", "b0", "b1", "b2", "b3"]
+        );
+        let backwards: Vec<String> = Combinations::positional(&charsets).rev().collect();
+        assert_eq!(backwards.first().map(String::as_str), Some("b3"));
+        assert_eq!(backwards.len(), 8);
+
+        assert_eq!(positional_to_index("b1", &charsets).unwrap(), 5);
+        assert!(positional_to_index("1b", &charsets).is_err());
+        assert!(positional_to_index("b", &charsets).is_err());
+
+        let range: Vec<String> = Combinations::positional_range(&charsets, 3..6).collect();
+        assert_eq!(range, ["a3", "b0", "b1"]);
+        let range: Vec<String> = Combinations::positional_range(&charsets, 3..6)
+            .rev()
+            .collect();
+        assert_eq!(range, ["b1", "b0", "a3"]);
+        assert_eq!(Combinations::positional_range(&charsets, 4..4).count(), 0);
+
+        let empty = vec!["ab".chars().collect(), Vec::new()];
+        assert_eq!(Combinations::positional(&empty).count(), 0);
+        assert_eq!(estimate_positional(&empty), CombinationCount::Exact(0));
+    }
+
+    #[test]
+    fn test_permutation_index_roundtrip() {
+        let charset: Vec<char> = "abcd".chars().collect();
+        let mut buffer = Vec::new();
+        let candidates: Vec<String> = (0..24)
+            .map(|index| index_to_permutation(index, &charset, 3, &mut buffer))
+            .collect();
+        assert_eq!(&candidates[..3], ["abc", "abd", "acb"]);
+        assert_eq!(candidates[23], "dcb");
+
+        // Lexicographic order of the full product without the repeats
+        let mut expected = Vec::new();
+        generate_combinations(&charset, 3, "", &mut expected);
+        expected.retain(|candidate| {
+            let distinct: std::collections::HashSet<char> = candidate.chars().collect();
+            distinct.len() == 3
+        });
+        assert_eq!(candidates, expected);
+        for (index, candidate) in candidates.iter().enumerate() {
+            assert_eq!(
+                permutation_to_index(candidate, &charset).unwrap(),
+                index as u128
+            );
+        }
+        assert!(permutation_to_index("aba", &charset).is_err());
+        assert!(permutation_to_index("abe", &charset).is_err());
+    }
+
+    #[test]
+    fn test_estimate_permutations() {
+        let charset: Vec<char> = "abc".chars().collect();
+        assert_eq!(
+            estimate_permutations(&charset, 0),
+            CombinationCount::Exact(1)
+        );
+        assert_eq!(
+            estimate_permutations(&charset, 2),
+            CombinationCount::Exact(6)
+        );
+        assert_eq!(
+            estimate_permutations(&charset, 4),
+            CombinationCount::Exact(0)
+        );
+
+        let alphanumeric: Vec<char> = crate::charset::LOWER_ALPHABET
+            .chars()
+            .chain('A'..='Z')
+            .chain('0'..='9')
+            .collect();
+        assert_eq!(
+            estimate_permutations(&alphanumeric, 8),
+            CombinationCount::Exact((55..=62).product())
+        );
+    }
+
+    fn english_model() -> ProbabilityModel {
+        let frequencies = [
+            8.2, 1.5, 2.8, 4.3, 12.7, 2.2, 2.0, 6.1, 7.0, 0.15, 0.77, 4.0, 2.4, 6.7, 7.5, 1.9,
+            0.095, 6.0, 6.3, 9.1, 2.8, 0.98, 2.4, 0.15, 2.0, 0.074,
+        ];
+        let weights = ('a'..='z')
+            .zip(frequencies)
+            .map(|(c, weight)| (c.to_string(), weight))
+            .collect();
+        ProbabilityModel::from_weights(weights).unwrap()
+    }
+
+    #[test]
+    fn test_probability_model_validation() {
+        let model = |pairs: &[(&str, f64)]| {
+            ProbabilityModel::from_weights(
+                pairs
+                    .iter()
+                    .map(|&(key, weight)| (key.to_string(), weight))
+                    .collect(),
+            )
+        };
+        assert!(model(&[("a", 1.0), ("ä", 0.5)]).is_ok());
+        assert!(model(&[("ab", 1.0)]).is_err());
+        assert!(model(&[("", 1.0)]).is_err());
+        assert!(model(&[("a", 0.0)]).is_err());
+        assert!(model(&[("a", f64::NAN)]).is_err());
+        assert!(model(&[]).is_err());
+
+        let charset: Vec<char> = "abc".chars().collect();
+        assert_eq!(model(&[("a", 1.0)]).unwrap().missing(&charset), ['b', 'c']);
+    }
+
+    #[test]
+    fn test_weighted_combinations_likely_first() {
+        let charset: Vec<char> = crate::charset::LOWER_ALPHABET.chars().collect();
+        let model = english_model();
+        let mut iter = WeightedCombinationIter::new(&charset, &model, 7..=8, None);
+        let password = iter.score("password").unwrap();
+        let unlikely = iter.score("zzzzzzz").unwrap();
+        assert!(password > unlikely);
+
+        assert_eq!(iter.next().as_deref(), Some("eeeeeee"));
+        let mut previous = iter.score("eeeeeee").unwrap();
+        for _ in 0..5000 {
+            let candidate = iter.next().unwrap();
+            let score = iter.score(&candidate).unwrap();
+            assert!(score <= previous, "{candidate} out of order");
+            assert!(score > password);
+            previous = score;
+        }
+    }
+
+    #[test]
+    fn test_weighted_combinations_exhaustive() {
+        let charset: Vec<char> = "abcd".chars().collect();
+        let model = ProbabilityModel::from_weights(
+            [("a", 0.1), ("b", 0.4), ("c", 0.3), ("d", 0.2)]
+                .iter()
+                .map(|&(key, weight)| (key.to_string(), weight))
+                .collect(),
+        )
+        .unwrap();
+
+        for seed in [None, Some(7)] {
+            let iter = WeightedCombinationIter::new(&charset, &model, 1..=4, seed);
+            let scorer = WeightedCombinationIter::new(&charset, &model, 1..=4, seed);
+            let candidates: Vec<String> = iter.collect();
+            assert_eq!(candidates.len(), 4 + 16 + 64 + 256);
+            let distinct: std::collections::HashSet<&String> = candidates.iter().collect();
+            assert_eq!(distinct.len(), candidates.len());
+            let scores: Vec<f64> = candidates
+                .iter()
+                .map(|candidate| scorer.score(candidate).unwrap())
+                .collect();
+            assert!(scores.windows(2).all(|pair| pair[0] >= pair[1]));
+            if seed.is_none() {
+                assert_eq!(&candidates[..3], ["b", "c", "d"]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_password_rejects_nul() {
+        let pkcs12 = fixture("umlaut.p12");
+        assert!(try_password(&pkcs12, "pä55"));
+        assert!(!try_password(&pkcs12, "pä55\0"));
+        assert!(!try_password(&pkcs12, "\0"));
+    }
+
+    #[test]
+    fn test_fast_mac_check() {
+        let umlaut = fixture_bytes("umlaut.p12");
+        assert!(fast_mac_check(&umlaut, "pä55"));
+        assert!(!fast_mac_check(&umlaut, "pa55"));
+        assert!(!fast_mac_check(&umlaut, "pä55\0"));
+        assert!(fast_mac_check(&fixture_bytes("latin1.p12"), "Grüße"));
+        assert!(fast_mac_check(&fixture_bytes("empty.p12"), ""));
+        assert!(!fast_mac_check(&fixture_bytes("empty.p12"), "x"));
+        assert!(!fast_mac_check(b"not a certificate", ""));
+
+        // Without a MAC every password passes on to the decryption
+        let no_mac = fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/benchmarks/certificate.p12"
+        ))
+        .unwrap();
+        assert!(fast_mac_check(&no_mac, "wrong"));
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+        use std::collections::HashSet;
+
+        /// Charsets of up to 6 distinct characters, including multibyte ones
+        fn charset() -> impl Strategy<Value = Vec<char>> {
+            prop::collection::hash_set(any::<char>(), 1..=6)
+                .prop_map(|set| set.into_iter().collect())
+        }
+
+        proptest! {
+            #[test]
+            fn generate_combinations_covers_keyspace(charset in charset(), length in 0u8..=4) {
+                let mut combinations = Vec::new();
+                generate_combinations(&charset, length, "", &mut combinations);
+                prop_assert_eq!(combinations.len(), charset.len().pow(length as u32));
+
+                let allowed: HashSet<char> = charset.iter().copied().collect();
+                for combination in &combinations {
+                    prop_assert_eq!(combination.chars().count(), length as usize);
+                    prop_assert!(combination.chars().all(|c| allowed.contains(&c)));
+                }
+                let unique: HashSet<&String> = combinations.iter().collect();
+                prop_assert_eq!(unique.len(), combinations.len());
+            }
+
+            #[test]
+            fn index_to_combination_matches_generated_order(
+                charset in charset(),
+                length in 1u8..=4,
+                seed in any::<u128>(),
+            ) {
+                let mut combinations = Vec::new();
+                generate_combinations(&charset, length, "", &mut combinations);
+                let index = seed % combinations.len() as u128;
+                let mut buffer = Vec::new();
+                prop_assert_eq!(
+                    &index_to_combination(index, &charset, length as usize, &mut buffer),
+                    &combinations[index as usize]
+                );
+            }
+
+            #[test]
+            fn positional_matches_uniform_order(
+                charset in charset(),
+                length in 0usize..=4,
+                seed in any::<u128>(),
+            ) {
+                let charsets = vec![charset.clone(); length];
+                let index = seed % charset.len().pow(length as u32) as u128;
+                let mut buffer = Vec::new();
+                prop_assert_eq!(
+                    index_to_positional(index, &charsets, &mut buffer),
+                    index_to_combination(index, &charset, length, &mut buffer)
+                );
+            }
+
+            #[test]
+            fn positional_index_roundtrip(
+                charsets in prop::collection::vec(charset(), 0..=5),
+            ) {
+                let combinations: Vec<String> = Combinations::positional(&charsets).collect();
+                prop_assert_eq!(
+                    estimate_positional(&charsets),
+                    CombinationCount::Exact(combinations.len() as u128)
+                );
+                let mut buffer = Vec::new();
+                for (index, combination) in combinations.iter().enumerate() {
+                    let index = index as u128;
+                    prop_assert_eq!(&index_to_positional(index, &charsets, &mut buffer), combination);
+                    prop_assert_eq!(positional_to_index(combination, &charsets).unwrap(), index);
+                }
+            }
+
+            #[test]
+            fn combination_index_roundtrip(
+                charset in charset(),
+                length in 0usize..=40,
+                seed in any::<u128>(),
+            ) {
+                let total = match estimate_combinations(&charset, length) {
+                    CombinationCount::Exact(total) => total,
+                    CombinationCount::TooLarge => u128::MAX,
+                };
+                let index = seed % total;
+                let mut buffer = Vec::new();
+                let combination = index_to_combination(index, &charset, length, &mut buffer);
+                prop_assert_eq!(combination.chars().count(), length);
+                prop_assert_eq!(combination_to_index(&combination, &charset).unwrap(), index);
+            }
+        }
     }
 }
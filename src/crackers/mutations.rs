@@ -0,0 +1,57 @@
+//! Word mutations for dictionary attacks.
+//!
+//! This module builds the extension tables applied to every dictionary entry,
+//! e.g. the numeric suffixes of `--append-numbers`.
+
+/// Longest numeric suffix supported by `--append-numbers`
+pub const MAX_SUFFIX_DIGITS: u8 = 4;
+
+/// Builds every numeric suffix with exactly `digits` digits.
+///
+/// Suffixes are zero-padded, so `digits = 2` yields `00` through `99`.
+///
+/// # Arguments
+///
+/// * `digits` - Number of digits of every suffix
+pub fn fixed_numeric_suffixes(digits: u8) -> Vec<String> {
+    let width = digits as usize;
+    (0..10u32.pow(digits as u32))
+        .map(|n| format!("{n:0width$}"))
+        .collect()
+}
+
+/// Builds every numeric suffix with 1 to `max_digits` digits, shortest first.
+///
+/// For `max_digits = 2` this is `0`-`9` followed by `00`-`99`, 110 suffixes.
+///
+/// # Arguments
+///
+/// * `max_digits` - Number of digits of the longest suffix
+pub fn numeric_suffixes(max_digits: u8) -> Vec<String> {
+    (1..=max_digits).flat_map(fixed_numeric_suffixes).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numeric_suffixes() {
+        let suffixes = numeric_suffixes(2);
+        assert_eq!(suffixes.len(), 110);
+        assert_eq!(suffixes[0], "0");
+        assert_eq!(suffixes[9], "9");
+        assert_eq!(suffixes[10], "00");
+        assert_eq!(suffixes[109], "99");
+
+        assert_eq!(numeric_suffixes(MAX_SUFFIX_DIGITS).len(), 11110);
+    }
+
+    #[test]
+    fn test_fixed_numeric_suffixes() {
+        let suffixes = fixed_numeric_suffixes(3);
+        assert_eq!(suffixes.len(), 1000);
+        assert_eq!(suffixes[0], "000");
+        assert_eq!(suffixes[42], "042");
+    }
+}
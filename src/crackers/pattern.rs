@@ -3,23 +3,802 @@
 //! This module provides functionality for cracking passwords using a pattern
 //! where some positions are fixed and others are variable. For example,
 //! "Pass@@rd" would try all combinations replacing @ symbols.
-use crate::types::{CrackResult, PasswordCracker};
-use anyhow::Result;
+//!
+//! A symbol followed by a quantifier such as `@{1,4}` stands for one to four
+//! variable positions. Such patterns are expanded into fixed-length patterns,
+//! tried shortest first, whose combinations are numbered consecutively. A
+//! quantifier after a fixed character is an error, `\{` is a literal brace.
+//! Likewise `\@` is a literal symbol and `\\` a literal backslash.
+//!
+//! With a word list, the token `%W` is replaced by every word of the list in
+//! turn and the variable positions are tried for each word.
+//!
+//! A group like `[-_.]` is a variable position trying only the characters it
+//! lists, as if it were a symbol with its own charset.
+//!
+//! With a numeric order, runs of adjacent digit positions count upward from
+//! a start value like an integer counter.
+//!
+//! All positions of a linked symbol take the same character, so they count
+//! as a single variable position in the keyspace.
+use super::dictionary::DictionaryCracker;
+use super::permutation::IndexPermutation;
+use super::{ProbabilityModel, WeightedCombinationIter};
+use crate::checkpoint::{
+    certificate_fingerprint, Checkpoint, CheckpointWriter, WriteCadence, FORMAT_VERSION,
+};
+use crate::progress_file::{Position, ProgressSaver};
+use crate::types::{
+    CombinationCount, CrackMode, CrackResult, PasswordCracker, PkcsError, WorkUnit,
+};
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
 use openssl::pkcs12::Pkcs12;
 use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+/// Token replaced by every word of the word list, see [`PatternCracker::with_word_list`]
+pub const WORD_TOKEN: &str = "%W";
+
+/// Stands for the word in a pattern until a word is filled in, a private
+/// use character that is [`RESERVED`]
+const WORD_SLOT: char = '\u{E000}';
+
+/// Slots standing for the `[...]` groups of the patterns, [`RESERVED`]
+/// private use characters following [`WORD_SLOT`]
+const GROUP_SLOTS: std::ops::RangeInclusive<char> = '\u{E001}'..='\u{F8FE}';
+
+/// Stands for an escaped `{` until the quantifiers are expanded, so that it
+/// never starts a quantifier
+const BRACE_SLOT: char = '\u{F8FF}';
+
+/// Characters the parser uses as slots, typed patterns containing them are
+/// rejected by [`parse_groups`]
+const RESERVED: &[RangeInclusive<char>] = &[
+    WORD_SLOT..=WORD_SLOT,
+    GROUP_SLOTS,
+    BRACE_SLOT..=BRACE_SLOT,
+    SYMBOL_SLOTS,
+    YEAR_SLOT..=SHORT_YEAR_SLOT,
+];
+
+/// Slots standing for escaped symbols until the variable positions are
+/// found, the n-th slot for the n-th symbol; supplementary private use
+/// characters that are [`RESERVED`]
+const SYMBOL_SLOTS: std::ops::RangeInclusive<char> = '\u{F0000}'..='\u{FFFFD}';
+
+/// Token for a year of [`PatternCracker::with_years`], most recent first
+pub const YEAR_TOKEN: &str = "?y";
+
+/// Token for the last two digits of a year of [`PatternCracker::with_years`]
+pub const SHORT_YEAR_TOKEN: &str = "?Y";
+
+/// Years the year tokens try without [`PatternCracker::with_years`]
+pub const DEFAULT_YEARS: RangeInclusive<u16> = 1950..=2030;
+
+/// Latest year the year tokens support
+pub const MAX_YEAR: u16 = 9999;
+
+/// Stands for [`YEAR_TOKEN`] in a parsed pattern, a symbol whose charset
+/// holds the years
+const YEAR_SLOT: char = '\u{10FFFC}';
+
+/// Stands for [`SHORT_YEAR_TOKEN`] in a parsed pattern
+const SHORT_YEAR_SLOT: char = '\u{10FFFD}';
+
+/// First of the private use characters standing for a year in a
+/// combination, followed by the two-digit years at [`SHORT_YEAR_VALUES`].
+/// In the positions of year tokens they are replaced by the digits of the
+/// year when a candidate is filled in.
+const YEAR_VALUES: u32 = 0x100000;
+
+/// First of the characters standing for a two-digit year
+const SHORT_YEAR_VALUES: u32 = YEAR_VALUES + MAX_YEAR as u32 + 1;
+
+/// Characters standing for the years of a year token, most recent first.
+///
+/// Two-digit years of a range spanning more than a century are tried once.
+///
+/// # Arguments
+///
+/// * `years` - Years to try
+/// * `short` - Whether to try the last two digits of the years
+fn year_charset(years: &RangeInclusive<u16>, short: bool) -> Vec<char> {
+    let mut charset: Vec<char> = years
+        .clone()
+        .rev()
+        .map(|year| match short {
+            true => SHORT_YEAR_VALUES + u32::from(year % 100),
+            false => YEAR_VALUES + u32::from(year),
+        })
+        .filter_map(char::from_u32)
+        .collect();
+    let mut seen = std::collections::HashSet::new();
+    charset.retain(|c| seen.insert(*c));
+    charset
+}
+
+/// Parses a `--years` value like `1990-2025`, a single year is a range of one.
+///
+/// # Errors
+///
+/// Returns an error if a year is not a number up to [`MAX_YEAR`] or the
+/// range is reversed
+pub fn parse_years(value: &str) -> Result<RangeInclusive<u16>> {
+    let (first, last) = value.split_once('-').unwrap_or((value, value));
+    let parse = |year: &str| -> Result<u16> {
+        match year.trim().parse::<u16>() {
+            Ok(year) if year <= MAX_YEAR => Ok(year),
+            _ => bail!("Invalid year '{year}', expected a number up to {MAX_YEAR}"),
+        }
+    };
+    let (first, last) = (parse(first)?, parse(last)?);
+    if first > last {
+        bail!("Invalid year range '{value}', {first} is after {last}");
+    }
+    Ok(first..=last)
+}
+
+/// A pattern symbol with its own charset, from `--symbol`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
+pub struct PatternSymbol {
+    /// Symbol marking variable positions in the pattern
+    pub symbol: char,
+    /// Characters to try in the positions of the symbol
+    pub charset: String,
+}
+
+/// Parses a `--symbol` value.
+///
+/// `SYM=SPEC` takes the charset from `--charset` specifiers, e.g. `#=n` for
+/// digits or `@=lower,digits`. `SYM:CHARS` lists the characters literally,
+/// with the escape sequences of `--custom-chars`, e.g. `%:!?`. Repeated
+/// characters are tried once.
+///
+/// # Errors
+///
+/// Returns an error if the value has neither form, names an unknown charset
+/// or gives no characters
+pub fn parse_symbol(value: &str) -> Result<PatternSymbol> {
+    let mut chars = value.chars();
+    let (Some(symbol), Some(separator)) = (chars.next(), chars.next()) else {
+        bail!("Invalid symbol '{value}', expected SYM=SPEC or SYM:CHARS, e.g. '#=n'");
+    };
+    let rest = chars.as_str();
+    let mut charset = match separator {
+        '=' => crate::charset::resolve_charset_spec(rest)?
+            .iter()
+            .map(|preset| (preset.chars)())
+            .collect(),
+        ':' => crate::charset::unescape_chars(rest)
+            .with_context(|| format!("Invalid characters of symbol '{symbol}'"))?,
+        _ => bail!("Invalid symbol '{value}', expected SYM=SPEC or SYM:CHARS, e.g. '#=n'"),
+    };
+    let mut seen = std::collections::HashSet::new();
+    charset.retain(|c| seen.insert(c));
+    if charset.is_empty() {
+        bail!("Symbol '{symbol}' has no characters");
+    }
+    Ok(PatternSymbol { symbol, charset })
+}
+
+/// Parses a `--linked-symbol` value.
+///
+/// A single character is a linked symbol trying the characters of the
+/// pattern symbol, other values have the forms of [`parse_symbol`].
+///
+/// # Errors
+///
+/// Returns an error if the value is not a single character and not a valid
+/// `--symbol` value
+pub fn parse_linked_symbol(value: &str) -> Result<PatternSymbol> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(symbol), None) => Ok(PatternSymbol {
+            symbol,
+            charset: String::new(),
+        }),
+        _ => parse_symbol(value),
+    }
+}
+
+/// Order of the runs of adjacent digit positions, from `--numeric-order`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum NumericOrder {
+    /// Count upward from a start value, wrapping around to zero after the
+    /// largest number the run holds
+    Ascending(u128),
+}
+
+/// Parses a `--numeric-order` value, `asc` or `asc:START`.
+///
+/// # Errors
+///
+/// Returns an error if the value has another form or the start is not a
+/// non-negative integer
+pub fn parse_numeric_order(value: &str) -> Result<NumericOrder> {
+    let start = match value.split_once(':') {
+        None if value == "asc" => 0,
+        Some(("asc", start)) => start
+            .parse()
+            .with_context(|| format!("Invalid start '{start}', expected a non-negative integer"))?,
+        _ => {
+            bail!("Invalid numeric order '{value}', expected 'asc' or 'asc:START', e.g. 'asc:1999'")
+        }
+    };
+    Ok(NumericOrder::Ascending(start))
+}
+
+/// Adds `start` to every digit run of a combination, dropping the carry out
+/// of the run, so that the runs count upward from `start` as the index of
+/// the combination grows.
+///
+/// # Arguments
+///
+/// * `combination` - Combination of the variable positions
+/// * `runs` - Indices of the digit runs in the combination
+/// * `start` - Value the runs start at
+fn shift_digit_runs(combination: &str, runs: &[Range<usize>], start: u128) -> String {
+    let mut chars: Vec<char> = combination.chars().collect();
+    for run in runs {
+        let mut carry = start;
+        for digit in chars[run.clone()].iter_mut().rev() {
+            let sum = digit.to_digit(10).expect("runs hold digits") as u128 + carry % 10;
+            carry = carry / 10 + sum / 10;
+            *digit = char::from_digit((sum % 10) as u32, 10).expect("a digit is below 10");
+        }
+    }
+    chars.into_iter().collect()
+}
+
+/// Case variants of the fixed letters tried by `--toggle-case`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CaseToggle {
+    /// As typed, all lowercase, all uppercase and only the first letter uppercase
+    Common,
+    /// Every mix of lowercase and uppercase letters
+    All,
+}
+
+/// Most fixed letters [`CaseToggle::All`] toggles, each doubles the candidates
+const MAX_TOGGLED_LETTERS: usize = 16;
+
+/// Returns the only character of a case mapping, `None` if it has several.
+pub(super) fn single(mut mapping: impl Iterator<Item = char>) -> Option<char> {
+    let c = mapping.next()?;
+    mapping.next().is_none().then_some(c)
+}
+
+/// Lists the case variants of the fixed letters of a pattern, as typed first.
+///
+/// Letters whose lowercase or uppercase form takes several characters, like
+/// `ß`, stay as typed. Duplicate variants are tried once.
+///
+/// # Arguments
+///
+/// * `pattern` - The pattern with a placeholder in every variable position
+/// * `positions` - Character indices of the variable positions
+/// * `toggle` - Which variants to list
+///
+/// # Errors
+///
+/// Returns an error if [`CaseToggle::All`] would toggle more than
+/// [`MAX_TOGGLED_LETTERS`] letters
+fn case_variants(pattern: &str, positions: &[usize], toggle: CaseToggle) -> Result<Vec<String>> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let letters: Vec<(usize, char, char)> = chars
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !positions.contains(i))
+        .filter_map(|(i, &c)| {
+            let lower = single(c.to_lowercase())?;
+            let upper = single(c.to_uppercase())?;
+            (lower != upper).then_some((i, lower, upper))
+        })
+        .collect();
+    let variant = |uppercase: &dyn Fn(usize) -> bool| -> String {
+        let mut chars = chars.clone();
+        for (n, &(i, lower, upper)) in letters.iter().enumerate() {
+            chars[i] = if uppercase(n) { upper } else { lower };
+        }
+        chars.into_iter().collect()
+    };
+
+    let mut variants = vec![pattern.to_string()];
+    match toggle {
+        CaseToggle::Common => {
+            variants.push(variant(&|_| false));
+            variants.push(variant(&|_| true));
+            variants.push(variant(&|n| n == 0));
+        }
+        CaseToggle::All => {
+            if letters.len() > MAX_TOGGLED_LETTERS {
+                bail!(
+                    "--toggle-case all supports up to {MAX_TOGGLED_LETTERS} fixed letters, \
+                     the pattern has {}; use --toggle-case common",
+                    letters.len()
+                );
+            }
+            for mask in 0..1u32 << letters.len() {
+                variants.push(variant(&|n| mask >> n & 1 == 1));
+            }
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    variants.retain(|variant| seen.insert(variant.clone()));
+    Ok(variants)
+}
+
+/// Reads the patterns of a `--pattern-file`, with their line numbers.
+///
+/// Blank lines are skipped. A `#` at the start of a line or after whitespace
+/// starts a comment, so such a `#` cannot be a pattern symbol.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read
+pub fn read_pattern_file(path: &Path) -> Result<Vec<(usize, String)>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read pattern file: {}", path.display()))?;
+    Ok(content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let comment = line
+                .char_indices()
+                .find(|&(at, c)| {
+                    c == '#'
+                        && line[..at]
+                            .chars()
+                            .next_back()
+                            .is_none_or(char::is_whitespace)
+                })
+                .map_or(line.len(), |(at, _)| at);
+            let pattern = line[..comment].trim();
+            (!pattern.is_empty()).then(|| (i + 1, pattern.to_string()))
+        })
+        .collect())
+}
+
+/// Replaces every `[...]` group of a pattern by a slot standing for the
+/// characters it lists.
+///
+/// A backslash escapes `[`, `]`, `{`, `}` and `\` inside and outside of
+/// groups, and the symbols outside of groups; other backslashes are fixed
+/// characters. An escaped `{` outside of groups becomes [`BRACE_SLOT`] and
+/// an escaped symbol its slot in [`SYMBOL_SLOTS`]. Repeated characters of a
+/// group are tried once and groups listing the same characters share a slot.
+///
+/// # Arguments
+///
+/// * `pattern` - The pattern as typed
+/// * `symbols` - Symbols marking variable positions
+/// * `groups` - Slots with the characters of their group, extended by the
+///   new groups of the pattern
+///
+/// # Errors
+///
+/// Returns an error if the pattern contains a [`RESERVED`] character, a
+/// group is not closed or lists no characters, or the patterns have more
+/// distinct groups than there are slots
+fn parse_groups(
+    pattern: &str,
+    symbols: &[char],
+    groups: &mut Vec<(char, Vec<char>)>,
+) -> Result<String> {
+    if let Some(reserved) = pattern
+        .chars()
+        .find(|c| RESERVED.iter().any(|range| range.contains(c)))
+    {
+        bail!(
+            "Pattern '{pattern}' contains U+{:04X}, a private use character \
+             reserved for parsing patterns",
+            reserved as u32
+        );
+    }
+    let escaped = |&(_, c): &(usize, char)| matches!(c, '[' | ']' | '{' | '}' | '?' | '\\');
+    let mut parsed = String::with_capacity(pattern.len());
+    let mut chars = pattern.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\\' => parsed.push(
+                match chars.next_if(|next| escaped(next) || symbols.contains(&next.1)) {
+                    Some((_, '{')) => BRACE_SLOT,
+                    Some((_, c)) => match symbols.iter().position(|&symbol| symbol == c) {
+                        Some(index) => SYMBOL_SLOTS
+                            .clone()
+                            .nth(index)
+                            .context("Too many symbols to escape")?,
+                        None => c,
+                    },
+                    None => c,
+                },
+            ),
+            '[' => {
+                let mut group = Vec::new();
+                loop {
+                    match chars.next() {
+                        Some((_, ']')) => break,
+                        Some((_, '\\')) => {
+                            group.push(chars.next_if(escaped).map_or('\\', |(_, c)| c))
+                        }
+                        Some((_, c)) => group.push(c),
+                        None => bail!(
+                            "Unterminated group '{}' in the pattern '{pattern}', \
+                             escape a literal '[' as '\\['",
+                            &pattern[start..]
+                        ),
+                    }
+                }
+                if group.is_empty() {
+                    bail!("Empty group '[]' in the pattern '{pattern}'");
+                }
+                let mut seen = std::collections::HashSet::new();
+                group.retain(|c| seen.insert(*c));
+                let slot = match groups.iter().find(|(_, chars)| *chars == group) {
+                    Some((slot, _)) => *slot,
+                    None => {
+                        let Some(slot) = GROUP_SLOTS.clone().nth(groups.len()) else {
+                            bail!(
+                                "The patterns have more than {} distinct groups",
+                                groups.len()
+                            );
+                        };
+                        groups.push((slot, group));
+                        slot
+                    }
+                };
+                parsed.push(slot);
+            }
+            '?' => match chars.next_if(|&(_, next)| matches!(next, 'y' | 'Y')) {
+                Some((_, 'y')) => parsed.push(YEAR_SLOT),
+                Some(_) => parsed.push(SHORT_YEAR_SLOT),
+                None => parsed.push('?'),
+            },
+            c => parsed.push(c),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Most fixed-length patterns a pattern with quantifiers may expand into.
+const MAX_EXPANSIONS: usize = 4096;
+
+/// Run time at [`crate::keyspace::REFERENCE_RATE`] above which the size of
+/// the search space is warned about, one year
+const WARN_SEARCH_SECS: u128 = 365 * 24 * 60 * 60;
+
+/// Chunks planned per worker thread, so threads that finish their chunks
+/// early take over the remaining ones instead of idling.
+const CHUNKS_PER_THREAD: u128 = 16;
+
+/// Seconds a chunk should take a thread once the rate on the certificate is
+/// known, so the last chunks of a pattern end close together.
+const CHUNK_SECS: u128 = 2;
+
+/// A part of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Piece {
+    /// A character, fixed or a symbol
+    Char(char),
+    /// A symbol repeated `min..=max` times, from `SYM{min,max}` or `SYM{n}`
+    Repeat(char, usize, usize),
+}
+
+/// Splits a pattern into characters and quantified symbols.
+///
+/// A quantifier is only recognized directly after one of `symbols`, braces
+/// that do not form a quantifier are fixed characters.
+///
+/// # Errors
+///
+/// Returns an error if the lower bound of a quantifier exceeds its upper
+/// bound or a quantifier follows a fixed character
+fn parse_pieces(pattern: &str, symbols: &[char]) -> Result<Vec<Piece>> {
+    let mut pieces = Vec::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        let quantifier = parse_quantifier(rest);
+        if let (Some((_, _, len)), false) = (quantifier, symbols.contains(&c)) {
+            bail!(
+                "Quantifier '{}' follows a fixed character, only symbols and groups take \
+                 quantifiers; escape a literal '{{' as '\\{{'",
+                &rest[..len]
+            );
+        }
+        match quantifier {
+            Some((min, max, len)) => {
+                if min > max {
+                    bail!(
+                        "Invalid quantifier '{c}{}' in the pattern, {min} exceeds {max}",
+                        &rest[..len]
+                    );
+                }
+                pieces.push(Piece::Repeat(c, min, max));
+                rest = &rest[len..];
+            }
+            None => pieces.push(Piece::Char(c)),
+        }
+    }
+    Ok(pieces)
+}
+
+/// Parses a `{min,max}` or `{n}` quantifier at the start of `s`.
+///
+/// # Returns
+///
+/// The bounds and the length of the quantifier in bytes, `None` if `s` does
+/// not start with a quantifier.
+fn parse_quantifier(s: &str) -> Option<(usize, usize, usize)> {
+    let end = s.find('}')?;
+    let bounds = s.strip_prefix('{')?.get(..end - 1)?;
+    let (min, max) = bounds.split_once(',').unwrap_or((bounds, bounds));
+    let parse = |n: &str| {
+        n.bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| n.parse().ok())
+            .flatten()
+    };
+    Some((parse(min)?, parse(max)?, end + 1))
+}
+
+/// Expands quantified symbols into every fixed-length pattern, shortest first.
+///
+/// Patterns of equal length keep the order of their repetition counts, with
+/// the last quantifier changing fastest.
+///
+/// # Errors
+///
+/// Returns an error if there are more than [`MAX_EXPANSIONS`] patterns
+fn expand(pieces: &[Piece]) -> Result<Vec<String>> {
+    let mut patterns = vec![(0, String::new())];
+    for piece in pieces {
+        match *piece {
+            Piece::Char(c) => {
+                for (length, pattern) in &mut patterns {
+                    *length += 1;
+                    pattern.push(c);
+                }
+            }
+            Piece::Repeat(c, min, max) => {
+                if patterns.len().saturating_mul(max - min + 1) > MAX_EXPANSIONS {
+                    bail!("Pattern expands into more than {MAX_EXPANSIONS} fixed-length patterns");
+                }
+                patterns = patterns
+                    .iter()
+                    .flat_map(|(length, pattern)| {
+                        (min..=max).map(move |n| {
+                            let mut pattern = pattern.clone();
+                            pattern.extend(std::iter::repeat_n(c, n));
+                            (length + n, pattern)
+                        })
+                    })
+                    .collect();
+            }
+        }
+    }
+    patterns.sort_by_key(|(length, _)| *length);
+    Ok(patterns.into_iter().map(|(_, pattern)| pattern).collect())
+}
+
+/// A pattern split into its fixed and variable positions.
+struct Template {
+    /// Index of the pattern this fixed-length pattern was expanded from
+    source: usize,
+    /// The pattern with a placeholder in every variable position, followed
+    /// by the case variants of its fixed letters tried for every combination
+    variants: Vec<String>,
+    /// Character indices of the variable positions
+    positions: Vec<usize>,
+    /// Characters of every variable position
+    charsets: Vec<Vec<char>>,
+    /// Further positions of linked symbols, with the index into `positions`
+    /// of the first position of their symbol whose character they repeat
+    links: Vec<(usize, usize)>,
+    /// Runs of adjacent variable positions trying exactly the digits, as
+    /// indices into `positions`
+    digit_runs: Vec<Range<usize>>,
+    /// Variable positions of year tokens, as indices into `positions`
+    years: Vec<usize>,
+}
+
+impl Template {
+    /// Fills a word into the word slots of the pattern.
+    ///
+    /// The word is kept as is, variable positions after a slot move by the
+    /// length of the word.
+    ///
+    /// # Arguments
+    ///
+    /// * `word` - Word from the word list
+    fn with_word(&self, word: &str) -> Self {
+        let word_len = word.chars().count();
+        let slots: Vec<usize> = self.variants[0]
+            .chars()
+            .enumerate()
+            .filter_map(|(i, c)| (c == WORD_SLOT).then_some(i))
+            .collect();
+        let shift = |position: usize| {
+            let before = slots.iter().filter(|&&slot| slot < position).count();
+            position + before * word_len - before
+        };
+        Self {
+            source: self.source,
+            variants: self
+                .variants
+                .iter()
+                .map(|variant| variant.replace(WORD_SLOT, word))
+                .collect(),
+            positions: self
+                .positions
+                .iter()
+                .map(|&position| shift(position))
+                .collect(),
+            charsets: self.charsets.clone(),
+            links: self
+                .links
+                .iter()
+                .map(|&(position, first)| (shift(position), first))
+                .collect(),
+            digit_runs: self.digit_runs.clone(),
+            years: self.years.clone(),
+        }
+    }
+}
+
+impl Template {
+    /// Returns the character ranges of the runs of adjacent variable
+    /// positions in the candidate filled into `buffer`.
+    ///
+    /// Linked positions are variable, a year token spans the digits of its year.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - Characters of the candidate from [`PatternCracker::fill_pattern`]
+    fn group_ranges(&self, buffer: &[char]) -> Vec<Range<usize>> {
+        let mut variable = vec![false; buffer.len()];
+        for position in self
+            .positions
+            .iter()
+            .chain(self.links.iter().map(|(position, _)| position))
+        {
+            variable[*position] = true;
+        }
+        let years: Vec<usize> = self
+            .years
+            .iter()
+            .map(|&year| self.positions[year])
+            .collect();
+
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        let mut offset = 0;
+        for (i, &c) in buffer.iter().enumerate() {
+            let width = match years.contains(&i) {
+                true if (c as u32) < SHORT_YEAR_VALUES => {
+                    (c as u32 - YEAR_VALUES).to_string().len()
+                }
+                true => 2,
+                false => 1,
+            };
+            if variable[i] {
+                match ranges.last_mut() {
+                    Some(range) if range.end == offset => range.end += width,
+                    _ => ranges.push(offset..offset + width),
+                }
+            }
+            offset += width;
+        }
+        ranges
+    }
+}
+
+/// The combinations of a fixed-length pattern to test.
+struct Segment<'a> {
+    /// The pattern with the charsets of its variable positions
+    template: &'a Template,
+    /// Number of combinations of the pattern
+    count: u128,
+    /// Positions of the combinations to test, in traversal order
+    range: Range<u128>,
+    /// Position of the first combination of the pattern in the combinations
+    /// of all fixed-length patterns
+    offset: u128,
+}
+
+/// Returns the length of the chunks `combinations` are split into.
+///
+/// Aims at [`CHUNKS_PER_THREAD`] chunks per thread, with chunks of at least one
+/// and at most `max` combinations. A single thread has no one to hand work to,
+/// so it only splits at `max`.
+fn plan_chunk_len(combinations: u128, threads: usize, max: u128) -> u128 {
+    let chunks = match threads {
+        0 | 1 => 1,
+        threads => threads as u128 * CHUNKS_PER_THREAD,
+    };
+    combinations.div_ceil(chunks).clamp(1, max.max(1))
+}
+
+/// Receivers of the tested ranges of combinations, by their positions in the
+/// combinations of all fixed-length patterns.
+#[derive(Clone, Copy, Default)]
+struct Trackers<'a> {
+    /// Progress file, if any
+    saver: Option<&'a ProgressSaver>,
+    /// Session file, if any
+    session: Option<&'a CheckpointWriter>,
+}
+
+impl Trackers<'_> {
+    /// Records a range of combinations as tested.
+    fn complete(&self, range: Range<u128>) {
+        if let Some(saver) = self.saver {
+            saver.complete(range.clone());
+        }
+        if let Some(session) = self.session {
+            session.complete(range);
+        }
+    }
+}
+
 /// Implements pattern-based password cracking.
 ///
 /// Uses a template pattern where certain positions are fixed and others
 /// are tried with all possible characters from the charset.
 pub struct PatternCracker {
-    /// Template pattern (e.g., "Pass@@rd")
-    pattern: String,
+    /// Template patterns (e.g., "Pass@@rd"), tried in order
+    patterns: Vec<String>,
     /// Characters to try in variable positions
     charset: String,
     /// Characters to try in variable positions
     pattern_symbol: char,
+    /// Seed for shuffling the candidates, `None` keeps the sequential order
+    random_seed: Option<u64>,
+    /// Share of the search space processed by this cracker
+    work_unit: WorkUnit,
+    /// Number of combinations at the start of the work unit to skip
+    skip: u128,
+    /// Maximum number of combinations to try, `None` for the whole work unit
+    limit: Option<u128>,
+    /// Number of candidates per parallel work item, `None` picks it from the pattern
+    chunk_size: Option<usize>,
+    /// Whether to walk the combinations from the top downward
+    reverse: bool,
+    /// Character weights ordering the combinations, most likely first
+    probability_model: Option<ProbabilityModel>,
+    /// Further symbols with their own charsets
+    symbols: Vec<PatternSymbol>,
+    /// Symbols whose positions all take the same character
+    linked: Vec<char>,
+    /// Case variants of the fixed letters tried for every combination
+    case_toggle: Option<CaseToggle>,
+    /// Words replacing [`WORD_TOKEN`] in the patterns, if any
+    word_list: Option<DictionaryCracker>,
+    /// Session file progress is saved to, if any
+    session_path: Option<PathBuf>,
+    /// Whether to resume the run saved in the session file
+    restore: bool,
+    /// How often the session file is written
+    session_cadence: WriteCadence,
+    /// Order of the digit runs, `None` for the index order
+    numeric_order: Option<NumericOrder>,
+    /// Years of the year tokens
+    years: RangeInclusive<u16>,
 }
 
 impl PatternCracker {
@@ -32,234 +811,2430 @@ impl PatternCracker {
     /// * `pattern_symbol` - Symbol marking variable positions (e.g., '@')
     pub fn new(pattern: String, charset: String, pattern_symbol: char) -> Self {
         Self {
-            pattern,
+            patterns: vec![pattern],
             charset,
             pattern_symbol,
+            random_seed: None,
+            work_unit: WorkUnit::FULL,
+            skip: 0,
+            limit: None,
+            chunk_size: None,
+            reverse: false,
+            probability_model: None,
+            symbols: Vec::new(),
+            linked: Vec::new(),
+            case_toggle: None,
+            word_list: None,
+            session_path: None,
+            restore: false,
+            session_cadence: WriteCadence::default(),
+            numeric_order: None,
+            years: DEFAULT_YEARS,
         }
     }
 
-    /// Processes a chunk of pattern combinations.
+    /// Adds another pattern, tried after the previous ones.
+    ///
+    /// Positions of work units, `with_reverse` and progress files count the
+    /// combinations of all patterns consecutively.
     ///
     /// # Arguments
     ///
-    /// * `chunk` - Bytes from the memory-mapped file
-    /// * `pattern` - The template pattern
-    /// * `unknown_positions` - Indices of variable positions in the pattern
-    /// * `pkcs12` - The PKCS#12 certificate to crack
-    /// * `result` - Shared result tracking structure
-    #[inline(always)]
-    fn process_chunk(
-        chunk: &[String],
-        pattern: &str,
-        unknown_positions: &[usize],
-        pkcs12: &Pkcs12,
-        result: &Arc<Mutex<CrackResult>>,
-    ) -> bool {
-        let mut password_chars = Vec::with_capacity(pattern.len());
-
-        for combination in chunk {
-            {
-                let result_guard = result.lock().unwrap();
-                if result_guard.password.is_some() {
-                    return true;
-                }
-                result_guard.increment_attempts();
-            }
+    /// * `pattern` - Template pattern with fixed and variable positions
+    pub fn with_pattern(mut self, pattern: String) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
 
-            password_chars.clear();
-            password_chars.extend(pattern.chars());
+    /// Makes the cracker try candidates in a seeded random order.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Seed for the PRNG permuting the combination indices
+    pub fn with_random_order(mut self, seed: u64) -> Self {
+        self.random_seed = Some(seed);
+        self
+    }
 
-            for (pos, c) in unknown_positions.iter().zip(combination.chars()) {
-                password_chars[*pos] = c;
-            }
+    /// Restricts the cracker to a share of the search space.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_unit` - Share of the search space to process
+    pub fn with_work_unit(mut self, work_unit: WorkUnit) -> Self {
+        self.work_unit = work_unit;
+        self
+    }
 
-            let password: String = password_chars.iter().collect();
-            if super::check_password(pkcs12, &password, result) {
-                return true;
-            }
-        }
-        false
+    /// Skips a number of combinations at the start of the work unit.
+    ///
+    /// Combinations of all fixed-length patterns are numbered consecutively
+    /// in traversal order, or counted in the weighted order with
+    /// `with_probability_model`. Does not apply to a word list.
+    ///
+    /// # Arguments
+    ///
+    /// * `skip` - Number of combinations to skip
+    pub fn with_skip(mut self, skip: u128) -> Self {
+        self.skip = skip;
+        self
     }
 
-    /// Generates combinations for variable positions in the pattern.
+    /// Stops the search after a number of combinations.
     ///
-    /// Similar to the main combination generator, but specifically for
-    /// filling in the variable positions in the pattern.
+    /// Counted from the first combination after `with_skip`, so a slice of
+    /// the search space stays the same when the run is resumed.
     ///
     /// # Arguments
     ///
-    /// * `charset` - Characters to use in combinations
-    /// * `length` - Number of positions to fill
-    /// * `current` - Current combination being built
-    /// * `result` - Vector to store generated combinations
-    fn generate_pattern_combinations(
-        charset: &[char],
-        length: u8,
-        current: &str,
-        result: &mut Vec<String>,
-    ) {
-        if length == 0 {
-            result.push(current.to_owned());
-            return;
-        }
+    /// * `limit` - Maximum number of combinations to try
+    pub fn with_limit(mut self, limit: u128) -> Self {
+        self.limit = Some(limit);
+        self
+    }
 
-        let mut new_str = current.to_owned();
-        for &c in charset {
-            new_str.push(c);
-            Self::generate_pattern_combinations(charset, length - 1, &new_str, result);
-            new_str.pop();
-        }
+    /// Walks the combinations backwards, highest index first.
+    ///
+    /// Work units refer to positions in this reversed traversal order.
+    pub fn with_reverse(mut self) -> Self {
+        self.reverse = true;
+        self
     }
 
-    /// Generates chunks of combinations for large pattern sizes to avoid memory issues
-    /// and improve parallelism.
+    /// Saves progress to a session file while cracking.
+    ///
+    /// The file records the patterns, the charsets of the symbols and the
+    /// certificate, a restored run must match them. Sessions do not apply
+    /// with `with_probability_model` or `with_word_list`.
     ///
     /// # Arguments
     ///
-    /// * `charset` - Characters to use in combinations
-    /// * `unknown_positions` - Number of unknown positions
-    /// * `chunk_size` - Size of each chunk
-    /// * `pkcs12` - The PKCS#12 certificate to crack
-    /// * `result` - Shared result tracking structure
-    /// * `pattern` - The template pattern
-    /// * `positions` - Indices of variable positions in the pattern
+    /// * `path` - Path to the session file
+    /// * `restore` - Whether to resume the run saved in the file
+    pub fn with_session(mut self, path: PathBuf, restore: bool) -> Self {
+        self.session_path = Some(path);
+        self.restore = restore;
+        self
+    }
+
+    /// Sets how often the session file is written, every 30 seconds by default.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// Returns `true` if the password was found, `false` otherwise.
-    fn process_chunks_in_parallel(
-        charset: &[char],
-        unknown_count: usize,
-        chunk_size: usize,
-        pkcs12: &Arc<Pkcs12>,
-        result: &Arc<Mutex<CrackResult>>,
-        pattern: &str,
-        positions: &[usize],
-    ) -> bool {
-        let charset_len = charset.len();
-        let mut total_combinations: usize = 1;
-        for _ in 0..unknown_count {
-            // Overflow protection for very large combination spaces
-            if total_combinations > usize::MAX / charset_len {
-                total_combinations = usize::MAX / 2;
-                break;
-            }
-            total_combinations *= charset_len;
-        }
-
-        let adjusted_chunk_size = if unknown_count > 4 {
-            charset_len.pow(3)
-        } else {
-            chunk_size
-        };
-
-        println!(
-            "Processing {} combinations in chunks of ~{}",
-            total_combinations, adjusted_chunk_size
-        );
-
-        // We'll use position indices to iterate through the combination space
-        // The "position indices" approach allows us to process combinations
-        // without generating them all at once
-
-        let num_chunks = (total_combinations + adjusted_chunk_size - 1) / adjusted_chunk_size;
-        let chunks_range = 0..num_chunks;
-
-        // Use Rayon for parallel processing of chunks
-        chunks_range
-            .into_par_iter()
-            .find_any(|chunk_idx| {
-                let start_idx = chunk_idx * adjusted_chunk_size;
-                let end_idx = (start_idx + adjusted_chunk_size).min(total_combinations);
-
-                // Generate just this chunk of combinations
-                let mut chunk_combinations = Vec::with_capacity(end_idx - start_idx);
-                for combo_idx in start_idx..end_idx {
-                    // Convert the linear index to a combination
-                    let mut indices = Vec::with_capacity(unknown_count);
-                    let mut remaining = combo_idx;
-
-                    for _ in 0..unknown_count {
-                        indices.push(remaining % charset_len);
-                        remaining /= charset_len;
-                    }
+    /// * `cadence` - Time or number of tested combinations between two writes
+    pub fn with_session_cadence(mut self, cadence: WriteCadence) -> Self {
+        self.session_cadence = cadence;
+        self
+    }
 
-                    // Generate the actual combination string
-                    let combination: String = indices.into_iter().map(|idx| charset[idx]).collect();
+    /// Counts runs of adjacent digit positions upward like an integer.
+    ///
+    /// A run is a sequence of adjacent variable positions whose charset is
+    /// exactly the digits, e.g. `@@@@` with `-c n`. With a start of 1999
+    /// a four-digit run tries 1999, 2000, ... 9999 and then 0000 to 1998;
+    /// starts beyond the largest number of a run wrap around. Positions and
+    /// work units are unchanged, only the digits at each position differ.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - Order of the digit runs
+    pub fn with_numeric_order(mut self, order: NumericOrder) -> Self {
+        self.numeric_order = Some(order);
+        self
+    }
 
-                    chunk_combinations.push(combination);
-                }
+    /// Sets the years [`YEAR_TOKEN`] and [`SHORT_YEAR_TOKEN`] try, [`DEFAULT_YEARS`] by default.
+    ///
+    /// A year token is one variable position trying every year, most recent
+    /// first, so it counts as a single position of `years` characters in the
+    /// keyspace, work units and progress files.
+    ///
+    /// # Arguments
+    ///
+    /// * `years` - Years to try, up to [`MAX_YEAR`]
+    pub fn with_years(mut self, years: RangeInclusive<u16>) -> Self {
+        self.years = years;
+        self
+    }
 
-                Self::process_chunk(&chunk_combinations, pattern, positions, pkcs12, result)
-            })
-            .is_some()
+    /// Applies the numeric order to the digit runs of a combination.
+    fn order_digits(&self, template: &Template, combination: String) -> String {
+        match self.numeric_order {
+            Some(NumericOrder::Ascending(start)) if !template.digit_runs.is_empty() => {
+                shift_digit_runs(&combination, &template.digit_runs, start)
+            }
+            _ => combination,
+        }
     }
-}
 
-impl PasswordCracker for PatternCracker {
-    /// Attempts to crack the PKCS#12 password using pattern-based approach.
+    /// Tries combinations in decreasing probability under per-character weights.
     ///
-    /// # Performance
+    /// Replaces the index order, so progress files and `with_reverse` do not
+    /// apply. Combined with `with_random_order` the order is randomized but
+    /// still favors likely characters.
     ///
-    /// The time complexity is O(n^v) where:
-    /// - n is the size of the character set
-    /// - v is the number of variable positions in the pattern
+    /// # Arguments
     ///
-    /// This is generally much more efficient than pure brute force when
-    /// parts of the password are known.
-    fn crack(&self, pkcs12: &Arc<Pkcs12>, result: &Arc<Mutex<CrackResult>>) -> Result<()> {
-        let mut password = String::with_capacity(self.pattern.len());
-        let mut unknown_positions = Vec::with_capacity(self.pattern.len());
-
-        // Pre-process pattern
-        for (i, c) in self.pattern.chars().enumerate() {
-            if c == self.pattern_symbol {
-                unknown_positions.push(i);
-                password.push('?');
-            } else {
+    /// * `model` - Weights of the charset characters
+    pub fn with_probability_model(mut self, model: ProbabilityModel) -> Self {
+        self.probability_model = Some(model);
+        self
+    }
+
+    /// Replaces [`WORD_TOKEN`] in the patterns by every word of a word list.
+    ///
+    /// The words are streamed in file order and all combinations of the
+    /// patterns are tried for a word before the next one. Units of
+    /// `with_work_unit` take the words round-robin, progress files and
+    /// `with_probability_model` do not apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `words` - Dictionary with the words, one per entry
+    pub fn with_word_list(mut self, words: DictionaryCracker) -> Self {
+        self.word_list = Some(words);
+        self
+    }
+
+    /// Declares another symbol marking variable positions, with its own charset.
+    ///
+    /// A symbol equal to the pattern symbol replaces its charset.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol and the characters to try in its positions
+    pub fn with_symbol(mut self, symbol: PatternSymbol) -> Self {
+        self.symbols.push(symbol);
+        self
+    }
+
+    /// Declares a symbol whose positions all take the same character.
+    ///
+    /// With a linked `&`, `&secret&` only tries candidates like `AsecretA`
+    /// with both positions equal, one variable position in the keyspace
+    /// instead of two. An empty charset takes the characters of the pattern
+    /// symbol. Otherwise it works like [`PatternCracker::with_symbol`].
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - The symbol and the characters to try in its positions
+    pub fn with_linked_symbol(mut self, mut symbol: PatternSymbol) -> Self {
+        if symbol.charset.is_empty() {
+            symbol.charset = self.charset.clone();
+        }
+        self.linked.push(symbol.symbol);
+        self.symbols.push(symbol);
+        self
+    }
+
+    /// Splits the patterns into fixed-length patterns with their fixed and
+    /// variable positions, in the order of the patterns and shortest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a symbol is declared twice or does not occur in
+    /// any pattern, the pattern symbol occurs but has no characters, a group
+    /// or quantifier is invalid, or a pattern lacks [`WORD_TOKEN`] with a
+    /// word list
+    fn templates(&self) -> Result<Vec<Template>> {
+        let mut symbols: Vec<(char, Vec<char>)> = Vec::with_capacity(self.symbols.len() + 1);
+        for PatternSymbol { symbol, charset } in &self.symbols {
+            if symbols.iter().any(|(declared, _)| declared == symbol) {
+                bail!("Symbol '{symbol}' is declared more than once");
+            }
+            if !self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.contains(*symbol))
+            {
+                match self.patterns.as_slice() {
+                    [pattern] => {
+                        bail!("Symbol '{symbol}' does not occur in the pattern '{pattern}'")
+                    }
+                    _ => bail!("Symbol '{symbol}' does not occur in any pattern"),
+                }
+            }
+            symbols.push((*symbol, charset.chars().collect()));
+        }
+        if !symbols
+            .iter()
+            .any(|(symbol, _)| *symbol == self.pattern_symbol)
+        {
+            symbols.push((self.pattern_symbol, self.charset.chars().collect()));
+        }
+
+        let declared: Vec<char> = symbols.iter().map(|(symbol, _)| *symbol).collect();
+        let mut groups = Vec::new();
+        let mut patterns = Vec::with_capacity(self.patterns.len());
+        for pattern in &self.patterns {
+            let parsed = parse_groups(pattern, &declared, &mut groups)?;
+            patterns.push(match &self.word_list {
+                Some(_) if !parsed.contains(WORD_TOKEN) => {
+                    bail!("Pattern '{pattern}' has no {WORD_TOKEN} token for the word list")
+                }
+                Some(_) => parsed.replace(WORD_TOKEN, &WORD_SLOT.to_string()),
+                None => parsed,
+            });
+        }
+        symbols.extend(groups);
+        symbols.push((YEAR_SLOT, year_charset(&self.years, false)));
+        symbols.push((SHORT_YEAR_SLOT, year_charset(&self.years, true)));
+
+        let names: Vec<char> = symbols.iter().map(|(symbol, _)| *symbol).collect();
+        let mut templates = Vec::new();
+        for (source, pattern) in patterns.iter().enumerate() {
+            for pattern in expand(&parse_pieces(pattern, &names)?)? {
+                templates.push(Self::template(
+                    source,
+                    &pattern,
+                    &symbols,
+                    &self.linked,
+                    self.case_toggle,
+                )?);
+            }
+        }
+        Ok(templates)
+    }
+
+    /// Builds the templates and checks the patterns before a run.
+    ///
+    /// Warns when the search space takes longer than a year at
+    /// [`crate::keyspace::REFERENCE_RATE`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the templates cannot be built, the pattern symbol
+    /// occurs but the charset is empty, or no pattern has anything to fill
+    /// in, so only the patterns themselves would be tested
+    fn validated_templates(&self) -> Result<Vec<Template>> {
+        let symbol = self.pattern_symbol;
+        let redeclared = self
+            .symbols
+            .iter()
+            .any(|declared| declared.symbol == symbol);
+        if self.charset.is_empty()
+            && !redeclared
+            && self.patterns.iter().any(|pattern| pattern.contains(symbol))
+        {
+            bail!("The charset is empty, so '{symbol}' has no characters to try; check --charset, --charset-range and --no-space");
+        }
+        let templates = self.templates()?;
+        let fixed = templates
+            .iter()
+            .all(|template| template.positions.is_empty() && template.variants.len() == 1);
+        if fixed && self.word_list.is_none() {
+            match self.patterns.as_slice() {
+                [pattern] => bail!(
+                    "Pattern '{pattern}' has no '{symbol}' or other symbol to fill in, so it is a single password; test it with a one-line dictionary (-d) instead"
+                ),
+                _ => bail!(
+                    "No pattern has a '{symbol}' or other symbol to fill in, so they are plain passwords; test them with a dictionary (-d) instead"
+                ),
+            }
+        }
+        let candidates =
+            Self::estimate_templates(&templates, |template| template.variants.len() as u128);
+        let reference_rate = crate::keyspace::REFERENCE_RATE;
+        match candidates {
+            CombinationCount::Exact(count) if count / reference_rate > WARN_SEARCH_SECS => {
+                eprintln!(
+                    "Warning: the patterns have {} candidates, {} at {}M/s; consider fewer unknown positions or smaller charsets",
+                    crate::keyspace::format_magnitude(count),
+                    crate::keyspace::format_duration(count, reference_rate),
+                    reference_rate / 1_000_000
+                );
+            }
+            _ => {}
+        }
+        Ok(templates)
+    }
+
+    /// Splits a fixed-length pattern into fixed and variable positions.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Index of the pattern it was expanded from
+    /// * `pattern` - Pattern without quantifiers, with the slots of escaped
+    ///   braces and symbols
+    /// * `symbols` - Every symbol with the characters of its positions, the
+    ///   declared symbols first in the order of [`SYMBOL_SLOTS`]
+    /// * `linked` - Symbols whose positions all take the same character
+    /// * `toggle` - Case variants of the fixed letters to try, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a symbol in the pattern has no characters or the
+    /// case variants cannot be listed
+    fn template(
+        source: usize,
+        pattern: &str,
+        symbols: &[(char, Vec<char>)],
+        linked: &[char],
+        toggle: Option<CaseToggle>,
+    ) -> Result<Template> {
+        let mut placeholders = String::with_capacity(pattern.len());
+        let mut template = Template {
+            source,
+            variants: Vec::new(),
+            positions: Vec::with_capacity(pattern.len()),
+            charsets: Vec::new(),
+            links: Vec::new(),
+            digit_runs: Vec::new(),
+            years: Vec::new(),
+        };
+        // First variable position of every linked symbol seen so far
+        let mut firsts: Vec<(char, usize)> = Vec::new();
+        for (i, c) in pattern.chars().enumerate() {
+            match symbols.iter().find(|(symbol, _)| *symbol == c) {
+                Some((_, charset)) => {
+                    if charset.is_empty() {
+                        match c {
+                            YEAR_SLOT | SHORT_YEAR_SLOT => {
+                                bail!("The year tokens have no years to try")
+                            }
+                            c => bail!("Symbol '{c}' has no characters to try"),
+                        }
+                    }
+                    placeholders.push('?');
+                    if let Some(&(_, first)) = firsts.iter().find(|(symbol, _)| *symbol == c) {
+                        template.links.push((i, first));
+                        continue;
+                    }
+                    if linked.contains(&c) {
+                        firsts.push((c, template.positions.len()));
+                    }
+                    if matches!(c, YEAR_SLOT | SHORT_YEAR_SLOT) {
+                        template.years.push(template.positions.len());
+                    }
+                    template.positions.push(i);
+                    template.charsets.push(charset.clone());
+                }
+                None => placeholders.push(match c {
+                    BRACE_SLOT => '{',
+                    c if SYMBOL_SLOTS.contains(&c) => {
+                        symbols[c as usize - *SYMBOL_SLOTS.start() as usize].0
+                    }
+                    c => c,
+                }),
+            }
+        }
+        let digits: Vec<char> = crate::charset::DIGITS.chars().collect();
+        for (i, charset) in template.charsets.iter().enumerate() {
+            if *charset != digits {
+                continue;
+            }
+            match template.digit_runs.last_mut() {
+                Some(run)
+                    if run.end == i && template.positions[i - 1] + 1 == template.positions[i] =>
+                {
+                    run.end += 1;
+                }
+                _ => template.digit_runs.push(i..i + 1),
+            }
+        }
+        template.variants = match toggle {
+            Some(toggle) => case_variants(&placeholders, &template.positions, toggle)?,
+            None => vec![placeholders],
+        };
+        Ok(template)
+    }
+
+    /// Computes the number of combinations of all fixed-length patterns.
+    ///
+    /// # Arguments
+    ///
+    /// * `templates` - The fixed-length patterns
+    /// * `per_combination` - Number of items counted for every combination of
+    ///   a pattern, e.g. its case variants to count candidates
+    fn estimate_templates(
+        templates: &[Template],
+        per_combination: fn(&Template) -> u128,
+    ) -> CombinationCount {
+        templates
+            .iter()
+            .try_fold(0u128, |sum, template| {
+                match super::estimate_positional(&template.charsets) {
+                    CombinationCount::Exact(count) => {
+                        sum.checked_add(count.checked_mul(per_combination(template))?)
+                    }
+                    CombinationCount::TooLarge => None,
+                }
+            })
+            .map_or(CombinationCount::TooLarge, CombinationCount::Exact)
+    }
+
+    /// Also tries case variants of the fixed letters for every combination.
+    ///
+    /// Multiplies the candidates by the number of variants, work units and
+    /// progress files still count combinations.
+    ///
+    /// # Arguments
+    ///
+    /// * `toggle` - Which variants to try
+    pub fn with_case_toggle(mut self, toggle: CaseToggle) -> Self {
+        self.case_toggle = Some(toggle);
+        self
+    }
+
+    /// Sets the number of candidates per parallel work item.
+    ///
+    /// By default every pattern is split into at least 16 chunks per thread,
+    /// of at most `CHUNK_SIZE` candidates or the candidates a thread tests in
+    /// about two seconds once the rate is known.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - Candidates per chunk, at least 1
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size.max(1));
+        self
+    }
+
+    /// Returns an iterator over the candidates of the patterns.
+    ///
+    /// The candidates are generated one at a time, pattern by pattern in the
+    /// order of the crack and backwards with `with_reverse`. Case variants
+    /// and the numeric order apply; the random order, the probability model,
+    /// work units, skips and limits do not. The iterator is sequential, use
+    /// `par_bridge()` to consume it in parallel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the patterns are invalid or a word list is set
+    pub fn combinations_iter(&self) -> Result<CombinationIter<'_>> {
+        if self.word_list.is_some() {
+            bail!("Pattern candidates with a word list cannot be iterated");
+        }
+        let mut templates = self.templates()?;
+        if !self.reverse {
+            templates.reverse();
+        }
+        let mut iter = CombinationIter {
+            cracker: self,
+            templates,
+            current: None,
+            combination: None,
+            variant: 0,
+            buffer: Vec::new(),
+        };
+        iter.next_template();
+        Ok(iter)
+    }
+
+    /// Processes a chunk of pattern combinations.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - Combinations to place into the variable positions, generated
+    ///   one at a time
+    /// * `template` - The pattern with its case variants and variable positions
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    /// * `result` - Shared result tracking structure
+    #[inline(always)]
+    fn process_chunk(
+        chunk: impl IntoIterator<Item = impl AsRef<str>>,
+        template: &Template,
+        pkcs12: &Pkcs12,
+        result: &Arc<Mutex<CrackResult>>,
+    ) -> bool {
+        let run = result.lock().unwrap().run();
+        let mut password_chars = Vec::with_capacity(template.variants[0].chars().count());
+
+        for combination in chunk {
+            for pattern in &template.variants {
+                let password = Self::fill_pattern(
+                    &mut password_chars,
+                    pattern,
+                    &template.positions,
+                    &template.links,
+                    &template.years,
+                    combination.as_ref(),
+                );
+                if run.is_stopped() {
+                    return true;
+                }
+                if run.skips_oversized(&password) {
+                    continue;
+                }
+                run.increment_attempts();
+
+                if super::check_password(pkcs12, &password, &run, result) {
+                    Self::record_groups(template, &password_chars, &password, result);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Records the values of the variable groups of the matched candidate.
+    ///
+    /// The values are taken from the password, so they are reported as they
+    /// matched for case folds and reversed candidates. Nothing is recorded
+    /// if a fold changed the number of characters or there are no groups.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The pattern the candidate was filled into
+    /// * `buffer` - Characters of the candidate from [`Self::fill_pattern`]
+    /// * `candidate` - The candidate that matched
+    /// * `result` - Shared result tracking structure holding the password
+    fn record_groups(
+        template: &Template,
+        buffer: &[char],
+        candidate: &str,
+        result: &Arc<Mutex<CrackResult>>,
+    ) {
+        let mut result = result.lock().unwrap();
+        let Some(password) = &result.password else {
+            return;
+        };
+        let mut password: Vec<char> = password.chars().collect();
+        if result.found_reversed {
+            password.reverse();
+        }
+        if password.len() != candidate.chars().count() {
+            return;
+        }
+        let groups: Vec<String> = template
+            .group_ranges(buffer)
+            .into_iter()
+            .map(|range| password[range].iter().collect())
+            .collect();
+        if !groups.is_empty() {
+            result.matched_groups = Some(groups);
+        }
+    }
+
+    /// Builds a candidate by placing the combination into the variable positions.
+    ///
+    /// Positions are character indices, so multibyte characters in both the
+    /// pattern and the combination are handled correctly. The characters of
+    /// year tokens are replaced by the digits of their year.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - Reusable buffer for the candidate characters
+    /// * `pattern` - The template pattern
+    /// * `unknown_positions` - Indices of variable positions in the pattern
+    /// * `links` - Further positions of linked symbols with the index of the
+    ///   variable position whose character they repeat
+    /// * `years` - Indices into `unknown_positions` of the year tokens
+    /// * `combination` - Characters to place into the variable positions
+    #[inline(always)]
+    fn fill_pattern(
+        buffer: &mut Vec<char>,
+        pattern: &str,
+        unknown_positions: &[usize],
+        links: &[(usize, usize)],
+        years: &[usize],
+        combination: &str,
+    ) -> String {
+        buffer.clear();
+        buffer.extend(pattern.chars());
+
+        for (pos, c) in unknown_positions.iter().zip(combination.chars()) {
+            buffer[*pos] = c;
+        }
+        for &(pos, first) in links {
+            buffer[pos] = buffer[unknown_positions[first]];
+        }
+
+        if years.is_empty() {
+            return buffer.iter().collect();
+        }
+        let mut password = String::with_capacity(buffer.len() + 3 * years.len());
+        let mut years = years.iter().map(|&year| unknown_positions[year]).peekable();
+        for (i, &c) in buffer.iter().enumerate() {
+            if years.next_if_eq(&i).is_none() {
                 password.push(c);
+            } else if (c as u32) < SHORT_YEAR_VALUES {
+                let _ = write!(password, "{}", c as u32 - YEAR_VALUES);
+            } else {
+                let _ = write!(password, "{:02}", c as u32 - SHORT_YEAR_VALUES);
             }
         }
+        password
+    }
 
-        let charset: Vec<char> = self.charset.chars().collect();
-        let unknown_count = unknown_positions.len();
+    /// Skips the positions a progress file records as tested.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - Shared result tracking structure
+    /// * `range` - Positions of the combinations to test, in traversal order
+    ///
+    /// # Returns
+    ///
+    /// The first position to test and the progress saver to report tested
+    /// ranges to, if any.
+    fn resume(
+        result: &Arc<Mutex<CrackResult>>,
+        range: &Range<u128>,
+    ) -> (u128, Option<Arc<ProgressSaver>>) {
+        let saver = result.lock().unwrap().progress_saver();
+        let mut start = range.start;
+        if let Some(saver) = &saver {
+            if let Some(Position::Pattern { index }) = saver.saved() {
+                start = start.max(index).min(range.end);
+            }
+            saver.begin(Position::Pattern { index: start });
+        }
+        (start, saver)
+    }
 
-        println!(
-            "Generating pattern combinations for {} unknown positions",
-            unknown_count
+    /// Creates the checkpoint writer for `--session`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the writer and, with `--restore`, the saved checkpoint to resume from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the saved session cannot be read or belongs to
+    /// different patterns, symbol charsets, seed, direction, work unit or
+    /// certificate.
+    fn open_session(
+        &self,
+        path: &Path,
+        pkcs12: &Pkcs12,
+        uses_years: bool,
+    ) -> Result<(CheckpointWriter, Option<Checkpoint>)> {
+        let mut symbols = BTreeMap::from([(self.pattern_symbol, self.charset.clone())]);
+        symbols.extend(
+            self.symbols
+                .iter()
+                .map(|symbol| (symbol.symbol, symbol.charset.clone())),
         );
+        let current = Checkpoint {
+            version: FORMAT_VERSION,
+            charset: self.charset.clone(),
+            min_len: 0,
+            max_len: 0,
+            random_seed: self.random_seed,
+            work_unit: self.work_unit,
+            reverse: self.reverse,
+            length_order: None,
+            policy: String::new(),
+            no_repeats: false,
+            patterns: self.patterns.clone(),
+            symbols,
+            linked: self.linked.clone(),
+            years: uses_years.then(|| self.years.clone()),
+            fingerprint: certificate_fingerprint(pkcs12)?,
+            length: 0,
+            low_water: 0,
+            completed: Vec::new(),
+        };
+        if !self.restore {
+            let writer = CheckpointWriter::new(path.to_path_buf(), current)
+                .with_cadence(self.session_cadence);
+            return Ok((writer, None));
+        }
 
-        let found = if unknown_count >= 4 {
-            Self::process_chunks_in_parallel(
-                &charset,
-                unknown_count,
-                super::CHUNK_SIZE,
-                pkcs12,
-                result,
-                &password,
-                &unknown_positions,
-            )
-        } else {
-            let mut combinations = Vec::new();
-            Self::generate_pattern_combinations(
-                &charset,
-                unknown_count as u8,
-                "",
-                &mut combinations,
-            );
+        let saved = Checkpoint::load(path)?;
+        saved.ensure_matches(&current)?;
+        println!("Resuming session at combination {}", saved.low_water);
+        let writer = CheckpointWriter::new(
+            path.to_path_buf(),
+            Checkpoint {
+                version: FORMAT_VERSION,
+                ..saved.clone()
+            },
+        )
+        .with_cadence(self.session_cadence);
+        Ok((writer, Some(saved)))
+    }
+
+    /// Splits the weighted combinations of this work unit among the patterns.
+    ///
+    /// `skip` and `limit` count the combinations of all patterns in the order
+    /// they are tried.
+    ///
+    /// # Returns
+    ///
+    /// The number of skipped and tried combinations of every pattern.
+    fn weighted_shares(&self, templates: &[Template]) -> Vec<(u128, u128)> {
+        let WorkUnit { index, total } = self.work_unit;
+        let mut skip = self.skip;
+        let mut limit = self.limit.unwrap_or(u128::MAX);
+        templates
+            .iter()
+            .map(|template| {
+                let CombinationCount::Exact(count) = super::estimate_positional(&template.charsets)
+                else {
+                    unreachable!("the sum of the counts fits into u128");
+                };
+                let share = count.saturating_sub(index as u128).div_ceil(total as u128);
+                let skipped = share.min(skip);
+                let tried = (share - skipped).min(limit);
+                skip -= skipped;
+                limit -= tried;
+                (skipped, tried)
+            })
+            .collect()
+    }
+
+    /// Tests the combinations most likely under the probability model first.
+    ///
+    /// Fixed-length patterns are tried shortest first. Units of `--work-unit`
+    /// take the combinations of every pattern round-robin.
+    ///
+    /// # Arguments
+    ///
+    /// * `templates` - The fixed-length patterns
+    /// * `shares` - Skipped and tried combinations of every pattern, from
+    ///   [`Self::weighted_shares`]
+    /// * `model` - Weights of the charset characters
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    /// * `result` - Shared result tracking structure
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the password was found, `false` otherwise.
+    fn process_weighted(
+        &self,
+        templates: &[Template],
+        shares: &[(u128, u128)],
+        model: &ProbabilityModel,
+        pkcs12: &Pkcs12,
+        result: &Arc<Mutex<CrackResult>>,
+    ) -> bool {
+        let WorkUnit { index, total } = self.work_unit;
+        let share = templates
+            .iter()
+            .zip(shares)
+            .map(|(template, (_, tried))| tried * template.variants.len() as u128)
+            .fold(0u128, u128::saturating_add);
+        result.lock().unwrap().progress.total = Some(share);
 
-            combinations
-                .par_chunks(super::CHUNK_SIZE)
-                .find_any(|chunk| {
-                    Self::process_chunk(chunk, &password, &unknown_positions, pkcs12, result)
+        let chunk_size = self.chunk_size.unwrap_or(super::CHUNK_SIZE);
+        let chunks = templates
+            .iter()
+            .zip(shares)
+            .flat_map(|(template, &(skipped, tried))| {
+                let mut combinations = WeightedCombinationIter::positional(
+                    &template.charsets,
+                    model,
+                    self.random_seed,
+                )
+                .skip(index)
+                .step_by(total)
+                .skip(usize::try_from(skipped).unwrap_or(usize::MAX))
+                .take(usize::try_from(tried).unwrap_or(usize::MAX));
+                std::iter::from_fn(move || {
+                    let chunk: Vec<String> = combinations.by_ref().take(chunk_size).collect();
+                    (!chunk.is_empty()).then_some((template, chunk))
                 })
-                .is_some()
+            });
+        chunks
+            .par_bridge()
+            .find_any(|(template, chunk)| Self::process_chunk(chunk, template, pkcs12, result))
+            .is_some()
+    }
+
+    /// Tests the combinations of the patterns for every word of the word list.
+    ///
+    /// Words are read in batches of one word per thread, whose combinations
+    /// are tested in parallel. Units of `--work-unit` take the words
+    /// round-robin.
+    ///
+    /// # Arguments
+    ///
+    /// * `words` - The word list
+    /// * `templates` - The fixed-length patterns with word slots
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    /// * `result` - Shared result tracking structure
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the password was found, `false` otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the word list cannot be read.
+    fn process_words(
+        &self,
+        words: &DictionaryCracker,
+        templates: &[Template],
+        pkcs12: &Pkcs12,
+        result: &Arc<Mutex<CrackResult>>,
+    ) -> Result<bool> {
+        let WorkUnit { index, total } = self.work_unit;
+        let mut order: Vec<&Template> = templates.iter().collect();
+        if self.reverse {
+            order.reverse();
+        }
+        let segments: Vec<(&Template, u128, Option<IndexPermutation>)> = order
+            .into_iter()
+            .map(|template| {
+                let CombinationCount::Exact(count) = super::estimate_positional(&template.charsets)
+                else {
+                    unreachable!("the sum of the counts fits into u128");
+                };
+                let permutation = self
+                    .random_seed
+                    .map(|seed| IndexPermutation::new(count, seed));
+                (template, count, permutation)
+            })
+            .collect();
+        let per_word: u128 = segments
+            .iter()
+            .map(|(template, count, _)| count * template.variants.len() as u128)
+            .sum();
+        println!("Trying {per_word} candidates for every word");
+
+        let test_word = |word: &String| {
+            segments.iter().any(|(template, count, permutation)| {
+                let template = template.with_word(word);
+                let segment = Segment {
+                    template: &template,
+                    count: *count,
+                    range: 0..*count,
+                    offset: 0,
+                };
+                self.process_chunks_in_parallel(
+                    &segment,
+                    permutation.as_ref(),
+                    Trackers::default(),
+                    pkcs12,
+                    result,
+                )
+            })
         };
 
+        let batch_size = rayon::current_num_threads();
+        let mut batch: Vec<String> = Vec::with_capacity(batch_size);
+        let mut seen = 0usize;
+        let mut tried = 0usize;
+        let test_batch = |batch: &mut Vec<String>, tried: &mut usize| {
+            result.lock().unwrap().begin_stage(
+                format!("words {}-{}", *tried + 1, *tried + batch.len()),
+                per_word * batch.len() as u128,
+            );
+            *tried += batch.len();
+            let found = batch.par_iter().any(test_word);
+            batch.clear();
+            found
+        };
+        let mut found = words.for_each_word(|word| {
+            seen += 1;
+            if (seen - 1) % total != index {
+                return false;
+            }
+            batch.push(word.to_string());
+            batch.len() == batch_size && test_batch(&mut batch, &mut tried)
+        })?;
+        if !found && !batch.is_empty() {
+            found = test_batch(&mut batch, &mut tried);
+        }
         if !found {
-            println!("All combinations exhausted, password not found");
+            println!("Tried the combinations of {tried} words");
         }
+        Ok(found)
+    }
 
-        Ok(())
+    /// Returns the number of combinations per parallel work item of a pattern.
+    ///
+    /// Without `with_chunk_size`, the combinations are split into at least
+    /// [`CHUNKS_PER_THREAD`] chunks per thread, so patterns with few
+    /// combinations per position still keep every thread busy. Chunks are
+    /// capped at `CHUNK_SIZE` candidates, and once the run has lasted a second
+    /// at the candidates a thread tests in [`CHUNK_SECS`], so slow
+    /// certificates do not leave threads waiting on a few long chunks. Rayon
+    /// splits the remaining chunk indices among threads as they go idle.
+    ///
+    /// # Arguments
+    ///
+    /// * `segment` - The pattern and the positions of its combinations to test
+    /// * `result` - Shared result tracking structure, for the rate so far
+    fn chunk_len(&self, segment: &Segment, result: &Arc<Mutex<CrackResult>>) -> u128 {
+        if let Some(chunk_size) = self.chunk_size {
+            return chunk_size as u128;
+        }
+        let threads = rayon::current_num_threads();
+        let measured = {
+            let result = result.lock().unwrap();
+            let elapsed = result.start_time.elapsed().as_secs() as u128;
+            let attempts = result.get_attempts() as u128;
+            (elapsed > 0 && attempts > 0).then(|| attempts * CHUNK_SECS / elapsed / threads as u128)
+        };
+        let max_candidates = measured.map_or(super::CHUNK_SIZE as u128, |candidates| {
+            candidates.clamp(1, super::CHUNK_SIZE as u128)
+        });
+        plan_chunk_len(
+            segment.range.end - segment.range.start,
+            threads,
+            max_candidates / segment.template.variants.len() as u128,
+        )
+    }
+
+    /// Tests the combinations of a pattern in parallel chunks of positions.
+    ///
+    /// Every chunk generates its combinations one at a time: in index order
+    /// by counting up from the first combination of the chunk, in a random
+    /// order by decoding the permuted indices. Nothing is materialized, so
+    /// patterns of any size take the same path.
+    ///
+    /// # Arguments
+    ///
+    /// * `segment` - The pattern and the positions of its combinations to test
+    /// * `order` - Permutation of the combination indices of a random order, if any
+    /// * `trackers` - Progress and session files to report tested ranges to
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    /// * `result` - Shared result tracking structure
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the password was found, `false` otherwise.
+    fn process_chunks_in_parallel(
+        &self,
+        segment: &Segment,
+        order: Option<&IndexPermutation>,
+        trackers: Trackers,
+        pkcs12: &Pkcs12,
+        result: &Arc<Mutex<CrackResult>>,
+    ) -> bool {
+        let Segment {
+            template,
+            count: total_combinations,
+            range,
+            offset,
+        } = segment;
+        let total_combinations = *total_combinations;
+        let chunk_size = self.chunk_len(segment, result);
+        let num_chunks = (range.end - range.start).div_ceil(chunk_size);
+
+        (0..num_chunks)
+            .into_par_iter()
+            .find_any(|chunk_idx| {
+                let start_idx = range.start + chunk_idx * chunk_size;
+                let end_idx = (start_idx + chunk_size).min(range.end);
+
+                // Positions count backwards from the last combination when reversed
+                let combinations: Box<dyn Iterator<Item = String>> = match order {
+                    Some(order) => {
+                        let mut buffer = Vec::with_capacity(template.positions.len());
+                        Box::new((start_idx..end_idx).map(move |position| {
+                            let combo_idx = if self.reverse {
+                                total_combinations - 1 - position
+                            } else {
+                                position
+                            };
+                            super::index_to_positional(
+                                order.get(combo_idx),
+                                &template.charsets,
+                                &mut buffer,
+                            )
+                        }))
+                    }
+                    None if self.reverse => Box::new(
+                        super::Combinations::positional_range(
+                            &template.charsets,
+                            total_combinations - end_idx..total_combinations - start_idx,
+                        )
+                        .rev(),
+                    ),
+                    None => Box::new(super::Combinations::positional_range(
+                        &template.charsets,
+                        start_idx..end_idx,
+                    )),
+                };
+
+                if Self::process_chunk(
+                    combinations.map(|combination| self.order_digits(template, combination)),
+                    template,
+                    pkcs12,
+                    result,
+                ) {
+                    return true;
+                }
+                trackers.complete(offset + start_idx..offset + end_idx);
+                false
+            })
+            .is_some()
+    }
+}
+
+/// Iterator over the candidates of a [`PatternCracker`], see
+/// [`PatternCracker::combinations_iter`].
+pub struct CombinationIter<'a> {
+    /// The cracker whose order the candidates follow
+    cracker: &'a PatternCracker,
+    /// Fixed-length patterns still to iterate, the next one last
+    templates: Vec<Template>,
+    /// The pattern being iterated with its remaining combinations
+    current: Option<(Template, super::Combinations<'static>)>,
+    /// Combination whose case variants are being yielded
+    combination: Option<String>,
+    /// Index of the next case variant of the combination
+    variant: usize,
+    /// Reusable buffer for the candidate characters
+    buffer: Vec<char>,
+}
+
+impl CombinationIter<'_> {
+    /// Moves on to the combinations of the next fixed-length pattern.
+    fn next_template(&mut self) {
+        self.current = self.templates.pop().map(|template| {
+            let combinations = super::Combinations::positional(&template.charsets);
+            (template, combinations)
+        });
+    }
+}
+
+impl Iterator for CombinationIter<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            let (template, combinations) = self.current.as_mut()?;
+            if let Some(combination) = &self.combination {
+                if let Some(variant) = template.variants.get(self.variant) {
+                    self.variant += 1;
+                    return Some(PatternCracker::fill_pattern(
+                        &mut self.buffer,
+                        variant,
+                        &template.positions,
+                        &template.links,
+                        &template.years,
+                        combination,
+                    ));
+                }
+            }
+            self.variant = 0;
+            let combination = if self.cracker.reverse {
+                combinations.next_back()
+            } else {
+                combinations.next()
+            };
+            self.combination =
+                combination.map(|combination| self.cracker.order_digits(template, combination));
+            if self.combination.is_none() {
+                self.next_template();
+            }
+        }
+    }
+}
+
+impl PasswordCracker for PatternCracker {
+    /// Attempts to crack the PKCS#12 password using pattern-based approach.
+    ///
+    /// Positions of `with_work_unit`, `with_reverse` and progress files count
+    /// the combinations of all fixed-length patterns consecutively, shortest
+    /// pattern first. Random orders shuffle the combinations of each pattern.
+    ///
+    /// # Performance
+    ///
+    /// The time complexity is O(n₁·n₂·…·nᵥ) where:
+    /// - v is the number of variable positions in the pattern
+    /// - nᵢ is the size of the character set of position i
+    ///
+    /// This is generally much more efficient than pure brute force when
+    /// parts of the password are known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the symbols or quantifiers are invalid, see
+    /// [`PatternCracker::with_symbol`], the charset is empty, no pattern has a
+    /// variable position or the search space does not fit into `u128`.
+    fn crack(&self, pkcs12: &Arc<Pkcs12>, result: &Arc<Mutex<CrackResult>>) -> Result<()> {
+        let templates = self.validated_templates()?;
+        let unknown_counts = templates.iter().map(|template| template.positions.len());
+        let (fewest, most) = (
+            unknown_counts.clone().min().unwrap_or(0),
+            unknown_counts.max().unwrap_or(0),
+        );
+
+        if fewest == most {
+            println!("Generating pattern combinations for {most} unknown positions");
+        } else {
+            println!(
+                "Generating pattern combinations for {} patterns of {fewest} to {most} unknown positions",
+                templates.len()
+            );
+        }
+
+        let total_combinations = match Self::estimate_templates(&templates, |_| 1) {
+            CombinationCount::Exact(total) => total,
+            CombinationCount::TooLarge => {
+                return Err(
+                    anyhow::Error::new(PkcsError::SearchSpaceExceeded).context(format!(
+                        "Search space exceeds {} combinations; reduce the number of unknown \
+                         positions or the charset size",
+                        u128::MAX
+                    )),
+                )
+            }
+        };
+
+        // Combinations of this work unit after `skip` and `limit`
+        let mut covered = total_combinations;
+        let found = if let Some(words) = &self.word_list {
+            self.process_words(words, &templates, pkcs12, result)?
+        } else if let Some(model) = &self.probability_model {
+            let shares = self.weighted_shares(&templates);
+            covered = shares.iter().map(|(_, tried)| tried).sum();
+            self.process_weighted(&templates, &shares, model, pkcs12, result)
+        } else {
+            let unit_range = self.work_unit.range(total_combinations);
+            let first = unit_range
+                .start
+                .saturating_add(self.skip)
+                .min(unit_range.end);
+            let last = self.limit.map_or(unit_range.end, |limit| {
+                unit_range.end.min(first.saturating_add(limit))
+            });
+            let node_range = first..last;
+            covered = last - first;
+            let (session, saved) = match &self.session_path {
+                Some(path) => {
+                    let uses_years = templates.iter().any(|template| !template.years.is_empty());
+                    let (writer, saved) = self.open_session(path, pkcs12, uses_years)?;
+                    (Some(writer), saved)
+                }
+                None => (None, None),
+            };
+            // Ranges above the saved low-water mark are tested again
+            let resumed = saved
+                .as_ref()
+                .map_or(first, |saved| saved.low_water.clamp(first, last));
+            let (start, saver) = Self::resume(result, &(resumed..last));
+            if let Some(session) = &session {
+                let completed = saved.map_or_else(Vec::new, |saved| saved.completed);
+                session.start_at(start, &completed);
+            }
+            let trackers = Trackers {
+                saver: saver.as_deref(),
+                session: session.as_ref(),
+            };
+
+            // Reversed, the patterns are walked longest first and each one backwards
+            let mut order: Vec<&Template> = templates.iter().collect();
+            if self.reverse {
+                order.reverse();
+            }
+            let mut offset = 0;
+            let mut segments = Vec::with_capacity(order.len());
+            for template in order {
+                let CombinationCount::Exact(count) = super::estimate_positional(&template.charsets)
+                else {
+                    unreachable!("the sum of the counts fits into u128");
+                };
+                let span = offset..offset + count;
+                offset += count;
+                let segment = Segment {
+                    template,
+                    count,
+                    range: start.max(span.start) - span.start
+                        ..node_range.end.min(span.end).max(span.start) - span.start,
+                    offset: span.start,
+                };
+                if !segment.range.is_empty() {
+                    segments.push(segment);
+                }
+            }
+            // The status line reports the share and ETA of the candidates left
+            result.lock().unwrap().progress.total = Some(
+                segments
+                    .iter()
+                    .map(|segment| {
+                        (segment.range.end - segment.range.start)
+                            * segment.template.variants.len() as u128
+                    })
+                    .fold(0u128, u128::saturating_add),
+            );
+
+            let mut found = false;
+            for (i, segment) in segments.iter().enumerate() {
+                let source = segment.template.source;
+                let first_of_pattern = i == 0 || segments[i - 1].template.source != source;
+                if self.patterns.len() > 1 && first_of_pattern {
+                    let pattern_segments = segments[i..]
+                        .iter()
+                        .take_while(|segment| segment.template.source == source);
+                    let remaining: u128 = pattern_segments
+                        .clone()
+                        .map(|segment| segment.range.end - segment.range.start)
+                        .sum();
+                    let candidates = pattern_segments
+                        .map(|segment| {
+                            (segment.range.end - segment.range.start)
+                                * segment.template.variants.len() as u128
+                        })
+                        .sum();
+                    println!(
+                        "Trying pattern {}/{}: {} ({remaining} combinations)",
+                        source + 1,
+                        self.patterns.len(),
+                        self.patterns[source]
+                    );
+                    result
+                        .lock()
+                        .unwrap()
+                        .begin_stage(format!("pattern {}", source + 1), candidates);
+                }
+                let segment_len = segment.range.end - segment.range.start;
+                let chunk_size = self.chunk_len(segment, result);
+                if segment_len > chunk_size {
+                    println!("Processing {segment_len} combinations in chunks of ~{chunk_size}");
+                }
+                let order = self
+                    .random_seed
+                    .map(|seed| IndexPermutation::new(segment.count, seed));
+                found = self.process_chunks_in_parallel(
+                    segment,
+                    order.as_ref(),
+                    trackers,
+                    pkcs12,
+                    result,
+                );
+                if found {
+                    break;
+                }
+            }
+            if let Some(session) = &session {
+                session.save()?;
+            }
+            found
+        };
+
+        if !found && covered < total_combinations {
+            println!(
+                "All combinations of this node exhausted, password not found; it covered \
+                 {covered} of {total_combinations} combinations ({:.2}% of the search space)",
+                covered as f64 / total_combinations as f64 * 100.0
+            );
+        } else if !found {
+            println!("All combinations exhausted, password not found");
+        }
+
+        Ok(())
+    }
+
+    fn estimate_count(&self) -> Option<u128> {
+        let templates = self.templates().ok()?;
+        let total = match Self::estimate_templates(&templates, |template| {
+            template.variants.len() as u128
+        }) {
+            CombinationCount::Exact(total) => total,
+            CombinationCount::TooLarge => return None,
+        };
+        match &self.word_list {
+            Some(words) => {
+                let mut count = 0u128;
+                words
+                    .for_each_word(|_| {
+                        count += 1;
+                        false
+                    })
+                    .ok()?;
+                total.checked_mul(count)
+            }
+            None => Some(total),
+        }
+    }
+
+    fn work_items(&self) -> Result<Option<CombinationCount>> {
+        if self.word_list.is_some() {
+            self.templates()?;
+            return Ok(None);
+        }
+        if self.probability_model.is_some() {
+            return Ok(None);
+        }
+        Ok(Some(Self::estimate_templates(&self.templates()?, |_| 1)))
+    }
+
+    fn mode(&self) -> CrackMode {
+        CrackMode::Pattern
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crackers::dictionary::DelimiterMode;
+    use crate::test_fixtures::fixture;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_estimate_count_matches_attempts() {
+        let cracker = PatternCracker::new("p@@5".to_string(), "abc".to_string(), '@');
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+
+        assert_eq!(cracker.estimate_count(), Some(9));
+        assert_eq!(result.lock().unwrap().get_attempts() as u128, 9);
+    }
+
+    #[test]
+    fn test_progress_total() {
+        let total = |cracker: PatternCracker| {
+            let result = Arc::new(Mutex::new(CrackResult::new()));
+            cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+            let result = result.lock().unwrap();
+            assert_eq!(result.progress.total, Some(result.get_attempts() as u128));
+            result.progress.total
+        };
+        let cracker = || {
+            PatternCracker::new("pä@{1,2}".to_string(), "ab".to_string(), '@')
+                .with_case_toggle(CaseToggle::Common)
+        };
+
+        // 2 + 4 combinations, each as "pä", "PÄ" and "Pä"
+        assert_eq!(total(cracker()), Some(18));
+        // The second unit holds 3 combinations of "pä@@"
+        assert_eq!(
+            total(cracker().with_work_unit(WorkUnit { index: 1, total: 2 })),
+            Some(9)
+        );
+        assert_eq!(total(cracker().with_skip(1).with_limit(2)), Some(6));
+    }
+
+    #[test]
+    fn test_restore_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        let pkcs12 = fixture("umlaut.p12");
+        let with_hash = |charset: &str| {
+            PatternCracker::new("p@{1,2}".to_string(), "abc".to_string(), '@')
+                .with_symbol(PatternSymbol {
+                    symbol: '#',
+                    charset: charset.to_string(),
+                })
+                .with_pattern("p#".to_string())
+        };
+        let cracker = || with_hash("xy");
+
+        cracker()
+            .with_session(path.clone(), false)
+            .crack(&pkcs12, &Arc::new(Mutex::new(CrackResult::new())))
+            .unwrap();
+        let mut saved = Checkpoint::load(&path).unwrap();
+        assert_eq!(saved.low_water, 14);
+        assert_eq!(saved.patterns, ["p@{1,2}", "p#"]);
+        assert_eq!(
+            saved.symbols,
+            BTreeMap::from([('@', "abc".to_string()), ('#', "xy".to_string())])
+        );
+
+        // Pretend the run was interrupted within "p@@"
+        saved.low_water = 5;
+        saved.save(&path).unwrap();
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker()
+            .with_session(path.clone(), true)
+            .crack(&pkcs12, &result)
+            .unwrap();
+        assert_eq!(result.lock().unwrap().get_attempts(), 9);
+        assert_eq!(Checkpoint::load(&path).unwrap().low_water, 14);
+
+        let mismatch = |cracker: PatternCracker| {
+            cracker
+                .with_session(path.clone(), true)
+                .crack(&pkcs12, &Arc::new(Mutex::new(CrackResult::new())))
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            mismatch(PatternCracker::new(
+                "p@{1,2}".to_string(),
+                "abc".to_string(),
+                '@'
+            )),
+            r#"Session was saved for the patterns ["p@{1,2}", "p#"]"#
+        );
+        assert_eq!(
+            mismatch(with_hash("xz")),
+            "Session was saved with different symbol charsets"
+        );
+        assert_eq!(
+            mismatch(cracker().with_reverse()),
+            "Session was saved without --reverse"
+        );
+    }
+
+    #[test]
+    fn test_numeric_order() {
+        assert_eq!(
+            parse_numeric_order("asc").unwrap(),
+            NumericOrder::Ascending(0)
+        );
+        assert_eq!(
+            parse_numeric_order("asc:1999").unwrap(),
+            NumericOrder::Ascending(1999)
+        );
+        assert!(parse_numeric_order("desc").is_err());
+        assert!(parse_numeric_order("asc:-1").is_err());
+
+        let cracker = PatternCracker::new(
+            "Backup@@@@".to_string(),
+            crate::charset::DIGITS.to_string(),
+            '@',
+        )
+        .with_numeric_order(NumericOrder::Ascending(1999));
+        let templates = cracker.templates().unwrap();
+        let template = &templates[0];
+        assert_eq!(template.digit_runs, vec![0..4]);
+        let mut buffer = Vec::new();
+        let mut combination = |index| {
+            cracker.order_digits(
+                template,
+                super::super::index_to_positional(index, &template.charsets, &mut buffer),
+            )
+        };
+        assert_eq!(combination(0), "1999");
+        assert_eq!(combination(1), "2000");
+        assert_eq!(combination(8000), "9999");
+        assert_eq!(combination(8001), "0000");
+
+        // Runs end at fixed characters and positions of other charsets
+        let templates = PatternCracker::new("@@-@#@".to_string(), "0123456789".to_string(), '@')
+            .with_symbol(PatternSymbol {
+                symbol: '#',
+                charset: "ab".to_string(),
+            })
+            .templates()
+            .unwrap();
+        assert_eq!(templates[0].digit_runs, [0..2, 2..3, 4..5]);
+        assert_eq!(shift_digit_runs("12a99", &[0..2, 3..5], 5), "17a04");
+        assert_eq!(
+            shift_digit_runs("0", std::slice::from_ref(&(0..1)), 1234),
+            "4"
+        );
+    }
+
+    #[test]
+    fn test_reverse_order() {
+        let attempts = |pattern: &str, charset: &str| {
+            let cracker = PatternCracker::new(pattern.to_string(), charset.to_string(), '@')
+                .with_reverse()
+                .with_chunk_size(1);
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+            let result = session.result().lock().unwrap();
+            assert_eq!(result.password.as_deref(), Some("pä55"));
+            result.get_attempts()
+        };
+
+        // "pä55" is the last of the 2 candidates
+        assert_eq!(attempts("p@55", "aä"), 1);
+        // "pä55" has index 2·27 + 1·9 = 63 of 81, i.e. position 17 when
+        // walking backwards
+        assert_eq!(attempts("@@@@", "5äp"), 18);
+    }
+
+    #[test]
+    fn test_both_paths_share_positions() {
+        let password: Vec<char> = "pä55".chars().collect();
+        let charset: Vec<char> = "5äp".chars().collect();
+        // The last characters of the password are unknown, 4 of them take
+        // the chunked path
+        for unknown_count in 1..=4 {
+            let known = password.len() - unknown_count;
+            let pattern: String = password[..known]
+                .iter()
+                .copied()
+                .chain(std::iter::repeat_n('@', unknown_count))
+                .collect();
+            let tail: String = password[known..].iter().collect();
+            let index = crate::crackers::combination_to_index(&tail, &charset).unwrap();
+            let total = 3u128.pow(unknown_count as u32);
+
+            for reverse in [false, true] {
+                let mut cracker =
+                    PatternCracker::new(pattern.clone(), "5äp".to_string(), '@').with_chunk_size(1);
+                if reverse {
+                    cracker = cracker.with_reverse();
+                }
+                let session = crate::session::CrackSession::new(1).unwrap();
+                session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+
+                let position = if reverse { total - 1 - index } else { index };
+                let result = session.result().lock().unwrap();
+                assert_eq!(result.password.as_deref(), Some("pä55"));
+                assert_eq!(result.get_attempts() as u128, position + 1, "{pattern}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_patterns_are_validated() {
+        let error = |cracker: PatternCracker| {
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session
+                .run(&cracker, &fixture("umlaut.p12"))
+                .unwrap_err()
+                .to_string()
+        };
+        let fixed = PatternCracker::new("pä55".to_string(), "5".to_string(), '@');
+        assert!(error(fixed).starts_with("Pattern 'pä55' has no '@'"));
+        let empty = PatternCracker::new("pä@@".to_string(), String::new(), '@');
+        assert!(error(empty).starts_with("The charset is empty"));
+
+        // Case variants and words are something to fill in
+        let toggled = PatternCracker::new("PÄ55".to_string(), String::new(), '@')
+            .with_case_toggle(CaseToggle::All);
+        let session = crate::session::CrackSession::new(1).unwrap();
+        session.run(&toggled, &fixture("umlaut.p12")).unwrap();
+        assert_eq!(
+            session.result().lock().unwrap().password.as_deref(),
+            Some("pä55")
+        );
+    }
+
+    #[test]
+    fn test_year_tokens() {
+        assert_eq!(parse_years("1990-2025").unwrap(), 1990..=2025);
+        assert_eq!(parse_years("2024").unwrap(), 2024..=2024);
+        assert!(parse_years("2025-1990").is_err());
+        assert!(parse_years("1990-10000").is_err());
+
+        let candidates = |pattern: &str, years| {
+            PatternCracker::new(pattern.to_string(), "ab".to_string(), '@')
+                .with_years(years)
+                .combinations_iter()
+                .unwrap()
+                .collect::<Vec<_>>()
+        };
+        // Most recent first, one position of the keyspace
+        assert_eq!(
+            candidates("x?y@", 1999..=2001),
+            ["x2001a", "x2001b", "x2000a", "x2000b", "x1999a", "x1999b"]
+        );
+        assert_eq!(candidates("?Y", 2008..=2010), ["10", "09", "08"]);
+        // Two-digit years of a range over a century are tried once
+        assert_eq!(candidates("?Y", 1905..=2010).len(), 100);
+        assert_eq!(candidates("a\\?y@", 1999..=2001), ["a?ya", "a?yb"]);
+        assert_eq!(candidates("[?y]", 1999..=2001), ["?", "y"]);
+
+        let cracker = PatternCracker::new("pä?Y".to_string(), String::new(), '@');
+        assert_eq!(cracker.estimate_count(), Some(81));
+        let session = crate::session::CrackSession::new(1).unwrap();
+        session
+            .run(&cracker.with_years(1950..=2060), &fixture("umlaut.p12"))
+            .unwrap();
+        let result = session.result().lock().unwrap();
+        assert_eq!(result.password.as_deref(), Some("pä55"));
+        assert_eq!(result.get_attempts(), 6);
+    }
+
+    #[test]
+    fn test_chunk_plan_keeps_threads_busy() {
+        // Patterns with 5 and more unknown positions of a small charset
+        for unknown_count in 5..=12 {
+            let combinations = 2u128.pow(unknown_count);
+            for threads in 1..=64 {
+                let chunk_len =
+                    plan_chunk_len(combinations, threads, super::super::CHUNK_SIZE as u128);
+                let chunks = combinations.div_ceil(chunk_len);
+                assert!(chunks >= (threads as u128).min(combinations));
+            }
+        }
+        // Degenerate patterns with a single combination or a tiny cap
+        assert_eq!(plan_chunk_len(1, 8, super::super::CHUNK_SIZE as u128), 1);
+        assert_eq!(plan_chunk_len(1_000_000, 8, 0), 1);
+        assert_eq!(plan_chunk_len(u128::MAX, 8, 1000), 1000);
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        let cracker = PatternCracker::new("@@@@@".to_string(), "ab".to_string(), '@');
+        let templates = cracker.templates().unwrap();
+        let segment = Segment {
+            template: &templates[0],
+            count: 32,
+            range: 0..32,
+            offset: 0,
+        };
+        let chunk_len = pool.install(|| cracker.chunk_len(&segment, &result));
+        assert!(32u128.div_ceil(chunk_len) >= 8);
+        let cracker = cracker.with_chunk_size(16);
+        assert_eq!(pool.install(|| cracker.chunk_len(&segment, &result)), 16);
+    }
+
+    #[test]
+    fn test_matched_groups() {
+        let groups = |cracker: PatternCracker, session: crate::session::CrackSession| {
+            session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+            let result = session.result().lock().unwrap();
+            assert!(result.password.is_some());
+            result.matched_groups.clone()
+        };
+        let session = || crate::session::CrackSession::new(1).unwrap();
+        let pattern = |pattern: &str, charset: &str| {
+            PatternCracker::new(pattern.to_string(), charset.to_string(), '@')
+        };
+
+        assert_eq!(
+            groups(pattern("p@5@", "ä5"), session()),
+            Some(vec!["ä".to_string(), "5".to_string()])
+        );
+        let digits = parse_symbol("#=n").unwrap();
+        assert_eq!(
+            groups(pattern("@ä5#", "p").with_symbol(digits.clone()), session()),
+            Some(vec!["p".to_string(), "5".to_string()])
+        );
+        let groups_of = |cracker, session| groups(cracker, session).unwrap().join("|");
+        assert_eq!(
+            groups_of(pattern("p@#5", "ä").with_symbol(digits), session()),
+            "ä5"
+        );
+        assert_eq!(
+            groups_of(pattern("pä?Y", "").with_years(1950..=2060), session()),
+            "55"
+        );
+        // Taken from the password as it matched
+        assert_eq!(
+            groups_of(
+                pattern("PÄ@@", "5"),
+                session().with_case_sensitivity(crate::types::CaseSensitivity::Lower)
+            ),
+            "55"
+        );
+        assert_eq!(
+            groups_of(pattern("5@äp", "5"), session().with_also_reversed()),
+            "5"
+        );
+        assert_eq!(groups_of(pattern("[Pp]ä55", ""), session()), "p");
+        // Case variants of fixed letters are not groups
+        assert_eq!(
+            groups(
+                pattern("Pä55", "").with_case_toggle(CaseToggle::Common),
+                session()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_symbol() {
+        let digits = parse_symbol("#=n").unwrap();
+        assert_eq!(digits.symbol, '#');
+        assert_eq!(digits.charset, "0123456789");
+        assert_eq!(parse_symbol("#=hex,n").unwrap().charset, "0123456789abcdef");
+        assert_eq!(parse_symbol("%:!?!").unwrap().charset, "!?");
+        assert_eq!(parse_symbol("%:\\t").unwrap().charset, "\t");
+        assert_eq!(parse_symbol("==a").unwrap().symbol, '=');
+
+        assert!(parse_symbol("#").is_err());
+        assert!(parse_symbol("#n").is_err());
+        assert!(parse_symbol("#=unknown").is_err());
+        assert!(parse_symbol("#=").is_err());
+        assert!(parse_symbol("#:").is_err());
+    }
+
+    #[test]
+    fn test_symbols_multiply_keyspace() {
+        let cracker = PatternCracker::new("Pa@@w#rd".to_string(), "abc".to_string(), '@')
+            .with_symbol(parse_symbol("#=n").unwrap());
+        assert_eq!(cracker.estimate_count(), Some(3 * 3 * 10));
+        assert_eq!(
+            cracker.work_items().unwrap(),
+            Some(CombinationCount::Exact(90))
+        );
+
+        // Declaring the pattern symbol replaces its charset
+        let cracker = cracker.with_symbol(parse_symbol("@:xy").unwrap());
+        assert_eq!(cracker.estimate_count(), Some(2 * 2 * 10));
+    }
+
+    #[test]
+    fn test_invalid_symbols() {
+        let cracker = |symbols: &[&str]| {
+            symbols.iter().fold(
+                PatternCracker::new("p@#5".to_string(), "aä".to_string(), '@'),
+                |cracker, symbol| cracker.with_symbol(parse_symbol(symbol).unwrap()),
+            )
+        };
+        let error = cracker(&["%=n"]).work_items().unwrap_err();
+        assert!(error.to_string().contains("does not occur"), "{error}");
+        let error = cracker(&["#=n", "#=a"]).work_items().unwrap_err();
+        assert!(error.to_string().contains("more than once"), "{error}");
+        assert_eq!(cracker(&["%=n"]).estimate_count(), None);
+
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        assert!(cracker(&["%=n"])
+            .crack(&fixture("umlaut.p12"), &result)
+            .is_err());
+    }
+
+    #[test]
+    fn test_symbols_find_password() {
+        let attempts = |pattern: &str, chunk_size| {
+            let cracker = PatternCracker::new(pattern.to_string(), "pä".to_string(), '@')
+                .with_symbol(parse_symbol("#=n").unwrap())
+                .with_chunk_size(chunk_size);
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+            let result = session.result().lock().unwrap();
+            assert_eq!(result.password.as_deref(), Some("pä55"));
+            result.get_attempts()
+        };
+
+        // "ä" is digit 1 of 2, "5" digit 5 of 10
+        assert_eq!(attempts("p@#5", 1), 16);
+        // 4 unknown positions take the chunked path: ((0·2 + 1)·10 + 5)·10 + 5
+        assert_eq!(attempts("@@##", 1), 156);
+        assert_eq!(attempts("@@##", 7), 156);
+    }
+
+    #[test]
+    fn test_linked_symbols() {
+        let candidates = |cracker: PatternCracker| -> Vec<String> {
+            cracker.combinations_iter().unwrap().collect()
+        };
+        let linked = |value: &str| parse_linked_symbol(value).unwrap();
+
+        // One variable position for both '&', taking the pattern charset
+        let cracker = || {
+            PatternCracker::new("pä&&".to_string(), "5a".to_string(), '@')
+                .with_linked_symbol(linked("&"))
+        };
+        assert_eq!(cracker().estimate_count(), Some(2));
+        assert_eq!(candidates(cracker()), ["pä55", "päaa"]);
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker().crack(&fixture("umlaut.p12"), &result).unwrap();
+        assert_eq!(result.lock().unwrap().password.as_deref(), Some("pä55"));
+
+        // Linked symbols take their own characters, next to unlinked positions
+        let cracker = PatternCracker::new("&%@&%".to_string(), "01".to_string(), '@')
+            .with_linked_symbol(linked("&:ab"))
+            .with_linked_symbol(linked("%:xy"));
+        assert_eq!(cracker.estimate_count(), Some(8));
+        assert_eq!(
+            candidates(cracker),
+            ["ax0ax", "ax1ax", "ay0ay", "ay1ay", "bx0bx", "bx1bx", "by0by", "by1by"]
+        );
+
+        // Quantified and escaped linked symbols
+        let cracker = PatternCracker::new("&{1,2}-\\&".to_string(), "xy".to_string(), '@')
+            .with_linked_symbol(linked("&"));
+        assert_eq!(candidates(cracker), ["x-&", "y-&", "xx-&", "yy-&"]);
+        let error = PatternCracker::new("@@".to_string(), "xy".to_string(), '@')
+            .with_linked_symbol(linked("&"))
+            .work_items()
+            .unwrap_err();
+        assert!(error.to_string().contains("does not occur"), "{error}");
+    }
+
+    #[test]
+    fn test_expand_quantifiers() {
+        let patterns =
+            |pattern: &str| expand(&parse_pieces(pattern, &['@', '#']).unwrap()).unwrap();
+
+        assert_eq!(
+            patterns("Summer@{1,4}"),
+            ["Summer@", "Summer@@", "Summer@@@", "Summer@@@@"]
+        );
+        assert_eq!(patterns("@{2}#{0,1}"), ["@@", "@@#"]);
+        // Shortest first, the last quantifier changing fastest
+        assert_eq!(patterns("@{0,1}#{0,1}"), ["", "#", "@", "@#"]);
+        // Malformed quantifiers are fixed characters
+        assert_eq!(patterns("a{x}@{x}@{,2}"), ["a{x}@{x}@{,2}"]);
+
+        let error = parse_pieces("p@{3,1}", &['@']).unwrap_err();
+        assert!(error.to_string().contains("3 exceeds 1"), "{error}");
+        for pattern in ["a{1,2}@", "@a{3}"] {
+            let error = parse_pieces(pattern, &['@']).unwrap_err();
+            assert!(
+                error.to_string().contains("follows a fixed character"),
+                "{error}"
+            );
+        }
+        let error = expand(&parse_pieces("@{0,99}@{0,99}", &['@']).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("more than 4096"), "{error}");
+    }
+
+    #[test]
+    fn test_quantifier_sums_keyspace() {
+        let cracker = PatternCracker::new("p@{0,2}".to_string(), "abc".to_string(), '@');
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+
+        assert_eq!(cracker.estimate_count(), Some(1 + 3 + 9));
+        assert_eq!(
+            cracker.work_items().unwrap(),
+            Some(CombinationCount::Exact(13))
+        );
+        assert_eq!(result.lock().unwrap().get_attempts(), 13);
+    }
+
+    #[test]
+    fn test_quantifier_positions_are_concatenated() {
+        let attempts = |configure: fn(PatternCracker) -> PatternCracker| {
+            let cracker = configure(
+                PatternCracker::new("pä@{1,3}".to_string(), "5a".to_string(), '@')
+                    .with_chunk_size(1),
+            );
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+            let result = session.result().lock().unwrap();
+            (result.password.clone(), result.get_attempts())
+        };
+        let found = |attempts| (Some("pä55".to_string()), attempts);
+
+        // 2 combinations of "pä@" before "pä55", the first of "pä@@"
+        assert_eq!(attempts(|cracker| cracker), found(3));
+        // Backwards through the 8 combinations of "pä@@@", then "pä@@"
+        assert_eq!(attempts(PatternCracker::with_reverse), found(12));
+        // Units split the 2 + 4 + 8 combinations at position 7, within "pä@@@"
+        assert_eq!(
+            attempts(|cracker| cracker.with_work_unit(WorkUnit { index: 0, total: 2 })),
+            found(3)
+        );
+        assert_eq!(
+            attempts(|cracker| cracker.with_work_unit(WorkUnit { index: 1, total: 2 })),
+            (None, 7)
+        );
+    }
+
+    #[test]
+    fn test_skip_and_limit_slice_work_unit() {
+        let attempts = |configure: &dyn Fn(PatternCracker) -> PatternCracker| {
+            let cracker = configure(
+                PatternCracker::new("pä@@".to_string(), "a5b".to_string(), '@').with_chunk_size(1),
+            );
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+            let result = session.result().lock().unwrap();
+            (result.password.clone(), result.get_attempts())
+        };
+
+        // "pä55" is the fifth of the 9 combinations
+        assert_eq!(attempts(&|cracker| cracker.with_skip(5)), (None, 4));
+        assert_eq!(attempts(&|cracker| cracker.with_limit(4)), (None, 4));
+        assert_eq!(
+            attempts(&|cracker| cracker.with_skip(4).with_limit(1)),
+            (Some("pä55".to_string()), 1)
+        );
+        assert_eq!(attempts(&|cracker| cracker.with_skip(20)), (None, 0));
+        // Three nodes take 3 combinations each, only the second finds it
+        let nodes: Vec<_> = (0..3)
+            .map(|index| attempts(&|cracker| cracker.with_work_unit(WorkUnit { index, total: 3 })))
+            .collect();
+        assert_eq!(nodes, [(None, 3), (Some("pä55".to_string()), 2), (None, 3)]);
+        // Skip and limit count within the work unit
+        assert_eq!(
+            attempts(&|cracker| cracker
+                .with_work_unit(WorkUnit { index: 1, total: 3 })
+                .with_skip(2)),
+            (None, 1)
+        );
+    }
+
+    #[test]
+    fn test_quantifier_chunked_path() {
+        let cracker =
+            PatternCracker::new("@{3,4}".to_string(), "5äp".to_string(), '@').with_chunk_size(1);
+        let session = crate::session::CrackSession::new(1).unwrap();
+        session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+        let result = session.result().lock().unwrap();
+
+        assert_eq!(result.password.as_deref(), Some("pä55"));
+        // 27 combinations of "@@@", then index 2·27 + 1·9 of "@@@@"
+        assert_eq!(result.get_attempts(), 27 + 64);
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        let mut groups = Vec::new();
+        let parsed = parse_groups("a[-_.]b[x\\]]\\[c\\d[-_.]", &[], &mut groups).unwrap();
+        assert_eq!(parsed, "a\u{E001}b\u{E002}[c\\d\u{E001}");
+        assert_eq!(
+            groups,
+            [
+                ('\u{E001}', vec!['-', '_', '.']),
+                ('\u{E002}', vec!['x', ']'])
+            ]
+        );
+        // Repeated characters are tried once, the slot of an equal group is reused
+        parse_groups("[aab]", &[], &mut groups).unwrap();
+        parse_groups("[ab]", &[], &mut groups).unwrap();
+        assert_eq!(groups[2], ('\u{E003}', vec!['a', 'b']));
+        assert_eq!(groups.len(), 3);
+
+        // An escaped brace never starts a quantifier
+        assert_eq!(
+            parse_groups("@\\{2}", &[], &mut groups).unwrap(),
+            "@\u{F8FF}2}"
+        );
+        let cracker = PatternCracker::new("a\\{2}@\\{1}".to_string(), "5".to_string(), '@');
+        assert_eq!(cracker.templates().unwrap()[0].variants, ["a{2}?{1}"]);
+
+        let error = parse_groups("Acme[-_2", &[], &mut groups).unwrap_err();
+        assert!(
+            error.to_string().contains("Unterminated group '[-_2'"),
+            "{error}"
+        );
+        let error = parse_groups("Acme[ab\\]", &[], &mut groups).unwrap_err();
+        assert!(error.to_string().contains("Unterminated group"), "{error}");
+        let error = parse_groups("Acme[]", &[], &mut groups).unwrap_err();
+        assert!(error.to_string().contains("Empty group"), "{error}");
+    }
+
+    #[test]
+    fn test_reserved_characters() {
+        for (pattern, reserved) in [
+            ("a\u{F8FF}@", "U+F8FF"),
+            ("[\u{F8FF}]@", "U+F8FF"),
+            ("a\u{E000}@", "U+E000"),
+            ("a\u{E001}@", "U+E001"),
+            ("[ab]\u{F8FE}@", "U+F8FE"),
+            ("a\u{F0000}@", "U+F0000"),
+            ("a\u{10FFFD}@", "U+10FFFD"),
+        ] {
+            let error = parse_groups(pattern, &['@'], &mut Vec::new()).unwrap_err();
+            assert!(
+                error.to_string().contains(&format!("contains {reserved}")),
+                "{error}"
+            );
+        }
+        // A typed word slot would otherwise vanish from the candidates
+        let cracker = PatternCracker::new("a\u{E000}@".to_string(), "5".to_string(), '@');
+        assert!(cracker.templates().is_err());
+        // A typed group slot would otherwise take the characters of a group
+        let cracker = PatternCracker::new("[xy]\u{E001}@".to_string(), "5".to_string(), '@');
+        assert!(cracker.templates().is_err());
+        // Other private use characters are fixed characters
+        let cracker = PatternCracker::new("\u{100000}@".to_string(), "5".to_string(), '@');
+        assert_eq!(cracker.templates().unwrap()[0].variants, ["\u{100000}?"]);
+    }
+
+    #[test]
+    fn test_escaped_symbols() {
+        assert_eq!(
+            parse_groups("a\\@b@\\#\\\\", &['@', '#'], &mut Vec::new()).unwrap(),
+            "a\u{F0000}b@\u{F0001}\\"
+        );
+
+        // Escaped occurrences are fixed, the others stay variable positions
+        let cracker = PatternCracker::new("me\\@x@\\\\@".to_string(), "ab".to_string(), '@')
+            .with_symbol(PatternSymbol {
+                symbol: '#',
+                charset: "12".to_string(),
+            })
+            .with_pattern("\\#@{2}#".to_string());
+        let templates = cracker.templates().unwrap();
+        assert_eq!(templates[0].variants, ["me@x?\\?"]);
+        assert_eq!(templates[0].positions, [4, 6]);
+        assert_eq!(templates[1].variants, ["#???"]);
+        assert_eq!(templates[1].charsets[2], ['1', '2']);
+
+        // An escaped symbol takes no quantifier
+        let Err(error) =
+            PatternCracker::new("\\@{2}".to_string(), "ab".to_string(), '@').templates()
+        else {
+            panic!("a quantifier after a fixed character is an error");
+        };
+        assert!(
+            error.to_string().contains("follows a fixed character"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_groups_use_position_sizes() {
+        // 3 separators, 3 digits, 2 · 2 characters of the symbol
+        let cracker = PatternCracker::new("pä[-_.]5[012]@@".to_string(), "5ä".to_string(), '@');
+        assert_eq!(cracker.estimate_count(), Some(3 * 3 * 2 * 2));
+        assert_eq!(
+            cracker.work_items().unwrap(),
+            Some(CombinationCount::Exact(36))
+        );
+
+        // Symbols and braces inside a group are listed characters, a quantifier
+        // after a group repeats its position
+        for pattern in ["p[äa]@[5@]", "[p]ä[@{5]5", "p[ä]{1}[5]{2}"] {
+            for chunk_size in [None, Some(1)] {
+                let mut cracker = PatternCracker::new(pattern.to_string(), "5".to_string(), '@');
+                if let Some(chunk_size) = chunk_size {
+                    cracker = cracker.with_chunk_size(chunk_size);
+                }
+                let result = Arc::new(Mutex::new(CrackResult::new()));
+                cracker.crack(&fixture("umlaut.p12"), &result).unwrap();
+                let result = result.lock().unwrap();
+                assert_eq!(result.password.as_deref(), Some("pä55"), "{pattern}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_pattern_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("patterns.txt");
+        std::fs::write(
+            &path,
+            "# Company name and year\nAcme@@@@  # 2000-2099\n\n  Pa#@w0rd\nSummer@{1,2}#\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_pattern_file(&path).unwrap(),
+            [
+                (2, "Acme@@@@".to_string()),
+                (4, "Pa#@w0rd".to_string()),
+                (5, "Summer@{1,2}#".to_string()),
+            ]
+        );
+        assert!(read_pattern_file(&dir.path().join("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn test_patterns_run_in_order() {
+        let attempts = |configure: fn(PatternCracker) -> PatternCracker| {
+            let cracker = configure(
+                PatternCracker::new("x@".to_string(), "5a".to_string(), '@')
+                    .with_pattern("pä@@".to_string())
+                    .with_pattern("p@55".to_string())
+                    .with_chunk_size(1),
+            );
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+            let result = session.result().lock().unwrap();
+            (result.password.clone(), result.get_attempts())
+        };
+        let found = |attempts| (Some("pä55".to_string()), attempts);
+
+        // All 2 candidates of "x@", then "pä55" first of "pä@@"
+        assert_eq!(attempts(|cracker| cracker), found(3));
+        // Backwards through "p@55", then "pä@@"
+        assert_eq!(attempts(PatternCracker::with_reverse), found(2 + 4));
+        // Units split the 2 + 4 + 2 combinations in half, within "pä@@"
+        assert_eq!(
+            attempts(|cracker| cracker.with_work_unit(WorkUnit { index: 1, total: 2 })),
+            (None, 4)
+        );
+
+        let cracker = PatternCracker::new("x@".to_string(), "ab".to_string(), '@')
+            .with_pattern("y#".to_string())
+            .with_symbol(parse_symbol("#=n").unwrap());
+        assert_eq!(cracker.estimate_count(), Some(2 + 10));
+        let error = cracker
+            .with_symbol(parse_symbol("%=n").unwrap())
+            .work_items()
+            .unwrap_err();
+        assert!(error.to_string().contains("does not occur in any pattern"));
+    }
+
+    #[test]
+    fn test_combinations_iter() {
+        let candidates = |cracker: PatternCracker| -> Vec<String> {
+            cracker.combinations_iter().unwrap().collect()
+        };
+        let cracker = || PatternCracker::new("a@@".to_string(), "xyz".to_string(), '@');
+
+        let expected = [
+            "axx", "axy", "axz", "ayx", "ayy", "ayz", "azx", "azy", "azz",
+        ];
+        assert_eq!(candidates(cracker()), expected);
+        let mut reversed = expected;
+        reversed.reverse();
+        assert_eq!(candidates(cracker().with_reverse()), reversed);
+
+        // Patterns in order, case variants after every combination
+        let cracker = PatternCracker::new("@".to_string(), "12".to_string(), '@')
+            .with_pattern("b@".to_string())
+            .with_case_toggle(CaseToggle::All);
+        assert_eq!(candidates(cracker), ["1", "2", "b1", "B1", "b2", "B2"]);
+
+        // The crack tests the combinations in the same order
+        let run = |cracker: PatternCracker| {
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session
+                .run(&cracker.with_chunk_size(1), &fixture("umlaut.p12"))
+                .unwrap();
+            let result = session.result().lock().unwrap();
+            (result.password.clone(), result.get_attempts())
+        };
+        for reverse in [false, true] {
+            let cracker = || {
+                let cracker = PatternCracker::new("@@@5".to_string(), "5äp".to_string(), '@');
+                if reverse {
+                    cracker.with_reverse()
+                } else {
+                    cracker
+                }
+            };
+            let position = candidates(cracker())
+                .iter()
+                .position(|candidate| candidate == "pä55")
+                .unwrap();
+            assert_eq!(run(cracker()), (Some("pä55".to_string()), position + 1));
+        }
+    }
+
+    #[test]
+    fn test_case_variants() {
+        let variants = |pattern: &str, positions: &[usize], toggle| {
+            case_variants(pattern, positions, toggle).unwrap()
+        };
+
+        assert_eq!(
+            variants("acme??", &[4, 5], CaseToggle::Common),
+            ["acme??", "ACME??", "Acme??"]
+        );
+        assert_eq!(
+            variants("?cMe", &[0], CaseToggle::Common),
+            ["?cMe", "?cme", "?CME", "?Cme"]
+        );
+        // Variable positions, non-letters and letters without a single-character
+        // mapping are left alone
+        assert_eq!(variants("aß1?", &[3], CaseToggle::All), ["aß1?", "Aß1?"]);
+        assert_eq!(
+            variants("ab", &[], CaseToggle::All),
+            ["ab", "Ab", "aB", "AB"]
+        );
+        assert_eq!(variants("??", &[0, 1], CaseToggle::All), ["??"]);
+
+        let error = case_variants(&"a".repeat(17), &[], CaseToggle::All).unwrap_err();
+        assert!(
+            error.to_string().contains("up to 16 fixed letters"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_case_toggle_multiplies_candidates() {
+        let run = |cracker: PatternCracker| {
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session
+                .run(&cracker.with_chunk_size(1), &fixture("umlaut.p12"))
+                .unwrap();
+            let result = session.result().lock().unwrap();
+            (result.password.clone(), result.get_attempts())
+        };
+
+        // "P?55" and "p?55" for every combination, "pä55" is the last candidate
+        let cracker = || {
+            PatternCracker::new("P@55".to_string(), "aä".to_string(), '@')
+                .with_case_toggle(CaseToggle::Common)
+        };
+        assert_eq!(cracker().estimate_count(), Some(2 * 2));
+        assert_eq!(
+            cracker().work_items().unwrap(),
+            Some(CombinationCount::Exact(2))
+        );
+        assert_eq!(run(cracker()), (Some("pä55".to_string()), 4));
+
+        // Composes with symbols: ä is digit 1 of 2, 5 digit 5 of 10
+        let cracker = PatternCracker::new("P@#5".to_string(), "aä".to_string(), '@')
+            .with_symbol(parse_symbol("#=n").unwrap())
+            .with_case_toggle(CaseToggle::All);
+        assert_eq!(cracker.estimate_count(), Some(2 * 20));
+        assert_eq!(run(cracker), (Some("pä55".to_string()), 2 * 16));
+    }
+
+    #[test]
+    fn test_word_list_fills_token() {
+        let mut words = tempfile::NamedTempFile::new().unwrap();
+        write!(words, "x\np\npä\n\nq\n").unwrap();
+        let attempts = |configure: fn(PatternCracker) -> PatternCracker| {
+            let cracker = configure(
+                PatternCracker::new("%W@5".to_string(), "a5".to_string(), '@')
+                    .with_word_list(DictionaryCracker::new(
+                        words.path().to_path_buf(),
+                        DelimiterMode::SingleByte(b'\n'),
+                    ))
+                    .with_chunk_size(1),
+            );
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session.run(&cracker, &fixture("umlaut.p12")).unwrap();
+            let result = session.result().lock().unwrap();
+            (result.password.clone(), result.get_attempts())
+        };
+        let found = |attempts| (Some("pä55".to_string()), attempts);
+
+        // 2 combinations of "x" and "p", then "pä55" last of "pä"
+        assert_eq!(attempts(|cracker| cracker), found(6));
+        assert_eq!(attempts(PatternCracker::with_reverse), found(5));
+        // The second unit takes "p" and "q"
+        assert_eq!(
+            attempts(|cracker| cracker.with_work_unit(WorkUnit { index: 1, total: 2 })),
+            (None, 4)
+        );
+
+        let cracker = PatternCracker::new("%W@5".to_string(), "a5".to_string(), '@')
+            .with_word_list(DictionaryCracker::new(
+                words.path().to_path_buf(),
+                DelimiterMode::SingleByte(b'\n'),
+            ));
+        assert_eq!(cracker.estimate_count(), Some(4 * 2));
+        assert_eq!(cracker.work_items().unwrap(), None);
+        let error = cracker
+            .with_pattern("@@".to_string())
+            .work_items()
+            .unwrap_err();
+        assert!(error.to_string().contains("has no %W token"));
+    }
+
+    #[test]
+    fn test_with_word_keeps_symbols_fixed() {
+        let cracker =
+            PatternCracker::new("%W@%W".to_string(), "5".to_string(), '@').with_word_list(
+                DictionaryCracker::new(PathBuf::new(), DelimiterMode::SingleByte(b'\n')),
+            );
+        let template = cracker.templates().unwrap().remove(0).with_word("@ä");
+        assert_eq!(template.variants, vec!["@ä?@ä"]);
+        assert_eq!(template.positions, vec![2]);
+
+        let mut buffer = Vec::new();
+        assert_eq!(
+            PatternCracker::fill_pattern(
+                &mut buffer,
+                &template.variants[0],
+                &template.positions,
+                &template.links,
+                &template.years,
+                "5"
+            ),
+            "@ä5@ä"
+        );
+    }
+
+    #[test]
+    fn test_keyspace_beyond_usize() {
+        // 2^65 combinations, the password is the very last one
+        let pkcs12 = fixture("last_candidate.p12");
+        let cracker = PatternCracker::new("@".repeat(65), "ab".to_string(), '@')
+            .with_work_unit(WorkUnit { index: 0, total: 2 })
+            .with_reverse()
+            .with_chunk_size(1);
+        let session = crate::session::CrackSession::new(1).unwrap();
+        session.run(&cracker, &pkcs12).unwrap();
+
+        let result = session.result().lock().unwrap();
+        assert_eq!(result.password, Some("b".repeat(65)));
+        assert_eq!(result.get_attempts(), 1);
+    }
+
+    #[test]
+    fn test_multibyte_paths_agree() {
+        let pkcs12 = fixture("strasse.p12");
+        // Variable positions between multibyte fixed characters, "ß" and "ö"
+        // taken from a multibyte charset
+        let attempts = |configure: &dyn Fn(PatternCracker) -> PatternCracker| {
+            let cracker = configure(
+                PatternCracker::new("Stra@e@ü".to_string(), "eäöüß".to_string(), '@')
+                    .with_chunk_size(1),
+            );
+            let session = crate::session::CrackSession::new(1).unwrap();
+            session.run(&cracker, &pkcs12).unwrap();
+            let result = session.result().lock().unwrap();
+            assert_eq!(result.password.as_deref(), Some("Straßeöü"));
+            result.get_attempts()
+        };
+
+        // ß is digit 4 and ö digit 2 of 5
+        assert_eq!(attempts(&|cracker| cracker), 4 * 5 + 2 + 1);
+        for seed in [None, Some(1), Some(2)] {
+            for reverse in [false, true] {
+                let configure = |mut cracker: PatternCracker| {
+                    if let Some(seed) = seed {
+                        cracker = cracker.with_random_order(seed);
+                    }
+                    if reverse {
+                        cracker = cracker.with_reverse();
+                    }
+                    cracker
+                };
+                assert_eq!(
+                    attempts(&configure),
+                    attempts(&|cracker| configure(cracker).with_chunk_size(3)),
+                    "seed {seed:?}, reverse {reverse}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_pattern_multibyte() {
+        let charset: Vec<char> = "ж😀".chars().collect();
+        let mut combinations = Vec::new();
+        crate::crackers::generate_combinations(&charset, 2, "", &mut combinations);
+        assert_eq!(combinations, vec!["жж", "ж😀", "😀ж", "😀😀"]);
+
+        let mut buffer = Vec::new();
+        let password =
+            PatternCracker::fill_pattern(&mut buffer, "П?р?ль", &[1, 3], &[], &[], "😀ж");
+        assert_eq!(password, "П😀ржль");
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+        use std::collections::HashSet;
+
+        /// Fills `pattern` with every combination of both generation paths.
+        fn candidates(pattern: &[char], charset: &[char]) -> (Vec<String>, Vec<String>) {
+            let template: String = pattern.iter().collect();
+            let positions: Vec<usize> = (0..pattern.len()).filter(|&i| pattern[i] == '?').collect();
+            let mut buffer = Vec::new();
+            let mut fill = |combination: &str| {
+                PatternCracker::fill_pattern(
+                    &mut buffer,
+                    &template,
+                    &positions,
+                    &[],
+                    &[],
+                    combination,
+                )
+            };
+
+            let mut combinations: Vec<String> = Vec::new();
+            crate::crackers::generate_combinations(
+                charset,
+                positions.len() as u8,
+                "",
+                &mut combinations,
+            );
+            let generated = combinations.iter().map(|c| fill(c)).collect();
+
+            let total = charset.len().pow(positions.len() as u32) as u128;
+            let mut digits = Vec::new();
+            let chunked = (0..total)
+                .map(|index| {
+                    fill(&crate::crackers::index_to_combination(
+                        index,
+                        charset,
+                        positions.len(),
+                        &mut digits,
+                    ))
+                })
+                .collect();
+            (generated, chunked)
+        }
+
+        proptest! {
+            #[test]
+            fn candidates_keep_fixed_characters(
+                pattern in prop::collection::vec(prop_oneof![Just('?'), any::<char>()], 0..8)
+                    .prop_filter("at most 4 unknown positions", |p| p.iter().filter(|&&c| c == '?').count() <= 4),
+                charset in prop::collection::hash_set(any::<char>(), 1..=4),
+            ) {
+                let charset: Vec<char> = charset.into_iter().collect();
+                let (generated, chunked) = candidates(&pattern, &charset);
+                for candidate in generated.iter().chain(&chunked) {
+                    let chars: Vec<char> = candidate.chars().collect();
+                    prop_assert_eq!(chars.len(), pattern.len());
+                    for (c, p) in chars.iter().zip(&pattern) {
+                        if *p == '?' {
+                            prop_assert!(charset.contains(c));
+                        } else {
+                            prop_assert_eq!(c, p);
+                        }
+                    }
+                }
+                // Both paths enumerate the same candidates in the same order
+                prop_assert_eq!(&generated, &chunked);
+                let unique: HashSet<&String> = generated.iter().collect();
+                prop_assert_eq!(unique.len(), generated.len());
+            }
+
+        }
     }
 }
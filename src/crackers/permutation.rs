@@ -0,0 +1,169 @@
+//! Seeded random orderings of combination spaces.
+//!
+//! Used by `--random-order` to visit candidates in a reproducible, shuffled
+//! order. Small index spaces are shuffled in memory with Fisher-Yates, large
+//! ones are mapped through a Feistel-network bijection so that the
+//! permutation never has to be materialized.
+use rand::seq::SliceRandom;
+use rand::RngCore;
+use rand::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+/// Largest index space that is shuffled in memory.
+/// 1M indices take 8MB, beyond that the Feistel bijection is used.
+const SHUFFLE_LIMIT: u128 = 1 << 20;
+
+/// Number of rounds of the Feistel network.
+const FEISTEL_ROUNDS: usize = 4;
+
+/// Shuffles `items` in place with a PRNG seeded from `seed`.
+pub(crate) fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+    items.shuffle(&mut rng);
+}
+
+/// A seeded bijection of the index space `0..total`.
+pub(crate) enum IndexPermutation {
+    /// Fully materialized permutation for small spaces
+    Shuffled(Vec<usize>),
+    /// Feistel network with cycle-walking for large spaces
+    Feistel {
+        /// Size of the permuted index space
+        total: u128,
+        /// Number of bits in each half of the Feistel block
+        half_bits: u32,
+        /// Per-round keys derived from the seed
+        keys: [u64; FEISTEL_ROUNDS],
+    },
+}
+
+impl IndexPermutation {
+    /// Creates a new permutation of `0..total` derived from `seed`.
+    pub(crate) fn new(total: u128, seed: u64) -> Self {
+        if total <= SHUFFLE_LIMIT {
+            let mut indices: Vec<usize> = (0..total as usize).collect();
+            shuffle(&mut indices, seed);
+            return Self::Shuffled(indices);
+        }
+
+        let bits = u128::BITS - (total - 1).leading_zeros();
+        let half_bits = bits.div_ceil(2);
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let mut keys = [0u64; FEISTEL_ROUNDS];
+        for key in keys.iter_mut() {
+            *key = rng.next_u64();
+        }
+
+        Self::Feistel {
+            total,
+            half_bits,
+            keys,
+        }
+    }
+
+    /// Returns the permuted position of `index`.
+    ///
+    /// `index` must be smaller than the size of the index space.
+    #[inline(always)]
+    pub(crate) fn get(&self, index: u128) -> u128 {
+        match self {
+            Self::Shuffled(indices) => indices[index as usize] as u128,
+            Self::Feistel {
+                total,
+                half_bits,
+                keys,
+            } => {
+                // Cycle-walking: the Feistel block may be larger than the
+                // index space, so re-encrypt until we land inside it.
+                let mut value = index;
+                loop {
+                    value = Self::feistel(value, *half_bits, keys);
+                    if value < *total {
+                        return value;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Encrypts `value` with a balanced Feistel network over `2 * half_bits` bits.
+    #[inline(always)]
+    fn feistel(value: u128, half_bits: u32, keys: &[u64; FEISTEL_ROUNDS]) -> u128 {
+        // Halves are at most 64 bits wide, so they fit the u64 round function
+        let mask = u64::MAX >> (u64::BITS - half_bits);
+        let mut left = (value >> half_bits) as u64;
+        let mut right = value as u64 & mask;
+        for &key in keys {
+            let next = left ^ (Self::round(right, key) & mask);
+            left = right;
+            right = next;
+        }
+        ((left as u128) << half_bits) | right as u128
+    }
+
+    /// Round function of the Feistel network (SplitMix64 finalizer).
+    #[inline(always)]
+    fn round(value: u64, key: u64) -> u64 {
+        let mut z = value.wrapping_add(key).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_bijection(permutation: &IndexPermutation, total: u128) -> bool {
+        let mut seen = vec![false; total as usize];
+        for i in 0..total {
+            let j = permutation.get(i);
+            if j >= total || seen[j as usize] {
+                return false;
+            }
+            seen[j as usize] = true;
+        }
+        true
+    }
+
+    #[test]
+    fn test_shuffle_same_seed_same_order() {
+        let mut first: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        let mut second = first.clone();
+        shuffle(&mut first, 42);
+        shuffle(&mut second, 42);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_index_permutation_same_seed_same_order() {
+        for total in [1000, SHUFFLE_LIMIT + 17] {
+            let first = IndexPermutation::new(total, 7);
+            let second = IndexPermutation::new(total, 7);
+            assert!((0..1000).all(|i| first.get(i) == second.get(i)));
+        }
+    }
+
+    #[test]
+    fn test_index_permutation_is_bijection() {
+        assert!(is_bijection(&IndexPermutation::new(1000, 1), 1000));
+        assert!(is_bijection(
+            &IndexPermutation::new(SHUFFLE_LIMIT + 17, 1),
+            SHUFFLE_LIMIT + 17
+        ));
+    }
+
+    #[test]
+    fn test_index_permutation_beyond_usize() {
+        for total in [usize::MAX as u128 + 2, u128::MAX] {
+            let permutation = IndexPermutation::new(total, 3);
+            let mut seen = std::collections::HashSet::new();
+            for i in (0..1000).chain(total - 1000..total) {
+                let j = permutation.get(i);
+                assert!(j < total);
+                assert!(seen.insert(j));
+            }
+        }
+    }
+}
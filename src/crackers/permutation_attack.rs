@@ -0,0 +1,265 @@
+//! Permutation password cracking implementation.
+//!
+//! This module provides functionality for cracking passwords that are made of
+//! a known set of words or segments in an unknown order: every ordering of the
+//! words is concatenated and tested as a candidate.
+use crate::keyspace;
+use crate::types::{CrackMode, CrackResult, PasswordCracker, WorkUnit};
+use anyhow::Result;
+use openssl::pkcs12::Pkcs12;
+use rayon::prelude::*;
+use std::sync::{Arc, Mutex};
+
+/// Largest number of words that is permuted without a keyspace warning.
+pub const FULLY_SUPPORTED_WORDS: usize = 8;
+
+/// Orderings of a list of items, generated with Heap's algorithm.
+struct Heap<'a> {
+    /// The items in their current order
+    items: Vec<&'a str>,
+    /// Heap's algorithm stack state, one counter per position
+    counters: Vec<usize>,
+    /// Position being permuted
+    position: usize,
+    /// Whether the initial order has been yielded
+    started: bool,
+}
+
+impl Iterator for Heap<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        if !self.started {
+            self.started = true;
+            return Some(self.items.concat());
+        }
+        while self.position < self.items.len() {
+            let position = self.position;
+            if self.counters[position] < position {
+                if position.is_multiple_of(2) {
+                    self.items.swap(0, position);
+                } else {
+                    self.items.swap(self.counters[position], position);
+                }
+                self.counters[position] += 1;
+                self.position = 1;
+                return Some(self.items.concat());
+            }
+            self.counters[position] = 0;
+            self.position += 1;
+        }
+        None
+    }
+}
+
+/// Returns the concatenation of every ordering of the items.
+///
+/// The items in their given order come first. Each of the `n!` orderings is
+/// yielded once, so equal items produce equal candidates.
+///
+/// # Arguments
+///
+/// * `items` - The words or segments to order
+///
+/// # Example
+///
+/// ```
+/// use pkcs12cracker::crackers::permutation_attack::permutations;
+///
+/// let candidates: Vec<String> = permutations(&["a", "b", "c"]).collect();
+/// assert_eq!(candidates, ["abc", "bac", "cab", "acb", "bca", "cba"]);
+/// ```
+pub fn permutations<'a>(items: &[&'a str]) -> impl Iterator<Item = String> + 'a {
+    Heap {
+        items: items.to_vec(),
+        counters: vec![0; items.len()],
+        position: 1,
+        started: false,
+    }
+}
+
+/// Returns `n!`, or `None` if it does not fit in a `u128`.
+pub fn factorial(n: usize) -> Option<u128> {
+    (1..=n as u128).try_fold(1u128, |product, k| product.checked_mul(k))
+}
+
+/// Implementation of permutation-based password cracking.
+pub struct PermutationCracker {
+    /// Words to order, without duplicates
+    words: Vec<String>,
+    /// Share of the candidates processed by this cracker
+    work_unit: WorkUnit,
+    /// Number of candidates per parallel work item
+    chunk_size: usize,
+}
+
+impl PermutationCracker {
+    /// Creates a new PermutationCracker instance.
+    ///
+    /// Repeated words are only used once.
+    ///
+    /// # Arguments
+    ///
+    /// * `words` - Words or segments the password is made of
+    pub fn new(words: Vec<String>) -> Self {
+        let mut distinct = Vec::with_capacity(words.len());
+        for word in words {
+            if !distinct.contains(&word) {
+                distinct.push(word);
+            }
+        }
+        Self {
+            words: distinct,
+            work_unit: WorkUnit::FULL,
+            chunk_size: super::CHUNK_SIZE,
+        }
+    }
+
+    /// Restricts the cracker to a share of the candidates.
+    ///
+    /// Orderings are dealt out round-robin: unit `i` of `n` tries every
+    /// `n`-th ordering starting at the `i`-th.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_unit` - Share of the candidates to process
+    pub fn with_work_unit(mut self, work_unit: WorkUnit) -> Self {
+        self.work_unit = work_unit;
+        self
+    }
+
+    /// Sets the number of candidates per parallel work item.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - Candidates per chunk, at least 1
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Processes a chunk of password candidates.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - Password candidates to test
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    /// * `result` - Shared result tracking structure
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the correct password is found in this chunk.
+    fn process_chunk(chunk: &[String], pkcs12: &Pkcs12, result: &Arc<Mutex<CrackResult>>) -> bool {
+        let run = result.lock().unwrap().run();
+        for password in chunk {
+            if run.is_stopped() {
+                return true;
+            }
+            if run.skips_oversized(password) {
+                continue;
+            }
+            run.increment_attempts();
+
+            if super::check_password(pkcs12, password, &run, result) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl PasswordCracker for PermutationCracker {
+    /// Attempts to crack the PKCS#12 password with every ordering of the words.
+    ///
+    /// Orderings are generated lazily, one chunk at a time, so memory does not
+    /// grow with the number of words.
+    fn crack(&self, pkcs12: &Arc<Pkcs12>, result: &Arc<Mutex<CrackResult>>) -> Result<()> {
+        println!(
+            "Starting permutation attack over {} words",
+            self.words.len()
+        );
+        let count = factorial(self.words.len());
+        if self.words.len() > FULLY_SUPPORTED_WORDS {
+            eprintln!(
+                "Warning: {} words have {} orderings, consider --permutation-max-n",
+                self.words.len(),
+                count.map_or_else(|| "too many".to_string(), keyspace::format_magnitude)
+            );
+        }
+
+        let WorkUnit { index, total } = self.work_unit;
+        // Orderings `index`, `index + total`, ... below `count`
+        let share = count.map_or(u128::MAX, |count| {
+            count.saturating_sub(index as u128).div_ceil(total as u128)
+        });
+        result
+            .lock()
+            .unwrap()
+            .begin_stage("permutations".to_string(), share);
+
+        let words: Vec<&str> = self.words.iter().map(String::as_str).collect();
+        let mut candidates = permutations(&words).skip(index).step_by(total);
+        let chunks = std::iter::from_fn(|| {
+            let chunk: Vec<String> = candidates.by_ref().take(self.chunk_size).collect();
+            (!chunk.is_empty()).then_some(chunk)
+        });
+        chunks
+            .par_bridge()
+            .find_any(|chunk| Self::process_chunk(chunk, pkcs12, result));
+
+        Ok(())
+    }
+
+    fn estimate_count(&self) -> Option<u128> {
+        factorial(self.words.len())
+    }
+
+    fn mode(&self) -> CrackMode {
+        CrackMode::Permutation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_permutations_all_orderings() {
+        let items = ["a", "b", "c", "d", "e"];
+        let candidates: Vec<String> = permutations(&items).collect();
+        assert_eq!(candidates.len(), 120);
+        assert_eq!(candidates[0], "abcde");
+        let distinct: HashSet<&String> = candidates.iter().collect();
+        assert_eq!(distinct.len(), 120);
+        assert!(candidates.iter().all(|candidate| {
+            let mut chars: Vec<char> = candidate.chars().collect();
+            chars.sort_unstable();
+            chars == ['a', 'b', 'c', 'd', 'e']
+        }));
+    }
+
+    #[test]
+    fn test_permutations_small() {
+        assert_eq!(permutations(&[]).collect::<Vec<_>>(), [""]);
+        assert_eq!(permutations(&["Foo"]).collect::<Vec<_>>(), ["Foo"]);
+        let candidates: Vec<String> = permutations(&["Foo", "Bar", "Baz"]).collect();
+        assert_eq!(candidates.len(), 6);
+        assert!(candidates.contains(&"BazFooBar".to_string()));
+    }
+
+    #[test]
+    fn test_factorial() {
+        assert_eq!(factorial(0), Some(1));
+        assert_eq!(factorial(8), Some(40320));
+        assert_eq!(factorial(34), Some(295232799039604140847618609643520000000));
+        assert_eq!(factorial(35), None);
+    }
+
+    #[test]
+    fn test_new_removes_duplicates() {
+        let cracker = PermutationCracker::new(vec!["a".into(), "b".into(), "a".into()]);
+        assert_eq!(cracker.words, ["a", "b"]);
+        assert_eq!(cracker.estimate_count(), Some(2));
+    }
+}
@@ -0,0 +1,311 @@
+//! Password policy constraints for brute force.
+//!
+//! A policy such as "at least one uppercase letter, two digits and one
+//! symbol" rules out most of a keyspace. Candidates violating the policy are
+//! skipped before they are tested, and the number of candidates satisfying
+//! it is counted exactly so that estimates and ETAs stay meaningful.
+use anyhow::{bail, Context, Result};
+use std::fmt;
+
+/// A class of characters a policy can require.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CharClass {
+    /// Lowercase letters
+    Lower,
+    /// Uppercase letters
+    Upper,
+    /// Digits
+    Digit,
+    /// Everything that is not a letter or a digit, including space
+    Special,
+}
+
+impl CharClass {
+    /// All classes, in the order they are reported.
+    pub const ALL: [CharClass; 4] = [Self::Lower, Self::Upper, Self::Digit, Self::Special];
+
+    /// Returns `true` if `c` belongs to the class.
+    ///
+    /// The classes are disjoint, every character belongs to at most one of them.
+    pub fn contains(self, c: char) -> bool {
+        match self {
+            Self::Lower => c.is_lowercase(),
+            Self::Upper => c.is_uppercase(),
+            Self::Digit => c.is_numeric(),
+            Self::Special => !c.is_alphanumeric(),
+        }
+    }
+
+    /// Name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lower => "lower",
+            Self::Upper => "upper",
+            Self::Digit => "digit",
+            Self::Special => "special",
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses a character class name as accepted by `--require`.
+///
+/// # Errors
+///
+/// Returns an error listing the valid names if `name` is unknown.
+pub fn parse_char_class(name: &str) -> Result<CharClass> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "lower" | "lowercase" => Ok(CharClass::Lower),
+        "upper" | "uppercase" => Ok(CharClass::Upper),
+        "digit" | "digits" => Ok(CharClass::Digit),
+        "special" | "symbol" | "symbols" => Ok(CharClass::Special),
+        _ => {
+            bail!("Unknown character class '{name}', expected one of: lower, upper, digit, special")
+        }
+    }
+}
+
+/// Parses a `CLASS=N` value as accepted by `--require-count`.
+///
+/// # Errors
+///
+/// Returns an error if the class is unknown or `N` is not a positive number.
+pub fn parse_class_count(value: &str) -> Result<(CharClass, usize)> {
+    let Some((class, count)) = value.split_once('=') else {
+        bail!("Expected CLASS=N, e.g. digit=2, got '{value}'");
+    };
+    let count: usize = count
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid count in '{value}'"))?;
+    if count == 0 {
+        bail!("Count in '{value}' must be at least 1");
+    }
+    Ok((parse_char_class(class)?, count))
+}
+
+/// Minimum number of characters of each class a candidate must contain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CandidatePolicy {
+    /// Required classes with their minimum counts, at most one entry per class
+    requirements: Vec<(CharClass, usize)>,
+}
+
+impl CandidatePolicy {
+    /// Creates a policy from `--require` and `--require-count`.
+    ///
+    /// A class named in both keeps the larger minimum.
+    ///
+    /// # Arguments
+    ///
+    /// * `require` - Classes that must appear at least once
+    /// * `counts` - Classes that must appear at least the given number of times
+    pub fn new(require: &[CharClass], counts: &[(CharClass, usize)]) -> Self {
+        let mut requirements: Vec<(CharClass, usize)> = Vec::new();
+        let pairs = require
+            .iter()
+            .map(|&class| (class, 1))
+            .chain(counts.iter().copied());
+        for (class, count) in pairs {
+            match requirements.iter_mut().find(|(c, _)| *c == class) {
+                Some((_, min)) => *min = (*min).max(count),
+                None => requirements.push((class, count)),
+            }
+        }
+        requirements.sort_by_key(|(class, _)| CharClass::ALL.iter().position(|c| c == class));
+        Self { requirements }
+    }
+
+    /// Returns `true` if the policy does not restrict candidates.
+    pub fn is_empty(&self) -> bool {
+        self.requirements.is_empty()
+    }
+
+    /// Returns `true` if `candidate` satisfies every requirement.
+    #[inline(always)]
+    pub fn allows(&self, candidate: &str) -> bool {
+        self.requirements.iter().all(|&(class, min)| {
+            candidate
+                .chars()
+                .filter(|&c| class.contains(c))
+                .take(min)
+                .count()
+                == min
+        })
+    }
+
+    /// Counts the combinations of `length` characters from `charset` that satisfy the policy.
+    ///
+    /// Counts up to the required minimum of each class are tracked with
+    /// dynamic programming, one position at a time, so the result is exact
+    /// without enumerating the keyspace. The count saturates at `u128::MAX`.
+    ///
+    /// # Arguments
+    ///
+    /// * `charset` - Characters to use in combinations
+    /// * `length` - Length of the combinations
+    pub fn count_allowed(&self, charset: &[char], length: usize) -> u128 {
+        // Number of charset characters in each required class, and in none of them
+        let sizes: Vec<u128> = self
+            .requirements
+            .iter()
+            .map(|&(class, _)| charset.iter().filter(|&&c| class.contains(c)).count() as u128)
+            .collect();
+        let others = charset.len() as u128 - sizes.iter().sum::<u128>();
+
+        // States are the per-class counts capped at the minimum, in mixed radix
+        let radices: Vec<usize> = self.requirements.iter().map(|&(_, min)| min + 1).collect();
+        let states: usize = radices.iter().product();
+        let strides: Vec<usize> = radices
+            .iter()
+            .scan(1, |stride, &radix| {
+                let current = *stride;
+                *stride *= radix;
+                Some(current)
+            })
+            .collect();
+
+        let mut counts = vec![0u128; states];
+        counts[0] = 1;
+        for _ in 0..length {
+            let mut next = vec![0u128; states];
+            for (state, &count) in counts.iter().enumerate().filter(|(_, &c)| c > 0) {
+                next[state] = next[state].saturating_add(count.saturating_mul(others));
+                for (class, &size) in sizes.iter().enumerate() {
+                    let digit = state / strides[class] % radices[class];
+                    let target = if digit + 1 < radices[class] {
+                        state + strides[class]
+                    } else {
+                        state
+                    };
+                    next[target] = next[target].saturating_add(count.saturating_mul(size));
+                }
+            }
+            counts = next;
+        }
+        counts[states - 1]
+    }
+
+    /// Estimates how many of `raw` positions of a length satisfy the policy.
+    ///
+    /// Exact when `raw` covers the whole length, otherwise the share of the
+    /// whole length is applied to `raw`.
+    ///
+    /// # Arguments
+    ///
+    /// * `charset` - Characters to use in combinations
+    /// * `length` - Length of the combinations
+    /// * `raw` - Number of positions of that length
+    pub fn estimate_allowed(&self, charset: &[char], length: usize, raw: u128) -> u128 {
+        if self.is_empty() {
+            return raw;
+        }
+        let total = (charset.len() as u128).saturating_pow(length as u32);
+        let allowed = self.count_allowed(charset, length);
+        if raw >= total {
+            allowed
+        } else {
+            (raw as f64 * allowed as f64 / total as f64).round() as u128
+        }
+    }
+}
+
+impl fmt::Display for CandidatePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let requirements: Vec<String> = self
+            .requirements
+            .iter()
+            .map(|(class, min)| format!("{class}>={min}"))
+            .collect();
+        f.write_str(&requirements.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force_count(policy: &CandidatePolicy, charset: &[char], length: usize) -> u128 {
+        let total = (charset.len() as u128).pow(length as u32);
+        let mut buffer = Vec::new();
+        (0..total)
+            .filter(|&index| {
+                let candidate =
+                    crate::crackers::index_to_combination(index, charset, length, &mut buffer);
+                policy.allows(&candidate)
+            })
+            .count() as u128
+    }
+
+    #[test]
+    fn test_allows() {
+        let policy = CandidatePolicy::new(
+            &[CharClass::Upper, CharClass::Special],
+            &[(CharClass::Digit, 2)],
+        );
+        assert!(policy.allows("Pa55!"));
+        assert!(policy.allows("É 12"));
+        assert!(!policy.allows("Pa5!"));
+        assert!(!policy.allows("pa55!"));
+        assert!(!policy.allows("Pa55"));
+        assert!(CandidatePolicy::default().allows(""));
+    }
+
+    #[test]
+    fn test_count_allowed_matches_enumeration() {
+        let charset: Vec<char> = "aB1!é".chars().collect();
+        let policies = [
+            CandidatePolicy::new(&[CharClass::Upper], &[]),
+            CandidatePolicy::new(
+                &[CharClass::Upper, CharClass::Digit, CharClass::Special],
+                &[],
+            ),
+            CandidatePolicy::new(&[CharClass::Lower], &[(CharClass::Digit, 2)]),
+            CandidatePolicy::default(),
+        ];
+        for policy in &policies {
+            for length in 0..=5 {
+                assert_eq!(
+                    policy.count_allowed(&charset, length),
+                    brute_force_count(policy, &charset, length),
+                    "{policy} at length {length}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_count_allowed_missing_class() {
+        let charset: Vec<char> = "abc".chars().collect();
+        let policy = CandidatePolicy::new(&[CharClass::Digit], &[]);
+        assert_eq!(policy.count_allowed(&charset, 4), 0);
+    }
+
+    #[test]
+    fn test_merge_requirements() {
+        let policy = CandidatePolicy::new(
+            &[CharClass::Special, CharClass::Digit],
+            &[(CharClass::Digit, 3), (CharClass::Digit, 2)],
+        );
+        assert_eq!(policy.to_string(), "digit>=3,special>=1");
+    }
+
+    #[test]
+    fn test_parse_class_count() {
+        assert_eq!(parse_class_count("digit=2").unwrap(), (CharClass::Digit, 2));
+        assert_eq!(parse_class_count("Upper=1").unwrap(), (CharClass::Upper, 1));
+        assert!(parse_class_count("digit").is_err());
+        assert!(parse_class_count("digit=0").is_err());
+        assert!(parse_class_count("vowel=1").is_err());
+    }
+}
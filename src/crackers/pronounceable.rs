@@ -0,0 +1,474 @@
+//! Pronounceable password cracking implementation.
+//!
+//! This module provides a brute force variant for made-up words that can be
+//! said out loud. Instead of every combination of a charset, it generates the
+//! words of this grammar:
+//!
+//! ```text
+//! candidate = word digits
+//! word      = C (V C)* [V] | V (C V)* [C]
+//! C         = one of CONSONANTS, including the digraphs ch, ph, sh, th, wh
+//! V         = one of VOWELS
+//! digits    = 0 to max_digits decimal digits
+//! ```
+//!
+//! Consonants and vowels strictly alternate, so every word has a single
+//! parse and every candidate is generated once. The length of a candidate
+//! counts characters, a digraph counts as two. Candidates are numbered by
+//! length, then by the number of trailing digits, then by word and digits in
+//! lexicographic order of their units, which lets chunks be decoded from
+//! their index like brute force combinations.
+use crate::types::{
+    CombinationCount, CrackMode, CrackResult, PasswordCracker, PkcsError, WorkUnit,
+};
+use anyhow::{bail, Result};
+use openssl::pkcs12::Pkcs12;
+use rayon::prelude::*;
+use std::sync::{Arc, Mutex};
+
+/// Consonant units: every consonant letter and common digraphs, in alphabetical order
+pub const CONSONANTS: [&str; 26] = [
+    "b", "c", "ch", "d", "f", "g", "h", "j", "k", "l", "m", "n", "p", "ph", "q", "r", "s", "sh",
+    "t", "th", "v", "w", "wh", "x", "y", "z",
+];
+
+/// Vowel units
+pub const VOWELS: [&str; 5] = ["a", "e", "i", "o", "u"];
+
+/// Largest number of trailing digits
+pub const MAX_DIGITS: u8 = 4;
+
+/// Kind of the next unit of a word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Consonant,
+    Vowel,
+}
+
+impl Unit {
+    fn units(self) -> &'static [&'static str] {
+        match self {
+            Self::Consonant => &CONSONANTS,
+            Self::Vowel => &VOWELS,
+        }
+    }
+
+    fn other(self) -> Self {
+        match self {
+            Self::Consonant => Self::Vowel,
+            Self::Vowel => Self::Consonant,
+        }
+    }
+}
+
+/// Candidates of the pronounceable grammar between two lengths.
+///
+/// # Example
+///
+/// ```
+/// use pkcs12cracker::crackers::pronounceable::Pronounceable;
+///
+/// let grammar = Pronounceable::new(2, 0).unwrap();
+/// // "ba" to "zu", the digraphs "ch" to "wh", then "ab" to "uz"
+/// assert_eq!(grammar.count(2), Some(21 * 5 + 5 + 5 * 21));
+/// assert_eq!(grammar.candidate(2, 0), "ba");
+/// ```
+pub struct Pronounceable {
+    /// Number of trailing digits a candidate may have
+    max_digits: u8,
+    /// `ways[len][kind]`: words of exactly `len` characters starting with
+    /// a unit of `kind` (0 consonant, 1 vowel), `None` beyond `u128`
+    ways: Vec<[Option<u128>; 2]>,
+}
+
+impl Pronounceable {
+    /// Prepares the grammar for candidates of up to `max_len` characters.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_len` - Longest candidate length
+    /// * `max_digits` - Number of trailing digits a candidate may have, at most [`MAX_DIGITS`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `max_digits` exceeds [`MAX_DIGITS`]
+    pub fn new(max_len: u8, max_digits: u8) -> Result<Self> {
+        if max_digits > MAX_DIGITS {
+            bail!("At most {MAX_DIGITS} trailing digits are supported, got {max_digits}");
+        }
+        // The empty rest of a word, after its last unit
+        let mut ways = vec![[Some(1), Some(1)]];
+        for len in 1..=max_len as usize {
+            let mut row = [Some(0); 2];
+            for (slot, kind) in [Unit::Consonant, Unit::Vowel].into_iter().enumerate() {
+                let rest = kind.other() as usize;
+                row[slot] = kind
+                    .units()
+                    .iter()
+                    .filter(|unit| unit.len() <= len)
+                    .try_fold(0u128, |sum, unit| {
+                        sum.checked_add(ways[len - unit.len()][rest]?)
+                    });
+            }
+            ways.push(row);
+        }
+        Ok(Self { max_digits, ways })
+    }
+
+    /// Number of words of exactly `len` characters, without digits.
+    fn words(&self, len: usize) -> Option<u128> {
+        if len == 0 {
+            return Some(0);
+        }
+        let [consonant, vowel] = self.ways[len];
+        consonant?.checked_add(vowel?)
+    }
+
+    /// Number of trailing digits candidates of a length may have, with the
+    /// number of candidates for each.
+    fn digit_groups(&self, len: u8) -> impl Iterator<Item = (u32, Option<u128>)> + '_ {
+        (0..=self.max_digits.min(len.saturating_sub(1)) as u32).map(move |digits| {
+            let count = self
+                .words(len as usize - digits as usize)
+                .and_then(|words| words.checked_mul(10u128.pow(digits)));
+            (digits, count)
+        })
+    }
+
+    /// Returns the number of candidates of a length, `None` if it does not fit into `u128`.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - Candidate length in characters, at most the `max_len` of the grammar
+    pub fn count(&self, len: u8) -> Option<u128> {
+        self.digit_groups(len)
+            .try_fold(0u128, |sum, (_, count)| sum.checked_add(count?))
+    }
+
+    /// Returns the candidate of a length at an index.
+    ///
+    /// # Arguments
+    ///
+    /// * `len` - Candidate length in characters, at most the `max_len` of the grammar
+    /// * `index` - Index of the candidate, below [`Pronounceable::count`]
+    pub fn candidate(&self, len: u8, mut index: u128) -> String {
+        let mut candidate = String::with_capacity(len as usize);
+        for (digits, count) in self.digit_groups(len) {
+            let count = count.unwrap_or(u128::MAX);
+            if index >= count {
+                index -= count;
+                continue;
+            }
+            let suffixes = 10u128.pow(digits);
+            self.push_word(
+                &mut candidate,
+                len as usize - digits as usize,
+                index / suffixes,
+            );
+            if digits > 0 {
+                candidate.push_str(&format!(
+                    "{:0width$}",
+                    index % suffixes,
+                    width = digits as usize
+                ));
+            }
+            break;
+        }
+        candidate
+    }
+
+    /// Appends the word of exactly `len` characters at an index.
+    fn push_word(&self, word: &mut String, mut len: usize, mut index: u128) {
+        let consonant_words = self.ways[len][0].unwrap_or(u128::MAX);
+        let mut kind = if index < consonant_words {
+            Unit::Consonant
+        } else {
+            index -= consonant_words;
+            Unit::Vowel
+        };
+        while len > 0 {
+            let rest = kind.other() as usize;
+            for unit in kind.units().iter().filter(|unit| unit.len() <= len) {
+                let count = self.ways[len - unit.len()][rest].unwrap_or(u128::MAX);
+                if index < count {
+                    word.push_str(unit);
+                    len -= unit.len();
+                    break;
+                }
+                index -= count;
+            }
+            kind = kind.other();
+        }
+    }
+}
+
+/// Implementation of pronounceable password cracking.
+pub struct PronounceableCracker {
+    /// Minimum password length to try
+    min_len: u8,
+    /// Maximum password length to try
+    max_len: u8,
+    /// Number of trailing digits a candidate may have
+    max_digits: u8,
+    /// Share of the candidates processed by this cracker
+    work_unit: WorkUnit,
+    /// Number of candidates per parallel work item
+    chunk_size: usize,
+}
+
+impl PronounceableCracker {
+    /// Creates a new PronounceableCracker instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_len` - Minimum password length to test
+    /// * `max_len` - Maximum password length to test
+    /// * `max_digits` - Number of trailing digits a candidate may have, at most [`MAX_DIGITS`]
+    pub fn new(min_len: u8, max_len: u8, max_digits: u8) -> Self {
+        Self {
+            min_len,
+            max_len,
+            max_digits,
+            work_unit: WorkUnit::FULL,
+            chunk_size: super::CHUNK_SIZE,
+        }
+    }
+
+    /// Restricts the cracker to a share of the search space.
+    ///
+    /// Candidates of all lengths are numbered consecutively, shortest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `work_unit` - Share of the search space to process
+    pub fn with_work_unit(mut self, work_unit: WorkUnit) -> Self {
+        self.work_unit = work_unit;
+        self
+    }
+
+    /// Sets the number of candidates per parallel work item.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - Candidates per chunk, at least 1
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Counts the candidates of all lengths, `None` if they do not fit into `u128`.
+    fn total(&self, grammar: &Pronounceable) -> Option<u128> {
+        (self.min_len..=self.max_len)
+            .try_fold(0u128, |sum, len| sum.checked_add(grammar.count(len)?))
+    }
+
+    /// Processes a chunk of password candidates.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk` - Password candidates to test
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    /// * `result` - Shared result tracking structure
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if the correct password is found in this chunk.
+    fn process_chunk(chunk: &[String], pkcs12: &Pkcs12, result: &Arc<Mutex<CrackResult>>) -> bool {
+        let run = result.lock().unwrap().run();
+        for password in chunk {
+            if run.is_stopped() {
+                return true;
+            }
+            if run.skips_oversized(password) {
+                continue;
+            }
+            run.increment_attempts();
+
+            if super::check_password(pkcs12, password, &run, result) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl PasswordCracker for PronounceableCracker {
+    /// Attempts to crack the PKCS#12 password with pronounceable candidates.
+    ///
+    /// Lengths are processed shortest first, the candidates of each length
+    /// are decoded from their index inside each chunk.
+    fn crack(&self, pkcs12: &Arc<Pkcs12>, result: &Arc<Mutex<CrackResult>>) -> Result<()> {
+        println!(
+            "Generating pronounceable passwords with length between {} and {}{}",
+            self.min_len,
+            self.max_len,
+            if self.max_digits > 0 {
+                format!(" and up to {} trailing digits", self.max_digits)
+            } else {
+                String::new()
+            }
+        );
+        let grammar = Pronounceable::new(self.max_len, self.max_digits)?;
+        let Some(total) = self.total(&grammar) else {
+            return Err(PkcsError::SearchSpaceExceeded.into());
+        };
+        let unit_range = self.work_unit.range(total);
+
+        let chunk_size = self.chunk_size as u128;
+        let mut offset = 0;
+        for len in self.min_len..=self.max_len {
+            let count = grammar.count(len).unwrap_or(0);
+            let start = unit_range.start.max(offset) - offset;
+            let end = unit_range.end.min(offset + count).saturating_sub(offset);
+            offset += count;
+            if start >= end {
+                continue;
+            }
+            result
+                .lock()
+                .unwrap()
+                .begin_stage(format!("length {len}"), end - start);
+
+            let found = (0..(end - start).div_ceil(chunk_size))
+                .into_par_iter()
+                .find_any(|&chunk| {
+                    let first = start + chunk * chunk_size;
+                    let candidates: Vec<String> = (first..(first + chunk_size).min(end))
+                        .map(|index| grammar.candidate(len, index))
+                        .collect();
+                    Self::process_chunk(&candidates, pkcs12, result)
+                })
+                .is_some();
+            if found {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn estimate_count(&self) -> Option<u128> {
+        Pronounceable::new(self.max_len, self.max_digits)
+            .ok()
+            .and_then(|grammar| self.total(&grammar))
+    }
+
+    fn work_items(&self) -> Result<Option<CombinationCount>> {
+        let grammar = Pronounceable::new(self.max_len, self.max_digits)?;
+        Ok(Some(self.total(&grammar).map_or(
+            CombinationCount::TooLarge,
+            CombinationCount::Exact,
+        )))
+    }
+
+    fn mode(&self) -> CrackMode {
+        CrackMode::Pronounceable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Whether a word follows the grammar, by trying every split into units.
+    fn is_word(word: &str, next: Option<Unit>) -> bool {
+        if word.is_empty() {
+            return next.is_some();
+        }
+        [Unit::Consonant, Unit::Vowel]
+            .into_iter()
+            .filter(|&kind| next.is_none_or(|next| next == kind))
+            .any(|kind| {
+                kind.units().iter().any(|unit| {
+                    word.strip_prefix(unit)
+                        .is_some_and(|rest| is_word(rest, Some(kind.other())))
+                })
+            })
+    }
+
+    #[test]
+    fn test_count_per_length() {
+        let grammar = Pronounceable::new(10, 0).unwrap();
+        let counts: Vec<u128> = (1..=8).map(|len| grammar.count(len).unwrap()).collect();
+        assert_eq!(
+            counts,
+            [26, 215, 2780, 23225, 297275, 2508125, 31794500, 270785000]
+        );
+        // About 800 times fewer candidates than 26^8 ≈ 2.1e11 lowercase combinations
+        assert!(grammar.count(8).unwrap() * 700 < 26u128.pow(8));
+
+        // Digits multiply the words of each shorter length
+        let digits = Pronounceable::new(10, 2).unwrap();
+        assert_eq!(digits.count(4), Some(23225 + 2780 * 10 + 215 * 100));
+        assert_eq!(digits.count(1), Some(26));
+        for len in 1..=4 {
+            let candidates: Vec<String> = (0..digits.count(len).unwrap())
+                .map(|index| digits.candidate(len, index))
+                .collect();
+            let distinct: HashSet<&String> = candidates.iter().collect();
+            assert_eq!(distinct.len(), candidates.len(), "length {len}");
+            assert!(candidates.iter().all(|c| c.chars().count() == len as usize));
+        }
+    }
+
+    #[test]
+    fn test_every_word_follows_grammar() {
+        let grammar = Pronounceable::new(4, 0).unwrap();
+        for len in 1..=4 {
+            let words: HashSet<String> = (0..grammar.count(len).unwrap())
+                .map(|index| grammar.candidate(len, index))
+                .collect();
+            // Every string of the grammar over its letters is generated
+            let expected = (0..26u128.pow(len as u32))
+                .map(|index| {
+                    let charset: Vec<char> = ('a'..='z').collect();
+                    crate::crackers::index_to_combination(
+                        index,
+                        &charset,
+                        len as usize,
+                        &mut Vec::new(),
+                    )
+                })
+                .filter(|candidate| is_word(candidate, None))
+                .count();
+            assert_eq!(words.len(), expected, "length {len}");
+            assert!(words.iter().all(|word| is_word(word, None)));
+        }
+    }
+
+    #[test]
+    fn test_representative_candidates() {
+        let grammar = Pronounceable::new(8, 2).unwrap();
+        let words = 2508125;
+        let candidates: Vec<String> = [0, 1, 5, 10, words - 1, words, words + 2_972_750, 7803374]
+            .into_iter()
+            .map(|index| grammar.candidate(6, index))
+            .collect();
+        assert_eq!(
+            candidates,
+            ["bababa", "bababe", "babaca", "babach", "uzuzuz", "babab0", "baba00", "uzuz99"]
+        );
+
+        for word in ["banana", "thomas", "shiloh", "shadow", "ozuki"] {
+            assert!(is_word(word, None), "{word}");
+        }
+        for word in ["bbbbbb", "strong", "aeiouu", "ban4na"] {
+            assert!(!is_word(word, None), "{word}");
+        }
+        // Every 9973rd candidate follows the grammar
+        let count = grammar.count(6).unwrap();
+        assert!((0..count).step_by(9973).all(|index| {
+            let candidate = grammar.candidate(6, index);
+            is_word(
+                candidate.trim_end_matches(|c: char| c.is_ascii_digit()),
+                None,
+            )
+        }));
+    }
+
+    #[test]
+    fn test_too_many_digits() {
+        assert!(Pronounceable::new(8, MAX_DIGITS + 1).is_err());
+    }
+}
@@ -0,0 +1,563 @@
+//! Hashcat-compatible word mangling rules.
+//!
+//! This module parses the most used functions of Hashcat `.rule` files and
+//! applies them to dictionary entries, see `--rules`. Positions are given as
+//! `0`-`9` and `A`-`Z` for 10-35. Unlike Hashcat, which works on bytes,
+//! rules work on characters, so positions count the characters of
+//! multibyte words. Rules that would grow a word beyond [`MAX_LENGTH`]
+//! characters leave it unchanged, as in Hashcat.
+//!
+//! Rules of John the Ripper are parsed into the same [`RuleOp`]s by
+//! [`super::john_rules`], including its character classes and rejection
+//! commands.
+use super::pattern::single;
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Longest word rules produce, the limit of Hashcat
+pub const MAX_LENGTH: usize = 255;
+
+/// A rule function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleOp {
+    /// `:` leaves the word unchanged
+    Noop,
+    /// `l` lowercases all letters
+    Lowercase,
+    /// `u` uppercases all letters
+    Uppercase,
+    /// `c` uppercases the first letter and lowercases the rest
+    Capitalize,
+    /// `C` lowercases the first letter and uppercases the rest
+    InvertCapitalize,
+    /// `t` toggles the case of all letters
+    ToggleCase,
+    /// `TN` toggles the case of the character at N
+    ToggleAt(usize),
+    /// `r` reverses the word
+    Reverse,
+    /// `d` appends the word to itself
+    Duplicate,
+    /// `pN` appends the word to itself N times
+    DuplicateTimes(usize),
+    /// `f` appends the reversed word
+    Reflect,
+    /// `{` moves the first character to the end
+    RotateLeft,
+    /// `}` moves the last character to the front
+    RotateRight,
+    /// `$X` appends X
+    Append(char),
+    /// `^X` prepends X
+    Prepend(char),
+    /// `[` deletes the first character
+    TruncateLeft,
+    /// `]` deletes the last character
+    TruncateRight,
+    /// `DN` deletes the character at N
+    DeleteAt(usize),
+    /// `iNX` inserts X at N
+    InsertAt(usize, char),
+    /// `oNX` overwrites the character at N with X
+    OverwriteAt(usize, char),
+    /// `sXY` replaces every X with Y
+    Replace(CharClass, char),
+    /// `@X` deletes every X
+    Purge(CharClass),
+    /// `zN` duplicates the first character N times
+    DuplicateFirst(usize),
+    /// `ZN` duplicates the last character N times
+    DuplicateLast(usize),
+    /// `LN` shifts the bits of the character at N left
+    ShiftLeftAt(usize),
+    /// `RN` shifts the bits of the character at N right
+    ShiftRightAt(usize),
+    /// `>N` rejects words of N characters or fewer
+    RejectUnlessLonger(usize),
+    /// `<N` rejects words of N characters or more
+    RejectUnlessShorter(usize),
+    /// `_N` rejects words not of exactly N characters
+    RejectUnlessLength(usize),
+    /// `!X` rejects words containing X
+    RejectIfContains(CharClass),
+    /// `/X` rejects words not containing X
+    RejectUnlessContains(CharClass),
+    /// `(X` rejects words not starting with X
+    RejectUnlessFirst(CharClass),
+    /// `)X` rejects words not ending with X
+    RejectUnlessLast(CharClass),
+    /// `=NX` rejects words without X at N
+    RejectUnlessAt(usize, CharClass),
+}
+
+/// Characters a rule function matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    /// A single character
+    Char(char),
+    /// Every character of a class
+    Class(ClassKind),
+    /// Every character outside of a class
+    NotClass(ClassKind),
+}
+
+/// A character class of John the Ripper, `?v` for vowels etc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassKind {
+    /// `?v` ASCII vowels
+    Vowel,
+    /// `?c` ASCII consonants
+    Consonant,
+    /// `?w` space and tab
+    Whitespace,
+    /// `?p` punctuation, ``.,:;'"?!` ``
+    Punctuation,
+    /// `?s` symbols, `$%^&*()-_+=|\<>[]{}#@/~`
+    Symbol,
+    /// `?l` lowercase letters
+    Lower,
+    /// `?u` uppercase letters
+    Upper,
+    /// `?d` digits
+    Digit,
+    /// `?a` letters
+    Letter,
+    /// `?x` letters and digits
+    Alphanumeric,
+    /// `?z` every character
+    Any,
+}
+
+impl ClassKind {
+    /// Checks whether a character belongs to the class.
+    fn contains(self, c: char) -> bool {
+        match self {
+            Self::Vowel => "aeiouAEIOU".contains(c),
+            Self::Consonant => c.is_ascii_alphabetic() && !"aeiouAEIOU".contains(c),
+            Self::Whitespace => c == ' ' || c == '\t',
+            Self::Punctuation => ".,:;'\"?!`".contains(c),
+            Self::Symbol => "$%^&*()-_+=|\\<>[]{}#@/~".contains(c),
+            Self::Lower => c.is_lowercase(),
+            Self::Upper => c.is_uppercase(),
+            Self::Digit => c.is_ascii_digit(),
+            Self::Letter => c.is_alphabetic(),
+            Self::Alphanumeric => c.is_alphabetic() || c.is_ascii_digit(),
+            Self::Any => true,
+        }
+    }
+}
+
+impl CharClass {
+    /// Checks whether a rule function matches a character.
+    fn matches(self, c: char) -> bool {
+        match self {
+            Self::Char(expected) => c == expected,
+            Self::Class(kind) => kind.contains(c),
+            Self::NotClass(kind) => !kind.contains(c),
+        }
+    }
+}
+
+/// Parses a position argument, `0`-`9` or `A`-`Z`.
+pub(super) fn position(c: char) -> Result<usize> {
+    match c {
+        '0'..='9' => Ok(c as usize - '0' as usize),
+        'A'..='Z' => Ok(c as usize - 'A' as usize + 10),
+        _ => bail!("Invalid position '{c}', expected 0-9 or A-Z"),
+    }
+}
+
+/// Parses a line of a rule file into its rule functions.
+///
+/// Spaces between functions are ignored.
+///
+/// # Errors
+///
+/// Returns an error for unsupported functions, missing arguments or
+/// invalid positions
+pub fn parse_rule(line: &str) -> Result<Vec<RuleOp>> {
+    let mut chars = line.chars();
+    let mut ops = Vec::new();
+    while let Some(function) = chars.next() {
+        let mut argument = || {
+            chars
+                .next()
+                .with_context(|| format!("Rule function '{function}' is missing an argument"))
+        };
+        let op = match function {
+            ' ' => continue,
+            ':' => RuleOp::Noop,
+            'l' => RuleOp::Lowercase,
+            'u' => RuleOp::Uppercase,
+            'c' => RuleOp::Capitalize,
+            'C' => RuleOp::InvertCapitalize,
+            't' => RuleOp::ToggleCase,
+            'T' => RuleOp::ToggleAt(position(argument()?)?),
+            'r' => RuleOp::Reverse,
+            'd' => RuleOp::Duplicate,
+            'p' => RuleOp::DuplicateTimes(position(argument()?)?),
+            'f' => RuleOp::Reflect,
+            '{' => RuleOp::RotateLeft,
+            '}' => RuleOp::RotateRight,
+            '$' => RuleOp::Append(argument()?),
+            '^' => RuleOp::Prepend(argument()?),
+            '[' => RuleOp::TruncateLeft,
+            ']' => RuleOp::TruncateRight,
+            'D' => RuleOp::DeleteAt(position(argument()?)?),
+            'i' => RuleOp::InsertAt(position(argument()?)?, argument()?),
+            'o' => RuleOp::OverwriteAt(position(argument()?)?, argument()?),
+            's' => RuleOp::Replace(CharClass::Char(argument()?), argument()?),
+            '@' => RuleOp::Purge(CharClass::Char(argument()?)),
+            'z' => RuleOp::DuplicateFirst(position(argument()?)?),
+            'Z' => RuleOp::DuplicateLast(position(argument()?)?),
+            'L' => RuleOp::ShiftLeftAt(position(argument()?)?),
+            'R' => RuleOp::ShiftRightAt(position(argument()?)?),
+            _ => bail!("Unsupported rule function '{function}'"),
+        };
+        ops.push(op);
+    }
+    Ok(ops)
+}
+
+/// Reads the rules of a Hashcat `.rule` file, one rule per line.
+///
+/// Blank lines and lines starting with `#` are skipped. Rules with
+/// functions outside the supported subset are skipped with a warning.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or has no supported rule
+pub fn parse_rules_file(path: &Path) -> Result<Vec<Vec<RuleOp>>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read rules file: {}", path.display()))?;
+    let mut rules = Vec::new();
+    let mut skipped = 0;
+    let mut first_error = None;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_rule(line) {
+            Ok(ops) => rules.push(ops),
+            Err(e) => {
+                skipped += 1;
+                first_error.get_or_insert((i + 1, e));
+            }
+        }
+    }
+    if let Some((line, e)) = first_error {
+        eprintln!(
+            "Warning: skipped {skipped} unsupported rules of {}, first on line {line}: {e:#}",
+            path.display()
+        );
+    }
+    if rules.is_empty() {
+        bail!("No supported rules in {}", path.display());
+    }
+    Ok(rules)
+}
+
+/// Maps a character to its uppercase or lowercase form, if that is a
+/// single character.
+fn to_case(c: char, upper: bool) -> char {
+    let mapped = if upper {
+        single(c.to_uppercase())
+    } else {
+        single(c.to_lowercase())
+    };
+    mapped.unwrap_or(c)
+}
+
+/// Toggles the case of a letter.
+fn toggle(c: char) -> char {
+    to_case(c, !c.is_uppercase())
+}
+
+/// Applies the functions of a rule to a word, in order.
+///
+/// # Arguments
+///
+/// * `ops` - Functions of the rule
+/// * `word` - Dictionary entry
+///
+/// # Returns
+///
+/// The mangled word, `None` if the word is empty or longer than
+/// [`MAX_LENGTH`], a rejection function rejects it or the rule deletes
+/// every character.
+pub fn apply_ruleset(ops: &[RuleOp], word: &str) -> Option<String> {
+    let mut chars: Vec<char> = word.chars().collect();
+    if chars.is_empty() || chars.len() > MAX_LENGTH {
+        return None;
+    }
+    for op in ops {
+        let len = chars.len();
+        let fits = |added: usize| len.saturating_add(added) <= MAX_LENGTH;
+        match *op {
+            RuleOp::Noop => {}
+            RuleOp::Lowercase => chars.iter_mut().for_each(|c| *c = to_case(*c, false)),
+            RuleOp::Uppercase => chars.iter_mut().for_each(|c| *c = to_case(*c, true)),
+            RuleOp::Capitalize | RuleOp::InvertCapitalize => {
+                let first_upper = *op == RuleOp::Capitalize;
+                for (i, c) in chars.iter_mut().enumerate() {
+                    *c = to_case(*c, (i == 0) == first_upper);
+                }
+            }
+            RuleOp::ToggleCase => chars.iter_mut().for_each(|c| *c = toggle(*c)),
+            RuleOp::ToggleAt(n) => {
+                if let Some(c) = chars.get_mut(n) {
+                    *c = toggle(*c);
+                }
+            }
+            RuleOp::Reverse => chars.reverse(),
+            RuleOp::Duplicate => {
+                if fits(len) {
+                    chars.extend_from_within(..);
+                }
+            }
+            RuleOp::DuplicateTimes(n) => {
+                if fits(len.saturating_mul(n)) {
+                    for _ in 0..n {
+                        chars.extend_from_within(..len);
+                    }
+                }
+            }
+            RuleOp::Reflect => {
+                if fits(len) {
+                    chars.extend_from_within(..);
+                    chars[len..].reverse();
+                }
+            }
+            RuleOp::RotateLeft => {
+                if len > 0 {
+                    chars.rotate_left(1);
+                }
+            }
+            RuleOp::RotateRight => {
+                if len > 0 {
+                    chars.rotate_right(1);
+                }
+            }
+            RuleOp::Append(c) => {
+                if fits(1) {
+                    chars.push(c);
+                }
+            }
+            RuleOp::Prepend(c) => {
+                if fits(1) {
+                    chars.insert(0, c);
+                }
+            }
+            RuleOp::TruncateLeft => {
+                if len > 0 {
+                    chars.remove(0);
+                }
+            }
+            RuleOp::TruncateRight => {
+                chars.pop();
+            }
+            RuleOp::DeleteAt(n) => {
+                if n < len {
+                    chars.remove(n);
+                }
+            }
+            RuleOp::InsertAt(n, c) => {
+                if n <= len && fits(1) {
+                    chars.insert(n, c);
+                }
+            }
+            RuleOp::OverwriteAt(n, c) => {
+                if let Some(old) = chars.get_mut(n) {
+                    *old = c;
+                }
+            }
+            RuleOp::Replace(from, to) => chars
+                .iter_mut()
+                .filter(|c| from.matches(**c))
+                .for_each(|c| *c = to),
+            RuleOp::Purge(purged) => chars.retain(|&c| !purged.matches(c)),
+            RuleOp::DuplicateFirst(n) => {
+                if len > 0 && fits(n) {
+                    chars.splice(0..0, std::iter::repeat_n(chars[0], n));
+                }
+            }
+            RuleOp::DuplicateLast(n) => {
+                if len > 0 && fits(n) {
+                    chars.extend(std::iter::repeat_n(chars[len - 1], n));
+                }
+            }
+            RuleOp::ShiftLeftAt(n) | RuleOp::ShiftRightAt(n) => {
+                // Bit shifts only apply to single-byte characters, as in Latin-1
+                if let Some(c) = chars.get_mut(n).filter(|c| (**c as u32) < 0x100) {
+                    let byte = *c as u8;
+                    *c = char::from(match op {
+                        RuleOp::ShiftLeftAt(_) => byte << 1,
+                        _ => byte >> 1,
+                    });
+                }
+            }
+            RuleOp::RejectUnlessLonger(n) => {
+                if len <= n {
+                    return None;
+                }
+            }
+            RuleOp::RejectUnlessShorter(n) => {
+                if len >= n {
+                    return None;
+                }
+            }
+            RuleOp::RejectUnlessLength(n) => {
+                if len != n {
+                    return None;
+                }
+            }
+            RuleOp::RejectIfContains(class) => {
+                if chars.iter().any(|&c| class.matches(c)) {
+                    return None;
+                }
+            }
+            RuleOp::RejectUnlessContains(class) => {
+                if !chars.iter().any(|&c| class.matches(c)) {
+                    return None;
+                }
+            }
+            RuleOp::RejectUnlessFirst(class) => {
+                if !chars.first().is_some_and(|&c| class.matches(c)) {
+                    return None;
+                }
+            }
+            RuleOp::RejectUnlessLast(class) => {
+                if !chars.last().is_some_and(|&c| class.matches(c)) {
+                    return None;
+                }
+            }
+            RuleOp::RejectUnlessAt(n, class) => {
+                if !chars.get(n).is_some_and(|&c| class.matches(c)) {
+                    return None;
+                }
+            }
+        }
+    }
+    (!chars.is_empty()).then(|| chars.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Examples of the Hashcat rule reference, all applied to "p@ssW0rd"
+    const REFERENCE: &[(&str, &str)] = &[
+        (":", "p@ssW0rd"),
+        ("l", "p@ssw0rd"),
+        ("u", "P@SSW0RD"),
+        ("c", "P@ssw0rd"),
+        ("C", "p@SSW0RD"),
+        ("t", "P@SSw0RD"),
+        ("T3", "p@sSW0rd"),
+        ("r", "dr0Wss@p"),
+        ("d", "p@ssW0rdp@ssW0rd"),
+        ("p2", "p@ssW0rdp@ssW0rdp@ssW0rd"),
+        ("f", "p@ssW0rddr0Wss@p"),
+        ("{", "@ssW0rdp"),
+        ("}", "dp@ssW0r"),
+        ("$1", "p@ssW0rd1"),
+        ("^1", "1p@ssW0rd"),
+        ("[", "@ssW0rd"),
+        ("]", "p@ssW0r"),
+        ("D3", "p@sW0rd"),
+        ("i4!", "p@ss!W0rd"),
+        ("o3$", "p@s$W0rd"),
+        ("ss$", "p@$$W0rd"),
+        ("@s", "p@W0rd"),
+        ("z2", "ppp@ssW0rd"),
+        ("Z2", "p@ssW0rddd"),
+        ("L2", "p@æsW0rd"),
+        ("R2", "p@9sW0rd"),
+    ];
+
+    #[test]
+    fn test_reference_outputs() {
+        for (rule, expected) in REFERENCE {
+            let ops = parse_rule(rule).unwrap();
+            assert_eq!(
+                apply_ruleset(&ops, "p@ssW0rd").as_deref(),
+                Some(*expected),
+                "rule {rule}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rules_compose() {
+        let ops = parse_rule("c $2 $0 ^!").unwrap();
+        assert_eq!(ops.len(), 4);
+        assert_eq!(apply_ruleset(&ops, "summer").as_deref(), Some("!Summer20"));
+        // Positions count characters, not bytes
+        let ops = parse_rule("o1a T0 $ ").unwrap();
+        assert_eq!(apply_ruleset(&ops, "pä55").as_deref(), Some("Pa55 "));
+    }
+
+    #[test]
+    fn test_out_of_range_and_limits() {
+        // Positions beyond the word leave it unchanged
+        for rule in ["TA", "D9", "o9x", "i9x", "L9"] {
+            let ops = parse_rule(rule).unwrap();
+            assert_eq!(
+                apply_ruleset(&ops, "word").as_deref(),
+                Some("word"),
+                "{rule}"
+            );
+        }
+        assert_eq!(
+            apply_ruleset(&parse_rule("i4x").unwrap(), "word").as_deref(),
+            Some("wordx")
+        );
+
+        let long = "a".repeat(MAX_LENGTH);
+        assert_eq!(
+            apply_ruleset(&[RuleOp::Duplicate], &long),
+            Some(long.clone())
+        );
+        assert_eq!(
+            apply_ruleset(&[RuleOp::Append('b')], &long),
+            Some(long.clone())
+        );
+        assert_eq!(apply_ruleset(&[], &format!("{long}a")), None);
+        assert_eq!(apply_ruleset(&[], ""), None);
+        assert_eq!(
+            apply_ruleset(&[RuleOp::Purge(CharClass::Char('a'))], "aaa"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_rule_errors() {
+        let error = |rule| parse_rule(rule).unwrap_err().to_string();
+        assert!(error("x04").contains("Unsupported rule function 'x'"));
+        assert!(error("$").contains("missing an argument"));
+        assert!(error("i5").contains("missing an argument"));
+        assert!(error("Ta").contains("Invalid position 'a'"));
+    }
+
+    #[test]
+    fn test_parse_rules_file() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "# best rules\n:\r\nu\n\nx04\n$ \n").unwrap();
+        let rules = parse_rules_file(file.path()).unwrap();
+        assert_eq!(
+            rules,
+            vec![
+                vec![RuleOp::Noop],
+                vec![RuleOp::Uppercase],
+                vec![RuleOp::Append(' ')]
+            ]
+        );
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(file, "# nothing\nx04\n").unwrap();
+        let error = parse_rules_file(file.path()).unwrap_err();
+        assert!(error.to_string().contains("No supported rules"));
+    }
+}
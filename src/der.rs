@@ -0,0 +1,281 @@
+//! Minimal reader for the DER encoding of PKCS#12 files.
+//!
+//! Only the parts readable without the password are walked, so a small
+//! TLV reader is enough and no ASN.1 library is needed.
+/// DER encoded OID of PKCS#7 `data` content, 1.2.840.113549.1.7.1
+pub(crate) const OID_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x01];
+
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_OCTET_STRING_CONSTRUCTED: u8 = 0x24;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_BMP_STRING: u8 = 0x1E;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+/// Bit of the tag marking a constructed encoding
+pub(crate) const TAG_CONSTRUCTED: u8 = 0x20;
+pub(crate) const TAG_EXPLICIT_0: u8 = 0xA0;
+
+/// Deepest nesting of constructed TLVs that is read.
+///
+/// PKCS#12 files nest about ten levels deep, deeper input is rejected before
+/// the recursion can overflow the stack.
+const MAX_DEPTH: usize = 32;
+
+/// Reads DER TLVs one after another.
+///
+/// Only definite lengths are supported, BER indefinite lengths end the
+/// reader as if the input was exhausted.
+pub(crate) struct Der<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Iterator for Der<'a> {
+    /// Tag and contents of the next TLV
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&tag, rest) = self.0.split_first()?;
+        let (&first, rest) = rest.split_first()?;
+        let (len, rest) = if first < 0x80 {
+            (first as usize, rest)
+        } else {
+            let count = (first & 0x7F) as usize;
+            if count == 0 || count > std::mem::size_of::<usize>() || rest.len() < count {
+                return None;
+            }
+            let len = rest[..count]
+                .iter()
+                .fold(0usize, |len, &byte| (len << 8) | byte as usize);
+            (len, &rest[count..])
+        };
+        if rest.len() < len {
+            self.0 = &[];
+            return None;
+        }
+        let (contents, rest) = rest.split_at(len);
+        self.0 = rest;
+        Some((tag, contents))
+    }
+}
+
+/// Returns the contents of a single TLV with the expected tag.
+pub(crate) fn expect(bytes: &[u8], tag: u8) -> Option<&[u8]> {
+    Der(bytes)
+        .next()
+        .filter(|&(actual, _)| actual == tag)
+        .map(|(_, contents)| contents)
+}
+
+/// Returns the bytes of an OCTET STRING, joining the segments of a constructed one.
+pub(crate) fn octet_string(tag: u8, contents: &[u8]) -> Option<Vec<u8>> {
+    nested_octet_string(tag, contents, 0)
+}
+
+/// Returns the bytes of an OCTET STRING nested `depth` levels deep.
+fn nested_octet_string(tag: u8, contents: &[u8], depth: usize) -> Option<Vec<u8>> {
+    match tag {
+        TAG_OCTET_STRING => Some(contents.to_vec()),
+        TAG_OCTET_STRING_CONSTRUCTED if depth < MAX_DEPTH => {
+            Der(contents).try_fold(Vec::new(), |mut bytes, (t, c)| {
+                bytes.extend(nested_octet_string(t, c, depth + 1)?);
+                Some(bytes)
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Returns the payload of a ContentInfo of type `data`.
+pub(crate) fn data_content(content_info: &[u8]) -> Option<Vec<u8>> {
+    let mut fields = Der(content_info);
+    let (TAG_OID, OID_DATA) = fields.next()? else {
+        return None;
+    };
+    let (TAG_EXPLICIT_0, explicit) = fields.next()? else {
+        return None;
+    };
+    let (tag, contents) = Der(explicit).next()?;
+    octet_string(tag, contents)
+}
+
+/// Rewrites the BER indefinite lengths of Windows and Java exports as
+/// definite lengths, so that [`Der`] can read the data.
+///
+/// Nested encodings inside OCTET STRINGs are left as they are.
+///
+/// # Returns
+///
+/// The data with definite lengths only, `None` if it is not valid BER or
+/// nests deeper than [`MAX_DEPTH`].
+pub(crate) fn definite_lengths(bytes: &[u8]) -> Option<Vec<u8>> {
+    rewrite_tlvs(bytes, 0)
+}
+
+/// Rewrites the TLVs of `bytes`, which are nested `depth` levels deep.
+fn rewrite_tlvs(mut bytes: &[u8], depth: usize) -> Option<Vec<u8>> {
+    let mut definite = Vec::with_capacity(bytes.len());
+    while !bytes.is_empty() {
+        bytes = rewrite_tlv(bytes, &mut definite, depth)?;
+    }
+    Some(definite)
+}
+
+/// Appends the first TLV of `bytes` with definite lengths to `out`.
+///
+/// # Returns
+///
+/// The bytes after the TLV, `None` if it is not valid BER or nests deeper
+/// than [`MAX_DEPTH`].
+fn rewrite_tlv<'a>(bytes: &'a [u8], out: &mut Vec<u8>, depth: usize) -> Option<&'a [u8]> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    let constructed = bytes.first()? & TAG_CONSTRUCTED != 0;
+    if let ([tag, 0x80, rest @ ..], true) = (bytes, constructed) {
+        // Indefinite length: TLVs up to the end-of-contents octets
+        let mut rest = rest;
+        let mut contents = Vec::new();
+        loop {
+            match rest {
+                [0, 0, tail @ ..] => {
+                    push_tlv(out, *tag, &contents);
+                    return Some(tail);
+                }
+                [] => return None,
+                _ => rest = rewrite_tlv(rest, &mut contents, depth + 1)?,
+            }
+        }
+    }
+    let mut tlvs = Der(bytes);
+    let (tag, contents) = tlvs.next()?;
+    if constructed {
+        push_tlv(out, tag, &rewrite_tlvs(contents, depth + 1)?);
+    } else {
+        push_tlv(out, tag, contents);
+    }
+    Some(tlvs.0)
+}
+
+/// Appends a TLV with a definite length to `out`.
+fn push_tlv(out: &mut Vec<u8>, tag: u8, contents: &[u8]) {
+    out.push(tag);
+    let len = contents.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes = len.to_be_bytes();
+        let skip = bytes.iter().take_while(|&&byte| byte == 0).count();
+        out.push(0x80 | (bytes.len() - skip) as u8);
+        out.extend(&bytes[skip..]);
+    }
+    out.extend(contents);
+}
+
+/// Returns a non-negative INTEGER that fits into `u32`.
+pub(crate) fn integer(contents: &[u8]) -> Option<u32> {
+    let contents = match contents {
+        [0, rest @ ..] => rest,
+        [first, ..] if first & 0x80 != 0 => return None,
+        contents => contents,
+    };
+    if contents.len() > 4 {
+        return None;
+    }
+    Some(
+        contents
+            .iter()
+            .fold(0u32, |value, &byte| (value << 8) | byte as u32),
+    )
+}
+
+/// Formats the contents of an OBJECT IDENTIFIER in dotted notation.
+pub(crate) fn oid_string(contents: &[u8]) -> String {
+    let mut arcs = Vec::new();
+    let mut arc = 0u128;
+    for &byte in contents {
+        arc = (arc << 7) | (byte & 0x7F) as u128;
+        if byte & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (arc / 40).min(2);
+                arcs.push(first);
+                arc -= first * 40;
+            }
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+    arcs.iter()
+        .map(u128::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_der_lengths() {
+        let long = [&[0x04, 0x81, 0x80][..], &[0xAB; 0x80]].concat();
+        let (tag, contents) = Der(&long).next().unwrap();
+        assert_eq!((tag, contents.len()), (TAG_OCTET_STRING, 0x80));
+        // Truncated and indefinite lengths end the reader
+        assert_eq!(Der(&long[..50]).next(), None);
+        assert_eq!(Der(&[0x30, 0x80, 0x00, 0x00]).next(), None);
+    }
+
+    #[test]
+    fn test_definite_lengths() {
+        // SEQUENCE (indefinite) { INTEGER 3, [0] (indefinite) { OCTET STRING "ab" } }
+        let ber = [
+            0x30, 0x80, 0x02, 0x01, 0x03, 0xA0, 0x80, 0x04, 0x02, b'a', b'b', 0x00, 0x00, 0x00,
+            0x00,
+        ];
+        let der = [
+            0x30, 0x09, 0x02, 0x01, 0x03, 0xA0, 0x04, 0x04, 0x02, b'a', b'b',
+        ];
+        assert_eq!(definite_lengths(&ber).unwrap(), der);
+        assert_eq!(definite_lengths(&der).unwrap(), der);
+
+        let long = [&[0x30, 0x80, 0x04, 0x81, 0x80][..], &[0xAB; 0x80], &[0, 0]].concat();
+        let rewritten = definite_lengths(&long).unwrap();
+        assert_eq!(rewritten[..5], [0x30, 0x81, 0x83, 0x04, 0x81]);
+        assert_eq!(rewritten.len(), 0x86);
+
+        // Missing end-of-contents, truncated contents and indefinite primitives
+        assert_eq!(definite_lengths(&ber[..13]), None);
+        assert_eq!(definite_lengths(&der[..8]), None);
+        assert_eq!(definite_lengths(&[0x04, 0x80, 0x00, 0x00]), None);
+    }
+
+    #[test]
+    fn test_deep_nesting_is_rejected() {
+        // 800 KB of nested indefinite SEQUENCEs would overflow the stack
+        let ber = [0x30, 0x80].repeat(400_000);
+        assert_eq!(definite_lengths(&ber), None);
+
+        let nested = |depth: usize| {
+            (0..depth).fold(vec![0x04, 0x01, b'a'], |inner, _| {
+                [&[0x24, inner.len() as u8][..], &inner].concat()
+            })
+        };
+        let shallow = nested(MAX_DEPTH);
+        assert_eq!(octet_string(shallow[0], &shallow[2..]), Some(b"a".to_vec()));
+        assert_eq!(definite_lengths(&shallow), Some(shallow.clone()));
+        let deep = nested(MAX_DEPTH + 1);
+        assert_eq!(octet_string(deep[0], &deep[2..]), None);
+        assert_eq!(definite_lengths(&deep), None);
+    }
+
+    #[test]
+    fn test_integer_and_oid() {
+        assert_eq!(integer(&[0x08, 0x00]), Some(2048));
+        assert_eq!(integer(&[0x00, 0x80]), Some(128));
+        assert_eq!(integer(&[0x80]), None);
+        assert_eq!(integer(&[1, 0, 0, 0, 0]), None);
+        assert_eq!(
+            oid_string(&[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x0C, 0x01, 0x03]),
+            "1.2.840.113549.1.12.1.3"
+        );
+        assert_eq!(oid_string(&[0x2B, 0x0E, 0x03, 0x02, 0x1A]), "1.3.14.3.2.26");
+        assert_eq!(oid_string(&[0x88, 0x37, 0x03]), "2.999.3");
+    }
+}
@@ -0,0 +1,138 @@
+//! CSV file of the passwords found in a run for `--save-all-found`.
+//!
+//! Every found password is written as a row of
+//! `certificate_path,password,attempts,elapsed_secs` and flushed right away,
+//! so the rows of certificates cracked before the process is killed are kept.
+use crate::types::CrackStats;
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+/// Column names of the header row
+pub const HEADER: [&str; 4] = ["certificate_path", "password", "attempts", "elapsed_secs"];
+
+/// Writes found passwords to a CSV file, one row per certificate.
+pub struct FoundCsv {
+    /// Writer of the CSV file
+    writer: csv::Writer<File>,
+    /// Whether a write failure was reported, later failures are silent
+    failed: bool,
+}
+
+impl FoundCsv {
+    /// Opens the CSV file and writes the header row.
+    ///
+    /// When appending, the header is only written to an empty or new file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the CSV file
+    /// * `append` - Whether to keep the rows of an existing file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or the header cannot be written
+    pub fn open(path: &Path, append: bool) -> Result<Self> {
+        let file = if append {
+            OpenOptions::new().create(true).append(true).open(path)
+        } else {
+            File::create(path)
+        }
+        .with_context(|| format!("Failed to open found passwords file: {}", path.display()))?;
+        let empty = file.metadata().map_or(true, |metadata| metadata.len() == 0);
+        let mut writer = csv::Writer::from_writer(file);
+        if empty {
+            writer
+                .write_record(HEADER)
+                .and_then(|()| Ok(writer.flush()?))
+                .with_context(|| {
+                    format!("Failed to write found passwords file: {}", path.display())
+                })?;
+        }
+        Ok(Self {
+            writer,
+            failed: false,
+        })
+    }
+
+    /// Writes the row of a certificate whose password was found and flushes it.
+    ///
+    /// Results without a password are skipped. Write failures are reported
+    /// once and do not stop the run.
+    ///
+    /// # Arguments
+    ///
+    /// * `certificate` - Path to the certificate as given on the command line
+    /// * `stats` - Statistics of the certificate
+    pub fn write(&mut self, certificate: &Path, stats: &CrackStats) {
+        let Some(password) = &stats.password else {
+            return;
+        };
+        let elapsed = stats.found_at.unwrap_or(stats.elapsed).as_secs_f64();
+        let row = [
+            certificate.to_string_lossy().into_owned(),
+            password.clone(),
+            stats.total_attempts.to_string(),
+            format!("{elapsed:.3}"),
+        ];
+        let written = self
+            .writer
+            .write_record(&row)
+            .and_then(|()| Ok(self.writer.flush()?));
+        if let Err(e) = written {
+            if !self.failed {
+                self.failed = true;
+                eprintln!("Warning: failed to write found passwords file: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::CrackMode;
+    use std::time::Duration;
+
+    fn stats(password: Option<&str>) -> CrackStats {
+        CrackStats {
+            total_attempts: 42,
+            elapsed: Duration::from_secs(3),
+            rate_per_sec: 14.0,
+            found: password.is_some(),
+            password: password.map(str::to_string),
+            found_at: password.map(|_| Duration::from_millis(1500)),
+            reversed: false,
+            matched_groups: None,
+            mode: CrackMode::Dictionary,
+            filtered: 0,
+            oversized: 0,
+        }
+    }
+
+    #[test]
+    fn test_rows_are_quoted_and_appended() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("found.csv");
+
+        let mut found = FoundCsv::open(&path, false).unwrap();
+        found.write(Path::new("a.p12"), &stats(Some("p@ss, \"word\"")));
+        found.write(Path::new("b.p12"), &stats(None));
+        drop(found);
+        let mut found = FoundCsv::open(&path, true).unwrap();
+        found.write(Path::new("c.p12"), &stats(Some("x")));
+        drop(found);
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "certificate_path,password,attempts,elapsed_secs\n\
+             a.p12,\"p@ss, \"\"word\"\"\",42,1.500\n\
+             c.p12,x,42,1.500\n"
+        );
+        FoundCsv::open(&path, false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "certificate_path,password,attempts,elapsed_secs\n"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Password candidates derived from the certificate itself.
+//!
+//! Passwords often relate to the certificate's subject: the name, the
+//! organization or an email address. A PKCS#12 file can be read partially
+//! without the password: bags in plain `data` content are not encrypted, only
+//! the private key is. Certificates are only readable if they were exported
+//! without certificate encryption (`openssl pkcs12 -certpbe NONE`, common for
+//! files exported by Windows and Java), friendly names of the key bags
+//! usually are.
+use crate::der::{
+    data_content, expect, octet_string, Der, TAG_BMP_STRING, TAG_EXPLICIT_0, TAG_OID,
+};
+use crate::types::CrackResult;
+use openssl::pkcs12::Pkcs12;
+use openssl::x509::X509;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// DER encoded OID of a PKCS#12 certificate bag, 1.2.840.113549.1.12.10.1.3
+const OID_CERT_BAG: &[u8] = &[
+    0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x0C, 0x0A, 0x01, 0x03,
+];
+/// DER encoded OID of a nested PKCS#12 safe contents bag, 1.2.840.113549.1.12.10.1.6
+const OID_SAFE_CONTENTS_BAG: &[u8] = &[
+    0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x0C, 0x0A, 0x01, 0x06,
+];
+/// DER encoded OID of an X.509 certificate in a certificate bag, 1.2.840.113549.1.9.22.1
+const OID_X509_CERTIFICATE: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x16, 0x01];
+/// DER encoded OID of the friendly name bag attribute, 1.2.840.113549.1.9.20
+const OID_FRIENDLY_NAME: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x14];
+
+/// Years appended to every word besides the validity years of the certificates.
+const COMMON_YEARS: std::ops::RangeInclusive<u32> = 2000..=2030;
+/// Suffixes appended to every word besides the years.
+const COMMON_SUFFIXES: [&str; 4] = ["1", "12", "123", "!"];
+
+/// Metadata readable from a PKCS#12 file without the password.
+#[derive(Debug, Default)]
+struct Metadata {
+    /// Certificates stored in unencrypted bags
+    certificates: Vec<X509>,
+    /// Friendly names of all unencrypted bags
+    friendly_names: Vec<String>,
+}
+
+impl Metadata {
+    /// Walks the unencrypted parts of a DER encoded PKCS#12 file.
+    ///
+    /// Encrypted content and anything that fails to parse is skipped.
+    fn read(pfx: &[u8]) -> Self {
+        let mut metadata = Self::default();
+        let Some(pfx) = expect(pfx, 0x30) else {
+            return metadata;
+        };
+        // version, authSafe ContentInfo, optional macData
+        let Some((_, auth_safe)) = Der(pfx).nth(1) else {
+            return metadata;
+        };
+        let Some(auth_safe) = data_content(auth_safe) else {
+            return metadata;
+        };
+        let Some(content_infos) = expect(&auth_safe, 0x30) else {
+            return metadata;
+        };
+        for (_, content_info) in Der(content_infos) {
+            // Encrypted and enveloped content is skipped here
+            if let Some(safe_contents) = data_content(content_info) {
+                if let Some(bags) = expect(&safe_contents, 0x30) {
+                    metadata.read_bags(bags);
+                }
+            }
+        }
+        metadata
+    }
+
+    /// Reads the certificates and friendly names of a SafeContents sequence.
+    fn read_bags(&mut self, bags: &[u8]) {
+        for (_, bag) in Der(bags) {
+            let mut fields = Der(bag);
+            let (Some((TAG_OID, bag_id)), Some((TAG_EXPLICIT_0, value))) =
+                (fields.next(), fields.next())
+            else {
+                continue;
+            };
+            if let Some((0x31, attributes)) = fields.next() {
+                self.read_attributes(attributes);
+            }
+
+            if bag_id == OID_CERT_BAG {
+                if let Some(certificate) = Self::certificate(value) {
+                    self.certificates.push(certificate);
+                }
+            } else if bag_id == OID_SAFE_CONTENTS_BAG {
+                if let Some(nested) = expect(value, 0x30) {
+                    self.read_bags(nested);
+                }
+            }
+        }
+    }
+
+    /// Parses the X.509 certificate of a CertBag.
+    fn certificate(value: &[u8]) -> Option<X509> {
+        let cert_bag = expect(value, 0x30)?;
+        let mut fields = Der(cert_bag);
+        let (TAG_OID, OID_X509_CERTIFICATE) = fields.next()? else {
+            return None;
+        };
+        let (TAG_EXPLICIT_0, explicit) = fields.next()? else {
+            return None;
+        };
+        let (tag, contents) = Der(explicit).next()?;
+        X509::from_der(&octet_string(tag, contents)?).ok()
+    }
+
+    /// Reads the friendly name from the attributes of a bag.
+    fn read_attributes(&mut self, attributes: &[u8]) {
+        for (_, attribute) in Der(attributes) {
+            let mut fields = Der(attribute);
+            let (Some((TAG_OID, OID_FRIENDLY_NAME)), Some((0x31, values))) =
+                (fields.next(), fields.next())
+            else {
+                continue;
+            };
+            for (tag, value) in Der(values) {
+                if tag == TAG_BMP_STRING && value.len() % 2 == 0 {
+                    let units: Vec<u16> = value
+                        .chunks_exact(2)
+                        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                        .collect();
+                    self.friendly_names.push(String::from_utf16_lossy(&units));
+                }
+            }
+        }
+    }
+}
+
+/// Returns the year of an ASN.1 time as printed by OpenSSL, e.g. "Oct 16 00:12:26 2026 GMT".
+fn year(time: &openssl::asn1::Asn1TimeRef) -> Option<u32> {
+    time.to_string()
+        .split_whitespace()
+        .find(|token| token.len() == 4 && token.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|token| token.parse().ok())
+}
+
+/// Collects candidates in insertion order, skipping empty and duplicate ones.
+#[derive(Default)]
+struct Candidates {
+    words: Vec<String>,
+    seen: HashSet<String>,
+}
+
+impl Candidates {
+    fn push(&mut self, word: &str) {
+        let word = word.trim();
+        if !word.is_empty() && self.seen.insert(word.to_string()) {
+            self.words.push(word.to_string());
+        }
+    }
+}
+
+/// Capitalizes the first character of a word and lowercases the rest.
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+/// Derives password candidates from the metadata of a PKCS#12 file.
+///
+/// Reads the subject and issuer DN and the subject alternative names of
+/// every certificate stored without encryption, and the friendly names of
+/// the bags. Candidates come most literal first: the raw fields, their
+/// parts (email local parts, host names, words), case variants, the words
+/// with digits stripped, and finally the words with years and common
+/// suffixes appended.
+///
+/// # Arguments
+///
+/// * `cert_bytes` - DER encoded PKCS#12 file
+///
+/// # Returns
+///
+/// The candidates without duplicates, empty if no metadata is readable
+/// without the password.
+pub fn derive_wordlist_from_metadata(cert_bytes: &[u8]) -> Vec<String> {
+    let metadata = Metadata::read(cert_bytes);
+
+    let mut fields = Vec::new();
+    let mut years = Vec::new();
+    for certificate in &metadata.certificates {
+        for entry in certificate.subject_name().entries() {
+            if let Ok(value) = entry.data().as_utf8() {
+                fields.push(value.to_string());
+            }
+        }
+        if let Some(names) = certificate.subject_alt_names() {
+            for name in names.iter() {
+                if let Some(value) = name.email().or(name.dnsname()).or(name.uri()) {
+                    fields.push(value.to_string());
+                }
+            }
+        }
+    }
+    fields.extend(metadata.friendly_names.iter().cloned());
+    for certificate in &metadata.certificates {
+        for entry in certificate.issuer_name().entries() {
+            if let Ok(value) = entry.data().as_utf8() {
+                fields.push(value.to_string());
+            }
+        }
+        years.extend(year(certificate.not_before()));
+        years.extend(year(certificate.not_after()));
+    }
+    years.extend(COMMON_YEARS);
+
+    // Raw fields and their parts
+    let mut words = Candidates::default();
+    for field in &fields {
+        words.push(field);
+    }
+    for field in &fields {
+        if let Some((local, domain)) = field.split_once('@') {
+            words.push(local);
+            words.push(domain.split('.').next().unwrap_or_default());
+        } else if field.contains('.') && !field.contains(' ') {
+            words.push(field.split('.').next().unwrap_or_default());
+        }
+        let compact: String = field.split_whitespace().collect();
+        words.push(&compact);
+        for part in field.split(|c: char| !c.is_alphanumeric()) {
+            if part.chars().count() >= 3 {
+                words.push(part);
+            }
+        }
+    }
+
+    // Case variants and digits stripped
+    let base = words.words.clone();
+    for word in &base {
+        words.push(&word.to_lowercase());
+        words.push(&word.to_uppercase());
+        words.push(&capitalized(word));
+    }
+    for word in words.words.clone() {
+        let stripped: String = word.chars().filter(|c| !c.is_ascii_digit()).collect();
+        words.push(&stripped);
+    }
+
+    // Years and common suffixes, only on the lowercase and capitalized forms
+    let mut candidates = Candidates::default();
+    for word in &words.words {
+        candidates.push(word);
+    }
+    let mut stems = Candidates::default();
+    for word in &base {
+        if !word.contains(' ') {
+            stems.push(&word.to_lowercase());
+            stems.push(&capitalized(word));
+        }
+    }
+    for stem in &stems.words {
+        for year in &years {
+            candidates.push(&format!("{stem}{year}"));
+        }
+        for suffix in COMMON_SUFFIXES {
+            candidates.push(&format!("{stem}{suffix}"));
+        }
+    }
+    candidates.words
+}
+
+/// Tests candidates against every certificate that is not cracked yet.
+///
+/// # Arguments
+///
+/// * `words` - Candidates to test
+/// * `pkcs12s` - The PKCS#12 certificates to test
+/// * `results` - Result tracking structure of every certificate
+///
+/// # Returns
+///
+/// Returns `true` if all certificates are cracked, `false` otherwise.
+pub(crate) fn try_words(
+    words: &[String],
+    pkcs12s: &[Arc<Pkcs12>],
+    results: &[Arc<Mutex<CrackResult>>],
+) -> bool {
+    let runs = crate::crackers::runs(results);
+    words
+        .par_iter()
+        .find_any(|candidate| {
+            let mut all_cracked = true;
+            let mut oversized = false;
+            for run in &runs {
+                if !run.is_found() {
+                    all_cracked = false;
+                    if run.skips_oversized(candidate) {
+                        oversized = true;
+                    } else {
+                        run.increment_attempts();
+                    }
+                }
+            }
+            all_cracked
+                || (!oversized
+                    && crate::crackers::check_password_multi(pkcs12s, candidate, &runs, results))
+        })
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::fixture_bytes;
+
+    #[test]
+    fn test_derive_from_unencrypted_certificate() {
+        // Exported with -certpbe NONE, subject CN=Initech, O=Initech Corp,
+        // emailAddress=peter.gibbons@initech.com and friendly name "Initech VPN"
+        let words = derive_wordlist_from_metadata(&fixture_bytes("metadata.p12"));
+        assert_eq!(words[0], "Initech");
+        for expected in [
+            "Initech Corp",
+            "Initech VPN",
+            "peter.gibbons@initech.com",
+            "peter.gibbons",
+            "gibbons",
+            "InitechCorp",
+            "initech",
+            "INITECH",
+            "initech2024",
+            "Initech123",
+        ] {
+            assert!(words.contains(&expected.to_string()), "{expected}");
+        }
+        let distinct: HashSet<&String> = words.iter().collect();
+        assert_eq!(distinct.len(), words.len());
+    }
+
+    #[test]
+    fn test_encrypted_certificate_has_no_metadata() {
+        assert!(derive_wordlist_from_metadata(&fixture_bytes("umlaut.p12")).is_empty());
+        assert!(derive_wordlist_from_metadata(b"not a certificate").is_empty());
+    }
+}
@@ -0,0 +1,54 @@
+//! Cleanup when the run is interrupted with Ctrl-C.
+//!
+//! The signal handler only sets a flag. A watcher thread polls it, runs the
+//! registered cleanup callbacks and exits, since files cannot be written
+//! from inside a signal handler.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+use std::time::Duration;
+
+/// Exit code of a process terminated by SIGINT
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Time between two checks of the interrupt flag
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Set by the signal handler
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// Callbacks run once the process is interrupted
+static CALLBACKS: Mutex<Vec<Box<dyn Fn() + Send>>> = Mutex::new(Vec::new());
+static INSTALL: Once = Once::new();
+
+extern "C" fn handle_interrupt(_signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Runs a callback when the process receives Ctrl-C, then exits with
+/// [`EXIT_INTERRUPTED`].
+///
+/// The first call installs the signal handler and the watcher thread.
+///
+/// # Arguments
+///
+/// * `callback` - Cleanup to run, e.g. flushing a file
+pub fn on_interrupt(callback: impl Fn() + Send + 'static) {
+    CALLBACKS.lock().unwrap().push(Box::new(callback));
+    INSTALL.call_once(|| {
+        // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+        unsafe {
+            libc::signal(
+                libc::SIGINT,
+                handle_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            );
+        }
+        std::thread::spawn(|| loop {
+            if INTERRUPTED.load(Ordering::SeqCst) {
+                for callback in CALLBACKS.lock().unwrap().iter() {
+                    callback();
+                }
+                std::process::exit(EXIT_INTERRUPTED);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        });
+    });
+}
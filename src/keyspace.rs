@@ -0,0 +1,256 @@
+//! Keyspace utilities.
+//!
+//! This module computes and formats the size of password search spaces.
+//! All arithmetic is done in `u128` and saturates instead of wrapping, so
+//! huge charsets and lengths are reported as a lower bound rather than a lie.
+
+/// Attempts per second used to give the keyspace a human-readable duration
+pub const REFERENCE_RATE: u128 = 1_000_000;
+
+/// Keyspace of a single password length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyspaceRow {
+    /// Password length
+    pub length: u8,
+    /// Number of candidates of exactly this length
+    pub keyspace: u128,
+    /// Number of candidates of this length and all shorter ones in the table
+    pub cumulative: u128,
+}
+
+/// Computes the number of candidates of a single length.
+///
+/// # Arguments
+///
+/// * `charset_size` - Number of characters in the charset
+/// * `length` - Password length
+/// * `distinct` - Whether candidates never repeat a character (`--no-repeats`)
+pub fn length_keyspace(charset_size: usize, length: u8, distinct: bool) -> u128 {
+    if !distinct {
+        return (charset_size as u128).saturating_pow(length as u32);
+    }
+    (0..length as usize)
+        .map(|position| charset_size.saturating_sub(position) as u128)
+        .fold(1, u128::saturating_mul)
+}
+
+/// Computes the keyspace for every length between `min_len` and `max_len`.
+///
+/// # Arguments
+///
+/// * `charset_size` - Number of characters in the charset
+/// * `min_len` - Shortest password length
+/// * `max_len` - Longest password length
+/// * `distinct` - Whether candidates never repeat a character (`--no-repeats`)
+pub fn keyspace_table(
+    charset_size: usize,
+    min_len: u8,
+    max_len: u8,
+    distinct: bool,
+) -> Vec<KeyspaceRow> {
+    let mut cumulative: u128 = 0;
+    (min_len..=max_len)
+        .map(|length| {
+            let keyspace = length_keyspace(charset_size, length, distinct);
+            cumulative = cumulative.saturating_add(keyspace);
+            KeyspaceRow {
+                length,
+                keyspace,
+                cumulative,
+            }
+        })
+        .collect()
+}
+
+/// Formats a count, switching to scientific notation for large values.
+///
+/// Saturated values are shown as a lower bound, e.g. `>3.4e38`.
+pub fn format_magnitude(count: u128) -> String {
+    if count == u128::MAX {
+        format!(">{:.1e}", count as f64)
+    } else if count < 1_000_000 {
+        count.to_string()
+    } else {
+        format!("{:.1e}", count as f64)
+    }
+}
+
+/// Formats the time needed to try `count` candidates at `rate` attempts per second.
+pub fn format_duration(count: u128, rate: u128) -> String {
+    const UNITS: [(&str, f64); 5] = [
+        ("seconds", 1.0),
+        ("minutes", 60.0),
+        ("hours", 3600.0),
+        ("days", 86400.0),
+        ("years", 31_557_600.0),
+    ];
+
+    let seconds = count as f64 / rate.max(1) as f64;
+    if seconds < 1.0 {
+        return "<1 second".to_string();
+    }
+
+    let (unit, size) = UNITS
+        .iter()
+        .rev()
+        .find(|(_, size)| seconds >= *size)
+        .unwrap_or(&UNITS[0]);
+    let value = seconds / size;
+    if value >= 1_000_000.0 {
+        format!("~{value:.1e} {unit}")
+    } else {
+        format!("~{value:.1} {unit}")
+    }
+}
+
+/// Formats a number of seconds as the two largest units, e.g. `3 days 14 hours`.
+///
+/// Estimates beyond a million years are shown in scientific notation.
+pub fn format_eta(seconds: f64) -> String {
+    const UNITS: [(&str, u64); 5] = [
+        ("year", 31_557_600),
+        ("day", 86400),
+        ("hour", 3600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+    let plural = |count: u64, unit: &str| match count {
+        1 => format!("1 {unit}"),
+        _ => format!("{count} {unit}s"),
+    };
+
+    if seconds < 1.0 {
+        return "less than 1 second".to_string();
+    }
+    let years = seconds / UNITS[0].1 as f64;
+    if years >= 1_000_000.0 {
+        return format!("{years:.1e} years");
+    }
+
+    let total = seconds.round() as u64;
+    let index = UNITS
+        .iter()
+        .position(|(_, size)| total >= *size)
+        .unwrap_or(UNITS.len() - 1);
+    let (unit, size) = UNITS[index];
+    let mut eta = plural(total / size, unit);
+    if let Some((next_unit, next_size)) = UNITS.get(index + 1) {
+        let rest = total % size / next_size;
+        if rest > 0 {
+            eta = format!("{eta} {}", plural(rest, next_unit));
+        }
+    }
+    eta
+}
+
+/// Prints the per-length keyspace table with cumulative totals.
+///
+/// # Arguments
+///
+/// * `charset_size` - Number of characters in the charset
+/// * `min_len` - Shortest password length
+/// * `max_len` - Longest password length
+/// * `distinct` - Whether candidates never repeat a character (`--no-repeats`)
+pub fn print_keyspace_table(charset_size: usize, min_len: u8, max_len: u8, distinct: bool) {
+    println!(
+        "Keyspace for a charset of {charset_size} characters{}:",
+        if distinct { " without repeats" } else { "" }
+    );
+    println!(
+        "  {:>6}  {:>12}  {:>12}  Time at {}M/s",
+        "Length",
+        "Keyspace",
+        "Cumulative",
+        REFERENCE_RATE / 1_000_000
+    );
+    for row in keyspace_table(charset_size, min_len, max_len, distinct) {
+        println!(
+            "  {:>6}  {:>12}  {:>12}  {}",
+            row.length,
+            format_magnitude(row.keyspace),
+            format_magnitude(row.cumulative),
+            format_duration(row.cumulative, REFERENCE_RATE)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyspace_table() {
+        let table = keyspace_table(10, 1, 3, false);
+        assert_eq!(
+            table,
+            vec![
+                KeyspaceRow {
+                    length: 1,
+                    keyspace: 10,
+                    cumulative: 10
+                },
+                KeyspaceRow {
+                    length: 2,
+                    keyspace: 100,
+                    cumulative: 110
+                },
+                KeyspaceRow {
+                    length: 3,
+                    keyspace: 1000,
+                    cumulative: 1110
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keyspace_table_beyond_usize() {
+        let table = keyspace_table(95, 12, 12, false);
+        assert_eq!(table[0].keyspace, 95u128.pow(12));
+        assert!(table[0].keyspace > usize::MAX as u128);
+
+        let table = keyspace_table(95, 255, 255, false);
+        assert_eq!(table[0].keyspace, u128::MAX);
+    }
+
+    #[test]
+    fn test_keyspace_table_distinct() {
+        let table = keyspace_table(10, 1, 3, true);
+        let keyspaces: Vec<u128> = table.iter().map(|row| row.keyspace).collect();
+        assert_eq!(keyspaces, [10, 90, 720]);
+        assert_eq!(table[2].cumulative, 820);
+
+        assert_eq!(length_keyspace(62, 8, true), (55..=62).product());
+        assert_eq!(length_keyspace(3, 4, true), 0);
+    }
+
+    #[test]
+    fn test_format_magnitude() {
+        assert_eq!(format_magnitude(999_999), "999999");
+        assert_eq!(format_magnitude(280_000_000_000_000), "2.8e14");
+        assert_eq!(format_magnitude(u128::MAX), ">3.4e38");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(10, REFERENCE_RATE), "<1 second");
+        assert_eq!(format_duration(90_000_000, REFERENCE_RATE), "~1.5 minutes");
+        assert_eq!(
+            format_duration(280_000_000_000, REFERENCE_RATE),
+            "~3.2 days"
+        );
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(0.4), "less than 1 second");
+        assert_eq!(format_eta(1.0), "1 second");
+        assert_eq!(format_eta(3600.0), "1 hour");
+        assert_eq!(
+            format_eta(3.0 * 86400.0 + 14.0 * 3600.0 + 59.0),
+            "3 days 14 hours"
+        );
+        assert_eq!(format_eta(125.0), "2 minutes 5 seconds");
+        assert_eq!(format_eta(4e15), "1.3e8 years");
+    }
+}
@@ -1,22 +1,39 @@
-mod args;
-mod charset;
-mod crackers;
-mod types;
-
-use anyhow::{Context, Result};
+use anyhow::{bail, Result};
 use clap::Parser;
-use crackers::{
-    bruteforce::BruteforceCracker, dictionary::DictionaryCracker, pattern::PatternCracker,
-};
 use openssl::pkcs12::Pkcs12;
-use rayon::ThreadPoolBuilder;
-use std::fs::File;
-use std::io::Read;
+use pkcs12cracker::args::OutputFormat;
+use pkcs12cracker::checkpoint::{certificate_fingerprint, WriteCadence};
+use pkcs12cracker::crackers::{
+    bruteforce::{BruteforceCracker, LengthOrder},
+    dictionary::{DelimiterMode, DictionaryCracker},
+    edit_distance::EditDistanceCracker,
+    john_rules,
+    markov::{MarkovCracker, MarkovModel},
+    mutations,
+    pattern::{read_pattern_file, PatternCracker},
+    permutation_attack::PermutationCracker,
+    policy::CandidatePolicy,
+    pronounceable::PronounceableCracker,
+    rules, ProbabilityModel,
+};
+use pkcs12cracker::found_csv::FoundCsv;
+use pkcs12cracker::progress_file::ProgressFile;
+use pkcs12cracker::session::CrackSession;
+use pkcs12cracker::types::{CombinationCount, CrackMode, PasswordCracker, PkcsError, WorkUnit};
+use pkcs12cracker::types::{CrackResult, CrackStats};
+use pkcs12cracker::{
+    args, cert_info, charset, checkpoint, keyspace, load_certificate, notify, platform,
+    progress_file, resume, CertificateSource,
+};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use types::{CrackResult, PasswordCracker};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-/// Typical size of a PKCS#12 certificate file for buffer pre-allocation
-const TYPICAL_PKCS12_SIZE: usize = 4096;
+/// Time between two updates of the live attempt counter
+const LIVE_COUNTER_INTERVAL: Duration = Duration::from_secs(1);
 
 fn main() {
     let args = args::Args::parse();
@@ -30,98 +47,1002 @@ fn main() {
 
 /// Main execution logic for the password cracker.
 ///
-/// Initializes the thread pool, loads the certificate, and executes
+/// Creates a cracking session, loads the certificate, and executes
 /// the appropriate cracking strategy based on command line arguments.
 ///
 /// # Errors
 ///
 /// Returns an error if:
+/// - A certificate file does not exist, unless `--force` is given
 /// - Thread pool initialization fails
-/// - Certificate loading fails
+/// - Certificate loading fails (skipped with `--dry-run`)
 /// - No cracking mode is specified
 /// - The selected cracking strategy fails
-fn run(args: args::Args) -> Result<()> {
-    setup_thread_pool(&args)?;
-    let pkcs12 = load_certificate(&args)?;
-    let result = Arc::new(Mutex::new(CrackResult::new()));
+fn run(mut args: args::Args) -> Result<()> {
+    if args.alphabet_test {
+        return print_alphabet(&args);
+    }
+    if args.interleave_lengths {
+        args.length_order = Some(LengthOrder::Interleaved);
+    }
+    args.validate_certificate_paths()?;
+    if args.show_cert_info {
+        return print_cert_info(&args.certificate_paths);
+    }
+    if args.quiet_found && args.certificate_paths.len() > 1 {
+        bail!("--quiet-found supports a single certificate");
+    }
+    // Everything but the password is suppressed, the output is restored to print it
+    let silenced = args
+        .quiet_found
+        .then(platform::silence_output)
+        .transpose()?;
+    if args.quiet_found {
+        args.output = OutputFormat::BarePassword;
+    }
+    if args.deterministic && args.random_order && args.seed.is_none() {
+        bail!("--deterministic with --random-order requires --seed");
+    }
+    // A single worker tests the candidates in traversal order
+    let threads = if args.deterministic {
+        1
+    } else {
+        args.threads as usize
+    };
+    let mut session = CrackSession::new(threads)?;
+    if args.status_interval > 0 {
+        session = session.with_status_interval(Duration::from_secs(args.status_interval));
+    }
+    if let Some(filter) = args.filter_regex.clone() {
+        session = session.with_filter(filter);
+    }
+    if let Some(max_bytes) = args.max_bytes {
+        session = session.with_max_bytes(max_bytes as usize);
+    }
+    if let Some(case_sensitivity) = args.case_insensitive {
+        session = session.with_case_sensitivity(case_sensitivity);
+    }
+    if args.also_reversed {
+        session = session.with_also_reversed();
+    }
+    if args.pre_hash {
+        session = session.with_pre_hash();
+    }
+    if args.words_from_certificate {
+        session = session.with_certificate_words();
+    }
+    if let Some(path) = args.log_candidates.clone() {
+        eprintln!(
+            "Warning: --log-candidates writes every tested candidate to {}, which slows cracking down and can produce huge files",
+            path.display()
+        );
+        session = session.with_candidate_log(path);
+    }
+    if let Some(every) = args.progress_save_every {
+        if args.certificate_paths.len() > 1 {
+            bail!("--progress-save-every supports a single certificate");
+        }
+        if args.progress_file.is_none() && args.certificate_paths[0] == Path::new("-") {
+            bail!("--progress-save-every with a certificate from stdin requires --progress-file");
+        }
+        let path = args
+            .progress_file
+            .clone()
+            .unwrap_or_else(|| progress_file::default_path(&args.certificate_paths[0]));
+        session = session.with_progress_file(path, every);
+    }
+    let random_seed = random_seed(&args);
+    let probability_model = probability_model(&args)?;
 
-    let cracker: Box<dyn PasswordCracker> = if let Some(pattern) = args.pattern.as_ref() {
+    let cracker: Box<dyn PasswordCracker> = if args.pattern.is_some() || args.pattern_file.is_some()
+    {
         let charset = charset::build_charset(&args)?;
-        Box::new(PatternCracker::new(
-            pattern.clone(),
-            charset,
-            args.pattern_symbol,
-        ))
-    } else if args.bruteforce_flag {
-        let charset = charset::build_charset(&args)?;
-        Box::new(BruteforceCracker::new(
+        let mut patterns = patterns(&args, &charset)?.into_iter();
+        let first = patterns.next().expect("patterns are not empty");
+        let mut cracker =
+            PatternCracker::new(first, charset, args.pattern_symbol).with_work_unit(args.work_unit);
+        for pattern in patterns {
+            cracker = cracker.with_pattern(pattern);
+        }
+        for symbol in &args.symbols {
+            cracker = cracker.with_symbol(symbol.clone());
+        }
+        for symbol in &args.linked_symbols {
+            cracker = cracker.with_linked_symbol(symbol.clone());
+        }
+        cracker = cracker.with_years(args.years.clone());
+        if let Some(chunk_size) = args.chunk_size {
+            cracker = cracker.with_chunk_size(chunk_size as usize);
+        }
+        if let Some(toggle) = args.toggle_case {
+            cracker = cracker.with_case_toggle(toggle);
+        }
+        if let Some(path) = &args.pattern_dict {
+            cracker = cracker.with_word_list(DictionaryCracker::new(
+                path.clone(),
+                DelimiterMode::SingleByte(b'\n'),
+            ));
+        }
+        if let Some(skip) = args.skip {
+            cracker = cracker.with_skip(skip);
+        }
+        if let Some(limit) = args.limit {
+            cracker = cracker.with_limit(limit);
+        }
+        if let Some(seed) = random_seed {
+            cracker = cracker.with_random_order(seed);
+        }
+        if args.reverse {
+            cracker = cracker.with_reverse();
+        }
+        if let Some(order) = args.numeric_order {
+            cracker = cracker.with_numeric_order(order);
+        }
+        if let Some(session) = args.session.clone() {
+            cracker = cracker.with_session(session, args.restore);
+            if let Some(cadence) = args.session_save_every {
+                cracker = cracker.with_session_cadence(cadence);
+            }
+        }
+        if let Some(model) = probability_model {
+            cracker = cracker.with_probability_model(model);
+        }
+        Box::new(cracker)
+    } else if args.pronounceable {
+        let mut cracker = PronounceableCracker::new(
             args.minumum_length,
             args.maximum_length,
-            charset,
-        ))
-    } else if let Some(dict_path) = args.dictionary_path {
-        Box::new(DictionaryCracker::new(dict_path, args.delimiter))
+            args.pronounceable_digits,
+        )
+        .with_work_unit(args.work_unit);
+        if let Some(chunk_size) = args.chunk_size {
+            cracker = cracker.with_chunk_size(chunk_size as usize);
+        }
+        Box::new(cracker)
+    } else if args.bruteforce_flag {
+        let charset = charset::build_charset(&args)?;
+        let mut cracker = BruteforceCracker::new(args.minumum_length, args.maximum_length, charset)
+            .with_work_unit(args.work_unit);
+        if let Some(chunk_size) = args.chunk_size {
+            cracker = cracker.with_chunk_size(chunk_size as usize);
+        }
+        if let Some(seed) = random_seed {
+            cracker = cracker.with_random_order(seed);
+        }
+        if args.reverse {
+            cracker = cracker.with_reverse();
+        }
+        if let Some(order) = args.length_order {
+            cracker = cracker.with_length_order(order);
+        }
+        if args.no_repeats {
+            cracker = cracker.with_no_repeats();
+        }
+        if let Some(candidate) = args.start_at.clone() {
+            cracker = cracker.with_start_at(candidate);
+        }
+        if let Some(skip) = args.skip {
+            cracker = cracker.with_skip(skip);
+        }
+        if let Some(limit) = args.limit {
+            cracker = cracker.with_limit(limit);
+        }
+        if args.smart_order {
+            cracker = cracker.with_smart_order();
+        }
+        if !args.require.is_empty() || !args.require_count.is_empty() {
+            cracker = cracker.with_policy(CandidatePolicy::new(&args.require, &args.require_count));
+        }
+        if let Some(session) = args.session.clone() {
+            cracker = cracker.with_session(session, args.restore);
+            if let Some(cadence) = args.session_save_every {
+                cracker = cracker.with_session_cadence(cadence);
+            }
+        }
+        if let Some(model) = probability_model {
+            cracker = cracker.with_probability_model(model);
+        }
+        Box::new(cracker)
+    } else if let Some(dict_path) = args.dictionary_path.clone() {
+        let mut cracker = DictionaryCracker::new(dict_path, args.delimiter.clone())
+            .with_work_unit(args.work_unit)
+            .with_encoding(args.wordlist_encoding);
+        if let Some(word_encoding) = args.word_encoding {
+            cracker = cracker.with_word_encoding(word_encoding);
+        }
+        if args.no_trim {
+            cracker = cracker.without_trim();
+        }
+        if let Some(order) = args.wordlist_sort {
+            let chunk_bytes = args
+                .wordlist_sort_chunk
+                .map(|megabytes| megabytes as usize * 1024 * 1024);
+            cracker = cracker.with_sort(order, chunk_bytes);
+        }
+        if let Some(count) = args.skip_n {
+            cracker = cracker.with_skip_n(count);
+        }
+        if let Some(count) = args.first_n {
+            cracker = cracker.with_first_n(count);
+        }
+        if let Some(max_digits) = args.append_numbers {
+            cracker = cracker.with_suffixes(mutations::numeric_suffixes(max_digits));
+        } else if let Some(digits) = args.append_numbers_fixed {
+            cracker = cracker.with_suffixes(mutations::fixed_numeric_suffixes(digits));
+        }
+        if let Some(path) = &args.rules {
+            let rules = rules::parse_rules_file(path)?;
+            println!("Loaded {} rules from {}", rules.len(), path.display());
+            cracker = cracker.with_rules(rules);
+        }
+        if let Some(path) = &args.john_rules {
+            let rules = john_rules::parse_john_rules_file(path, &args.john_rules_section)?;
+            println!(
+                "Loaded {} rules from [List.Rules:{}] of {}",
+                rules.len(),
+                args.john_rules_section,
+                path.display()
+            );
+            cracker = cracker.with_rules(rules);
+        }
+        Box::new(cracker)
+    } else if let Some(corpus_path) = args.markov_model.as_ref() {
+        let model = MarkovModel::train(corpus_path)?;
+        if let Some(save_path) = args.markov_save_bin.as_ref() {
+            model.save(save_path)?;
+        }
+        Box::new(markov_cracker(model, &args))
+    } else if let Some(model_path) = args.markov_model_bin.as_ref() {
+        let model = MarkovModel::load(model_path)?;
+        Box::new(markov_cracker(model, &args))
+    } else if let Some(word) = args.mutate.clone() {
+        let charset = charset::build_charset(&args)?;
+        let mut cracker =
+            EditDistanceCracker::new(word, args.distance, charset).with_work_unit(args.work_unit);
+        if let Some(chunk_size) = args.chunk_size {
+            cracker = cracker.with_chunk_size(chunk_size as usize);
+        }
+        Box::new(cracker)
+    } else if !args.permutation_words.is_empty() {
+        let mut words = args.permutation_words.clone();
+        if let Some(max_n) = args.permutation_max_n {
+            if words.len() > max_n as usize {
+                eprintln!(
+                    "Warning: --permutation-max-n {max_n} ignores the words {}",
+                    words[max_n as usize..].join(",")
+                );
+                words.truncate(max_n as usize);
+            }
+        }
+        let mut cracker = PermutationCracker::new(words).with_work_unit(args.work_unit);
+        if let Some(chunk_size) = args.chunk_size {
+            cracker = cracker.with_chunk_size(chunk_size as usize);
+        }
+        Box::new(cracker)
+    } else {
+        return Err(PkcsError::NoModeSpecified.into());
+    };
+
+    if args.dry_run {
+        if args.bruteforce_flag && !args.pronounceable {
+            let charset_size = charset::build_charset(&args)?.chars().count();
+            keyspace::print_keyspace_table(
+                charset_size,
+                args.minumum_length,
+                args.maximum_length,
+                args.no_repeats,
+            );
+        }
+        return print_dry_run(cracker.as_ref(), args.total_machines);
+    }
+    if let Some(count) = args.show_candidates {
+        return print_candidates(cracker.as_ref(), &args, count as usize);
+    }
+
+    let pkcs12s = args
+        .certificate_paths
+        .iter()
+        .map(load_certificate)
+        .collect::<Result<Vec<_>>>()?;
+    if let [pkcs12] = pkcs12s.as_slice() {
+        session = auto_resume(session, cracker.as_ref(), pkcs12, &args, random_seed)?;
+    }
+    print_estimate(cracker.as_ref());
+    if let Some(secs) = args.estimate_time {
+        let [pkcs12] = pkcs12s.as_slice() else {
+            bail!("--estimate-time supports a single certificate");
+        };
+        let rate = timed_benchmark(cracker.as_ref(), pkcs12, &args, threads, secs)?;
+        print_time_estimate(cracker.as_ref(), rate);
+    }
+    if args.deterministic {
+        println!("Starting password cracking in deterministic order on 1 thread...");
+    } else {
+        println!(
+            "Starting password cracking with {} threads...",
+            session.num_threads()
+        );
+    }
+
+    let found_csv = args
+        .save_all_found
+        .as_deref()
+        .map(|path| FoundCsv::open(path, args.save_all_found_append))
+        .transpose()?;
+    if let [pkcs12] = pkcs12s.as_slice() {
+        let stats = crack_single(&session, cracker.as_ref(), pkcs12, &args, found_csv)?;
+        if let Some(silenced) = silenced {
+            drop(silenced);
+            let Some(password) = stats.password else {
+                std::process::exit(1);
+            };
+            println!("{password}");
+        }
+        Ok(())
     } else {
-        return Err(anyhow::anyhow!(
-            "No cracking mode specified. Use --pattern, --brute-force, or --dictionary"
-        ));
+        crack_multiple(&session, cracker.as_ref(), &pkcs12s, &args, found_csv)
+    }
+}
+
+/// Saves the run to a state file and offers to resume an identical earlier run.
+///
+/// Only brute force, pattern and dictionary runs of a single certificate
+/// without `--session`, `--progress-save-every` or `--pattern-dict` are tracked, see
+/// [`resume`]. An earlier run is resumed with `--auto-resume` or after
+/// confirming the prompt. Without a terminal to ask on, it is left untouched
+/// and the new run is not tracked.
+///
+/// # Errors
+///
+/// Returns an error if the certificate cannot be encoded or the run
+/// parameters cannot be read
+fn auto_resume(
+    session: CrackSession,
+    cracker: &dyn PasswordCracker,
+    pkcs12: &Pkcs12,
+    args: &args::Args,
+    random_seed: Option<u64>,
+) -> Result<CrackSession> {
+    let tracked = matches!(
+        cracker.mode(),
+        CrackMode::BruteForce | CrackMode::Pattern | CrackMode::Dictionary
+    ) && args.session.is_none()
+        && args.progress_save_every.is_none()
+        && args.probability_model.is_none()
+        && args.pattern_dict.is_none()
+        && args.skip_n.is_none()
+        && args.first_n.is_none();
+    let Some(dir) = resume::state_dir().filter(|_| tracked) else {
+        return Ok(session);
+    };
+    let parameters = resume::run_parameters(args, random_seed)?;
+    let path = resume::state_path(
+        &dir,
+        &resume::run_key(&certificate_fingerprint(pkcs12)?, &parameters),
+    );
+
+    let saved = ProgressFile::load(&path).unwrap_or_else(|e| {
+        eprintln!("Warning: {e:#}, starting over");
+        None
+    });
+    if let Some(saved) = saved {
+        let work_items = match cracker.work_items()? {
+            Some(CombinationCount::Exact(items)) => Some(items),
+            Some(CombinationCount::TooLarge) | None => None,
+        };
+        let covered = match resume::covered_share(args, saved.position, work_items) {
+            Some(share) => format!("covered {:.1}%", share * 100.0),
+            None => format!("reached {}", saved.position),
+        };
+        let resume = if args.auto_resume {
+            println!("Previous run {covered}, resuming it");
+            true
+        } else if std::io::stdin().is_terminal() && !args.quiet_found {
+            confirm(&format!("Previous run {covered} - resume? [Y/n] "))?
+        } else {
+            println!("Previous run {covered}, rerun with --auto-resume to continue it");
+            return Ok(session);
+        };
+        if !resume {
+            // The new run overwrites the state file, start it from scratch
+            std::fs::remove_file(&path).ok();
+            std::fs::remove_file(checkpoint::backup_path(&path)).ok();
+        }
+    }
+
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        eprintln!(
+            "Warning: Failed to create state directory {}: {e}, the run cannot be resumed",
+            dir.display()
+        );
+        return Ok(session);
+    }
+    Ok(session.with_progress_file(path, WriteCadence::Attempts(resume::SAVE_EVERY)))
+}
+
+/// Asks a yes/no question on the terminal, answering yes by default.
+fn confirm(question: &str) -> Result<bool> {
+    print!("{question}");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(
+        answer.trim().to_lowercase().as_str(),
+        "" | "y" | "yes"
+    ))
+}
+
+/// Cracks a single certificate, prints the result and returns its statistics.
+fn crack_single(
+    session: &CrackSession,
+    cracker: &dyn PasswordCracker,
+    pkcs12: &Arc<Pkcs12>,
+    args: &args::Args,
+    found_csv: Option<FoundCsv>,
+) -> Result<CrackStats> {
+    let live_counter = std::io::stdout()
+        .is_terminal()
+        .then(|| LiveCounter::spawn(session.result()));
+    let outcome = session.run(cracker, pkcs12);
+    if let Some(live_counter) = live_counter {
+        live_counter.stop();
+    }
+    outcome?;
+
+    let stats = session.stats();
+    if let Some(mut found_csv) = found_csv {
+        found_csv.write(&args.certificate_paths[0], &stats);
+    }
+    match args.output {
+        OutputFormat::Text => print_stats("", &stats),
+        OutputFormat::Json => println!("{}", serde_json::to_string(&stats)?),
+        // Printed by the caller once the output is restored
+        OutputFormat::BarePassword => {}
+    }
+    if args.save_to_keychain {
+        save_to_keychain(&args.certificate_paths[0], &stats);
+    }
+    if args.notify {
+        if let Some(password) = &stats.password {
+            notify::password_found(password);
+        }
+    }
+    send_webhook(&args.certificate_paths[0], &stats, args);
+    #[cfg(target_os = "windows")]
+    if let Some(store) = &args.import_windows_store {
+        import_to_windows_store(&args.certificate_paths[0], &stats, store);
+    }
+
+    Ok(stats)
+}
+
+/// Stores a recovered password in the Keychain for `--save-to-keychain`.
+///
+/// Failures are reported as warnings, the password has been printed already.
+///
+/// # Arguments
+///
+/// * `path` - Path to the certificate, its file name is used as the service name
+/// * `stats` - Statistics of the run
+fn save_to_keychain(path: &Path, stats: &CrackStats) {
+    let Some(password) = &stats.password else {
+        return;
+    };
+    let service = path
+        .file_name()
+        .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy());
+    match platform::save_to_keychain(&service, password) {
+        Ok(()) => eprintln!("Saved password to the Keychain as '{service}'"),
+        Err(e) => eprintln!("Warning: {e:#}"),
+    }
+}
+
+/// Posts the result of a certificate to the `--webhook`, if any.
+///
+/// Results without a password are only posted with `--webhook-on-not-found`.
+/// Failures are reported as warnings, the result has been printed already.
+///
+/// # Arguments
+///
+/// * `path` - Path to the certificate
+/// * `stats` - Statistics of the run
+/// * `args` - Command line arguments with the webhook options
+fn send_webhook(path: &Path, stats: &CrackStats, args: &args::Args) {
+    let Some(url) = &args.webhook else {
+        return;
     };
+    if !stats.found && !args.webhook_on_not_found {
+        return;
+    }
+    let cert = path.to_string_lossy();
+    if let Err(e) = notify::send_webhook(url, args.webhook_token.as_deref(), &cert, stats) {
+        eprintln!("Warning: {e:#}");
+    }
+}
 
-    println!("Starting password cracking...");
-    cracker.crack(&pkcs12, &result)?;
+/// Imports a cracked certificate into a Windows system store for `--import-windows-store`.
+///
+/// Failures are reported as warnings, the password has been printed already.
+///
+/// # Arguments
+///
+/// * `path` - Path to the certificate
+/// * `stats` - Statistics of the run
+/// * `store` - Name of the system store
+#[cfg(target_os = "windows")]
+fn import_to_windows_store(path: &Path, stats: &CrackStats, store: &str) {
+    let Some(password) = &stats.password else {
+        return;
+    };
+    match platform::windows::import_to_store(path, password, store) {
+        Ok(()) => eprintln!("Imported {} into the {store} store", path.display()),
+        Err(e) => eprintln!("Warning: {e:#}"),
+    }
+}
 
-    let final_result = result.lock().unwrap();
-    match &final_result.password {
-        Some(password) => println!("Successfully found password: {password}"),
-        None => println!("Password not found"),
+/// Prints the final statistics of a run as text.
+///
+/// # Arguments
+///
+/// * `prefix` - Prepended to every line, e.g. the certificate path
+/// * `stats` - Statistics of the run
+fn print_stats(prefix: &str, stats: &CrackStats) {
+    match &stats.password {
+        Some(password) if stats.reversed => {
+            println!("{prefix}Successfully found password: {password} (reversed candidate)")
+        }
+        Some(password) => println!("{prefix}Successfully found password: {password}"),
+        None => println!("{prefix}Password not found"),
     }
-    println!("Total attempts: {}", final_result.get_attempts());
+    if let Some(groups) = &stats.matched_groups {
+        let groups: Vec<String> = groups
+            .iter()
+            .enumerate()
+            .map(|(i, group)| format!("group {}: '{group}'", i + 1))
+            .collect();
+        println!("{prefix}Matched groups: {}", groups.join(", "));
+    }
+    println!("{prefix}Total attempts: {}", stats.total_attempts);
+    if stats.filtered > 0 {
+        println!(
+            "{prefix}Skipped by --filter-regex: {} (generated but not tested)",
+            stats.filtered
+        );
+    }
+    if stats.oversized > 0 {
+        println!(
+            "{prefix}Skipped by --max-bytes: {} (too long, not tested)",
+            stats.oversized
+        );
+    }
+    println!(
+        "{prefix}Elapsed: {:.2} seconds ({:.0} attempts/sec)",
+        stats.elapsed.as_secs_f64(),
+        stats.rate_per_sec
+    );
+}
 
+/// Cracks several certificates in a single pass and prints a result per certificate.
+///
+/// Passwords are written to the `--save-all-found` file as soon as they are found.
+fn crack_multiple(
+    session: &CrackSession,
+    cracker: &dyn PasswordCracker,
+    pkcs12s: &[Arc<Pkcs12>],
+    args: &args::Args,
+    mut found_csv: Option<FoundCsv>,
+) -> Result<()> {
+    let paths = &args.certificate_paths;
+    let results = session.run_many_reporting(cracker, pkcs12s, |index, stats| {
+        if let Some(found_csv) = &mut found_csv {
+            found_csv.write(&paths[index], stats);
+        }
+    })?;
+    let stats: Vec<CrackStats> = results
+        .iter()
+        .map(|result| result.lock().unwrap().to_stats())
+        .collect();
+    match args.output {
+        OutputFormat::Text => {
+            for (path, stats) in paths.iter().zip(&stats) {
+                print_stats(&format!("{}: ", path.display()), stats);
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string(&stats)?),
+        OutputFormat::BarePassword => unreachable!("--quiet-found supports a single certificate"),
+    }
+    if args.save_to_keychain {
+        for (path, stats) in paths.iter().zip(&stats) {
+            save_to_keychain(path, stats);
+        }
+    }
+    if args.notify {
+        for password in stats.iter().filter_map(|stats| stats.password.as_ref()) {
+            notify::password_found(password);
+        }
+    }
+    for (path, stats) in paths.iter().zip(&stats) {
+        send_webhook(path, stats, args);
+    }
+    #[cfg(target_os = "windows")]
+    if let Some(store) = &args.import_windows_store {
+        for (path, stats) in paths.iter().zip(&stats) {
+            import_to_windows_store(path, stats, store);
+        }
+    }
     Ok(())
 }
 
-/// Initializes the global thread pool for parallel processing.
+/// Attempt counter redrawn in place on the terminal while cracking.
+///
+/// Only used when stdout is a terminal, piped output gets the newline
+/// terminated `--status-interval` lines instead.
+struct LiveCounter {
+    /// Set by the main thread to stop the counter
+    shutdown: Arc<AtomicBool>,
+    /// Handle of the counter thread
+    handle: JoinHandle<()>,
+}
+
+impl LiveCounter {
+    /// Starts redrawing the attempt counter every `LIVE_COUNTER_INTERVAL`.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - Shared result holding the attempt counter
+    fn spawn(result: &Arc<Mutex<CrackResult>>) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shutdown = Arc::clone(&shutdown);
+            let result = Arc::clone(result);
+            thread::spawn(move || {
+                let mut last = (Instant::now(), 0usize);
+                while !shutdown.load(Ordering::Relaxed) {
+                    thread::sleep(Duration::from_millis(50));
+                    if last.0.elapsed() < LIVE_COUNTER_INTERVAL {
+                        continue;
+                    }
+
+                    let attempts = result.lock().unwrap().get_attempts();
+                    let rate =
+                        attempts.saturating_sub(last.1) as f64 / last.0.elapsed().as_secs_f64();
+                    last = (Instant::now(), attempts);
+                    print!("\r\x1b[2KAttempts: {attempts} ({rate:.0}/sec)");
+                    let _ = std::io::stdout().flush();
+                }
+                print!("\r\x1b[2K");
+                let _ = std::io::stdout().flush();
+            })
+        };
+        Self { shutdown, handle }
+    }
+
+    /// Stops the counter and clears its line.
+    fn stop(self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        let _ = self.handle.join();
+    }
+}
+
+/// Configures a Markov cracker from the command line arguments.
+fn markov_cracker(model: MarkovModel, args: &args::Args) -> MarkovCracker {
+    let cracker = MarkovCracker::new(model, args.markov_min_prob).with_work_unit(args.work_unit);
+    match args.chunk_size {
+        Some(chunk_size) => cracker.with_chunk_size(chunk_size as usize),
+        None => cracker,
+    }
+}
+
+/// Prints the estimated number of candidates of the cracker.
+fn print_estimate(cracker: &dyn PasswordCracker) {
+    match cracker.estimate_count() {
+        Some(count) => println!(
+            "Estimated candidates: {} ({} at {}M/s)",
+            keyspace::format_magnitude(count),
+            keyspace::format_duration(count, keyspace::REFERENCE_RATE),
+            keyspace::REFERENCE_RATE / 1_000_000
+        ),
+        None => println!("Estimated candidates: unknown"),
+    }
+}
+
+/// Runs the cracker against the certificate for a few seconds for `--estimate-time`.
+///
+/// The benchmark uses its own session with the same threads, filter, byte
+/// limit and case folds as the run, so neither the run's result nor its
+/// progress files see it.
 ///
 /// # Arguments
 ///
-/// * `args` - Command line arguments containing thread count
+/// * `cracker` - The configured cracking strategy
+/// * `pkcs12` - The certificate to crack
+/// * `args` - Command line arguments with the candidate limits
+/// * `threads` - Number of worker threads of the run
+/// * `secs` - Duration of the benchmark
+///
+/// # Returns
+///
+/// The achieved attempts per second
 ///
 /// # Errors
 ///
-/// Returns an error if thread pool initialization fails
-fn setup_thread_pool(args: &args::Args) -> Result<()> {
-    ThreadPoolBuilder::new()
-        .num_threads(args.threads as usize)
-        .build_global()
-        .context("Failed to build thread pool")
+/// Returns an error if the thread pool cannot be created or the cracker fails
+fn timed_benchmark(
+    cracker: &dyn PasswordCracker,
+    pkcs12: &Arc<Pkcs12>,
+    args: &args::Args,
+    threads: usize,
+    secs: u64,
+) -> Result<f64> {
+    let mut session = CrackSession::new(threads)?.with_time_limit(Duration::from_secs(secs));
+    if let Some(filter) = args.filter_regex.clone() {
+        session = session.with_filter(filter);
+    }
+    if let Some(max_bytes) = args.max_bytes {
+        session = session.with_max_bytes(max_bytes as usize);
+    }
+    if let Some(case_sensitivity) = args.case_insensitive {
+        session = session.with_case_sensitivity(case_sensitivity);
+    }
+    if args.also_reversed {
+        session = session.with_also_reversed();
+    }
+    if args.pre_hash {
+        session = session.with_pre_hash();
+    }
+    println!("Benchmarking the certificate for {secs} seconds...");
+    session.run(cracker, pkcs12)?;
+    Ok(session.stats().rate_per_sec)
+}
+
+/// Prints the time the whole search space takes at the benchmarked rate.
+fn print_time_estimate(cracker: &dyn PasswordCracker, rate: f64) {
+    match cracker.estimate_count() {
+        Some(count) if rate > 0.0 => println!(
+            "Benchmark: {rate:.0} attempts/sec, estimated {} for {} candidates",
+            keyspace::format_eta(count as f64 / rate),
+            keyspace::format_magnitude(count)
+        ),
+        Some(_) => println!("Benchmark: no attempts finished, the time cannot be estimated"),
+        None => println!("Benchmark: {rate:.0} attempts/sec, the number of candidates is unknown"),
+    }
 }
 
-/// Loads and parses a PKCS#12 certificate from file.
+/// Prints the MAC and encryption algorithms of the certificates for `--show-cert-info`.
+///
+/// Warns about certificates whose key derivation has fewer than
+/// [`cert_info::WEAK_ITERATIONS`] iterations.
 ///
 /// # Arguments
 ///
-/// * `args` - Command line arguments containing certificate path
+/// * `paths` - Paths of the certificates, `-` for stdin
 ///
 /// # Errors
 ///
-/// Returns an error if:
-/// - The certificate file cannot be opened
-/// - The file cannot be read
-/// - The PKCS#12 data is invalid
-fn load_certificate(args: &args::Args) -> Result<Arc<Pkcs12>> {
-    let mut cert_file = File::open(&args.certificate_path).with_context(|| {
-        format!(
-            "Failed to open certificate file: {}",
-            args.certificate_path.display()
-        )
-    })?;
+/// Returns an error if a certificate cannot be read or parsed
+fn print_cert_info(paths: &[PathBuf]) -> Result<()> {
+    for (i, path) in paths.iter().enumerate() {
+        let data = CertificateSource::from_path(path).read()?;
+        let metadata = cert_info::parse_pkcs12_metadata(&data)?;
+        if paths.len() > 1 {
+            if i > 0 {
+                println!();
+            }
+            println!("{}:", path.display());
+        }
+        println!("{metadata}");
+        if metadata.is_weak() {
+            eprintln!(
+                "Warning: the key derivation of {} has only {} iterations, fewer than {}, \
+                 so candidates are cheap to test",
+                path.display(),
+                metadata.iteration_count,
+                cert_info::WEAK_ITERATIONS
+            );
+        }
+    }
+    Ok(())
+}
 
-    let mut cert_data = Vec::with_capacity(TYPICAL_PKCS12_SIZE);
-    cert_file
-        .read_to_end(&mut cert_data)
-        .context("Failed to read certificate data")?;
+/// Prints the resolved character set for `--alphabet-test`.
+///
+/// The characters are sorted by codepoint, one per line as `U+0021 '!'`,
+/// followed by their number.
+///
+/// # Errors
+///
+/// Returns an error if the character set options are invalid
+fn print_alphabet(args: &args::Args) -> Result<()> {
+    let mut chars: Vec<char> = charset::build_charset(args)?.chars().collect();
+    chars.sort_unstable();
+    for c in &chars {
+        println!("U+{:04X} '{c}'", *c as u32);
+    }
+    println!("Total characters: {}", chars.len());
+    Ok(())
+}
 
-    Ok(Arc::new(
-        Pkcs12::from_der(&cert_data).context("Failed to parse PKCS12 data")?,
-    ))
+/// Prints the first candidates of the cracker for `--show-candidates`.
+///
+/// The candidates are generated on a single thread, so they come in
+/// traversal order, and are filtered like in the run; the certificate is
+/// not loaded.
+///
+/// # Arguments
+///
+/// * `cracker` - The configured cracking strategy
+/// * `args` - Command line arguments with the candidate filters
+/// * `count` - Number of candidates to print
+///
+/// # Errors
+///
+/// Returns an error if the session cannot be created or the cracker fails
+fn print_candidates(cracker: &dyn PasswordCracker, args: &args::Args, count: usize) -> Result<()> {
+    let mut session = CrackSession::new(1)?;
+    if let Some(filter) = args.filter_regex.clone() {
+        session = session.with_filter(filter);
+    }
+    if let Some(max_bytes) = args.max_bytes {
+        session = session.with_max_bytes(max_bytes as usize);
+    }
+    println!("Mode: {}", cracker.mode());
+    match cracker.estimate_count() {
+        Some(keyspace) => println!(
+            "Keyspace: {} candidates",
+            keyspace::format_magnitude(keyspace)
+        ),
+        None => println!("Keyspace: unknown"),
+    }
+    let candidates = session.preview(cracker, count)?;
+    println!("First {} candidates:", candidates.len());
+    for candidate in candidates {
+        println!("{candidate}");
+    }
+    Ok(())
+}
+
+/// Prints the search space and the `--work-unit` breakdown without cracking.
+///
+/// # Arguments
+///
+/// * `cracker` - The configured cracking strategy
+/// * `total_machines` - Number of machines to split the search space for
+fn print_dry_run(cracker: &dyn PasswordCracker, total_machines: Option<u64>) -> Result<()> {
+    let machines = total_machines.unwrap_or(1) as usize;
+    print_estimate(cracker);
+
+    match cracker.work_items()? {
+        Some(CombinationCount::Exact(items)) => {
+            println!("Search space: {items} work items");
+            for index in 0..machines {
+                let range = WorkUnit {
+                    index,
+                    total: machines,
+                }
+                .range(items);
+                println!(
+                    "  --work-unit {index}/{machines}: items {}..{} ({} items)",
+                    range.start,
+                    range.end,
+                    range.end - range.start
+                );
+            }
+        }
+        Some(CombinationCount::TooLarge) => {
+            println!(
+                "Search space: more than {} work items, too large to split into work units",
+                u128::MAX
+            );
+        }
+        None => {
+            println!("Search space: not known up front, candidates are dealt out round-robin");
+            for index in 0..machines {
+                println!(
+                    "  --work-unit {index}/{machines}: every candidate n with n % {machines} == {index}"
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects the patterns of `--pattern` or `--pattern-file`.
+///
+/// Malformed lines of a pattern file are reported with their line number and
+/// skipped. Declared symbols only have to occur in some of its patterns.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments with the pattern options
+/// * `charset` - Characters of the pattern symbol
+///
+/// # Errors
+///
+/// Returns an error if the pattern file cannot be read or has no valid pattern
+fn patterns(args: &args::Args, charset: &str) -> Result<Vec<String>> {
+    let Some(path) = &args.pattern_file else {
+        return Ok(args.pattern.clone().into_iter().collect());
+    };
+    let mut patterns = Vec::new();
+    for (line, pattern) in read_pattern_file(path)? {
+        let mut cracker =
+            PatternCracker::new(pattern.clone(), charset.to_string(), args.pattern_symbol);
+        if let Some(toggle) = args.toggle_case {
+            cracker = cracker.with_case_toggle(toggle);
+        }
+        if let Some(path) = &args.pattern_dict {
+            cracker = cracker.with_word_list(DictionaryCracker::new(
+                path.clone(),
+                DelimiterMode::SingleByte(b'\n'),
+            ));
+        }
+        for symbol in &args.symbols {
+            if pattern.contains(symbol.symbol) {
+                cracker = cracker.with_symbol(symbol.clone());
+            }
+        }
+        for symbol in &args.linked_symbols {
+            if pattern.contains(symbol.symbol) {
+                cracker = cracker.with_linked_symbol(symbol.clone());
+            }
+        }
+        match cracker.work_items() {
+            Ok(_) => patterns.push(pattern),
+            Err(e) => eprintln!("Warning: skipping line {line} of {}: {e:#}", path.display()),
+        }
+    }
+    if patterns.is_empty() {
+        bail!("No valid patterns in {}", path.display());
+    }
+    Ok(patterns)
+}
+
+/// Loads the `--probability-model` weights, if any.
+///
+/// Warns about charset characters the model has no weight for.
+///
+/// # Errors
+///
+/// Returns an error if the model is used without `--pattern` or
+/// `--brute-force`, or cannot be read
+fn probability_model(args: &args::Args) -> Result<Option<ProbabilityModel>> {
+    let Some(path) = &args.probability_model else {
+        return Ok(None);
+    };
+    if args.pattern.is_none() && args.pattern_file.is_none() && !args.bruteforce_flag {
+        bail!("--probability-model requires --pattern, --pattern-file or --brute-force");
+    }
+    let model = ProbabilityModel::load(path)?;
+    let charset: Vec<char> = charset::build_charset(args)?.chars().collect();
+    let missing = model.missing(&charset);
+    if !missing.is_empty() {
+        eprintln!(
+            "Warning: the probability model has no weight for {}, using its smallest weight",
+            missing.iter().collect::<String>()
+        );
+    }
+    Ok(Some(model))
+}
+
+/// Resolves the seed for `--random-order`.
+///
+/// Falls back to a seed derived from the current time when `--seed` is not given,
+/// and prints it so that the run can be reproduced.
+///
+/// # Returns
+///
+/// Returns `None` if random ordering is disabled.
+fn random_seed(args: &args::Args) -> Option<u64> {
+    if !args.random_order {
+        return None;
+    }
+
+    let seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+    println!("Using random candidate order with seed {seed}");
+    Some(seed)
 }
@@ -0,0 +1,205 @@
+//! Notifications when a run completes.
+//!
+//! `--notify` announces a found password through the desktop notification
+//! system of the platform, with the `notifications` feature: the D-Bus
+//! notification spec on Linux, the Notification Center on macOS and toast
+//! notifications on Windows. Without the feature, or when no notification
+//! service is running (e.g. no D-Bus session over SSH), the terminal bell
+//! rings instead.
+//!
+//! `--webhook` posts the result of every certificate as JSON to a URL, e.g.
+//! a Slack incoming webhook or a job tracker of a distributed run.
+use crate::types::CrackStats;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::time::Duration;
+
+/// Summary line of every notification
+const SUMMARY: &str = "pkcs12cracker";
+
+/// Time allowed for a webhook request, including the connection
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// JSON body of a webhook request.
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    /// Whether the password was found
+    found: bool,
+    /// The password, if found
+    password: Option<&'a str>,
+    /// Path of the certificate
+    cert: &'a str,
+    /// Number of candidates tried
+    attempts: usize,
+    /// Duration of the run in seconds
+    elapsed_secs: f64,
+}
+
+/// Posts the result of a certificate to a webhook.
+///
+/// # Arguments
+///
+/// * `url` - URL to post to
+/// * `token` - Sent as a `Bearer` token in the `Authorization` header, if any
+/// * `cert` - Path of the certificate, as given on the command line
+/// * `stats` - Statistics of the run
+///
+/// # Errors
+///
+/// Returns an error if the request fails or the server does not respond
+/// with a success status
+pub fn send_webhook(url: &str, token: Option<&str>, cert: &str, stats: &CrackStats) -> Result<()> {
+    let payload = WebhookPayload {
+        found: stats.found,
+        password: stats.password.as_deref(),
+        cert,
+        attempts: stats.total_attempts,
+        elapsed_secs: stats.elapsed.as_secs_f64(),
+    };
+    let body = serde_json::to_string(&payload).context("Failed to serialize webhook payload")?;
+    let mut request = ureq::post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .set("Content-Type", "application/json");
+    if let Some(token) = token {
+        request = request.set("Authorization", &format!("Bearer {token}"));
+    }
+    request
+        .send_string(&body)
+        .with_context(|| format!("Failed to post the result to webhook {url}"))?;
+    Ok(())
+}
+
+/// Announces a found password, falling back to the terminal bell.
+///
+/// Failures are reported as warnings, the password has been printed already.
+///
+/// # Arguments
+///
+/// * `password` - The recovered password
+pub fn password_found(password: &str) {
+    if let Err(e) = show(&format!("Found: {password}")) {
+        eprintln!("Warning: {e:#}, ringing the terminal bell instead");
+        ring_bell(&mut std::io::stderr());
+    }
+}
+
+/// Shows a desktop notification.
+///
+/// # Errors
+///
+/// Returns an error if the notification service is unavailable.
+#[cfg(feature = "notifications")]
+fn show(body: &str) -> Result<()> {
+    notify_rust::Notification::new()
+        .summary(SUMMARY)
+        .body(body)
+        .show()
+        .map(drop)
+        .context("Failed to show a desktop notification")
+}
+
+/// Shows a desktop notification.
+///
+/// # Errors
+///
+/// Always returns an error, desktop notifications need the `notifications` feature.
+#[cfg(not(feature = "notifications"))]
+fn show(_body: &str) -> Result<()> {
+    anyhow::bail!("{SUMMARY} was built without the notifications feature")
+}
+
+/// Rings the terminal bell.
+///
+/// Written to stderr by [`password_found`] so that `--output json` stays
+/// parseable. Errors are ignored, there is nothing left to fall back to.
+fn ring_bell(out: &mut impl Write) {
+    let _ = out.write_all(b"\x07").and_then(|()| out.flush());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_bell() {
+        let mut out = Vec::new();
+        ring_bell(&mut out);
+        assert_eq!(out, b"\x07");
+    }
+
+    fn stats(password: Option<&str>) -> CrackStats {
+        CrackStats {
+            total_attempts: 42,
+            elapsed: Duration::from_millis(1500),
+            rate_per_sec: 28.0,
+            found: password.is_some(),
+            password: password.map(str::to_string),
+            found_at: password.map(|_| Duration::from_millis(1200)),
+            reversed: false,
+            matched_groups: None,
+            mode: crate::types::CrackMode::Pattern,
+            filtered: 0,
+            oversized: 0,
+        }
+    }
+
+    #[test]
+    fn test_send_webhook() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/hook")
+            .match_header("content-type", "application/json")
+            .match_header("authorization", "Bearer s3cret")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "found": true,
+                "password": "pä55",
+                "cert": "certs/umlaut.p12",
+                "attempts": 42,
+                "elapsed_secs": 1.5,
+            })))
+            .create();
+
+        let url = format!("{}/hook", server.url());
+        send_webhook(
+            &url,
+            Some("s3cret"),
+            "certs/umlaut.p12",
+            &stats(Some("pä55")),
+        )
+        .unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_send_webhook_not_found() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "found": false,
+                "password": null,
+            })))
+            .create();
+
+        send_webhook(&server.url(), None, "cert.p12", &stats(None)).unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_send_webhook_error_status() {
+        let mut server = mockito::Server::new();
+        server.mock("POST", "/").with_status(500).create();
+
+        let error = send_webhook(&server.url(), None, "cert.p12", &stats(None)).unwrap_err();
+        assert!(format!("{error:#}").contains("500"), "{error:#}");
+    }
+
+    #[cfg(not(feature = "notifications"))]
+    #[test]
+    fn test_show_needs_feature() {
+        let error = show("Found: secret").unwrap_err();
+        assert!(error.to_string().contains("notifications feature"));
+    }
+}
@@ -0,0 +1,42 @@
+//! macOS integrations.
+use anyhow::{bail, Context, Result};
+use std::process::Command;
+
+/// Stores a recovered password in the user's default keychain.
+///
+/// Runs `security add-generic-password`, updating the item if one with the
+/// same service and account already exists. The account is the current user.
+/// `security` only takes the password as an argument, so it is visible in the
+/// process list of other local users while the command runs.
+///
+/// # Arguments
+///
+/// * `service` - Service name of the keychain item, e.g. the certificate file name
+/// * `password` - The recovered password
+///
+/// # Errors
+///
+/// Returns an error if the `security` command cannot be run or fails.
+pub fn save_to_keychain(service: &str, password: &str) -> Result<()> {
+    let account = std::env::var("USER").unwrap_or_else(|_| "pkcs12cracker".to_string());
+    let output = Command::new("security")
+        .args([
+            "add-generic-password",
+            "-U",
+            "-a",
+            &account,
+            "-s",
+            service,
+            "-w",
+            password,
+        ])
+        .output()
+        .context("Failed to run the security command")?;
+    if !output.status.success() {
+        bail!(
+            "security add-generic-password failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
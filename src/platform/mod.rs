@@ -0,0 +1,98 @@
+//! Integrations with the operating system.
+//!
+//! Platform specific features live in one submodule per platform. The
+//! Keychain has a fallback on other platforms that returns an error, the
+//! Windows certificate store import only exists in Windows builds.
+use anyhow::{bail, Result};
+use std::ffi::CStr;
+use std::io::Write;
+
+#[cfg(target_os = "macos")]
+pub mod macos;
+#[cfg(target_os = "windows")]
+pub mod windows;
+
+#[cfg(target_os = "macos")]
+pub use macos::save_to_keychain;
+
+/// Stores a recovered password in the user's keychain.
+///
+/// # Errors
+///
+/// Always returns an error, the Keychain is only available on macOS.
+#[cfg(not(target_os = "macos"))]
+pub fn save_to_keychain(_service: &str, _password: &str) -> anyhow::Result<()> {
+    anyhow::bail!("--save-to-keychain is only available on macOS, the password was not saved")
+}
+
+/// Descriptors of standard output and standard error
+const OUTPUT_FDS: [libc::c_int; 2] = [1, 2];
+
+#[cfg(not(windows))]
+const NULL_DEVICE: &CStr = c"/dev/null";
+#[cfg(windows)]
+const NULL_DEVICE: &CStr = c"NUL";
+
+/// Standard output and standard error redirected to the null device.
+///
+/// The original descriptors are restored when the value is dropped. The
+/// redirection also silences output printed by the crackers, which write to
+/// stdout directly.
+pub struct SilencedOutput {
+    /// Duplicates of the original stdout and stderr descriptors
+    saved: [libc::c_int; 2],
+}
+
+/// Redirects standard output and standard error to the null device.
+///
+/// # Errors
+///
+/// Returns an error if the null device cannot be opened or a descriptor
+/// cannot be duplicated
+pub fn silence_output() -> Result<SilencedOutput> {
+    flush_output();
+    // SAFETY: the path is a valid C string, the descriptors are owned here
+    unsafe {
+        let null = libc::open(NULL_DEVICE.as_ptr(), libc::O_WRONLY);
+        if null < 0 {
+            bail!(
+                "Failed to open {}: {}",
+                NULL_DEVICE.to_string_lossy(),
+                std::io::Error::last_os_error()
+            );
+        }
+        let mut silenced = SilencedOutput { saved: [-1; 2] };
+        for (index, fd) in OUTPUT_FDS.into_iter().enumerate() {
+            silenced.saved[index] = libc::dup(fd);
+            if silenced.saved[index] < 0 || libc::dup2(null, fd) < 0 {
+                let error = std::io::Error::last_os_error();
+                libc::close(null);
+                // Dropping restores the descriptors redirected so far
+                bail!("Failed to redirect the output: {error}");
+            }
+        }
+        libc::close(null);
+        Ok(silenced)
+    }
+}
+
+impl Drop for SilencedOutput {
+    fn drop(&mut self) {
+        flush_output();
+        for (fd, saved) in OUTPUT_FDS.iter().zip(self.saved) {
+            if saved >= 0 {
+                // SAFETY: `saved` is a duplicate owned by this value
+                unsafe {
+                    libc::dup2(saved, *fd);
+                    libc::close(saved);
+                }
+            }
+        }
+    }
+}
+
+/// Writes out output still buffered for the current descriptors.
+fn flush_output() {
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+}
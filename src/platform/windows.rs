@@ -0,0 +1,117 @@
+//! Windows integrations.
+use anyhow::{bail, Context, Result};
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::path::Path;
+use std::ptr;
+use winapi::um::errhandlingapi::GetLastError;
+use winapi::um::wincrypt::{
+    CertAddCertificateContextToStore, CertCloseStore, CertEnumCertificatesInStore,
+    CertFreeCertificateContext, CertOpenSystemStoreW, PFXImportCertStore, CERT_CONTEXT,
+    CERT_STORE_ADD_REPLACE_EXISTING, CRYPT_DATA_BLOB, CRYPT_EXPORTABLE, CRYPT_USER_PROTECTED,
+    HCERTSTORE,
+};
+
+/// Closes a certificate store when dropped.
+struct Store(HCERTSTORE);
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        // SAFETY: the handle was returned by a successful open and is closed once
+        unsafe { CertCloseStore(self.0, 0) };
+    }
+}
+
+/// Converts a string to a NUL terminated UTF-16 string for the Win32 API.
+fn wide(text: &str) -> Vec<u16> {
+    OsStr::new(text).encode_wide().chain(Some(0)).collect()
+}
+
+/// Imports a PKCS#12 file into a system certificate store of the current user.
+///
+/// Private keys are imported as exportable and user protected, so Windows
+/// asks for confirmation whenever an application uses them.
+///
+/// # Arguments
+///
+/// * `pfx_path` - Path to the PKCS#12 file
+/// * `password` - The recovered password
+/// * `store` - Name of the system store, e.g. "MY" or "ROOT"
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, the import fails or a
+/// certificate cannot be added to the store.
+pub fn import_to_store(pfx_path: &Path, password: &str, store: &str) -> Result<()> {
+    let mut pfx = std::fs::read(pfx_path)
+        .with_context(|| format!("Failed to read certificate file: {}", pfx_path.display()))?;
+    let mut blob = CRYPT_DATA_BLOB {
+        cbData: u32::try_from(pfx.len()).context("Certificate file is too large")?,
+        pbData: pfx.as_mut_ptr(),
+    };
+    let password = wide(password);
+    let store_name = wide(store);
+
+    // SAFETY: `blob` points into `pfx` and both wide strings are NUL
+    // terminated, all of them outlive the calls. Every store handle is closed
+    // by `Store`, every context enumerated is either passed back to
+    // `CertEnumCertificatesInStore`, which frees it, or freed explicitly.
+    unsafe {
+        let imported = PFXImportCertStore(
+            &mut blob,
+            password.as_ptr(),
+            CRYPT_EXPORTABLE | CRYPT_USER_PROTECTED,
+        );
+        if imported.is_null() {
+            bail!("PFXImportCertStore failed with error {:#x}", GetLastError());
+        }
+        let imported = Store(imported);
+
+        let target = CertOpenSystemStoreW(0, store_name.as_ptr());
+        if target.is_null() {
+            bail!(
+                "Failed to open the {store} certificate store, error {:#x}",
+                GetLastError()
+            );
+        }
+        let target = Store(target);
+
+        let mut context: *const CERT_CONTEXT = ptr::null();
+        let mut count = 0;
+        loop {
+            context = CertEnumCertificatesInStore(imported.0, context);
+            if context.is_null() {
+                break;
+            }
+            let added = CertAddCertificateContextToStore(
+                target.0,
+                context,
+                CERT_STORE_ADD_REPLACE_EXISTING,
+                ptr::null_mut(),
+            );
+            if added == 0 {
+                let error = GetLastError();
+                CertFreeCertificateContext(context);
+                bail!("Failed to add a certificate to the {store} store, error {error:#x}");
+            }
+            count += 1;
+        }
+        if count == 0 {
+            bail!("The certificate file does not contain any certificates");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::fixture_path;
+
+    #[test]
+    fn test_import_wrong_password() {
+        let path = fixture_path("umlaut.p12");
+        let error = import_to_store(Path::new(&path), "wrong", "MY").unwrap_err();
+        assert!(error.to_string().contains("PFXImportCertStore failed"));
+    }
+}
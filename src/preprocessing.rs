@@ -0,0 +1,402 @@
+//! Word list preprocessing.
+//!
+//! User-provided word lists are rarely sorted the way `rockyou.txt` is, with
+//! the most likely passwords first. [`sort_wordlist`] reorders a list that
+//! fits into memory, [`sort_wordlist_external`] does the same with sorted
+//! runs spilled to temporary files and merged afterwards.
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom, Write};
+
+/// Order a word list is sorted in before cracking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SortOrder {
+    /// Most frequent words first, duplicates removed
+    Frequency,
+    /// Lexicographic order of the words
+    Alphabetical,
+    /// Shortest words first
+    Length,
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Frequency => "frequency",
+            Self::Alphabetical => "alphabetical",
+            Self::Length => "length",
+        })
+    }
+}
+
+/// Sorts a word list in memory.
+///
+/// Every order is stable, ties keep the order of the original list. For
+/// [`SortOrder::Frequency`] the list is de-duplicated first, the number of
+/// occurrences of a word being its frequency.
+///
+/// # Arguments
+///
+/// * `words` - Words in the order of the word list
+/// * `order` - Order to sort the words in
+pub fn sort_wordlist(mut words: Vec<String>, order: SortOrder) -> Vec<String> {
+    match order {
+        SortOrder::Frequency => {
+            // Count and first position of every word
+            let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+            for (index, word) in words.into_iter().enumerate() {
+                counts.entry(word).or_insert((0, index)).0 += 1;
+            }
+            let mut counted: Vec<(String, (usize, usize))> = counts.into_iter().collect();
+            counted.sort_unstable_by_key(|&(_, (count, first))| (Reverse(count), first));
+            counted.into_iter().map(|(word, _)| word).collect()
+        }
+        SortOrder::Alphabetical => {
+            words.sort();
+            words
+        }
+        SortOrder::Length => {
+            words.sort_by_key(|word| word.chars().count());
+            words
+        }
+    }
+}
+
+/// Sorts a word list that does not fit into memory and writes it to `out`.
+///
+/// Produces the same order as [`sort_wordlist`]. At most about `chunk_bytes`
+/// of words are held in memory, sorted and spilled to a temporary file at a
+/// time, the files are merged afterwards. Frequency order needs two passes:
+/// one grouping equal words to count them and one ordering them by count.
+///
+/// # Arguments
+///
+/// * `words` - Words in the order of the word list
+/// * `order` - Order to sort the words in
+/// * `chunk_bytes` - Approximate memory budget for a sorted run
+/// * `delimiter` - Written after every word but the last
+/// * `out` - Destination of the sorted word list
+///
+/// # Errors
+///
+/// Returns an error if a temporary file or `out` cannot be written.
+pub fn sort_wordlist_external(
+    words: impl Iterator<Item = String>,
+    order: SortOrder,
+    chunk_bytes: usize,
+    delimiter: &str,
+    out: impl Write,
+) -> Result<()> {
+    let indexed = words
+        .enumerate()
+        .map(|(index, word)| Ok((word, index as u64)));
+    let mut out = WordWriter::new(out, delimiter);
+
+    match order {
+        SortOrder::Alphabetical => {
+            let runs = spill_runs(indexed, chunk_bytes, |(word, index)| (word.clone(), *index))?;
+            for entry in merge_runs(runs, |(word, index)| (word.clone(), *index)) {
+                out.write(&entry?.0)?;
+            }
+        }
+        SortOrder::Length => {
+            let key = |(word, index): &(String, u64)| (word.chars().count(), *index);
+            let runs = spill_runs(indexed, chunk_bytes, key)?;
+            for entry in merge_runs(runs, key) {
+                out.write(&entry?.0)?;
+            }
+        }
+        SortOrder::Frequency => {
+            let by_word = |(word, index): &(String, u64)| (word.clone(), *index);
+            let runs = spill_runs(indexed, chunk_bytes, by_word)?;
+            let counted = CountEqual {
+                entries: merge_runs(runs, by_word),
+                pending: None,
+            };
+            // (word, count, first position), most frequent first
+            let by_count = |(_, count, first): &(String, u64, u64)| (Reverse(*count), *first);
+            let runs = spill_runs(counted, chunk_bytes, by_count)?;
+            for entry in merge_runs(runs, by_count) {
+                out.write(&entry?.0)?;
+            }
+        }
+    }
+    out.finish()
+}
+
+/// Writes words separated by a delimiter.
+struct WordWriter<'a, W: Write> {
+    out: BufWriter<W>,
+    delimiter: &'a str,
+    first: bool,
+}
+
+impl<'a, W: Write> WordWriter<'a, W> {
+    fn new(out: W, delimiter: &'a str) -> Self {
+        Self {
+            out: BufWriter::new(out),
+            delimiter,
+            first: true,
+        }
+    }
+
+    fn write(&mut self, word: &str) -> Result<()> {
+        if !self.first {
+            write!(self.out, "{}", self.delimiter)?;
+        }
+        self.first = false;
+        self.out
+            .write_all(word.as_bytes())
+            .context("Failed to write sorted word list")
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.out.flush().context("Failed to write sorted word list")
+    }
+}
+
+/// Records that take part in an external sort.
+trait Record: Serialize + DeserializeOwned {
+    /// Approximate memory held by the record
+    fn size(&self) -> usize;
+}
+
+impl Record for (String, u64) {
+    fn size(&self) -> usize {
+        self.0.len() + std::mem::size_of::<Self>()
+    }
+}
+
+impl Record for (String, u64, u64) {
+    fn size(&self) -> usize {
+        self.0.len() + std::mem::size_of::<Self>()
+    }
+}
+
+/// Sorts batches of about `chunk_bytes` records and writes each to its own
+/// temporary file, which is deleted once its reader is dropped.
+fn spill_runs<T: Record, K: Ord>(
+    records: impl Iterator<Item = Result<T>>,
+    chunk_bytes: usize,
+    key: impl Fn(&T) -> K,
+) -> Result<Vec<RunReader<T>>> {
+    let mut runs = Vec::new();
+    let mut batch = Vec::new();
+    let mut batch_bytes = 0;
+    let mut spill = |batch: &mut Vec<T>| -> Result<()> {
+        batch.sort_by_key(&key);
+        let mut file = tempfile::tempfile().context("Failed to create a sorted run")?;
+        let mut writer = BufWriter::new(&mut file);
+        for record in batch.drain(..) {
+            bincode::serialize_into(&mut writer, &record).context("Failed to write sorted run")?;
+        }
+        writer.flush().context("Failed to write sorted run")?;
+        drop(writer);
+        file.seek(SeekFrom::Start(0))
+            .context("Failed to read sorted run")?;
+        runs.push(RunReader::new(file));
+        Ok(())
+    };
+    for record in records {
+        let record = record?;
+        batch_bytes += record.size();
+        batch.push(record);
+        if batch_bytes >= chunk_bytes {
+            spill(&mut batch)?;
+            batch_bytes = 0;
+        }
+    }
+    if !batch.is_empty() {
+        spill(&mut batch)?;
+    }
+    Ok(runs)
+}
+
+/// Reads the records of a sorted run back.
+struct RunReader<T> {
+    reader: BufReader<File>,
+    _record: std::marker::PhantomData<T>,
+}
+
+impl<T> RunReader<T> {
+    fn new(file: File) -> Self {
+        Self {
+            reader: BufReader::new(file),
+            _record: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for RunReader<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match bincode::deserialize_from(&mut self.reader) {
+            Ok(record) => Some(Ok(record)),
+            Err(e) => match *e {
+                bincode::ErrorKind::Io(ref io)
+                    if io.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    None
+                }
+                _ => Some(Err(e).context("Failed to read sorted run")),
+            },
+        }
+    }
+}
+
+/// Head of a run in the merge heap, the smallest key is popped first.
+struct Head<K, T> {
+    key: K,
+    run: usize,
+    record: T,
+}
+
+impl<K: Ord, T> Ord for Head<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&other.key, other.run).cmp(&(&self.key, self.run))
+    }
+}
+
+impl<K: Ord, T> PartialOrd for Head<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> PartialEq for Head<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: Ord, T> Eq for Head<K, T> {}
+
+/// Merges sorted runs into a single sorted sequence.
+fn merge_runs<T: Record, K: Ord>(
+    mut runs: Vec<RunReader<T>>,
+    key: impl Fn(&T) -> K,
+) -> impl Iterator<Item = Result<T>> {
+    let mut heap = BinaryHeap::new();
+    let mut error = None;
+    for (run, reader) in runs.iter_mut().enumerate() {
+        match reader.next() {
+            Some(Ok(record)) => heap.push(Head {
+                key: key(&record),
+                run,
+                record,
+            }),
+            Some(Err(e)) => error = Some(e),
+            None => {}
+        }
+    }
+    std::iter::from_fn(move || {
+        if let Some(e) = error.take() {
+            return Some(Err(e));
+        }
+        let head = heap.pop()?;
+        match runs[head.run].next() {
+            Some(Ok(record)) => heap.push(Head {
+                key: key(&record),
+                run: head.run,
+                record,
+            }),
+            Some(Err(e)) => error = Some(e),
+            None => {}
+        }
+        Some(Ok(head.record))
+    })
+}
+
+/// Groups equal adjacent words into (word, count, first position).
+struct CountEqual<I> {
+    entries: I,
+    pending: Option<(String, u64, u64)>,
+}
+
+impl<I: Iterator<Item = Result<(String, u64)>>> Iterator for CountEqual<I> {
+    type Item = Result<(String, u64, u64)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.entries.next() {
+                Some(Ok((word, index))) => match &mut self.pending {
+                    Some((pending, count, _)) if *pending == word => *count += 1,
+                    pending => {
+                        if let Some(done) = pending.replace((word, 1, index)) {
+                            return Some(Ok(done));
+                        }
+                    }
+                },
+                Some(Err(e)) => return Some(Err(e)),
+                None => return self.pending.take().map(Ok),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(list: &str) -> Vec<String> {
+        list.split(' ').map(str::to_string).collect()
+    }
+
+    fn external(list: &str, order: SortOrder, chunk_bytes: usize) -> Vec<String> {
+        let mut out = Vec::new();
+        sort_wordlist_external(words(list).into_iter(), order, chunk_bytes, "\n", &mut out)
+            .unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .split('\n')
+            .map(str::to_string)
+            .collect()
+    }
+
+    const LIST: &str = "pear apple kiwi apple fig pear apple banana kiwi fig";
+
+    #[test]
+    fn test_sort_by_frequency() {
+        let sorted = sort_wordlist(words(LIST), SortOrder::Frequency);
+        assert_eq!(sorted, words("apple pear kiwi fig banana"));
+    }
+
+    #[test]
+    fn test_sort_alphabetical_and_length() {
+        assert_eq!(
+            sort_wordlist(words("pear fig apple fig"), SortOrder::Alphabetical),
+            words("apple fig fig pear")
+        );
+        assert_eq!(
+            sort_wordlist(words("pear fig apple kiwi"), SortOrder::Length),
+            words("fig pear kiwi apple")
+        );
+    }
+
+    #[test]
+    fn test_external_sort_matches_in_memory() {
+        for order in [
+            SortOrder::Frequency,
+            SortOrder::Alphabetical,
+            SortOrder::Length,
+        ] {
+            let expected = sort_wordlist(words(LIST), order);
+            // A budget of one byte spills every word to its own run
+            for chunk_bytes in [1, 100, usize::MAX] {
+                assert_eq!(external(LIST, order, chunk_bytes), expected, "{order}");
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Periodic progress reporting.
+//!
+//! Crackers publish what they are working on in [`Progress`], a background
+//! [`ProgressReporter`] samples the shared attempt counter every second and
+//! prints a status line with the rate of the last [`RATE_WINDOW`] every
+//! `--status-interval` seconds.
+use crate::keyspace::{format_duration, format_magnitude};
+use crate::types::CrackResult;
+use std::collections::VecDeque;
+use std::io::IsTerminal;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Time between two samples of the attempt counter
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Window the reported attempts per second are averaged over
+pub const RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// A part of the search space with a known size, e.g. one brute force length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressStage {
+    /// Name shown in the status line
+    pub label: String,
+    /// Number of candidates in the stage
+    pub total: u128,
+    /// Attempt counter when the stage started
+    pub start_attempts: usize,
+}
+
+/// Progress published by the running cracker.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of candidates of the whole run, if known
+    pub total: Option<u128>,
+    /// Stage currently being processed, if any
+    pub stage: Option<ProgressStage>,
+    /// Sample candidate of the region currently being tested, if published
+    pub region: Option<String>,
+}
+
+/// Formats a status line.
+///
+/// # Arguments
+///
+/// * `attempts` - Candidates tried so far
+/// * `rate` - Current attempts per second
+/// * `progress` - Progress published by the cracker
+pub fn format_status(attempts: usize, rate: f64, progress: &Progress) -> String {
+    let mut line = format!(
+        "Status: {} attempts, {}/s",
+        format_magnitude(attempts as u128),
+        format_magnitude(rate as u128)
+    );
+    if let Some(stage) = &progress.stage {
+        let done = attempts.saturating_sub(stage.start_attempts) as u128;
+        line.push_str(&format!(
+            " | {}: {}",
+            stage.label,
+            format_share(done, stage.total, rate)
+        ));
+    }
+    if let Some(total) = progress.total {
+        line.push_str(&format!(
+            " | total: {}",
+            format_share(attempts as u128, total, rate)
+        ));
+    }
+    if let Some(region) = &progress.region {
+        line.push_str(&format!(" | at {region:?}"));
+    }
+    line
+}
+
+/// Attempts per second over a sliding window of recent samples.
+#[derive(Debug)]
+pub struct RateWindow {
+    /// Length of the window
+    window: Duration,
+    /// Samples of the attempt counter, oldest first
+    samples: VecDeque<(Instant, usize)>,
+}
+
+impl RateWindow {
+    /// Creates an empty window.
+    ///
+    /// # Arguments
+    ///
+    /// * `window` - Only samples this recent are used for the rate
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Records a sample of the attempt counter and returns the rate over the window.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - Time of the sample
+    /// * `attempts` - Attempt counter at `now`
+    pub fn record(&mut self, now: Instant, attempts: usize) -> f64 {
+        // Keep the newest sample older than the window, it starts the window
+        while self.samples.len() > 1 && now.duration_since(self.samples[1].0) >= self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, attempts));
+
+        let (start, start_attempts) = self.samples[0];
+        let elapsed = now.duration_since(start).as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0.0;
+        }
+        attempts.saturating_sub(start_attempts) as f64 / elapsed
+    }
+}
+
+/// Formats "41.3% of 3.5e12, ETA ~6.2 hours" for `done` out of `total`.
+fn format_share(done: u128, total: u128, rate: f64) -> String {
+    let done = done.min(total);
+    let percent = if total == 0 {
+        100.0
+    } else {
+        done as f64 * 100.0 / total as f64
+    };
+    let eta = if rate < 1.0 {
+        "unknown".to_string()
+    } else {
+        format_duration(total - done, rate as u128)
+    };
+    format!("{percent:.1}% of {}, ETA {eta}", format_magnitude(total))
+}
+
+/// Prints a full line of output.
+///
+/// On a terminal the current line is cleared first, so that the line does not
+/// get mixed up with an in-place attempt counter.
+pub fn print_line(line: &str) {
+    if std::io::stdout().is_terminal() {
+        println!("\r\x1b[2K{line}");
+    } else {
+        println!("{line}");
+    }
+}
+
+/// Background thread printing a status line at a fixed interval.
+pub struct ProgressReporter {
+    /// Dropping or sending on this channel stops the thread
+    stop: Sender<()>,
+    /// Handle of the reporting thread
+    handle: JoinHandle<()>,
+}
+
+impl ProgressReporter {
+    /// Starts reporting the progress of a run.
+    ///
+    /// # Arguments
+    ///
+    /// * `result` - Shared result the cracker counts attempts and publishes progress in
+    /// * `interval` - Time between two status lines
+    pub fn spawn(result: Arc<Mutex<CrackResult>>, interval: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let handle = thread::spawn(move || {
+            let mut rates = RateWindow::new(RATE_WINDOW);
+            rates.record(Instant::now(), 0);
+            let mut last_print = Instant::now();
+            let sample_interval = SAMPLE_INTERVAL.min(interval);
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(sample_interval) {
+                let (attempts, progress) = {
+                    let guard = result.lock().unwrap();
+                    (guard.get_attempts(), guard.progress.clone())
+                };
+                let rate = rates.record(Instant::now(), attempts);
+                if last_print.elapsed() >= interval {
+                    last_print = Instant::now();
+                    print_line(&format_status(attempts, rate, &progress));
+                }
+            }
+        });
+        Self { stop, handle }
+    }
+
+    /// Stops the reporting thread and waits for it to exit.
+    pub fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.handle.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_status_without_progress() {
+        assert_eq!(
+            format_status(1500, 300.0, &Progress::default()),
+            "Status: 1500 attempts, 300/s"
+        );
+    }
+
+    #[test]
+    fn test_format_status_with_stage() {
+        let progress = Progress {
+            total: Some(4_000_000),
+            stage: Some(ProgressStage {
+                label: "length 7".to_string(),
+                total: 2_000_000,
+                start_attempts: 1_000_000,
+            }),
+            region: Some("k9Qa".to_string()),
+        };
+        assert_eq!(
+            format_status(2_000_000, 1000.0, &progress),
+            "Status: 2.0e6 attempts, 1000/s | length 7: 50.0% of 2.0e6, ETA ~16.7 minutes \
+             | total: 50.0% of 4.0e6, ETA ~33.3 minutes | at \"k9Qa\""
+        );
+    }
+
+    #[test]
+    fn test_rate_window() {
+        let start = Instant::now();
+        let mut rates = RateWindow::new(Duration::from_secs(10));
+        assert_eq!(rates.record(start, 0), 0.0);
+        assert_eq!(rates.record(start + Duration::from_secs(5), 5000), 1000.0);
+        assert_eq!(rates.record(start + Duration::from_secs(10), 6000), 600.0);
+        // The first five seconds dropped out of the window
+        assert_eq!(rates.record(start + Duration::from_secs(15), 6000), 100.0);
+        assert_eq!(rates.record(start + Duration::from_secs(25), 6000), 0.0);
+    }
+
+    #[test]
+    fn test_format_share_idle() {
+        assert_eq!(format_share(0, 10, 0.0), "0.0% of 10, ETA unknown");
+        assert_eq!(format_share(0, 0, 5.0), "100.0% of 0, ETA <1 second");
+    }
+}
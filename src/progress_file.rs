@@ -0,0 +1,469 @@
+//! Lightweight progress files for crash-safe resumption.
+//!
+//! Unlike a brute force session, a progress file only records a single
+//! position below which every candidate has been tested: the length and
+//! combination index for brute force, the combination index for patterns and
+//! the byte offset for dictionaries. It is written at the
+//! `--progress-save-every` cadence and a run of the same certificate skips
+//! forward to the saved position on startup.
+use crate::checkpoint::{
+    backup_path, certificate_fingerprint, load_json, write_atomic, ProgressTracker, WriteCadence,
+    FORMAT_VERSION, PATTERN_ORDER_VERSION,
+};
+use anyhow::{Context, Result};
+use openssl::pkcs12::Pkcs12;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Position in the search space below which every candidate has been tested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Position {
+    /// Every length before `length` in traversal order, and every position
+    /// of `length` below `index`
+    BruteForce { length: u8, index: u128 },
+    /// Every combination position below `index`
+    Pattern { index: u128 },
+    /// Every dictionary chunk before the byte `offset` of the file
+    Dictionary { offset: u64 },
+}
+
+impl Position {
+    /// Index in the current dimension, i.e. the combination index or byte offset.
+    pub fn index(self) -> u128 {
+        match self {
+            Self::BruteForce { index, .. } | Self::Pattern { index } => index,
+            Self::Dictionary { offset } => offset as u128,
+        }
+    }
+
+    /// Returns the same position moved to another index in its dimension.
+    fn with_index(self, index: u128) -> Self {
+        match self {
+            Self::BruteForce { length, .. } => Self::BruteForce { length, index },
+            Self::Pattern { .. } => Self::Pattern { index },
+            Self::Dictionary { .. } => Self::Dictionary {
+                offset: index as u64,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BruteForce { length, index } => write!(f, "length {length}, position {index}"),
+            Self::Pattern { index } => write!(f, "position {index}"),
+            Self::Dictionary { offset } => write!(f, "byte offset {offset}"),
+        }
+    }
+}
+
+/// Contents of a progress file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProgressFile {
+    /// Format version, see [`FORMAT_VERSION`]
+    #[serde(default = "legacy_version")]
+    pub version: u32,
+    /// SHA-256 of the certificate, hex encoded
+    pub fingerprint: String,
+    /// Every candidate before this position has been tested
+    pub position: Position,
+}
+
+/// Format version of progress files written before the version field existed
+fn legacy_version() -> u32 {
+    1
+}
+
+impl ProgressFile {
+    /// Creates a progress file in the current format version.
+    ///
+    /// # Arguments
+    ///
+    /// * `fingerprint` - SHA-256 of the certificate, hex encoded
+    /// * `position` - Every candidate before this position has been tested
+    pub fn new(fingerprint: String, position: Position) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            fingerprint,
+            position,
+        }
+    }
+
+    /// Loads a progress file written with [`ProgressFile::save`].
+    ///
+    /// Falls back to the previous generation if the file is damaged, see [`load_json`].
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if neither the file nor its backup exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if neither the file nor its backup is a valid progress file
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        load_json(path, "progress file")
+    }
+
+    /// Writes the progress file atomically, see [`write_atomic`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize progress")?;
+        write_atomic(path, json.as_bytes())
+            .with_context(|| format!("Failed to write progress file: {}", path.display()))
+    }
+}
+
+/// Returns the default progress file of a certificate, `<cert>.progress`.
+///
+/// # Arguments
+///
+/// * `certificate` - Path to the certificate file
+pub fn default_path(certificate: &Path) -> PathBuf {
+    let mut path = certificate.as_os_str().to_owned();
+    path.push(".progress");
+    PathBuf::from(path)
+}
+
+/// Writes the progress of a run to its progress file.
+///
+/// Crackers announce the dimension they work on with [`ProgressSaver::begin`]
+/// and report every finished range with [`ProgressSaver::complete`]. Chunks
+/// finish out of order, so only the low-water mark below which every range
+/// has completed is saved.
+pub struct ProgressSaver {
+    /// Path to the progress file
+    path: PathBuf,
+    /// How often the file is written
+    cadence: WriteCadence,
+    /// SHA-256 of the certificate, hex encoded
+    fingerprint: String,
+    /// Position saved by an earlier run of the same certificate
+    saved: Option<Position>,
+    /// Set by the attempts cadence, the next completed range writes the file
+    due: AtomicBool,
+    /// Time of the last write, for the interval cadence
+    last_write: Mutex<Instant>,
+    /// Current dimension and the ranges of it that completed
+    state: Mutex<Option<(Locate, ProgressTracker)>>,
+}
+
+/// Maps a tracked index to the position saved.
+type Locate = Box<dyn Fn(u128) -> Position + Send>;
+
+impl ProgressSaver {
+    /// Creates a saver, loading the progress an earlier run saved for the certificate.
+    ///
+    /// A progress file of another certificate is ignored and overwritten.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the progress file
+    /// * `cadence` - Time or number of attempts between two writes
+    /// * `pkcs12` - The PKCS#12 certificate being cracked
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the certificate cannot be encoded or an existing
+    /// progress file cannot be read
+    pub fn open(path: PathBuf, cadence: WriteCadence, pkcs12: &Pkcs12) -> Result<Self> {
+        let fingerprint = certificate_fingerprint(pkcs12)?;
+        let saved = match ProgressFile::load(&path)? {
+            Some(file)
+                if file.fingerprint == fingerprint
+                    && file.version < PATTERN_ORDER_VERSION
+                    && matches!(file.position, Position::Pattern { .. }) =>
+            {
+                println!(
+                    "Ignoring progress file {}, its pattern position is in the combination \
+                     order of format version {}",
+                    path.display(),
+                    file.version
+                );
+                None
+            }
+            Some(file) if file.fingerprint == fingerprint => {
+                println!(
+                    "Resuming from progress file {} at {}",
+                    path.display(),
+                    file.position
+                );
+                Some(file.position)
+            }
+            Some(_) => {
+                println!(
+                    "Ignoring progress file {}, it belongs to another certificate",
+                    path.display()
+                );
+                None
+            }
+            None => None,
+        };
+        Ok(Self {
+            path,
+            cadence: match cadence {
+                WriteCadence::Attempts(every) => WriteCadence::Attempts(every.max(1)),
+                interval => interval,
+            },
+            fingerprint,
+            saved,
+            due: AtomicBool::new(false),
+            last_write: Mutex::new(Instant::now()),
+            state: Mutex::new(None),
+        })
+    }
+
+    /// Position saved by an earlier run of the same certificate, if any.
+    pub fn saved(&self) -> Option<Position> {
+        self.saved
+    }
+
+    /// Starts tracking a dimension, e.g. a brute force length.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - First untested position of the dimension
+    pub fn begin(&self, position: Position) {
+        self.begin_mapped(position.index(), move |index| position.with_index(index));
+    }
+
+    /// Starts tracking an index space that spans several dimensions, e.g.
+    /// all brute force lengths numbered consecutively.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - First untested index
+    /// * `locate` - Maps an index to the position saved
+    pub fn begin_mapped(&self, start: u128, locate: impl Fn(u128) -> Position + Send + 'static) {
+        let tracker = ProgressTracker::new(start, &[]);
+        *self.state.lock().unwrap() = Some((Box::new(locate), tracker));
+    }
+
+    /// Called whenever the attempt counter grows.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - Attempt counter before the increment
+    /// * `current` - Attempt counter after the increment
+    #[inline(always)]
+    pub fn on_attempts(&self, previous: usize, current: usize) {
+        let WriteCadence::Attempts(every) = self.cadence else {
+            return;
+        };
+        if previous / every != current / every {
+            self.due.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Records a tested range of the current dimension and writes the file
+    /// once the write cadence is due.
+    ///
+    /// Write failures are reported but do not stop the run.
+    pub fn complete(&self, range: Range<u128>) {
+        let mut state = self.state.lock().unwrap();
+        if let Some((_, tracker)) = state.as_mut() {
+            tracker.complete(range);
+        }
+        let due = match self.cadence {
+            WriteCadence::Attempts(_) => self.due.swap(false, Ordering::Relaxed),
+            WriteCadence::Interval(interval) => {
+                let mut last_write = self.last_write.lock().unwrap();
+                let due = last_write.elapsed() >= interval;
+                if due {
+                    *last_write = Instant::now();
+                }
+                due
+            }
+        };
+        if due {
+            if let Err(e) = self.write(state.as_ref()) {
+                eprintln!("{e:#}");
+            }
+        }
+    }
+
+    /// Writes the progress file immediately, e.g. when the run stops.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn save(&self) -> Result<()> {
+        self.write(self.state.lock().unwrap().as_ref())
+    }
+
+    /// Removes the progress file and its backup, e.g. once the password is found.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file exists but cannot be removed
+    pub fn remove(&self) -> Result<()> {
+        for path in [self.path.clone(), backup_path(&self.path)] {
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    return Err(e).with_context(|| {
+                        format!("Failed to remove progress file: {}", path.display())
+                    })
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&self, state: Option<&(Locate, ProgressTracker)>) -> Result<()> {
+        let Some((locate, tracker)) = state else {
+            return Ok(());
+        };
+        ProgressFile::new(self.fingerprint.clone(), locate(tracker.low_water())).save(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::fixture;
+
+    #[test]
+    fn test_progress_file_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cert.p12.progress");
+        assert_eq!(ProgressFile::load(&path).unwrap(), None);
+
+        let file = ProgressFile::new(
+            "00".to_string(),
+            Position::BruteForce {
+                length: 5,
+                index: u128::MAX,
+            },
+        );
+        file.save(&path).unwrap();
+        assert_eq!(ProgressFile::load(&path).unwrap(), Some(file));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_saver_writes_low_water_mark() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress");
+        let pkcs12 = fixture("umlaut.p12");
+
+        let saver = ProgressSaver::open(path.clone(), WriteCadence::Attempts(10), &pkcs12).unwrap();
+        saver.begin(Position::Pattern { index: 0 });
+        saver.on_attempts(0, 20);
+        // The second range finished first, only the first one is safe to skip
+        saver.complete(10..20);
+        assert_eq!(
+            ProgressFile::load(&path).unwrap().unwrap().position,
+            Position::Pattern { index: 0 }
+        );
+        saver.on_attempts(20, 30);
+        saver.complete(0..10);
+
+        let resumed =
+            ProgressSaver::open(path.clone(), WriteCadence::Attempts(10), &pkcs12).unwrap();
+        assert_eq!(resumed.saved(), Some(Position::Pattern { index: 20 }));
+
+        resumed.remove().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_truncated_progress_file_falls_back_to_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress");
+        let first = ProgressFile::new("00".to_string(), Position::Pattern { index: 100 });
+        first.save(&path).unwrap();
+        ProgressFile::new("00".to_string(), Position::Pattern { index: 200 })
+            .save(&path)
+            .unwrap();
+
+        // Power loss halfway through writing the file
+        let json = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &json[..json.len() / 2]).unwrap();
+        assert_eq!(ProgressFile::load(&path).unwrap(), Some(first));
+    }
+
+    #[test]
+    fn test_saver_interval_cadence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress");
+        let pkcs12 = fixture("umlaut.p12");
+
+        let saver = ProgressSaver::open(
+            path.clone(),
+            WriteCadence::Interval(std::time::Duration::ZERO),
+            &pkcs12,
+        )
+        .unwrap();
+        saver.begin(Position::Pattern { index: 0 });
+        // No attempts are counted, the elapsed time alone makes the write due
+        saver.complete(0..10);
+        assert_eq!(
+            ProgressFile::load(&path).unwrap().unwrap().position,
+            Position::Pattern { index: 10 }
+        );
+
+        saver.complete(10..20);
+        saver.remove().unwrap();
+        assert!(!path.exists());
+        assert!(!backup_path(&path).exists());
+    }
+
+    #[test]
+    fn test_saver_ignores_other_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress");
+        ProgressFile::new("00".to_string(), Position::Dictionary { offset: 16384 })
+            .save(&path)
+            .unwrap();
+
+        let saver =
+            ProgressSaver::open(path, WriteCadence::Attempts(10), &fixture("umlaut.p12")).unwrap();
+        assert_eq!(saver.saved(), None);
+    }
+
+    #[test]
+    fn test_saver_ignores_pattern_position_in_old_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progress");
+        let pkcs12 = fixture("umlaut.p12");
+        let fingerprint = certificate_fingerprint(&pkcs12).unwrap();
+        let save = |version, position| {
+            ProgressFile {
+                version,
+                fingerprint: fingerprint.clone(),
+                position,
+            }
+            .save(&path)
+            .unwrap()
+        };
+
+        save(2, Position::Pattern { index: 20 });
+        let saver = ProgressSaver::open(path.clone(), WriteCadence::Attempts(10), &pkcs12).unwrap();
+        assert_eq!(saver.saved(), None);
+
+        // Other positions did not change their order
+        save(2, Position::Dictionary { offset: 20 });
+        let saver = ProgressSaver::open(path.clone(), WriteCadence::Attempts(10), &pkcs12).unwrap();
+        assert_eq!(saver.saved(), Some(Position::Dictionary { offset: 20 }));
+    }
+
+    #[test]
+    fn test_default_path() {
+        assert_eq!(
+            default_path(Path::new("certs/cert.p12")),
+            PathBuf::from("certs/cert.p12.progress")
+        );
+    }
+}
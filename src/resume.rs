@@ -0,0 +1,301 @@
+//! Automatic resume detection for repeated runs.
+//!
+//! Every brute force, pattern and dictionary run of a single certificate
+//! saves its position to a progress file in a per-user state directory. The
+//! file is named after a run key, a hash of the certificate fingerprint and
+//! every parameter that changes which candidates are tested in which order.
+//! Running the same command again finds the file of the earlier run, any
+//! other parameter leads to another key and never reuses it.
+use crate::args::Args;
+use crate::charset;
+use crate::crackers::bruteforce::LengthOrder;
+use crate::keyspace;
+use crate::progress_file::Position;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Number of attempts between two writes of the state file
+pub const SAVE_EVERY: usize = 100_000;
+
+/// Version of the run key, changed whenever the parameters it covers change
+const KEY_VERSION: u32 = 12;
+
+/// Returns the state directory for files of earlier runs.
+///
+/// `$XDG_STATE_HOME/pkcs12cracker`, falling back to
+/// `~/.local/state/pkcs12cracker`, or `%LOCALAPPDATA%\pkcs12cracker` on
+/// Windows. Returns `None` if none of these variables is set.
+pub fn state_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)?
+    } else if let Some(state) = std::env::var_os("XDG_STATE_HOME").filter(|s| !s.is_empty()) {
+        PathBuf::from(state)
+    } else {
+        PathBuf::from(std::env::var_os("HOME")?).join(".local/state")
+    };
+    Some(base.join("pkcs12cracker"))
+}
+
+/// Returns the state file of a run.
+///
+/// # Arguments
+///
+/// * `dir` - State directory, see [`state_dir`]
+/// * `key` - Run key, see [`run_key`]
+pub fn state_path(dir: &Path, key: &str) -> PathBuf {
+    dir.join(format!("{key}.progress"))
+}
+
+/// Describes every parameter of a run that affects the tested candidates
+/// or their order.
+///
+/// Dictionaries are identified by their canonical path, size and
+/// modification time, so an edited wordlist starts a new run. Pattern and
+/// rule files are identified by their content.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments of the run
+/// * `random_seed` - Seed of `--random-order` used by the run, if any
+///
+/// # Errors
+///
+/// Returns an error if the charset is invalid or the dictionary, pattern
+/// or rule file cannot be read
+pub fn run_parameters(args: &Args, random_seed: Option<u64>) -> Result<String> {
+    let dictionary = match &args.dictionary_path {
+        Some(path) => {
+            let metadata = fs::metadata(path)
+                .with_context(|| format!("Failed to read dictionary: {}", path.display()))?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_nanos());
+            let path = fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            Some((path, metadata.len(), modified))
+        }
+        None => None,
+    };
+    let pattern_file = match &args.pattern_file {
+        Some(path) => Some(
+            fs::read_to_string(path)
+                .with_context(|| format!("Failed to read pattern file: {}", path.display()))?,
+        ),
+        None => None,
+    };
+    let rules = match &args.rules {
+        Some(path) => Some(
+            fs::read_to_string(path)
+                .with_context(|| format!("Failed to read rules file: {}", path.display()))?,
+        ),
+        None => None,
+    };
+    let john_rules = match &args.john_rules {
+        Some(path) => Some((
+            fs::read_to_string(path).with_context(|| {
+                format!("Failed to read John the Ripper rules: {}", path.display())
+            })?,
+            &args.john_rules_section,
+        )),
+        None => None,
+    };
+    let mode = (
+        args.bruteforce_flag,
+        (args.pronounceable, args.pronounceable_digits),
+        (&args.pattern, pattern_file),
+        args.pattern_symbol,
+        args.toggle_case,
+        (&args.symbols, &args.linked_symbols, &args.years),
+        (args.minumum_length, args.maximum_length),
+        args.no_repeats,
+        dictionary,
+    );
+    let dictionary_options = (
+        &args.delimiter,
+        args.no_trim,
+        args.wordlist_encoding,
+        args.word_encoding,
+        args.wordlist_sort,
+        args.append_numbers,
+        args.append_numbers_fixed,
+        rules,
+        john_rules,
+    );
+    let order = (
+        random_seed,
+        args.smart_order,
+        args.reverse,
+        args.length_order,
+        &args.start_at,
+        args.skip,
+        args.limit,
+        args.work_unit,
+        args.numeric_order,
+    );
+    let filters = (
+        &args.require,
+        &args.require_count,
+        args.filter_regex.as_ref().map(|regex| regex.as_str()),
+        args.max_bytes,
+    );
+    Ok(format!(
+        "v{KEY_VERSION} {mode:?} {:?} {dictionary_options:?} {order:?} {filters:?}",
+        charset::build_charset(args)?
+    ))
+}
+
+/// Returns the run key of a certificate and run parameters, hex encoded.
+///
+/// # Arguments
+///
+/// * `fingerprint` - Fingerprint of the certificate
+/// * `parameters` - Parameters of the run, see [`run_parameters`]
+pub fn run_key(fingerprint: &str, parameters: &str) -> String {
+    let input = format!("{fingerprint}\n{parameters}");
+    openssl::sha::sha256(input.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Returns the share of the search space before a saved position, if known.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments of the run
+/// * `position` - Position saved by the earlier run
+/// * `work_items` - Number of work items of the run, if known, which pattern
+///   positions count
+pub fn covered_share(args: &Args, position: Position, work_items: Option<u128>) -> Option<f64> {
+    let (done, total) = match position {
+        Position::BruteForce { length, index } => {
+            let charset_size = charset::build_charset(args).ok()?.chars().count();
+            let table = keyspace::keyspace_table(
+                charset_size,
+                args.minumum_length,
+                args.maximum_length,
+                args.no_repeats,
+            );
+            let descending = match args.length_order {
+                Some(LengthOrder::Ascending) => false,
+                Some(LengthOrder::Descending) => true,
+                Some(LengthOrder::Interleaved) | None => args.reverse,
+            };
+            let before: u128 = table
+                .iter()
+                .filter(|row| {
+                    if descending {
+                        row.length > length
+                    } else {
+                        row.length < length
+                    }
+                })
+                .map(|row| row.keyspace)
+                .fold(0, u128::saturating_add);
+            (before.saturating_add(index), table.last()?.cumulative)
+        }
+        Position::Pattern { index } => (index, work_items?),
+        Position::Dictionary { offset } => (
+            offset as u128,
+            fs::metadata(args.dictionary_path.as_ref()?).ok()?.len() as u128,
+        ),
+    };
+    (total > 0).then(|| (done as f64 / total as f64).min(1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bruteforce_args() -> Args {
+        Args {
+            bruteforce_flag: true,
+            char_sets: Some("n".to_string()),
+            minumum_length: 1,
+            maximum_length: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_run_key_depends_on_parameters() {
+        let args = bruteforce_args();
+        let parameters = run_parameters(&args, None).unwrap();
+        let key = run_key("00", &parameters);
+        assert_eq!(key.len(), 64);
+        assert_eq!(key, run_key("00", &run_parameters(&args, None).unwrap()));
+        assert_ne!(key, run_key("01", &parameters));
+
+        let changed = [
+            Args {
+                maximum_length: 4,
+                ..bruteforce_args()
+            },
+            Args {
+                char_sets: Some("a".to_string()),
+                ..bruteforce_args()
+            },
+            Args {
+                reverse: true,
+                ..bruteforce_args()
+            },
+        ];
+        for args in changed {
+            assert_ne!(key, run_key("00", &run_parameters(&args, None).unwrap()));
+        }
+        assert_ne!(key, run_key("00", &run_parameters(&args, Some(7)).unwrap()));
+    }
+
+    #[test]
+    fn test_run_key_tracks_dictionary_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("words.txt");
+        fs::write(&path, "a\nb\n").unwrap();
+        let args = Args {
+            dictionary_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let before = run_parameters(&args, None).unwrap();
+        fs::write(&path, "a\nb\nc\n").unwrap();
+        assert_ne!(before, run_parameters(&args, None).unwrap());
+    }
+
+    #[test]
+    fn test_covered_share() {
+        let args = bruteforce_args();
+        // 10 + 100 of 1110 candidates
+        let share = covered_share(
+            &args,
+            Position::BruteForce {
+                length: 3,
+                index: 0,
+            },
+            None,
+        );
+        assert!((share.unwrap() - 110.0 / 1110.0).abs() < 1e-9);
+
+        let reversed = Args {
+            reverse: true,
+            ..bruteforce_args()
+        };
+        let share = covered_share(
+            &reversed,
+            Position::BruteForce {
+                length: 3,
+                index: 500,
+            },
+            None,
+        );
+        assert!((share.unwrap() - 500.0 / 1110.0).abs() < 1e-9);
+
+        let share = covered_share(&args, Position::Pattern { index: 38 }, Some(100));
+        assert_eq!(share, Some(0.38));
+        assert_eq!(
+            covered_share(&args, Position::Pattern { index: 38 }, None),
+            None
+        );
+    }
+}
@@ -0,0 +1,565 @@
+//! Cracking session management.
+//!
+//! A session owns the Rayon thread pool used by the crackers, so that
+//! several sessions can coexist in one process without touching the
+//! global pool.
+use crate::candidate_log::{CandidateLog, CandidatePreview};
+use crate::checkpoint::WriteCadence;
+use crate::heuristics;
+use crate::interrupt;
+use crate::progress::ProgressReporter;
+use crate::progress_file::ProgressSaver;
+use crate::types::{
+    CaseSensitivity, CrackResult, CrackStats, PasswordCracker, PkcsError, RunConfig,
+};
+use anyhow::{Context, Result};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::nid::Nid;
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::PKey;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use regex::Regex;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Time between two checks of [`CrackSession::run_many_reporting`] for found passwords
+pub const FOUND_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A single password cracking session.
+///
+/// Holds a dedicated thread pool and the shared result of the run.
+pub struct CrackSession {
+    /// Thread pool all parallel work of this session is executed on
+    thread_pool: ThreadPool,
+    /// Shared result tracking structure
+    result: Arc<Mutex<CrackResult>>,
+    /// Time between two status lines, `None` disables status reporting
+    status_interval: Option<Duration>,
+    /// Candidates not matching this regex are skipped without being tested
+    filter: Option<Arc<Regex>>,
+    /// Candidates longer than this many UTF-8 bytes are skipped without being tested
+    max_bytes: Option<usize>,
+    /// Progress file and the number of attempts between two writes
+    progress_file: Option<(PathBuf, WriteCadence)>,
+    /// Whether candidates derived from the certificate metadata are tried first
+    certificate_words: bool,
+    /// File every tested candidate is written to
+    candidate_log: Option<PathBuf>,
+    /// The cracker is stopped once the run takes this long
+    time_limit: Option<Duration>,
+    /// Case folds of every candidate tested besides the candidate itself
+    case_sensitivity: CaseSensitivity,
+    /// Whether the reversed form of every candidate is tested too
+    also_reversed: bool,
+    /// Whether a candidate's MAC is checked before the full decryption
+    pre_hash: bool,
+}
+
+impl CrackSession {
+    /// Creates a new CrackSession instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `threads` - Number of worker threads in the session's thread pool
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if thread pool initialization fails
+    pub fn new(threads: usize) -> Result<Self> {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|error| PkcsError::ThreadPoolError(error.to_string()))?;
+
+        Ok(Self {
+            thread_pool,
+            result: Arc::new(Mutex::new(CrackResult::new())),
+            status_interval: None,
+            filter: None,
+            max_bytes: None,
+            progress_file: None,
+            certificate_words: false,
+            candidate_log: None,
+            time_limit: None,
+            case_sensitivity: CaseSensitivity::default(),
+            also_reversed: false,
+            pre_hash: false,
+        })
+    }
+
+    /// Prints a status line with the progress at a fixed interval while running.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time between two status lines
+    pub fn with_status_interval(mut self, interval: Duration) -> Self {
+        self.status_interval = Some(interval);
+        self
+    }
+
+    /// Skips candidates that do not match a regex.
+    ///
+    /// The filter saves the decryption of rejected candidates, they are still
+    /// generated and counted as attempts.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Regex every tested candidate must match
+    pub fn with_filter(mut self, filter: Regex) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Skips candidates whose UTF-8 encoding is longer than a number of bytes.
+    ///
+    /// Unlike the filter, skipped candidates are not counted as attempts.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_bytes` - Maximum length of a tested candidate in bytes
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Saves the position of the run to a progress file and resumes from it.
+    ///
+    /// The file is written again when the run stops or is interrupted with
+    /// Ctrl-C, and removed once the password is found. Only supported by
+    /// [`CrackSession::run`].
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the progress file
+    /// * `cadence` - Time or number of attempts between two writes
+    pub fn with_progress_file(mut self, path: PathBuf, cadence: WriteCadence) -> Self {
+        self.progress_file = Some((path, cadence));
+        self
+    }
+
+    /// Tries candidates derived from the certificate metadata before the cracker.
+    ///
+    /// See [`heuristics::derive_wordlist_from_metadata`], the cracker only
+    /// runs if they do not crack every certificate.
+    pub fn with_certificate_words(mut self) -> Self {
+        self.certificate_words = true;
+        self
+    }
+
+    /// Writes every tested candidate to a file, one per line.
+    ///
+    /// The file is flushed when the run ends, including when it is
+    /// interrupted with Ctrl-C. Logging slows cracking down and the file
+    /// grows with every attempt.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the log file, an existing file is truncated
+    pub fn with_candidate_log(mut self, path: PathBuf) -> Self {
+        self.candidate_log = Some(path);
+        self
+    }
+
+    /// Also tests case folds of every candidate, e.g. its lowercase form.
+    ///
+    /// Each fold is another decryption, so the run slows down by the number
+    /// of folds. Folds do not count as attempts.
+    ///
+    /// # Arguments
+    ///
+    /// * `case_sensitivity` - Folds to test after each candidate
+    pub fn with_case_sensitivity(mut self, case_sensitivity: CaseSensitivity) -> Self {
+        self.case_sensitivity = case_sensitivity;
+        self
+    }
+
+    /// Also tests every candidate reversed character by character.
+    ///
+    /// The reversed form is another attempt, palindromes are tested once.
+    /// The result records whether the password matched reversed.
+    pub fn with_also_reversed(mut self) -> Self {
+        self.also_reversed = true;
+        self
+    }
+
+    /// Checks every candidate against the certificate's MAC before decrypting it.
+    ///
+    /// Wrong candidates are rejected by the MAC alone, see
+    /// [`fast_mac_check`](crate::crackers::fast_mac_check).
+    pub fn with_pre_hash(mut self) -> Self {
+        self.pre_hash = true;
+        self
+    }
+
+    /// Stops the cracker once a run takes longer than a time limit.
+    ///
+    /// The cracker returns as if the password was found, without its
+    /// "not found" messages; the result has no password.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - Maximum duration of a run
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Returns the number of threads in the session's thread pool.
+    pub fn num_threads(&self) -> usize {
+        self.thread_pool.current_num_threads()
+    }
+
+    /// Returns the shared result of this session.
+    pub fn result(&self) -> &Arc<Mutex<CrackResult>> {
+        &self.result
+    }
+
+    /// Returns a snapshot of the session's statistics.
+    ///
+    /// The result is only locked while the counters are read, so this can be
+    /// called from another thread while the session is running.
+    pub fn stats(&self) -> CrackStats {
+        self.result.lock().unwrap().to_stats()
+    }
+
+    /// Records the mode, candidate limits, case folds, candidate log, start
+    /// time and deadline of a run in its result.
+    fn start(
+        &self,
+        cracker: &dyn PasswordCracker,
+        result: &Arc<Mutex<CrackResult>>,
+        candidate_log: &Option<Arc<CandidateLog>>,
+        preview: Option<Arc<CandidatePreview>>,
+    ) {
+        let mut result = result.lock().unwrap();
+        result.mode = cracker.mode();
+        result.start_time = Instant::now();
+        let deadline = self.time_limit.map(|limit| result.start_time + limit);
+        result.configure(RunConfig {
+            filter: self.filter.clone(),
+            max_bytes: self.max_bytes,
+            case_sensitivity: self.case_sensitivity,
+            also_reversed: self.also_reversed,
+            pre_hash: self.pre_hash,
+            candidate_log: candidate_log.clone(),
+            deadline,
+            preview,
+        });
+    }
+
+    /// Creates the candidate log of a run, if enabled, and flushes it when
+    /// the process is interrupted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the log file cannot be created
+    fn open_candidate_log(&self) -> Result<Option<Arc<CandidateLog>>> {
+        let Some(path) = &self.candidate_log else {
+            return Ok(None);
+        };
+        let log = Arc::new(CandidateLog::create(path, self.num_threads())?);
+        let weak = Arc::downgrade(&log);
+        interrupt::on_interrupt(move || {
+            if let Some(log) = weak.upgrade() {
+                if let Err(e) = log.flush() {
+                    eprintln!("{e:#}");
+                }
+            }
+        });
+        Ok(Some(log))
+    }
+
+    /// Flushes the candidate log of a run, keeping the run's error if both fail.
+    fn finish_candidate_log(
+        candidate_log: Option<Arc<CandidateLog>>,
+        outcome: Result<()>,
+    ) -> Result<()> {
+        match candidate_log {
+            Some(log) => outcome.and(log.flush()),
+            None => outcome,
+        }
+    }
+
+    /// Tries the candidates derived from the metadata of every certificate,
+    /// if enabled.
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if all certificates are cracked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a certificate cannot be encoded
+    fn try_certificate_words(
+        &self,
+        pkcs12s: &[Arc<Pkcs12>],
+        results: &[Arc<Mutex<CrackResult>>],
+    ) -> Result<bool> {
+        if !self.certificate_words {
+            return Ok(false);
+        }
+        let mut words = Vec::new();
+        for pkcs12 in pkcs12s {
+            let der = pkcs12.to_der().context("Failed to encode certificate")?;
+            for word in heuristics::derive_wordlist_from_metadata(&der) {
+                if !words.contains(&word) {
+                    words.push(word);
+                }
+            }
+        }
+        if words.is_empty() {
+            println!("No certificate metadata is readable without the password");
+            return Ok(false);
+        }
+        println!(
+            "Trying {} candidates from certificate metadata",
+            words.len()
+        );
+        Ok(self
+            .thread_pool
+            .install(|| heuristics::try_words(&words, pkcs12s, results)))
+    }
+
+    /// Runs the given cracker inside the session's thread pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `cracker` - The cracking strategy to execute
+    /// * `pkcs12` - The PKCS#12 certificate to crack
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the progress file cannot be read or the cracker fails
+    pub fn run(&self, cracker: &dyn PasswordCracker, pkcs12: &Arc<Pkcs12>) -> Result<()> {
+        let saver = match &self.progress_file {
+            Some((path, cadence)) => Some(Arc::new(ProgressSaver::open(
+                path.clone(),
+                *cadence,
+                pkcs12,
+            )?)),
+            None => None,
+        };
+        self.result
+            .lock()
+            .unwrap()
+            .set_progress_saver(saver.clone());
+        if let Some(saver) = &saver {
+            let weak = Arc::downgrade(saver);
+            interrupt::on_interrupt(move || {
+                if let Some(saver) = weak.upgrade() {
+                    if let Err(e) = saver.save() {
+                        eprintln!("{e:#}");
+                    }
+                }
+            });
+        }
+        let candidate_log = self.open_candidate_log()?;
+
+        let reporter = self
+            .status_interval
+            .map(|interval| ProgressReporter::spawn(Arc::clone(&self.result), interval));
+
+        self.start(cracker, &self.result, &candidate_log, None);
+        let outcome = match self.try_certificate_words(
+            std::slice::from_ref(pkcs12),
+            std::slice::from_ref(&self.result),
+        ) {
+            Ok(true) => Ok(()),
+            Ok(false) => self
+                .thread_pool
+                .install(|| cracker.crack(pkcs12, &self.result)),
+            Err(e) => Err(e),
+        };
+
+        if let Some(reporter) = reporter {
+            reporter.stop();
+        }
+        let outcome = Self::finish_candidate_log(candidate_log, outcome);
+        if let (Some(saver), Ok(())) = (saver, &outcome) {
+            if self.result.lock().unwrap().password.is_some() {
+                saver.remove()?;
+            } else {
+                saver.save()?;
+            }
+        }
+        outcome
+    }
+
+    /// Runs a cracker against several certificates at once.
+    ///
+    /// Every candidate is tested against all certificates that are not cracked
+    /// yet, so the candidates are generated only once.
+    ///
+    /// # Arguments
+    ///
+    /// * `cracker` - The password cracker to use
+    /// * `pkcs12s` - The PKCS#12 certificates to crack
+    ///
+    /// # Returns
+    ///
+    /// The result of every certificate, in the order of `pkcs12s`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cracker does not support several certificates
+    /// or fails
+    pub fn run_many(
+        &self,
+        cracker: &dyn PasswordCracker,
+        pkcs12s: &[Arc<Pkcs12>],
+    ) -> Result<Vec<Arc<Mutex<CrackResult>>>> {
+        self.run_many_reporting(cracker, pkcs12s, |_, _| {})
+    }
+
+    /// Runs a cracker against several certificates at once, reporting every
+    /// certificate as soon as its password is found.
+    ///
+    /// The results are polled every [`FOUND_POLL_INTERVAL`] on a separate
+    /// thread, passwords found at the very end are reported before returning.
+    ///
+    /// # Arguments
+    ///
+    /// * `cracker` - The password cracker to use
+    /// * `pkcs12s` - The PKCS#12 certificates to crack
+    /// * `on_found` - Called with the index and statistics of every
+    ///   certificate whose password is found, once per certificate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cracker does not support several certificates
+    /// or fails
+    pub fn run_many_reporting(
+        &self,
+        cracker: &dyn PasswordCracker,
+        pkcs12s: &[Arc<Pkcs12>],
+        mut on_found: impl FnMut(usize, &CrackStats) + Send,
+    ) -> Result<Vec<Arc<Mutex<CrackResult>>>> {
+        let results: Vec<_> = pkcs12s
+            .iter()
+            .map(|_| Arc::new(Mutex::new(CrackResult::new())))
+            .collect();
+        let candidate_log = self.open_candidate_log()?;
+        for result in &results {
+            self.start(cracker, result, &candidate_log, None);
+        }
+        let finished = AtomicBool::new(false);
+        let outcome = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let mut reported = vec![false; results.len()];
+                loop {
+                    let last = finished.load(Ordering::Acquire);
+                    for (index, result) in results.iter().enumerate() {
+                        let stats = {
+                            let result = result.lock().unwrap();
+                            (!reported[index] && result.password.is_some())
+                                .then(|| result.to_stats())
+                        };
+                        if let Some(stats) = stats {
+                            reported[index] = true;
+                            on_found(index, &stats);
+                        }
+                    }
+                    if last {
+                        break;
+                    }
+                    std::thread::sleep(FOUND_POLL_INTERVAL);
+                }
+            });
+            let outcome = match self.try_certificate_words(pkcs12s, &results) {
+                Ok(true) => Ok(()),
+                Ok(false) => self
+                    .thread_pool
+                    .install(|| cracker.crack_many(pkcs12s, &results)),
+                Err(e) => Err(e),
+            };
+            finished.store(true, Ordering::Release);
+            outcome
+        });
+        Self::finish_candidate_log(candidate_log, outcome)?;
+        Ok(results)
+    }
+
+    /// Generates the first candidates of a cracker without testing them.
+    ///
+    /// The cracker runs against a placeholder certificate and every
+    /// candidate passing the filter is collected instead of being tested,
+    /// until `limit` candidates are collected. With a single thread they
+    /// come in traversal order. Candidate logs and certificate words do not
+    /// apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `cracker` - The password cracker to use
+    /// * `limit` - Number of candidates to generate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the placeholder certificate cannot be created or
+    /// the cracker fails
+    pub fn preview(&self, cracker: &dyn PasswordCracker, limit: usize) -> Result<Vec<String>> {
+        let placeholder = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)
+            .and_then(|group| EcKey::generate(&group))
+            .and_then(PKey::from_ec_key)
+            .and_then(|key| Pkcs12::builder().pkey(&key).build2(""))
+            .map(Arc::new)
+            .context("Failed to create a placeholder certificate")?;
+        let result = Arc::new(Mutex::new(CrackResult::new()));
+        let preview = Arc::new(CandidatePreview::new(limit));
+        self.start(cracker, &result, &None, Some(Arc::clone(&preview)));
+        self.thread_pool
+            .install(|| cracker.crack(&placeholder, &result))?;
+        drop(result);
+        Ok(Arc::into_inner(preview)
+            .map(CandidatePreview::into_candidates)
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::fixture;
+
+    #[test]
+    fn test_multiple_sessions_with_different_thread_counts() {
+        let first = CrackSession::new(2).unwrap();
+        let second = CrackSession::new(3).unwrap();
+
+        assert_eq!(first.num_threads(), 2);
+        assert_eq!(second.num_threads(), 3);
+        assert!(first.result().lock().unwrap().password.is_none());
+    }
+
+    #[test]
+    fn test_preview_collects_candidates_in_order() {
+        let cracker =
+            crate::crackers::pattern::PatternCracker::new("x@@".into(), "abc".into(), '@');
+        let session = CrackSession::new(1)
+            .unwrap()
+            .with_filter(Regex::new("^x[ab]").unwrap());
+        assert_eq!(
+            session.preview(&cracker, 4).unwrap(),
+            ["xaa", "xab", "xac", "xba"]
+        );
+        // Fewer candidates than the limit
+        assert_eq!(session.preview(&cracker, 100).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_time_limit_stops_cracker() {
+        let pkcs12 = fixture("umlaut.p12");
+        let cracker = crate::crackers::bruteforce::BruteforceCracker::new(8, 8, "abcdef".into());
+        let session = CrackSession::new(2)
+            .unwrap()
+            .with_time_limit(Duration::from_millis(200));
+
+        let started = Instant::now();
+        session.run(&cracker, &pkcs12).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let stats = session.stats();
+        assert!(stats.password.is_none());
+        assert!(stats.total_attempts > 0);
+    }
+}
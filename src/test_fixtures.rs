@@ -0,0 +1,18 @@
+//! Certificates of `tests/fixtures` shared by the unit tests.
+use openssl::pkcs12::Pkcs12;
+use std::sync::Arc;
+
+/// Returns the path of a certificate in `tests/fixtures`.
+pub(crate) fn fixture_path(name: &str) -> String {
+    format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Loads a certificate of `tests/fixtures`.
+pub(crate) fn fixture(name: &str) -> Arc<Pkcs12> {
+    crate::load_certificate(fixture_path(name)).unwrap()
+}
+
+/// Reads the DER bytes of a certificate in `tests/fixtures`.
+pub(crate) fn fixture_bytes(name: &str) -> Vec<u8> {
+    std::fs::read(fixture_path(name)).unwrap()
+}
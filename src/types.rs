@@ -1,9 +1,257 @@
 //! Core types for password cracking operations.
 //!
-use anyhow::Result;
+use crate::candidate_log::{CandidateLog, CandidatePreview};
+use crate::progress::{Progress, ProgressStage};
+use crate::progress_file::ProgressSaver;
+use anyhow::{bail, Result};
+use clap::ValueEnum;
 use openssl::pkcs12::Pkcs12;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use regex::Regex;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Password length in bytes beyond which many PKCS#12 implementations fail.
+pub const PRACTICAL_PASSWORD_BYTES: usize = 15;
+
+/// Errors library callers may want to tell apart.
+///
+/// Functions return them inside [`anyhow::Error`], match on the kind with
+/// `error.downcast_ref::<PkcsError>()`. Some are wrapped in a context naming
+/// the details, e.g. the setting a session differs in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PkcsError {
+    /// The certificate file does not exist
+    CertificateNotFound(PathBuf),
+    /// The data is not a PKCS#12 certificate, with the reason of the parser
+    CertificateParseError(String),
+    /// The dictionary file does not exist
+    DictionaryNotFound(PathBuf),
+    /// The worker threads could not be started, with the reason
+    ThreadPoolError(String),
+    /// None of the cracking modes was selected
+    NoModeSpecified,
+    /// A character set name or specification is invalid, with the reason
+    InvalidCharset(String),
+    /// A saved session belongs to a run with different settings
+    CheckpointMismatch,
+    /// The search space does not fit into `u128`
+    SearchSpaceExceeded,
+}
+
+impl fmt::Display for PkcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CertificateNotFound(path) => {
+                write!(f, "Certificate file not found: {}", path.display())
+            }
+            Self::CertificateParseError(reason) => {
+                write!(f, "Failed to parse PKCS12 data: {reason}")
+            }
+            Self::DictionaryNotFound(path) => {
+                write!(f, "Dictionary file not found: {}", path.display())
+            }
+            Self::ThreadPoolError(reason) => write!(f, "Failed to build thread pool: {reason}"),
+            Self::NoModeSpecified => write!(
+                f,
+                "No cracking mode specified. Use --pattern, --brute-force, --dictionary, \
+                 --markov-model, --mutate or --permutation-words"
+            ),
+            Self::InvalidCharset(reason) => f.write_str(reason),
+            Self::CheckpointMismatch => f.write_str("Session was saved for a different run"),
+            Self::SearchSpaceExceeded => {
+                write!(f, "Search space exceeds {} combinations", u128::MAX)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PkcsError {}
+
+/// Case folds of every candidate tested besides the candidate itself, from
+/// `--case-insensitive`.
+///
+/// Some PKCS#12 implementations normalize the password to lowercase or
+/// uppercase before deriving the key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum CaseSensitivity {
+    /// Only the candidate as generated
+    #[default]
+    #[value(skip)]
+    Sensitive,
+    /// Also the candidate in lowercase
+    Lower,
+    /// Also the candidate in uppercase
+    Upper,
+    /// Also the candidate in lowercase and in uppercase
+    Both,
+}
+
+impl CaseSensitivity {
+    /// Returns the folds of a candidate to test after it, leaving out folds
+    /// equal to the candidate or to each other.
+    pub fn folds(self, candidate: &str) -> Vec<String> {
+        let mut folds = Vec::new();
+        if matches!(self, Self::Lower | Self::Both) {
+            folds.push(candidate.to_lowercase());
+        }
+        if matches!(self, Self::Upper | Self::Both) {
+            folds.push(candidate.to_uppercase());
+        }
+        folds.retain(|fold| fold != candidate);
+        folds.dedup();
+        folds
+    }
+}
+
+/// Options of a run that stay fixed while the crackers run.
+///
+/// Every worker reads them for every candidate through the run's
+/// [`RunState`], without locking the [`CrackResult`].
+#[derive(Clone, Default)]
+pub struct RunConfig {
+    /// Candidates not matching this regex are skipped without being tested
+    pub filter: Option<Arc<Regex>>,
+    /// Candidates longer than this many UTF-8 bytes are skipped without being tested
+    pub max_bytes: Option<usize>,
+    /// Case folds of every candidate tested besides the candidate itself
+    pub case_sensitivity: CaseSensitivity,
+    /// Whether the reversed form of every candidate is tested too
+    pub also_reversed: bool,
+    /// Whether a candidate's MAC is checked before the full decryption
+    pub pre_hash: bool,
+    /// Records every tested candidate for `--log-candidates`, if any
+    pub candidate_log: Option<Arc<CandidateLog>>,
+    /// The crackers stop once this time has passed, used by timed benchmarks
+    pub deadline: Option<Instant>,
+    /// Collects the candidates instead of testing them, if any
+    pub preview: Option<Arc<CandidatePreview>>,
+}
+
+/// The part of a run the workers share without locking the [`CrackResult`].
+///
+/// Workers take it from [`CrackResult::run`] once per chunk. Testing a
+/// candidate then only reads the options and updates atomics, the result
+/// is locked to record a found password.
+#[derive(Default)]
+pub struct RunState {
+    /// Options of the run
+    pub config: RunConfig,
+    /// Writes the progress file every `--progress-save-every` attempts, if any
+    progress_saver: Option<Arc<ProgressSaver>>,
+    /// Set once the password is found
+    found: AtomicBool,
+    attempts: AtomicUsize,
+    /// Number of candidates skipped by the filter
+    filtered: AtomicUsize,
+    /// Number of candidates skipped by `max_bytes`
+    oversized: AtomicUsize,
+    /// Whether the warning about impractically long candidates was printed
+    warned_long: AtomicBool,
+}
+
+impl RunState {
+    /// Returns `true` once the password is found.
+    #[inline(always)]
+    pub fn is_found(&self) -> bool {
+        self.found.load(Ordering::Acquire)
+    }
+
+    /// Marks the password as found, stopping the workers.
+    pub(crate) fn mark_found(&self) {
+        self.found.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` once the crackers should stop: the password is found,
+    /// the deadline has passed or the preview is complete.
+    #[inline(always)]
+    pub fn is_stopped(&self) -> bool {
+        self.is_found()
+            || self
+                .config
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            || self
+                .config
+                .preview
+                .as_ref()
+                .is_some_and(|preview| preview.is_full())
+    }
+
+    /// Returns `true` if the candidate passes the filter and should be tested.
+    ///
+    /// The filter is matched without locking the result, so workers never wait
+    /// on each other's regex. Rejected candidates are counted, see
+    /// [`CrackResult::get_filtered`].
+    #[inline(always)]
+    pub fn accepts(&self, candidate: &str) -> bool {
+        match &self.config.filter {
+            Some(filter) if !filter.is_match(candidate) => {
+                self.filtered.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns `true` if the candidate is longer than `max_bytes` and must be skipped.
+    ///
+    /// Skipped candidates are counted, see [`CrackResult::get_oversized`],
+    /// and must not be counted as attempts. Without a limit a warning is
+    /// printed once for the first candidate longer than
+    /// [`PRACTICAL_PASSWORD_BYTES`].
+    #[inline(always)]
+    pub fn skips_oversized(&self, candidate: &str) -> bool {
+        match self.config.max_bytes {
+            Some(max_bytes) if candidate.len() > max_bytes => {
+                self.oversized.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Some(_) => false,
+            None => {
+                if candidate.len() > PRACTICAL_PASSWORD_BYTES
+                    && !self.warned_long.swap(true, Ordering::Relaxed)
+                {
+                    eprintln!(
+                        "Warning: testing candidates longer than {PRACTICAL_PASSWORD_BYTES} bytes, \
+                         which many PKCS#12 implementations cannot use. \
+                         Pass --max-bytes {PRACTICAL_PASSWORD_BYTES} to skip them."
+                    );
+                }
+                false
+            }
+        }
+    }
+
+    /// Increments the attempt counter atomically.
+    ///
+    /// As exact count is not important, we use relaxed ordering.
+    #[inline(always)]
+    pub fn increment_attempts(&self) {
+        self.add_attempts(1);
+    }
+
+    /// Adds a number of attempts at once, e.g. those of a finished chunk.
+    ///
+    /// Crossing a multiple of `--progress-save-every` schedules a write of
+    /// the progress file.
+    #[inline(always)]
+    pub fn add_attempts(&self, count: usize) {
+        let previous = self.attempts.fetch_add(count, Ordering::Relaxed);
+        if let Some(saver) = &self.progress_saver {
+            saver.on_attempts(previous, previous + count);
+        }
+    }
+}
 
 /// Represents the result of a password cracking attempt.
 ///
@@ -11,7 +259,21 @@ use std::sync::{Arc, Mutex};
 /// and tracks the number of attempts made.
 pub struct CrackResult {
     pub password: Option<String>,
-    attempts: AtomicUsize,
+    /// What the cracker is working on, for status reporting
+    pub progress: Progress,
+    /// When the run started, used to compute the elapsed time and rate
+    pub start_time: Instant,
+    /// Time from the start of the run until the password was found
+    pub found_at: Option<Duration>,
+    /// Attack mode of the run
+    pub mode: CrackMode,
+    /// Whether the password is the reversed form of a candidate
+    pub found_reversed: bool,
+    /// Values of the runs of adjacent variable positions of the pattern
+    /// candidate the password was found with
+    pub matched_groups: Option<Vec<String>>,
+    /// Options and counters the workers share without locking
+    run: Arc<RunState>,
 }
 
 impl CrackResult {
@@ -19,30 +281,318 @@ impl CrackResult {
     pub fn new() -> Self {
         Self {
             password: None,
-            attempts: AtomicUsize::new(0),
+            progress: Progress::default(),
+            start_time: Instant::now(),
+            found_at: None,
+            mode: CrackMode::default(),
+            found_reversed: false,
+            matched_groups: None,
+            run: Arc::default(),
         }
     }
 
-    /// Increments the attempt counter atomically.
+    /// Returns the state the workers share, to test candidates without
+    /// locking the result.
+    pub fn run(&self) -> Arc<RunState> {
+        Arc::clone(&self.run)
+    }
+
+    /// Returns the options of the run.
+    pub fn config(&self) -> &RunConfig {
+        &self.run.config
+    }
+
+    /// Sets the options of the run, keeping its counters.
     ///
-    /// As exact count is not important, we use relaxed ordering.
+    /// Must be called before the workers take the run state with
+    /// [`CrackResult::run`], they keep using the state they took.
+    pub fn configure(&mut self, config: RunConfig) {
+        self.replace_run(config, self.run.progress_saver.clone());
+    }
+
+    /// Returns the writer of the progress file, if any.
+    pub fn progress_saver(&self) -> Option<Arc<ProgressSaver>> {
+        self.run.progress_saver.clone()
+    }
+
+    /// Sets the writer of the progress file, see [`CrackResult::configure`].
+    pub fn set_progress_saver(&mut self, saver: Option<Arc<ProgressSaver>>) {
+        self.replace_run(self.run.config.clone(), saver);
+    }
+
+    /// Replaces the run state, carrying over its counters.
+    fn replace_run(&mut self, config: RunConfig, progress_saver: Option<Arc<ProgressSaver>>) {
+        let counter = |counter: &AtomicUsize| AtomicUsize::new(counter.load(Ordering::Relaxed));
+        self.run = Arc::new(RunState {
+            config,
+            progress_saver,
+            found: AtomicBool::new(self.run.is_found()),
+            attempts: counter(&self.run.attempts),
+            filtered: counter(&self.run.filtered),
+            oversized: counter(&self.run.oversized),
+            warned_long: AtomicBool::new(self.run.warned_long.load(Ordering::Relaxed)),
+        });
+    }
+
+    /// Returns `true` once the crackers should stop: the password is found,
+    /// the deadline has passed or the preview is complete.
+    #[inline(always)]
+    pub fn is_stopped(&self) -> bool {
+        self.password.is_some() || self.run.is_stopped()
+    }
+
+    /// Returns the number of candidates skipped for exceeding `max_bytes`.
+    pub fn get_oversized(&self) -> usize {
+        self.run.oversized.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of candidates skipped by the filter.
+    pub fn get_filtered(&self) -> usize {
+        self.run.filtered.load(Ordering::Relaxed)
+    }
+
+    /// Takes a snapshot of the run's statistics.
+    ///
+    /// Only reads the counters, so it is cheap enough to call while the
+    /// crackers are running.
+    pub fn to_stats(&self) -> CrackStats {
+        let total_attempts = self.get_attempts();
+        let elapsed = self.start_time.elapsed();
+        CrackStats {
+            total_attempts,
+            elapsed,
+            rate_per_sec: total_attempts as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+            found: self.password.is_some(),
+            password: self.password.clone(),
+            found_at: self.found_at,
+            reversed: self.found_reversed,
+            matched_groups: self.matched_groups.clone(),
+            mode: self.mode,
+            filtered: self.get_filtered(),
+            oversized: self.get_oversized(),
+        }
+    }
+
+    /// Increments the attempt counter atomically.
     #[inline(always)]
     pub fn increment_attempts(&self) {
-        self.attempts.fetch_add(1, Ordering::Relaxed);
+        self.run.increment_attempts();
+    }
+
+    /// Adds a number of attempts at once, see [`RunState::add_attempts`].
+    #[inline(always)]
+    pub fn add_attempts(&self, count: usize) {
+        self.run.add_attempts(count);
     }
 
     /// Returns the current number of password attempts.
     pub fn get_attempts(&self) -> usize {
-        self.attempts.load(Ordering::Relaxed)
+        self.run.attempts.load(Ordering::Relaxed)
+    }
+
+    /// Publishes the start of a new stage of known size.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - Name shown in the status line
+    /// * `total` - Number of candidates in the stage
+    pub fn begin_stage(&mut self, label: String, total: u128) {
+        self.progress.stage = Some(ProgressStage {
+            label,
+            total,
+            start_attempts: self.get_attempts(),
+        });
+        self.progress.region = None;
+    }
+}
+
+impl Default for CrackResult {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Attack mode a run uses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrackMode {
+    /// Words from a dictionary file
+    Dictionary,
+    /// Every combination of a charset
+    BruteForce,
+    /// Combinations filling the unknown positions of a pattern
+    Pattern,
+    /// Candidates generated from a Markov model
+    Markov,
+    /// Candidates within an edit distance of a base word
+    EditDistance,
+    /// Every ordering of a set of words
+    Permutation,
+    /// Made-up words alternating consonants and vowels
+    Pronounceable,
+    /// A cracker implemented outside this crate
+    #[default]
+    Custom,
+}
+
+impl fmt::Display for CrackMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dictionary => "dictionary",
+            Self::BruteForce => "brute force",
+            Self::Pattern => "pattern",
+            Self::Markov => "Markov",
+            Self::EditDistance => "edit distance",
+            Self::Permutation => "permutation",
+            Self::Pronounceable => "pronounceable",
+            Self::Custom => "custom",
+        })
+    }
+}
+
+/// Statistics of a run, computed by [`CrackResult::to_stats`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrackStats {
+    /// Number of candidates tried
+    pub total_attempts: usize,
+    /// Time since the run started, serialized as fractional seconds
+    #[serde(rename = "elapsed_secs", serialize_with = "serialize_secs")]
+    pub elapsed: Duration,
+    /// Average attempts per second since the run started
+    pub rate_per_sec: f64,
+    /// Whether the password was found
+    pub found: bool,
+    /// The password, if found
+    pub password: Option<String>,
+    /// Time from the start of the run until the password was found,
+    /// serialized as fractional seconds
+    #[serde(rename = "found_at_secs", serialize_with = "serialize_found_at")]
+    pub found_at: Option<Duration>,
+    /// Whether the password is the reversed form of a candidate (`--also-reversed`)
+    pub reversed: bool,
+    /// Values of the variable groups of the pattern candidate the password
+    /// was found with, in pattern order; `None` for other modes
+    pub matched_groups: Option<Vec<String>>,
+    /// Attack mode of the run
+    pub mode: CrackMode,
+    /// Number of candidates skipped by `--filter-regex`, included in `total_attempts`
+    pub filtered: usize,
+    /// Number of candidates skipped by `--max-bytes`, not included in `total_attempts`
+    pub oversized: usize,
+}
+
+fn serialize_secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn serialize_found_at<S: Serializer>(
+    found_at: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match found_at {
+        Some(duration) => serialize_secs(duration, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Size of a combination search space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombinationCount {
+    /// The search space fits into `u128` and holds exactly this many combinations
+    Exact(u128),
+    /// The search space is larger than `u128::MAX`
+    TooLarge,
+}
+
+/// A share of the search space assigned to one machine.
+///
+/// The search space is split into `total` contiguous, non-overlapping units
+/// and only the unit at `index` (0-based) is processed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkUnit {
+    /// 0-based index of the assigned unit
+    pub index: usize,
+    /// Total number of units the search space is split into
+    pub total: usize,
+}
+
+impl WorkUnit {
+    /// The whole search space as a single unit.
+    pub const FULL: Self = Self { index: 0, total: 1 };
+
+    /// Returns `true` if the unit covers the whole search space.
+    pub fn is_full(&self) -> bool {
+        self.total == 1
+    }
+
+    /// Returns the item indices this unit is responsible for.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - Total number of work items in the search space
+    pub fn range(&self, items: u128) -> Range<u128> {
+        // `items * i / total` could overflow, split `items` into whole units
+        // and a remainder smaller than `total` instead.
+        let total = self.total as u128;
+        let (per_unit, rest) = (items / total, items % total);
+        let bound = |i: usize| per_unit * i as u128 + rest * i as u128 / total;
+        bound(self.index)..bound(self.index + 1)
+    }
+}
+
+impl Default for WorkUnit {
+    fn default() -> Self {
+        Self::FULL
     }
 }
 
 /// The interface for password cracking implementations.
 ///
 /// This trait must be implemented by all password cracking strategies.
-pub trait PasswordCracker {
+/// Implementations must be `Sync`, as they are executed inside a session's
+/// thread pool.
+pub trait PasswordCracker: Sync {
     /// Attempts to crack the provided PKCS#12 certificate.
     fn crack(&self, pkcs12: &Arc<Pkcs12>, result: &Arc<Mutex<CrackResult>>) -> Result<()>;
+
+    /// Attempts to crack several certificates in a single pass over the candidates.
+    ///
+    /// Every candidate is tested against all certificates that are not cracked
+    /// yet, the run only stops early once all of them are cracked.
+    ///
+    /// # Errors
+    ///
+    /// The default implementation only supports a single certificate and
+    /// returns an error for more.
+    fn crack_many(
+        &self,
+        pkcs12s: &[Arc<Pkcs12>],
+        results: &[Arc<Mutex<CrackResult>>],
+    ) -> Result<()> {
+        match (pkcs12s, results) {
+            ([pkcs12], [result]) => self.crack(pkcs12, result),
+            _ => bail!("Multiple certificates are only supported in dictionary mode"),
+        }
+    }
+
+    /// Returns the number of candidates in the whole search space, if known up front.
+    ///
+    /// The estimate ignores `--work-unit` and any early exit on success.
+    fn estimate_count(&self) -> Option<u128> {
+        None
+    }
+
+    /// Returns the number of work items `--work-unit` splits, if known up front.
+    ///
+    /// Crackers returning `None` interleave candidates across work units instead.
+    fn work_items(&self) -> Result<Option<CombinationCount>> {
+        Ok(None)
+    }
+
+    /// Returns the attack mode reported in [`CrackStats`].
+    fn mode(&self) -> CrackMode {
+        CrackMode::Custom
+    }
 }
 
 #[cfg(test)]
@@ -58,6 +608,104 @@ mod tests {
         assert_eq!(result.get_attempts(), 1);
     }
 
+    #[test]
+    fn test_crack_stats() {
+        let mut result = CrackResult::new();
+        result.mode = CrackMode::BruteForce;
+        for _ in 0..10 {
+            result.increment_attempts();
+        }
+        result.password = Some("secret".to_string());
+
+        let stats = result.to_stats();
+        assert_eq!(stats.total_attempts, 10);
+        assert!(stats.found);
+        assert_eq!(stats.password.as_deref(), Some("secret"));
+        assert!(stats.rate_per_sec > 0.0);
+
+        let json: serde_json::Value = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["mode"], "brute_force");
+        assert_eq!(json["total_attempts"], 10);
+        assert!(json["elapsed_secs"].is_f64());
+        assert_eq!(json["found_at_secs"], serde_json::Value::Null);
+
+        result.found_at = Some(Duration::from_millis(250));
+        let json: serde_json::Value = serde_json::to_value(result.to_stats()).unwrap();
+        assert_eq!(json["found_at_secs"], 0.25);
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut result = CrackResult::new();
+        assert!(result.run().accepts("anything"));
+
+        result.configure(RunConfig {
+            filter: Some(Arc::new(Regex::new(r"\D$").unwrap())),
+            ..RunConfig::default()
+        });
+        let run = result.run();
+        assert!(run.accepts("pass!"));
+        assert!(!run.accepts("pass1"));
+        assert!(!run.accepts("pass2"));
+        assert_eq!(result.to_stats().filtered, 2);
+    }
+
+    #[test]
+    fn test_filter_does_not_lock_result() {
+        let mut result = CrackResult::new();
+        result.configure(RunConfig {
+            filter: Some(Arc::new(Regex::new("^a").unwrap())),
+            ..RunConfig::default()
+        });
+        let result = Mutex::new(result);
+        let run = result.lock().unwrap().run();
+
+        let _guard = result.lock().unwrap();
+        assert!(run.accepts("abc"));
+        assert!(!run.accepts("bcd"));
+    }
+
+    #[test]
+    fn test_skips_oversized() {
+        let mut result = CrackResult::new();
+        assert!(!result.run().skips_oversized(&"a".repeat(40)));
+
+        result.configure(RunConfig {
+            max_bytes: Some(4),
+            ..RunConfig::default()
+        });
+        let run = result.run();
+        assert!(!run.skips_oversized("pass"));
+        assert!(run.skips_oversized("pässe"));
+        assert!(run.skips_oversized("pä55"));
+        assert_eq!(result.to_stats().oversized, 2);
+    }
+
+    #[test]
+    fn test_configure_keeps_counters() {
+        let mut result = CrackResult::new();
+        result.add_attempts(3);
+        result.run().mark_found();
+        result.configure(RunConfig {
+            pre_hash: true,
+            ..RunConfig::default()
+        });
+        assert!(result.config().pre_hash);
+        assert_eq!(result.get_attempts(), 3);
+        assert!(result.run().is_stopped());
+    }
+
+    #[test]
+    fn test_case_folds() {
+        assert!(CaseSensitivity::Sensitive.folds("Pass").is_empty());
+        assert_eq!(CaseSensitivity::Lower.folds("Pass"), ["pass"]);
+        assert_eq!(CaseSensitivity::Upper.folds("Pass"), ["PASS"]);
+        assert_eq!(CaseSensitivity::Both.folds("Pass"), ["pass", "PASS"]);
+        assert_eq!(CaseSensitivity::Both.folds("pass"), ["PASS"]);
+        assert!(CaseSensitivity::Both.folds("1234").is_empty());
+        assert_eq!(CaseSensitivity::Upper.folds("straße"), ["STRASSE"]);
+    }
+
     #[test]
     fn test_crack_result_multi_threaded() {
         let result = Arc::new(Mutex::new(CrackResult::new()));
@@ -73,4 +721,24 @@ mod tests {
         }
         assert_eq!(result.lock().unwrap().get_attempts(), 100);
     }
+
+    #[test]
+    fn test_work_unit_coverage() {
+        for items in [0, 3, 4, 1001, usize::MAX as u128 + 3, u128::MAX] {
+            let mut next = 0;
+            for index in 0..4 {
+                let range = WorkUnit { index, total: 4 }.range(items);
+                assert_eq!(range.start, next);
+                assert!(range.start <= range.end);
+                next = range.end;
+            }
+            assert_eq!(next, items);
+        }
+    }
+
+    #[test]
+    fn test_work_unit_full() {
+        assert!(WorkUnit::FULL.is_full());
+        assert_eq!(WorkUnit::FULL.range(u128::MAX), 0..u128::MAX);
+    }
 }
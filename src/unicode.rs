@@ -0,0 +1,48 @@
+//! Conversions of raw dictionary words into PKCS#12 passwords.
+//!
+//! PKCS#12 derives its keys from the password as a BMPString, UTF-16BE code
+//! units. Some tools, notably Java keytool, read the typed password as
+//! Latin-1 characters before encoding it, so every byte becomes the code
+//! point of the same value.
+use clap::ValueEnum;
+
+/// Encodings of dictionary words selected by `--word-encoding`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum WordEncoding {
+    /// Every byte is the code point of the same value, ISO-8859-1 proper
+    #[value(name = "latin1", alias = "latin-1", alias = "iso-8859-1")]
+    Latin1,
+}
+
+/// Converts a Latin-1 word into the password PKCS#12 encodes.
+///
+/// The bytes 0x00–0xFF map to the code points U+0000–U+00FF, so the BMPString
+/// of the password holds every byte as a UCS-2 code unit. Unlike Windows-1252,
+/// 0x80–0x9F stay C1 control characters.
+///
+/// # Arguments
+///
+/// * `bytes` - The word as read from a Latin-1 dictionary
+pub fn latin1_to_pkcs12_password(bytes: &[u8]) -> String {
+    bytes.iter().map(|&byte| char::from(byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latin1_to_pkcs12_password() {
+        assert_eq!(latin1_to_pkcs12_password(b"caf\xe9"), "café");
+        assert_eq!(latin1_to_pkcs12_password(b"\x80\x9f\xff"), "\u{80}\u{9f}ÿ");
+        assert_eq!(latin1_to_pkcs12_password(b""), "");
+
+        let password = latin1_to_pkcs12_password(b"\xe9");
+        assert_eq!(password.encode_utf16().collect::<Vec<_>>(), [0x00e9]);
+    }
+}
@@ -0,0 +1,377 @@
+//! Brute force attacks with `-b`, including keyspaces too large to materialize.
+mod common;
+
+use assert_cmd::Command;
+use common::fixture;
+use predicates::prelude::*;
+
+#[test]
+fn test_bruteforce_length_five_streams_candidates() {
+    // 95^5 ≈ 7.7e9 candidates, far beyond what fits in memory as strings
+    Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args(["-b", "-c", "x", "-m", "5", "--max-length", "5"])
+        .arg(fixture("bruteforce.p12"))
+        .timeout(std::time::Duration::from_secs(60))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Successfully found password: aaaab",
+        ));
+}
+
+#[test]
+fn test_bruteforce_chunk_size_one() {
+    Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args(["-b", "--custom-chars", "ab", "-m", "5", "--max-length", "5"])
+        .args(["--chunk-size", "1"])
+        .arg(fixture("bruteforce.p12"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Successfully found password: aaaab",
+        ));
+}
+
+#[test]
+fn test_pattern_chunk_size_one() {
+    Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args(["-p", "p@55", "--custom-chars", "aäöü", "--chunk-size", "1"])
+        .arg(fixture("umlaut.p12"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Successfully found password: pä55",
+        ));
+}
+
+#[test]
+fn test_bruteforce_huge_chunk_exits_early() {
+    // Every thread gets a chunk of a million candidates, the run must still
+    // stop as soon as one of them finds the password
+    Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args(["-b", "-c", "x", "-m", "5", "--max-length", "5"])
+        .args(["--chunk-size", "1000000", "-t", "4"])
+        .arg(fixture("bruteforce.p12"))
+        .timeout(std::time::Duration::from_secs(60))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Successfully found password: aaaab",
+        ));
+}
+
+#[test]
+fn test_piped_output_has_no_carriage_returns() {
+    // Long enough for several status lines, which must not be drawn in place
+    Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args(["-b", "-c", "n", "--max-length", "4", "-t", "2"])
+        .args(["--status-interval", "1"])
+        .arg(fixture("umlaut.p12"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Status: "))
+        .stdout(predicate::str::is_match(r#"length 4: .* \| at "\d{4}""#).unwrap())
+        .stdout(predicate::str::contains("\r").not());
+}
+
+#[test]
+fn test_bruteforce_policy() {
+    Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args([
+            "-b",
+            "--custom-chars",
+            "5äpP",
+            "-m",
+            "4",
+            "--max-length",
+            "4",
+        ])
+        .args(["--require", "lower", "--require-count", "digit=2"])
+        .arg(fixture("umlaut.p12"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Policy lower>=1,digit>=2: 4806 of 707281 candidates",
+        ))
+        .stdout(predicate::str::contains(
+            "Successfully found password: pä55",
+        ));
+}
+
+#[test]
+fn test_length_orders() {
+    // "00012" is early in length 5: descending and interleaved reach it long
+    // before ascending has tried the 11110 shorter candidates
+    for (order, attempts) in [
+        ("ascending", 11123),
+        ("descending", 13),
+        ("interleaved", 323),
+    ] {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "-c", "n", "-m", "1", "--max-length", "5", "-t", "1"])
+            .args(["--chunk-size", "100", "--length-order", order])
+            .arg(fixture("early.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: 00012",
+            ))
+            .stdout(predicate::str::contains(format!(
+                "Total attempts: {attempts}\n"
+            )));
+    }
+}
+
+#[test]
+fn test_interleave_lengths() {
+    Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args(["-b", "-c", "n", "-m", "1", "--max-length", "5", "-t", "1"])
+        .args(["--chunk-size", "100", "--interleave-lengths"])
+        .arg(fixture("early.p12"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total attempts: 323\n"));
+
+    Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args([
+            "-b",
+            "-c",
+            "n",
+            "--interleave-lengths",
+            "--length-order",
+            "ascending",
+        ])
+        .arg(fixture("early.p12"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+mod alphabet_test {
+    //! Printing the resolved character set via `--alphabet-test`.
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_alphabet_test_without_certificate() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--alphabet-test", "-c", "n", "--custom-chars", "öä1"])
+            .assert()
+            .success()
+            .stdout(
+                "U+0030 '0'\nU+0031 '1'\nU+0032 '2'\nU+0033 '3'\nU+0034 '4'\n\
+             U+0035 '5'\nU+0036 '6'\nU+0037 '7'\nU+0038 '8'\nU+0039 '9'\n\
+             U+00E4 'ä'\nU+00F6 'ö'\nTotal characters: 12\n",
+            );
+    }
+
+    #[test]
+    fn test_alphabet_test_counts_presets_once() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--alphabet-test", "-c", "aAn", "--custom-chars", "a!"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("U+0021 '!'\nU+0030 '0'\n"))
+            .stdout(predicate::str::ends_with("Total characters: 63\n"));
+    }
+
+    #[test]
+    fn test_alphabet_test_rejects_invalid_charset() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--alphabet-test", "--charset-range", "U+0041-U+0030"])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("Total characters").not());
+    }
+}
+
+mod no_repeats {
+    //! Brute force without repeated characters with `--no-repeats`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::collections::HashSet;
+    use std::fs;
+
+    #[test]
+    fn test_no_repeats_emits_distinct_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("candidates.txt");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "-c", "n", "--min-length", "1", "--max-length", "3"])
+            .args(["--no-repeats", "-t", "1", "--log-candidates"])
+            .arg(&log)
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Keyspace for a charset of 10 characters without repeats:",
+            ))
+            // 10 + 10·9 + 10·9·8
+            .stdout(predicate::str::contains("Total attempts: 820\n"));
+
+        let content = fs::read_to_string(&log).unwrap();
+        let candidates: Vec<&str> = content.lines().collect();
+        assert_eq!(candidates.len(), 820);
+        assert_eq!(candidates.iter().collect::<HashSet<_>>().len(), 820);
+        for candidate in candidates {
+            let chars: HashSet<char> = candidate.chars().collect();
+            assert_eq!(chars.len(), candidate.chars().count(), "{candidate}");
+        }
+    }
+
+    #[test]
+    fn test_no_repeats_start_at() {
+        // "word40" and "word41" come right before "word42"
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "-c", "an", "--min-length", "6", "--max-length", "6"])
+            .args(["--no-repeats", "--start-at", "word40", "--limit", "5"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 3\n"));
+    }
+
+    #[test]
+    fn test_no_repeats_dry_run_keyspace() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "-c", "aAn", "--min-length", "8", "--max-length", "8"])
+            .args(["--no-repeats", "--dry-run"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            // 62·61·…·55 instead of 62^8 = 2.2e14
+            .stdout(predicate::str::contains("Estimated candidates: 1.4e14 "));
+    }
+
+    #[test]
+    fn test_no_repeats_reverse_start_at() {
+        // Length 3 has 3·2·1 candidates without repeats, not 3^3
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-b",
+                "-m",
+                "3",
+                "--max-length",
+                "3",
+                "--custom-chars",
+                "ak7",
+                "-c",
+                "",
+            ])
+            .args(["--no-repeats", "--reverse", "--start-at", "ka7", "-t", "1"])
+            .arg(fixture("pronounceable.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Successfully found password: ka7"))
+            .stdout(predicate::str::contains("Total attempts: 1\n"));
+    }
+}
+
+mod numeric_order {
+    //! Counting digit positions upward via `--numeric-order`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::fs;
+
+    #[test]
+    fn test_digits_count_up_from_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("candidates.txt");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "-c", "n", "--numeric-order", "asc:54"])
+            .arg("--deterministic")
+            .arg("--log-candidates")
+            .arg(&log)
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 2\n"));
+        assert_eq!(fs::read_to_string(&log).unwrap(), "pä54\npä55\n");
+    }
+
+    #[test]
+    fn test_numeric_order_requires_pattern() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "-c", "n", "--numeric-order", "asc:1999"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod pronounceable {
+    //! Pronounceable brute force with `--pronounceable`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_pronounceable_with_digits() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--pronounceable", "--pronounceable-digits", "1"])
+            .args(["-m", "3", "--max-length", "3", "-t", "1"])
+            .arg(fixture("pronounceable.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Estimated candidates: 4930 "))
+            .stdout(predicate::str::contains("Successfully found password: ka7"));
+    }
+
+    #[test]
+    fn test_pronounceable_without_digits() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-b",
+                "--pronounceable",
+                "-m",
+                "3",
+                "--max-length",
+                "3",
+                "-t",
+                "1",
+            ])
+            .arg(fixture("pronounceable.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"))
+            .stdout(predicate::str::contains("Total attempts: 2780\n"));
+    }
+
+    #[test]
+    fn test_pronounceable_conflicts_with_charset() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--pronounceable", "-c", "n"])
+            .arg(fixture("pronounceable.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+}
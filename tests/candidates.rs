@@ -0,0 +1,572 @@
+//! Options ordering, filtering, logging and testing the candidates of any attack.
+mod common;
+
+mod filter {
+    //! Skipping candidates with `--filter-regex`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_filter_dictionary() {
+        let wordlist = temp_file("foo\nbar\npä55");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .args(["-t", "1", "--filter-regex", "^p"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stdout(predicate::str::contains("Skipped by --filter-regex: 2"));
+    }
+
+    #[test]
+    fn test_filter_pattern_never_tests_rejected() {
+        // Every candidate ends in a digit, so none is tested
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä5@", "--custom-chars", "0123456789"])
+            .args(["--filter-regex", r"\D$"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"))
+            .stdout(predicate::str::contains("Skipped by --filter-regex: 10"));
+    }
+
+    #[test]
+    fn test_invalid_filter_rejected() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--filter-regex", "(unclosed"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid --filter-regex"));
+    }
+}
+
+mod max_bytes {
+    //! Skipping long candidates with `--max-bytes`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_max_bytes_dictionary() {
+        // "üüüüü" has five characters but ten bytes, "pä55" fits in five bytes
+        let wordlist = temp_file("foo\nüüüüü\npä55");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .args(["-t", "1", "--max-bytes", "5"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 2"))
+            .stdout(predicate::str::contains("Skipped by --max-bytes: 1"));
+    }
+
+    #[test]
+    fn test_max_bytes_bruteforce() {
+        // 27 characters of which "ä" takes two bytes: the 53 candidates of length
+        // two containing it are skipped, the other 703 are tested
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--custom-chars", "ä", "--max-length", "2"])
+            .args(["--max-bytes", "2"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"))
+            .stdout(predicate::str::contains("Total attempts: 703"))
+            .stdout(predicate::str::contains("Skipped by --max-bytes: 53"));
+    }
+
+    #[test]
+    fn test_long_candidates_warn_without_max_bytes() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "üüüüüüüü@", "--custom-chars", "0123456789"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("longer than 15 bytes").count(1))
+            .stdout(predicate::str::contains("Skipped by --max-bytes").not());
+    }
+}
+
+mod multibyte {
+    //! End-to-end cracking of certificates whose passwords contain multibyte characters.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_pattern_cracks_umlaut_password() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@55", "--custom-chars", "äöü"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_dictionary_cracks_accented_password() {
+        let wordlist = temp_file("cafe\ncafè\ncafé\ncaff\n");
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .arg(fixture("accent.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: café",
+            ));
+    }
+
+    #[test]
+    fn test_bruteforce_cracks_cjk_password() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-b",
+                "-c",
+                "n",
+                "--custom-chars",
+                "密码",
+                "--max-length",
+                "2",
+            ])
+            .arg(fixture("cjk.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Successfully found password: 密1"));
+    }
+
+    #[test]
+    fn test_pattern_cracks_multibyte_fixed_and_charset() {
+        // "Straße@@" with 13 characters
+        for extra in [
+            &[][..],
+            &["--reverse"],
+            &["--random-order", "--seed", "7"],
+            &["--work-unit", "1/2"],
+        ] {
+            Command::cargo_bin("pkcs12cracker")
+                .unwrap()
+                .args(["-p", "Straße@@", "-c", "n", "--custom-chars", "äöü"])
+                .args(extra)
+                .arg(fixture("strasse.p12"))
+                .assert()
+                .success()
+                .stdout(predicate::str::contains(
+                    "Successfully found password: Straßeöü",
+                ));
+        }
+    }
+
+    #[test]
+    fn test_pattern_multibyte_keyspace_and_max_bytes() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "Straße@@", "-c", "n", "--custom-chars", "äöü"])
+            .arg("--dry-run")
+            .arg(fixture("strasse.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Search space: 169 work items"));
+
+        // "Straßeöü" takes 11 bytes, the 9 candidates ending in two umlauts are skipped
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "Straße@@", "-c", "n", "--custom-chars", "äöü"])
+            .args(["--max-bytes", "10"])
+            .arg(fixture("strasse.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"))
+            .stdout(predicate::str::contains("Skipped by --max-bytes: 9"));
+    }
+}
+
+mod pre_hash {
+    //! Checking the MAC of every candidate before decrypting it via `--pre-hash`.
+    use crate::common::{fixture, wordlist};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_pre_hash_finds_password() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "--custom-chars", "5ab", "--pre-hash"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_pre_hash_checks_case_folds() {
+        let words = wordlist(&["letmein", "password", "secret"]);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .args(["--pre-hash", "--case-insensitive=upper"])
+            .arg(fixture("uppercase.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: PASSWORD",
+            ));
+    }
+}
+
+mod log_candidates {
+    //! Logging of the tested candidates with --log-candidates.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_log_candidates_pattern() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("candidates.txt");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "word4@", "-c", "n", "-t", "1", "--log-candidates"])
+            .arg(&log)
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ))
+            .stderr(predicate::str::contains(
+                "Warning: --log-candidates writes every tested candidate",
+            ));
+
+        let contents = std::fs::read_to_string(&log).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert!(lines.contains(&"word42"));
+        assert!(lines
+            .iter()
+            .all(|line| line.len() == 6 && line.starts_with("word4")));
+    }
+
+    #[test]
+    fn test_log_candidates_once_per_candidate() {
+        let dir = tempfile::tempdir().unwrap();
+        let wordlist = dir.path().join("words.txt");
+        std::fs::write(&wordlist, "secret\npä55\nword42\nletmein\n").unwrap();
+        let log = dir.path().join("candidates.txt");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-t", "1", "-d"])
+            .arg(&wordlist)
+            .arg("--log-candidates")
+            .arg(&log)
+            .arg(fixture("umlaut.p12"))
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success();
+
+        // Both certificates are cracked after "word42", "letmein" is never tested
+        let contents = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(contents, "secret\npä55\nword42\n");
+    }
+}
+
+mod show_candidates {
+    //! Previewing the first candidates via `--show-candidates`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_show_candidates_pattern() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@", "-c", "n", "--show-candidates", "3"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Mode: pattern\n"))
+            .stdout(predicate::str::contains("Keyspace: 10 candidates\n"))
+            .stdout(predicate::str::contains(
+                "First 3 candidates:\npä0\npä1\npä2\n",
+            ))
+            .stdout(predicate::str::contains("Successfully found password").not());
+    }
+
+    #[test]
+    fn test_show_candidates_applies_rules_and_filter() {
+        let words = temp_file("foo\nbar\n");
+        let rules = temp_file(":\nu\n$1\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--rules")
+            .arg(rules.path())
+            .args(["--filter-regex", "^[a-z]", "--show-candidates", "10"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Mode: dictionary\n"))
+            .stdout(predicate::str::contains(
+                "First 4 candidates:\nfoo\nfoo1\nbar\nbar1\n",
+            ));
+    }
+
+    #[test]
+    fn test_show_candidates_conflicts_with_dry_run() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@", "--show-candidates", "3", "--dry-run"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod deterministic {
+    //! Reproducible results with `--deterministic`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn total_attempts(stdout: &[u8]) -> String {
+        let stdout = String::from_utf8_lossy(stdout);
+        stdout
+            .lines()
+            .find(|line| line.starts_with("Total attempts:"))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_bruteforce_attempts_match_index() {
+        // "00012" is candidate 12 of length 5, nothing after it is tested
+        for _ in 0..2 {
+            Command::cargo_bin("pkcs12cracker")
+                .unwrap()
+                .args(["-b", "-c", "n", "-m", "5", "--max-length", "5"])
+                .args(["--deterministic", "-t", "4"])
+                .arg(fixture("early.p12"))
+                .assert()
+                .success()
+                .stdout(predicate::str::contains("deterministic order on 1 thread"))
+                .stdout(predicate::str::contains(
+                    "Successfully found password: 00012",
+                ))
+                .stdout(predicate::str::contains("Total attempts: 13\n"));
+        }
+    }
+
+    #[test]
+    fn test_random_order_is_reproducible() {
+        let run = || {
+            Command::cargo_bin("pkcs12cracker")
+                .unwrap()
+                .args(["-p", "000@@", "-c", "n", "--random-order", "--seed", "7"])
+                .arg("--deterministic")
+                .arg(fixture("early.p12"))
+                .assert()
+                .success()
+                .stdout(predicate::str::contains(
+                    "Successfully found password: 00012",
+                ))
+                .get_output()
+                .stdout
+                .clone()
+        };
+        assert_eq!(total_attempts(&run()), total_attempts(&run()));
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-p",
+                "000@@",
+                "-c",
+                "n",
+                "--random-order",
+                "--deterministic",
+            ])
+            .arg(fixture("early.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "--deterministic with --random-order requires --seed",
+            ));
+    }
+
+    #[test]
+    fn test_candidate_log_in_traversal_order() {
+        let dir = TempDir::new().unwrap();
+        let log = dir.path().join("candidates.txt");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-p",
+                "000@@",
+                "-c",
+                "n",
+                "--deterministic",
+                "--log-candidates",
+            ])
+            .arg(&log)
+            .arg(fixture("early.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Total attempts: 13\n"));
+
+        let expected: Vec<String> = (0..=12).map(|i| format!("000{i:02}")).collect();
+        let logged = fs::read_to_string(&log).unwrap();
+        assert_eq!(logged.lines().collect::<Vec<_>>(), expected);
+    }
+}
+
+mod probability_model {
+    //! Weighted candidate order with `--probability-model`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::fs;
+
+    fn write_model(dir: &tempfile::TempDir, json: &str) -> String {
+        let path = dir.path().join("model.json");
+        fs::write(&path, json).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_pattern_tries_likely_digits_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = write_model(
+            &dir,
+            r#"{"4": 0.5, "2": 0.4, "0": 0.01, "1": 0.01, "3": 0.01,
+            "5": 0.01, "6": 0.01, "7": 0.01, "8": 0.01, "9": 0.01}"#,
+        );
+        // "word44" is the most likely candidate, "word42" the second one
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "word@@", "-c", "n", "-t", "1"])
+            .args(["--probability-model", &model])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 2\n"));
+    }
+
+    #[test]
+    fn test_random_order_with_missing_weights() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = write_model(&dir, r#"{"4": 0.5, "2": 0.4, "0": 0.01}"#);
+        Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args(["-p", "word@@", "-c", "n", "-t", "1"])
+        .args([
+            "--probability-model",
+            &model,
+            "--random-order",
+            "--seed",
+            "3",
+        ])
+        .arg(fixture("word42.p12"))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "Warning: the probability model has no weight for 1356789, using its smallest weight",
+        ))
+        .stdout(predicate::str::contains(
+            "Successfully found password: word42",
+        ));
+    }
+
+    #[test]
+    fn test_probability_model_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let model = write_model(&dir, r#"{"ab": 0.5}"#);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "word@@", "-c", "n", "--probability-model", &model])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "key \"ab\" is not a single character",
+            ));
+
+        let model = write_model(&dir, r#"{"a": 0.5}"#);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--permutation-words", "a,b", "--probability-model", &model])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "--probability-model requires --pattern, --pattern-file or --brute-force",
+            ));
+    }
+}
+
+mod estimate_time {
+    //! Estimating the run time with a benchmark via `--estimate-time`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_estimate_time_precedes_full_run() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "--custom-chars", "5ab", "-t", "1"])
+            .args(["--estimate-time", "1"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(
+                predicate::str::is_match(
+                    r"Benchmark: \d+ attempts/sec, estimated .+ for 729 candidates",
+                )
+                .unwrap(),
+            )
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_estimate_time_conflicts_with_dry_run() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "--estimate-time", "1", "--dry-run"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
@@ -0,0 +1,284 @@
+//! Reading, inspecting and cracking the certificates.
+mod common;
+
+mod path {
+    //! Validation of the certificate paths.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    fn crack(path: &std::path::Path) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "word4@", "-c", "n", "-t", "1"])
+            .arg(path)
+            .assert()
+    }
+
+    #[test]
+    fn test_certificate_without_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cert");
+        std::fs::copy(fixture("word42.p12"), &path).unwrap();
+        crack(&path)
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ))
+            .stderr(predicate::str::contains("Warning").not());
+    }
+
+    #[test]
+    fn test_certificate_with_other_extension_warns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cert.bin");
+        std::fs::copy(fixture("word42.p12"), &path).unwrap();
+        crack(&path)
+            .success()
+            .stderr(predicate::str::contains(
+                "does not have a .p12, .pfx or .pem extension",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ));
+    }
+
+    #[test]
+    fn test_missing_certificate_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        crack(&dir.path().join("missing.p12"))
+            .failure()
+            .stderr(predicate::str::contains("Certificate file not found"));
+    }
+
+    #[test]
+    fn test_force_skips_validation() {
+        // The missing file is only noticed when it is loaded
+        let dir = tempfile::tempdir().unwrap();
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "word4@", "-c", "n", "--force"])
+            .arg(dir.path().join("missing.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Certificate file not found"));
+    }
+}
+
+mod words {
+    //! Candidates derived from the certificate with `--words-from-certificate`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_subject_common_name_is_tried_first() {
+        // The certificate is stored unencrypted and its CN is the password
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "--words-from-certificate",
+                "-b",
+                "--max-length",
+                "2",
+                "-t",
+                "1",
+            ])
+            .arg(fixture("metadata.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "candidates from certificate metadata",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: Initech",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 1\n"))
+            .stdout(predicate::str::contains("Starting brute force").not());
+    }
+
+    #[test]
+    fn test_falls_back_without_readable_metadata() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "--words-from-certificate",
+                "-p",
+                "p@55",
+                "--custom-chars",
+                "ä",
+                "-t",
+                "1",
+            ])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "No certificate metadata is readable without the password",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+}
+
+mod cert_info {
+    //! Showing the MAC and encryption algorithms of a certificate via `--show-cert-info`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_show_cert_info_without_mode() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("--show-cert-info")
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "MAC algorithm: HMAC-SHA256\n\
+             Encryption algorithm: PBES2 (PBKDF2 with HMAC-SHA256, AES-256-CBC)\n\
+             Iterations: 2048\n",
+            ))
+            .stdout(predicate::str::contains("Starting").not())
+            .stderr(predicate::str::contains("Warning").not());
+    }
+
+    #[test]
+    fn test_show_cert_info_warns_about_weak_iterations() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("--show-cert-info")
+            .arg(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/benchmarks/exportado.p12"
+            ))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Iterations: 1\n"))
+            .stderr(predicate::str::contains(
+                "has only 1 iterations, fewer than 1024",
+            ));
+    }
+
+    #[test]
+    fn test_show_cert_info_rejects_other_data() {
+        let file = temp_file("not a certificate");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("--show-cert-info")
+            .arg(file.path())
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "The data is not a DER encoded PKCS#12 file",
+            ));
+    }
+}
+
+mod multiple {
+    //! Dictionary attacks on several certificates in a single pass.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_all_certificates_cracked() {
+        let wordlist = temp_file("password\ncafé\n€uro\npä55\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .args([
+                fixture("accent.p12"),
+                fixture("euro.p12"),
+                fixture("umlaut.p12"),
+            ])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "accent.p12: Successfully found password: café",
+            ))
+            .stdout(predicate::str::contains(
+                "euro.p12: Successfully found password: €uro",
+            ))
+            .stdout(predicate::str::contains(
+                "umlaut.p12: Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_continues_after_first_match() {
+        let wordlist = temp_file("café\nfoo\nbar");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .args([fixture("accent.p12"), fixture("euro.p12")])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "accent.p12: Successfully found password: café",
+            ))
+            .stdout(predicate::str::contains("euro.p12: Password not found"))
+            .stdout(predicate::str::contains("euro.p12: Total attempts: 3"));
+    }
+
+    #[test]
+    fn test_multiple_certificates_require_dictionary() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "-c", "a", "--max-length", "2"])
+            .args([fixture("accent.p12"), fixture("euro.p12")])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Multiple certificates are only supported in dictionary mode",
+            ));
+    }
+}
+
+mod stdin {
+    //! Reading the certificate from stdin with `-`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_certificate_from_stdin() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@55", "--custom-chars", "aä", "-"])
+            .pipe_stdin(fixture("umlaut.p12"))
+            .unwrap()
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_short_stdin_fails() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@55", "-"])
+            .write_stdin([0x30, 0x82])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Certificate from stdin has 2 bytes",
+            ));
+    }
+
+    #[test]
+    fn test_stdin_only_once() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-d", "wordlist.txt", "-", "-"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Stdin can only be read once"));
+    }
+}
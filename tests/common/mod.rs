@@ -0,0 +1,25 @@
+//! Helpers shared by the integration tests.
+#![allow(dead_code)]
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+/// Returns the path of a certificate in `tests/fixtures`.
+pub fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Writes `content` to a temporary file, such as a wordlist or a rules file.
+pub fn temp_file(content: impl AsRef<[u8]>) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(content.as_ref()).unwrap();
+    file
+}
+
+/// Writes a wordlist with one word per line.
+pub fn wordlist(words: &[&str]) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    for word in words {
+        writeln!(file, "{word}").unwrap();
+    }
+    file
+}
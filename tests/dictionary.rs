@@ -0,0 +1,672 @@
+//! Dictionary attacks and the attacks deriving candidates from words.
+mod common;
+
+mod also_reversed {
+    //! Candidates also tested reversed via `--also-reversed`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_reversed_word_is_found() {
+        // Palindromes are tested once, the other words twice
+        let words = temp_file("abba\nfoo\n55äp\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .args(["--also-reversed", "-t", "1"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55 (reversed candidate)",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 5\n"));
+    }
+
+    #[test]
+    fn test_reversed_pattern_is_found() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "5@äp", "-c", "n", "--also-reversed", "-t", "1"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55 (reversed candidate)",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 12\n"));
+    }
+
+    #[test]
+    fn test_forward_match_is_not_marked() {
+        let words = temp_file("pä55\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .args(["--also-reversed", "--output", "json"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                r#""password":"pä55","found_at_secs""#,
+            ))
+            .stdout(predicate::str::contains(r#""reversed":false"#));
+    }
+
+    #[test]
+    fn test_also_reversed_requires_dictionary_or_pattern() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--also-reversed"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod case_insensitive {
+    //! Testing case folds of every candidate via `--case-insensitive`.
+    use crate::common::{fixture, wordlist};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_uppercase_fold_finds_password() {
+        let words = wordlist(&["letmein", "password", "secret"]);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--case-insensitive=upper")
+            .arg(fixture("uppercase.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: PASSWORD",
+            ));
+    }
+
+    #[test]
+    fn test_lowercase_fold_is_the_default() {
+        let words = wordlist(&["PÄ55"]);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--case-insensitive")
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 1\n"));
+    }
+
+    #[test]
+    fn test_case_sensitive_without_flag() {
+        let words = wordlist(&["password"]);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg(fixture("uppercase.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"));
+    }
+}
+
+mod delimiter {
+    //! Dictionary entry delimiters of several bytes and escaped delimiters via `--delimiter`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_multi_byte_delimiter() {
+        let words = temp_file("p|ä55||alpha||pä55||beta");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .args(["--delimiter", "||", "--no-trim"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 3\n"));
+    }
+
+    #[test]
+    fn test_escaped_delimiters() {
+        for (delimiter, content) in [("\\t", "alpha\tpä55"), ("\\r\\n", "alpha\r\npä55\r\n")] {
+            let words = temp_file(content);
+            Command::cargo_bin("pkcs12cracker")
+                .unwrap()
+                .arg("-d")
+                .arg(words.path())
+                .args(["--delimiter", delimiter, "--no-trim"])
+                .arg(fixture("umlaut.p12"))
+                .assert()
+                .success()
+                .stdout(predicate::str::contains(
+                    "Successfully found password: pä55",
+                ));
+        }
+    }
+
+    #[test]
+    fn test_empty_delimiter() {
+        let words = temp_file("pä55");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .args(["--delimiter", ""])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("The delimiter is empty"));
+    }
+}
+
+mod encodings {
+    //! Dictionary attacks on wordlists in non-UTF-8 encodings.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use tempfile::NamedTempFile;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| unit.to_le_bytes())
+            .collect()
+    }
+
+    fn crack(wordlist: &NamedTempFile, encoding: &str, cert: &str, password: &str) {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .args(["--wordlist-encoding", encoding])
+            .arg(fixture(cert))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!(
+                "Successfully found password: {password}"
+            )));
+    }
+
+    #[test]
+    fn test_utf8_wordlist() {
+        let wordlist = temp_file("euro\n€uro\n".as_bytes());
+        crack(&wordlist, "utf-8", "euro.p12", "€uro");
+    }
+
+    #[test]
+    fn test_latin1_wordlist() {
+        let wordlist = temp_file(b"cafe\ncaf\xe9\n");
+        crack(&wordlist, "latin-1", "accent.p12", "café");
+    }
+
+    #[test]
+    fn test_windows1252_wordlist() {
+        let wordlist = temp_file(b"euro\n\x80uro\n");
+        crack(&wordlist, "windows-1252", "euro.p12", "€uro");
+    }
+
+    #[test]
+    fn test_utf16le_wordlist() {
+        let wordlist = temp_file(utf16le("cafe\ncafé\n"));
+        crack(&wordlist, "utf-16le", "accent.p12", "café");
+    }
+
+    #[test]
+    fn test_bom_overrides_encoding() {
+        let utf16 = temp_file(utf16le("\u{feff}euro\n€uro\n"));
+        crack(&utf16, "latin-1", "euro.p12", "€uro");
+
+        let utf8 = temp_file("\u{feff}euro\n€uro\n".as_bytes());
+        crack(&utf8, "latin-1", "euro.p12", "€uro");
+    }
+
+    #[test]
+    fn test_latin1_word_encoding() {
+        let wordlist = temp_file(b"cafe\ncaf\xe9\x80\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .args(["--word-encoding", "latin1"])
+            .arg(fixture("iso8859_1.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: café\u{80}",
+            ));
+
+        // Windows-1252 reads 0x80 as "€"
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .args(["--wordlist-encoding", "latin-1"])
+            .arg(fixture("iso8859_1.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"));
+
+        let accent = temp_file(b"caf\xe9\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(accent.path())
+            .args(["--word-encoding", "latin1"])
+            .arg(fixture("accent.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: café",
+            ));
+    }
+}
+
+mod first_n {
+    //! Testing a window of the dictionary via `--skip-n` and `--first-n`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    fn wordlist() -> tempfile::NamedTempFile {
+        temp_file((0..50).map(|i| format!("word{i}\n\n")).collect::<String>())
+    }
+
+    #[test]
+    fn test_first_n_tests_exactly_n_words() {
+        let words = wordlist();
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .args(["--skip-n", "30", "--first-n", "10"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Tested dictionary entries 31 to 40",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 10\n"));
+    }
+
+    #[test]
+    fn test_first_n_window_finds_password() {
+        let words = wordlist();
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .args(["--skip-n", "40", "--first-n", "5", "-t", "1"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ));
+    }
+
+    #[test]
+    fn test_first_n_conflicts_with_work_unit() {
+        let words = wordlist();
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .args(["--first-n", "10", "--work-unit", "0/2"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod john_rules {
+    //! John the Ripper rules mangling dictionary entries via `--john-rules`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    const JOHN_CONF: &str = "\
+[Options]
+Wordlist = $JOHN/password.lst
+
+[List.Rules:Wordlist]
+:
+# Long words uppercased
+>8 u
+M Q
+
+[List.Rules:Digits]
+-c l $[0-9]$[0-9]
+";
+
+    #[test]
+    fn test_john_rules_find_password() {
+        let words = temp_file("WORD\n");
+        let conf = temp_file(JOHN_CONF);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--john-rules")
+            .arg(conf.path())
+            .args(["--john-rules-section", "digits"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Loaded 100 rules from [List.Rules:digits]",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 43\n"));
+    }
+
+    #[test]
+    fn test_john_rules_default_section() {
+        let words = temp_file("word42\n");
+        let conf = temp_file(JOHN_CONF);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--john-rules")
+            .arg(conf.path())
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Loaded 2 rules from [List.Rules:Wordlist]",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ))
+            .stderr(predicate::str::contains("skipped 1 unsupported rules of"));
+    }
+
+    #[test]
+    fn test_john_rules_missing_section() {
+        let words = temp_file("word42\n");
+        let conf = temp_file(JOHN_CONF);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--john-rules")
+            .arg(conf.path())
+            .args(["--john-rules-section", "Jumbo"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("No section [List.Rules:Jumbo]"));
+    }
+
+    #[test]
+    fn test_john_rules_conflict_with_hashcat_rules() {
+        let words = temp_file("word42\n");
+        let conf = temp_file(JOHN_CONF);
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--john-rules")
+            .arg(conf.path())
+            .arg("--rules")
+            .arg(conf.path())
+            .arg(fixture("word42.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod rules {
+    //! Hashcat rules mangling dictionary entries via `--rules`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_rules_find_password() {
+        let words = temp_file("alpha\nPÄ\nbeta\n");
+        let rules = temp_file("# lowercase with two fives\n:\nl $5 $5\nx04\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--rules")
+            .arg(rules.path())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Loaded 2 rules"))
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stderr(predicate::str::contains("skipped 1 unsupported rules of"))
+            .stderr(predicate::str::contains("first on line 4"));
+    }
+
+    #[test]
+    fn test_rules_keyspace() {
+        let words = temp_file("alpha\nbeta\ngamma");
+        let rules = temp_file(":\nu\nc\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--rules")
+            .arg(rules.path())
+            .args(["--append-numbers-fixed", "1", "--dry-run"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            // 3 entries, 3 rules, each without and with 10 digits
+            .stdout(predicate::str::contains("Estimated candidates: 99 "));
+    }
+
+    #[test]
+    fn test_rules_without_supported_rule() {
+        let words = temp_file("pä55\n");
+        let rules = temp_file("x04\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .arg("--rules")
+            .arg(rules.path())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("No supported rules"));
+    }
+
+    #[test]
+    fn test_rules_require_dictionary() {
+        let rules = temp_file(":\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-b")
+            .arg("--rules")
+            .arg(rules.path())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod wordlist_sort {
+    //! Sorting dictionaries with `--wordlist-sort`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use tempfile::NamedTempFile;
+
+    /// A word list whose most frequent entry, "word42", comes last in the file.
+    fn wordlist() -> NamedTempFile {
+        temp_file("alpha\nbeta\nword42\ngamma\nbeta\nword42\ndelta\nword42")
+    }
+
+    fn crack_sorted(extra: &[&str]) {
+        let wordlist = wordlist();
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .args(["-t", "1", "--wordlist-sort", "frequency"])
+            .args(extra)
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Sorted dictionary by frequency"))
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 1\n"));
+    }
+
+    #[test]
+    fn test_frequency_sort_tries_most_common_first() {
+        crack_sorted(&[]);
+    }
+
+    #[test]
+    fn test_external_frequency_sort() {
+        crack_sorted(&["--wordlist-sort-chunk", "1"]);
+    }
+
+    #[test]
+    fn test_sort_chunk_requires_sort() {
+        let wordlist = wordlist();
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(wordlist.path())
+            .args(["--wordlist-sort-chunk", "1"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--wordlist-sort"));
+    }
+}
+
+mod edit_distance {
+    //! Edit-distance attack with `--mutate`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_mutate_finds_transposition() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--mutate", "wrod42", "-t", "1"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ));
+    }
+
+    #[test]
+    fn test_mutate_base_word_first() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--mutate", "word42", "--distance", "2", "-t", "1"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Total attempts: 1\n"));
+    }
+
+    #[test]
+    fn test_mutate_warns_above_distance_two() {
+        // The password is one edit away, so distance 3 is never generated
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--mutate", "word4", "--distance", "3", "-c", "n", "-t", "1"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "Warning: distance 3 can produce up to",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ));
+    }
+
+    #[test]
+    fn test_distance_requires_mutate() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--distance", "2"])
+            .arg(fixture("word42.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--mutate"));
+    }
+}
+
+mod permutation {
+    //! Permutation attack with `--permutation-words`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_permutation_finds_ordering() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--permutation-words", "Foo,Bar,Baz", "-t", "1"])
+            .arg(fixture("permutation.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: BazFooBar",
+            ));
+    }
+
+    #[test]
+    fn test_permutation_max_n_ignores_words() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--permutation-words", "Foo,Bar,Baz,Qux", "-t", "1"])
+            .args(["--permutation-max-n", "3"])
+            .arg(fixture("permutation.p12"))
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "Warning: --permutation-max-n 3 ignores the words Qux",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: BazFooBar",
+            ));
+    }
+
+    #[test]
+    fn test_permutation_dry_run_estimate() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--permutation-words", "a,b,c,d,e,f,g,h,i", "--dry-run"])
+            .arg(fixture("permutation.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Estimated candidates: 362880 "));
+    }
+}
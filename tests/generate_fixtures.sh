@@ -0,0 +1,70 @@
+#!/bin/sh
+# Generates the PKCS#12 test fixtures in tests/fixtures with known passwords.
+#
+# Usage: tests/generate_fixtures.sh [--force]
+#
+# Existing fixtures are kept unless --force is given, so running the script
+# only adds fixtures that are missing. Every fixture gets a fresh key, the
+# tests only rely on the passwords listed below.
+set -eu
+
+force=0
+if [ "${1:-}" = "--force" ]; then
+    force=1
+fi
+
+dir=$(cd "$(dirname "$0")" && pwd)/fixtures
+work=$(mktemp -d)
+trap 'rm -rf "$work"' EXIT
+mkdir -p "$dir"
+
+# Creates a self-signed certificate and key for the given subject
+certificate() {
+    openssl req -x509 -newkey rsa:2048 -nodes -days 3650 -subj "$1" \
+        -keyout "$work/key.pem" -out "$work/cert.pem" 2>/dev/null
+}
+
+# fixture NAME PASSWORD [OPENSSL PKCS12 OPTIONS...]
+fixture() {
+    name=$1
+    password=$2
+    shift 2
+    out="$dir/$name.p12"
+    if [ -e "$out" ] && [ "$force" -eq 0 ]; then
+        return
+    fi
+    openssl pkcs12 -export -inkey "$work/key.pem" -in "$work/cert.pem" \
+        -out "$out" -passout "pass:$password" "$@"
+    chmod 644 "$out"
+    echo "Generated $name.p12"
+}
+
+certificate "/CN=pkcs12cracker test"
+
+# Password shapes covered by tests/integration.rs
+fixture empty ""
+fixture single "x"
+fixture alnum8 "Tr0ub4dr"
+fixture special 'p@ss w0rd!#$%'
+fixture latin1 "Grüße"
+
+# Fixtures of the individual features
+fixture accent "café"
+fixture bruteforce "aaaab"
+fixture cjk "密1"
+fixture early "00012"
+fixture euro "€uro"
+fixture last_candidate "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb"
+fixture permutation "BazFooBar"
+fixture progress "50000"
+fixture pronounceable "ka7"
+fixture umlaut "pä55"
+fixture uppercase "PASSWORD"
+fixture word42 "word42"
+fixture strasse "Straßeöü"
+# "café" and U+0080, Latin-1 byte 0x80 that Windows-1252 reads as "€"
+fixture iso8859_1 "$(printf 'caf\303\251\302\200')"
+
+# Words for --words-from-certificate come from the subject and friendly name
+certificate "/CN=Initech/O=Initech Corp/emailAddress=peter.gibbons@initech.com"
+fixture metadata "Initech" -name "Initech VPN" -certpbe NONE
@@ -0,0 +1,136 @@
+//! End-to-end cracking of the known-password fixtures through the library API.
+//!
+//! The fixtures are generated by `tests/generate_fixtures.sh`.
+mod common;
+
+use common::fixture;
+use pkcs12cracker::crackers::{
+    bruteforce::BruteforceCracker,
+    dictionary::{DelimiterMode, DictionaryCracker},
+    pattern::PatternCracker,
+};
+use pkcs12cracker::session::CrackSession;
+use pkcs12cracker::types::PasswordCracker;
+use pkcs12cracker::{load_certificate, verify_password};
+use std::fs;
+use std::path::PathBuf;
+
+/// Every fixture with its password
+const FIXTURES: &[(&str, &str)] = &[
+    ("empty.p12", ""),
+    ("single.p12", "x"),
+    ("alnum8.p12", "Tr0ub4dr"),
+    ("special.p12", "p@ss w0rd!#$%"),
+    ("latin1.p12", "Grüße"),
+    ("accent.p12", "café"),
+    ("bruteforce.p12", "aaaab"),
+    ("cjk.p12", "密1"),
+    ("early.p12", "00012"),
+    ("euro.p12", "€uro"),
+    ("metadata.p12", "Initech"),
+    ("permutation.p12", "BazFooBar"),
+    ("progress.p12", "50000"),
+    ("pronounceable.p12", "ka7"),
+    ("umlaut.p12", "pä55"),
+    ("word42.p12", "word42"),
+];
+
+/// Runs a cracker against a fixture and returns the password it found.
+fn crack(cracker: &dyn PasswordCracker, name: &str) -> Option<String> {
+    let pkcs12 = load_certificate(fixture(name)).unwrap();
+    let session = CrackSession::new(2).unwrap();
+    session.run(cracker, &pkcs12).unwrap();
+    let result = session.result().lock().unwrap();
+    result.password.clone()
+}
+
+/// Writes a wordlist and returns a dictionary cracker reading it.
+fn dictionary(dir: &tempfile::TempDir, words: &[&str]) -> DictionaryCracker {
+    let path: PathBuf = dir.path().join("words.txt");
+    fs::write(&path, words.join("\n") + "\n").unwrap();
+    DictionaryCracker::new(path, DelimiterMode::SingleByte(b'\n'))
+}
+
+#[test]
+fn test_fixture_passwords() {
+    for &(name, password) in FIXTURES {
+        assert!(verify_password(fixture(name), password).unwrap(), "{name}");
+        assert!(!verify_password(fixture(name), "wrong").unwrap(), "{name}");
+    }
+}
+
+#[test]
+fn test_bruteforce_empty_password() {
+    let cracker = BruteforceCracker::new(0, 2, "ab".to_string());
+    assert_eq!(crack(&cracker, "empty.p12").as_deref(), Some(""));
+}
+
+#[test]
+fn test_bruteforce_single_char_password() {
+    let cracker = BruteforceCracker::new(1, 2, "abcdefghijklmnopqrstuvwxyz".to_string());
+    assert_eq!(crack(&cracker, "single.p12").as_deref(), Some("x"));
+}
+
+#[test]
+fn test_pattern_alphanumeric_password() {
+    let charset: String = ('a'..='z').chain('A'..='Z').chain('0'..='9').collect();
+    let cracker = PatternCracker::new("Tr0ub4@@".to_string(), charset, '@');
+    assert_eq!(crack(&cracker, "alnum8.p12").as_deref(), Some("Tr0ub4dr"));
+}
+
+#[test]
+fn test_pattern_latin1_password() {
+    let cracker = PatternCracker::new("Gr@@e".to_string(), "äößüs".to_string(), '@');
+    assert_eq!(crack(&cracker, "latin1.p12").as_deref(), Some("Grüße"));
+}
+
+#[test]
+fn test_dictionary_special_characters() {
+    let dir = tempfile::tempdir().unwrap();
+    let cracker = dictionary(&dir, &["password", "p@ssw0rd", "p@ss w0rd!#$%", "letmein"]);
+    assert_eq!(
+        crack(&cracker, "special.p12").as_deref(),
+        Some("p@ss w0rd!#$%")
+    );
+}
+
+#[test]
+fn test_dictionary_latin1_password() {
+    let dir = tempfile::tempdir().unwrap();
+    let cracker = dictionary(&dir, &["Grusse", "Grüsse", "Grüße"]);
+    assert_eq!(crack(&cracker, "latin1.p12").as_deref(), Some("Grüße"));
+}
+
+#[test]
+fn test_password_not_found() {
+    let wordlist = ["password", "p@ssw0rd", "p@ss w0rd!#$%", "letmein"];
+    let dir = tempfile::tempdir().unwrap();
+    // The wordlist without its last entries misses the password
+    let cracker = dictionary(&dir, &wordlist[..2]);
+    assert_eq!(crack(&cracker, "special.p12"), None);
+
+    let cracker = BruteforceCracker::new(1, 3, "ab".to_string());
+    assert_eq!(crack(&cracker, "single.p12"), None);
+
+    let cracker = PatternCracker::new("Tr0ub4@@".to_string(), "0123456789".to_string(), '@');
+    assert_eq!(crack(&cracker, "alnum8.p12"), None);
+}
+
+#[test]
+fn test_found_at_only_when_found() {
+    let pkcs12 = load_certificate(fixture("special.p12")).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    for (words, found) in [
+        (&["password", "p@ss w0rd!#$%"][..], true),
+        (&["letmein"][..], false),
+    ] {
+        let session = CrackSession::new(1).unwrap();
+        session.run(&dictionary(&dir, words), &pkcs12).unwrap();
+        let stats = session.stats();
+        assert_eq!(stats.found, found);
+        assert_eq!(stats.found_at.is_some(), found);
+        if let Some(found_at) = stats.found_at {
+            assert!(found_at <= stats.elapsed);
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! Reporting the results, as JSON via `--output json` and through the other output options.
+mod common;
+
+use assert_cmd::Command;
+use common::{fixture, temp_file};
+
+fn last_line_json(stdout: &[u8]) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(stdout);
+    serde_json::from_str(stdout.lines().last().unwrap()).unwrap()
+}
+
+#[test]
+fn test_json_output() {
+    let output = Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args(["-p", "p@55", "--custom-chars", "aäöü", "--output", "json"])
+        .arg(fixture("umlaut.p12"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stats = last_line_json(&output.stdout);
+    assert_eq!(stats["found"], true);
+    assert_eq!(stats["password"], "pä55");
+    assert_eq!(stats["mode"], "pattern");
+    assert!(stats["total_attempts"].as_u64().unwrap() >= 1);
+    assert!(stats["elapsed_secs"].is_f64());
+    assert!(stats["rate_per_sec"].is_f64());
+    assert!(stats["found_at_secs"].as_f64().unwrap() <= stats["elapsed_secs"].as_f64().unwrap());
+}
+
+#[test]
+fn test_json_output_multiple_certificates() {
+    let wordlist = temp_file("café\nfoo");
+    let output = Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .arg("-d")
+        .arg(wordlist.path())
+        .args(["--output", "json"])
+        .args([fixture("accent.p12"), fixture("euro.p12")])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stats = last_line_json(&output.stdout);
+    assert_eq!(stats[0]["password"], "café");
+    assert_eq!(stats[0]["mode"], "dictionary");
+    assert!(stats[0]["found_at_secs"].is_f64());
+    assert_eq!(stats[1]["found"], false);
+    assert_eq!(stats[1]["password"], serde_json::Value::Null);
+    assert_eq!(stats[1]["found_at_secs"], serde_json::Value::Null);
+    assert_eq!(stats[1]["total_attempts"], 2);
+}
+mod quiet_found {
+    //! Printing only the found password via `--quiet-found`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+
+    #[test]
+    fn test_quiet_found_prints_only_password() {
+        let output = Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "-c", "n", "--quiet-found"])
+            .arg(fixture("umlaut.p12"))
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "pä55\n");
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    fn test_quiet_found_not_found() {
+        let output = Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "px@@", "-c", "n", "--quiet-found"])
+            .arg(fixture("umlaut.p12"))
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+        assert!(output.stderr.is_empty());
+    }
+
+    #[test]
+    fn test_quiet_found_reports_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(dir.path().join("words.txt"))
+            .arg("--quiet-found")
+            .arg(fixture("umlaut.p12"))
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+        assert!(String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Failed to read dictionary"));
+    }
+
+    #[test]
+    fn test_quiet_found_conflicts_with_json_output() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "--quiet-found", "--output", "json"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod save_all_found {
+    //! Found passwords of several certificates written to a CSV via `--save-all-found`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use tempfile::NamedTempFile;
+
+    /// Cracks the café, pä55 and never found €uro certificates.
+    fn crack(wordlist: &NamedTempFile, csv: &std::path::Path, append: bool) {
+        let mut command = Command::cargo_bin("pkcs12cracker").unwrap();
+        command
+            .arg("-d")
+            .arg(wordlist.path())
+            .arg("--save-all-found")
+            .arg(csv);
+        if append {
+            command.arg("--save-all-found-append");
+        }
+        command
+            .args([
+                fixture("accent.p12"),
+                fixture("umlaut.p12"),
+                fixture("euro.p12"),
+            ])
+            .assert()
+            .success();
+    }
+
+    /// Reads the CSV rows, the header first.
+    fn rows(csv: &std::path::Path) -> Vec<Vec<String>> {
+        csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_path(csv)
+            .unwrap()
+            .records()
+            .map(|record| record.unwrap().iter().map(str::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_found_passwords_are_saved() {
+        let wordlist = temp_file("café\nfoo\nbar\nbaz\npä55\n");
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("found.csv");
+        crack(&wordlist, &csv, false);
+
+        let mut rows = rows(&csv);
+        assert_eq!(
+            rows.remove(0),
+            ["certificate_path", "password", "attempts", "elapsed_secs"]
+        );
+        rows.sort();
+        assert_eq!(rows.len(), 2, "{rows:?}");
+        assert_eq!(rows[0][..2], [fixture("accent.p12"), "café".to_string()]);
+        assert_eq!(rows[1][..2], [fixture("umlaut.p12"), "pä55".to_string()]);
+        for row in &rows {
+            assert!(row[2].parse::<usize>().unwrap() > 0);
+            assert!(row[3].parse::<f64>().unwrap() >= 0.0);
+        }
+    }
+
+    #[test]
+    fn test_append_keeps_earlier_rows() {
+        let wordlist = temp_file("café\npä55\n");
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("found.csv");
+        crack(&wordlist, &csv, true);
+        crack(&wordlist, &csv, true);
+        let appended = rows(&csv);
+        assert_eq!(appended.len(), 1 + 4, "{appended:?}");
+        assert_eq!(appended[0][0], "certificate_path");
+
+        crack(&wordlist, &csv, false);
+        assert_eq!(rows(&csv).len(), 1 + 2);
+    }
+
+    #[test]
+    fn test_append_requires_file() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-d", "words.txt", "--save-all-found-append"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod notify {
+    //! Announcing results with `--notify` and `--webhook`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_notify_falls_back_to_bell() {
+        // Without a D-Bus session or the notifications feature the bell rings
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@55", "--custom-chars", "aä", "--notify"])
+            .arg(fixture("umlaut.p12"))
+            .env_remove("DBUS_SESSION_BUS_ADDRESS")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stderr(predicate::str::contains("\x07"));
+    }
+
+    #[test]
+    fn test_notify_only_on_success() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "x@", "--custom-chars", "ab", "--notify"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"))
+            .stderr(predicate::str::contains("\x07").not());
+    }
+
+    #[test]
+    fn test_webhook_posts_result() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "found": true,
+                "password": "pä55",
+            })))
+            .create();
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@55", "--custom-chars", "aä", "--webhook"])
+            .arg(server.url())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_webhook_only_on_success() {
+        let mut server = mockito::Server::new();
+        let mock = server.mock("POST", "/").expect(0).create();
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "x@", "--custom-chars", "ab", "--webhook"])
+            .arg(server.url())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_webhook_failure_is_a_warning() {
+        let mut server = mockito::Server::new();
+        server.mock("POST", "/").with_status(500).create();
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@55", "--custom-chars", "aä", "--webhook"])
+            .arg(server.url())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "Warning: Failed to post the result",
+            ));
+    }
+}
+
+mod keychain {
+    //! Storing recovered passwords with `--save-to-keychain`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    fn crack_and_save() -> Command {
+        let mut command = Command::cargo_bin("pkcs12cracker").unwrap();
+        command
+            .args(["-p", "p@55", "--custom-chars", "aä", "--save-to-keychain"])
+            .arg(fixture("umlaut.p12"));
+        command
+    }
+
+    /// Writes a fake `security` running `script` into `dir`.
+    ///
+    /// # Returns
+    ///
+    /// The `PATH` that finds the fake before the real command.
+    #[cfg(target_os = "macos")]
+    fn fake_security(dir: &std::path::Path, script: &str) -> String {
+        use std::os::unix::fs::PermissionsExt;
+
+        let security = dir.join("security");
+        std::fs::write(&security, format!("#!/bin/sh\n{script}\n")).unwrap();
+        std::fs::set_permissions(&security, std::fs::Permissions::from_mode(0o755)).unwrap();
+        format!("{}:{}", dir.display(), std::env::var("PATH").unwrap())
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_save_to_keychain() {
+        // The fake `security` records its arguments
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("security.log");
+        let path = fake_security(
+            dir.path(),
+            &format!("printf '%s\\n' \"$@\" > '{}'", log.display()),
+        );
+
+        crack_and_save()
+            .env("PATH", path)
+            .env("USER", "alice")
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "Saved password to the Keychain as 'umlaut.p12'",
+            ));
+
+        let args = std::fs::read_to_string(log).unwrap();
+        assert_eq!(
+            args.lines().collect::<Vec<_>>(),
+            [
+                "add-generic-password",
+                "-U",
+                "-a",
+                "alice",
+                "-s",
+                "umlaut.p12",
+                "-w",
+                "pä55"
+            ]
+        );
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn test_save_to_keychain_failure_is_a_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = fake_security(dir.path(), "echo 'keychain locked' >&2\nexit 1");
+
+        crack_and_save()
+            .env("PATH", path)
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("keychain locked"));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_save_to_keychain_unavailable() {
+        crack_and_save()
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stderr(predicate::str::contains(
+                "--save-to-keychain is only available on macOS",
+            ));
+    }
+}
@@ -0,0 +1,708 @@
+//! Pattern attacks with `-p` and the options shaping their candidates.
+mod common;
+
+use assert_cmd::Command;
+use common::fixture;
+use predicates::prelude::*;
+
+#[test]
+fn test_small_patterns_use_a_single_chunk() {
+    Command::cargo_bin("pkcs12cracker")
+        .unwrap()
+        .args(["-p", "p@@5", "--custom-chars", "aä5"])
+        .arg(fixture("umlaut.p12"))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("in chunks of").not())
+        .stdout(predicate::str::contains(
+            "Successfully found password: pä55",
+        ));
+}
+
+mod dict {
+    //! Dictionary words inside patterns via `--pattern-dict`.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_pattern_dict_finds_password() {
+        let words = temp_file("alpha\npä\r\nbeta\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "%W@@", "-c", "n", "--pattern-dict"])
+            .arg(words.path())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Trying 100 candidates for every word",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_pattern_dict_keyspace() {
+        let words = temp_file("alpha\nbeta\ngamma\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "%W@", "-c", "n", "--dry-run", "--pattern-dict"])
+            .arg(words.path())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Estimated candidates: 30 "));
+    }
+
+    #[test]
+    fn test_pattern_dict_needs_token() {
+        let words = temp_file("pä\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "-c", "n", "--pattern-dict"])
+            .arg(words.path())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("has no %W token"));
+    }
+
+    #[test]
+    fn test_pattern_dict_requires_pattern() {
+        let words = temp_file("pä\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--pattern-dict"])
+            .arg(words.path())
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod file {
+    //! Trying the patterns of a `--pattern-file` in order.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_pattern_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let patterns = dir.path().join("patterns.txt");
+        std::fs::write(
+            &patterns,
+            "# Likely shapes\nx@@   # two characters\np@{4,1}\npä@%\np@55\n",
+        )
+        .unwrap();
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["--pattern-file"])
+            .arg(&patterns)
+            .args(["--custom-chars", "5", "--symbol", "%:5", "-t", "1"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Warning: skipping line 3 of"))
+            .stderr(predicate::str::contains("4 exceeds 1"))
+            .stdout(predicate::str::contains(
+                "Trying pattern 1/3: x@@ (729 combinations)",
+            ))
+            .stdout(predicate::str::contains(
+                "Trying pattern 2/3: pä@% (27 combinations)",
+            ))
+            .stdout(predicate::str::contains("Trying pattern 3/3").not())
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            // Attempts add up over the patterns: 27² of "x@@", then "5" last of 27 in "pä@%"
+            .stdout(predicate::str::contains("Total attempts: 756"));
+    }
+
+    #[test]
+    fn test_pattern_file_without_valid_patterns() {
+        let dir = tempfile::tempdir().unwrap();
+        let patterns = dir.path().join("patterns.txt");
+        std::fs::write(&patterns, "# nothing\n@{2,1}\n").unwrap();
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("--pattern-file")
+            .arg(&patterns)
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("No valid patterns in"));
+    }
+
+    #[test]
+    fn test_pattern_file_conflicts_with_pattern() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@55", "--pattern-file", "patterns.txt"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("cannot be used with"));
+    }
+}
+
+mod groups {
+    //! Positions listing their own characters via groups such as `[-_.]`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_group_keyspace() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "Acme[-_.]2[012]@@", "-c", "n", "--dry-run"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            // 3 separators, 3 digits and 10 · 10 digits of the symbol
+            .stdout(predicate::str::contains("Search space: 900 work items"));
+    }
+
+    #[test]
+    fn test_group_finds_password() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "[pP][äa][5s]@", "-c", "n"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Generating pattern combinations for 4 unknown positions",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_unterminated_group() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä[5s@"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Unterminated group '[5s@'"));
+    }
+}
+
+mod quantifiers {
+    //! Variable-length patterns via quantifiers such as `@{1,4}`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_quantifier_keyspace() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-p",
+                "pä@{1,3}",
+                "--custom-chars",
+                "5a",
+                "-t",
+                "1",
+                "--dry-run",
+            ])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            // 27 + 27² + 27³ candidates of the default lowercase set and "5"
+            .stdout(predicate::str::contains("Search space: 20439 work items"));
+    }
+
+    #[test]
+    fn test_quantifier_finds_password() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@{0,4}", "--custom-chars", "ä5"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Generating pattern combinations for 5 patterns of 0 to 4 unknown positions",
+            ))
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_invalid_quantifier() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@{4,1}"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("4 exceeds 1"));
+    }
+
+    #[test]
+    fn test_quantifiers_of_several_symbols() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-p",
+                "p#{1}@{2}#{0,2}",
+                "-c",
+                "n",
+                "--symbol",
+                "#:äp",
+                "-t",
+                "1",
+            ])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            // 2 · 100 · (1 + 2 + 4) candidates, "pä55" is the 56th of "p#@@"
+            .stdout(predicate::str::contains("Estimated candidates: 1400 "))
+            .stdout(predicate::str::contains(
+                "Generating pattern combinations for 3 patterns of 3 to 5 unknown positions",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 56\n"));
+    }
+
+    #[test]
+    fn test_quantifier_after_fixed_character() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä5{2}"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Quantifier '{2}' follows a fixed character",
+            ));
+    }
+}
+
+mod symbols {
+    //! Pattern symbols with their own charsets via `--symbol`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_symbol_charsets() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@#5", "--custom-chars", "aä", "--symbol", "#=n"])
+            .args(["-t", "1", "--dry-run"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            // 27 characters of the default lowercase set and "ä", 10 digits
+            .stdout(predicate::str::contains("Search space: 270 work items"));
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@#%", "--custom-chars", "aä", "--symbol", "#=n"])
+            .args(["--symbol", "%:4\\x35"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_symbol_errors() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@@5", "--symbol", "#=n"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Symbol '#' does not occur in the pattern 'p@@5'",
+            ));
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@#5", "--symbol", "#n"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("expected SYM=SPEC or SYM:CHARS"));
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--symbol", "#=n"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn test_escaped_symbol_is_literal() {
+        // The password is "p@ss w0rd!#$%", only the last "@" is variable
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p\\@ss w0rd!#$@", "--custom-chars", "%"])
+            .args(["-t", "1"])
+            .arg(fixture("special.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: p@ss w0rd!#$%",
+            ))
+            // The lowercase letters and "%" for the single variable position
+            .stdout(predicate::str::contains("Estimated candidates: 27 "));
+    }
+}
+
+mod validation {
+    //! Checks of the pattern arguments before a pattern run.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_pattern_without_symbols_is_an_error() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä55"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Pattern 'pä55' has no '@' or other symbol to fill in",
+            ))
+            .stdout(predicate::str::contains("Total attempts").not());
+    }
+
+    #[test]
+    fn test_reserved_character_is_an_error() {
+        // U+F8FF is typed as the Apple logo on macOS
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "a\u{F8FF}@", "--show-candidates", "3"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("contains U+F8FF"))
+            .stdout(predicate::str::contains("a{").not());
+    }
+
+    #[test]
+    fn test_huge_pattern_keyspace_warns() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@@@@@@@@@@@", "-c", "a", "--limit", "2"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "Warning: the patterns have 9.5e16 candidates",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 2\n"));
+    }
+
+    #[test]
+    fn test_small_pattern_keyspace_does_not_warn() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "-c", "n"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Warning").not());
+    }
+}
+
+mod work_unit {
+    //! Pattern keyspaces split across nodes via `--work-unit`, `--skip` and `--limit`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_node_reports_covered_share() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "--custom-chars", "5ab", "--work-unit", "0/3"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "All combinations of this node exhausted, password not found; \
+             it covered 243 of 729 combinations (33.33% of the search space)",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 243\n"));
+    }
+
+    #[test]
+    fn test_node_finds_password_in_its_unit() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "--custom-chars", "5ab", "--work-unit", "2/3"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_skip_and_limit_with_pattern() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä@@", "--custom-chars", "5ab"])
+            .args(["--skip", "2", "--limit", "3", "-t", "1"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "it covered 3 of 729 combinations (0.41% of the search space)",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 3\n"));
+    }
+
+    #[test]
+    fn test_skip_requires_numbered_mode() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-d", "words.txt", "--skip", "2"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod linked_symbols {
+    //! Pattern symbols whose positions all take the same character via `--linked-symbol`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_linked_symbol_counts_once() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä&&", "-c", "n", "--linked-symbol", "&", "-t", "1"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 6\n"));
+    }
+
+    #[test]
+    fn test_linked_symbols_with_own_charsets() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-p",
+                "p&%%",
+                "--linked-symbol",
+                "&:aä",
+                "--linked-symbol",
+                "%=n",
+            ])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+    }
+
+    #[test]
+    fn test_linked_symbol_requires_pattern() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-d", "words.txt", "--linked-symbol", "&"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod year_token {
+    //! Year tokens `?y` and `?Y` in patterns, with `--years`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_short_year_finds_password() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä?Y", "--years", "1950-2060", "-t", "1"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 6\n"));
+    }
+
+    #[test]
+    fn test_year_is_one_position() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "Summer?y@", "-c", "n", "--show-candidates", "2"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Keyspace: 810 candidates\n"))
+            .stdout(predicate::str::contains(
+                "Generating pattern combinations for 2 unknown positions",
+            ))
+            .stdout(predicate::str::contains("Summer20300\nSummer20301\n"));
+    }
+
+    #[test]
+    fn test_restore_requires_same_years() {
+        let dir = tempfile::tempdir().unwrap();
+        let session = dir.path().join("session.json");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "px?Y", "--years", "2000-2010", "--session"])
+            .arg(&session)
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success();
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-p",
+                "px?Y",
+                "--years",
+                "2000-2011",
+                "--restore",
+                "--session",
+            ])
+            .arg(&session)
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Session was saved with different --years",
+            ));
+    }
+
+    #[test]
+    fn test_years_requires_pattern() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--years", "1990-2025"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
+
+mod matched_groups {
+    //! Reporting the values of the variable groups of a found pattern password.
+    use crate::common::{fixture, temp_file};
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_groups_are_printed() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "p@5#", "--custom-chars", "aä", "--symbol", "#=n"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55\nMatched groups: group 1: 'ä', group 2: '5'\n",
+            ));
+    }
+
+    #[test]
+    fn test_groups_in_json() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "pä#{2}", "--symbol", "#=n", "--output", "json"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""matched_groups":["55"]"#));
+    }
+
+    #[test]
+    fn test_no_groups_for_dictionary() {
+        let words = temp_file("pä55\n");
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .arg("-d")
+            .arg(words.path())
+            .args(["--output", "json"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#""matched_groups":null"#));
+    }
+}
+
+mod toggle_case {
+    //! Case variants of the fixed pattern letters via `--toggle-case`.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+
+    #[test]
+    fn test_toggle_case_finds_password() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "P@55", "--custom-chars", "aä", "--toggle-case"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: pä55",
+            ));
+
+        // Without the flag only "P?55" is tried
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "P@55", "--custom-chars", "aä"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"));
+    }
+
+    #[test]
+    fn test_toggle_case_keyspace() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-p", "Pä@5", "-c", "n", "--toggle-case=all", "--dry-run"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .success()
+            // 4 cases of "pä" for each of 10 digits
+            .stdout(predicate::str::contains("Estimated candidates: 40 "))
+            .stdout(predicate::str::contains("Search space: 10 work items"));
+    }
+
+    #[test]
+    fn test_toggle_case_requires_pattern() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--toggle-case"])
+            .arg(fixture("umlaut.p12"))
+            .assert()
+            .failure();
+    }
+}
@@ -0,0 +1,231 @@
+//! Resuming interrupted runs.
+mod common;
+
+mod auto_resume {
+    //! Automatic resume detection for identical runs.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::path::Path;
+
+    fn crack(state: &Path, args: &[&str]) -> assert_cmd::assert::Assert {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .env("XDG_STATE_HOME", state)
+            .args(["-c", "n", "-t", "1", "--status-interval", "0"])
+            .args(args)
+            .arg(fixture("word42.p12"))
+            .assert()
+    }
+
+    fn state_files(state: &Path) -> usize {
+        std::fs::read_dir(state.join("pkcs12cracker")).map_or(0, |entries| entries.count())
+    }
+
+    #[test]
+    fn test_identical_run_is_detected() {
+        let state = tempfile::tempdir().unwrap();
+        let args = [
+            "-b",
+            "--min-length",
+            "5",
+            "--max-length",
+            "5",
+            "--limit",
+            "300",
+        ];
+        crack(state.path(), &args)
+            .success()
+            .stdout(predicate::str::contains("Previous run").not());
+        assert_eq!(state_files(state.path()), 1);
+
+        // Without a terminal the earlier run is only resumed with --auto-resume
+        crack(state.path(), &args)
+            .success()
+            .stdout(predicate::str::contains(
+                "Previous run covered 0.3%, rerun with --auto-resume to continue it",
+            ))
+            .stdout(predicate::str::contains("Resuming").not());
+
+        crack(state.path(), &[&args[..], &["--auto-resume"]].concat())
+            .success()
+            .stdout(predicate::str::contains(
+                "Previous run covered 0.3%, resuming it",
+            ))
+            .stdout(predicate::str::contains("at length 5, position 300"));
+    }
+
+    #[test]
+    fn test_different_parameters_are_not_resumed() {
+        let state = tempfile::tempdir().unwrap();
+        crack(
+            state.path(),
+            &[
+                "-b",
+                "--min-length",
+                "5",
+                "--max-length",
+                "5",
+                "--limit",
+                "300",
+            ],
+        )
+        .success();
+        crack(
+            state.path(),
+            &[
+                "-b",
+                "--min-length",
+                "5",
+                "--max-length",
+                "5",
+                "--limit",
+                "200",
+                "--auto-resume",
+            ],
+        )
+        .success()
+        .stdout(predicate::str::contains("Previous run").not())
+        .stdout(predicate::str::contains("Resuming").not());
+        assert_eq!(state_files(state.path()), 2);
+    }
+
+    #[test]
+    fn test_state_removed_once_found() {
+        let state = tempfile::tempdir().unwrap();
+        crack(state.path(), &["-p", "word4@"])
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ));
+        assert_eq!(state_files(state.path()), 0);
+    }
+}
+
+mod progress_file {
+    //! Resuming runs from a `--progress-save-every` progress file.
+    use crate::common::fixture;
+    use assert_cmd::Command;
+    use predicates::prelude::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bruteforce_resumes_after_crash_at_50000() {
+        let dir = TempDir::new().unwrap();
+        let progress = dir.path().join("progress.json");
+        let run = || {
+            let mut command = Command::cargo_bin("pkcs12cracker").unwrap();
+            command
+                .args(["-b", "-c", "n", "-m", "5", "--max-length", "5"])
+                .args(["--chunk-size", "1000", "--progress-save-every", "1000"])
+                .arg("--progress-file")
+                .arg(&progress)
+                .arg(fixture("progress.p12"));
+            command
+        };
+
+        // The run stops at attempt 50 000, just before the password "50000"
+        run()
+            .args(["--limit", "50000"])
+            .timeout(std::time::Duration::from_secs(120))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"))
+            .stdout(predicate::str::contains("Total attempts: 50000"));
+        let saved = fs::read_to_string(&progress).unwrap();
+        assert!(saved.contains("\"index\": 50000"), "{saved}");
+
+        // Nothing before the saved position is tested again, nothing after it is skipped
+        run()
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Resuming from progress file"))
+            .stdout(predicate::str::contains("length 5, position 50000"))
+            .stdout(predicate::str::contains(
+                "Successfully found password: 50000",
+            ))
+            .stdout(predicate::str::contains("Total attempts: 1\n"));
+        assert!(!progress.exists());
+    }
+
+    #[test]
+    fn test_dictionary_resumes_at_byte_offset() {
+        let dir = TempDir::new().unwrap();
+        let progress = dir.path().join("progress.json");
+        let wordlist = dir.path().join("words.txt");
+        let words: Vec<String> = (0..6000).map(|i| format!("w{i:05}")).collect();
+        fs::write(&wordlist, words.join("\n")).unwrap();
+        let run = || {
+            let mut command = Command::cargo_bin("pkcs12cracker").unwrap();
+            command
+                .arg("-d")
+                .arg(&wordlist)
+                .args(["--progress-save-every", "100", "--progress-file"])
+                .arg(&progress)
+                .arg(fixture("word42.p12"));
+            command
+        };
+
+        run()
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"));
+
+        // Only the last, partial chunk of 16384 bytes is tested again
+        fs::write(&wordlist, words.join("\n") + "\nword42").unwrap();
+        run()
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("at byte offset 41999"))
+            .stdout(predicate::str::contains(
+                "Successfully found password: word42",
+            ))
+            .stdout(predicate::str::is_match("Total attempts: 1[0-9]{3}\n").unwrap());
+    }
+
+    #[test]
+    fn test_progress_file_requires_save_every() {
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--progress-file", "progress.json"])
+            .arg(fixture("progress.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--progress-save-every"));
+    }
+
+    #[test]
+    fn test_progress_save_every_duration() {
+        let dir = TempDir::new().unwrap();
+        let progress = dir.path().join("progress.json");
+        // Saved when the run stops even if the interval never elapsed
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args([
+                "-b",
+                "-c",
+                "n",
+                "--max-length",
+                "2",
+                "--progress-save-every",
+                "1h",
+            ])
+            .arg("--progress-file")
+            .arg(&progress)
+            .arg(fixture("progress.p12"))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Password not found"));
+        let saved = fs::read_to_string(&progress).unwrap();
+        assert!(saved.contains("\"version\": 3"), "{saved}");
+
+        Command::cargo_bin("pkcs12cracker")
+            .unwrap()
+            .args(["-b", "--progress-save-every", "5d"])
+            .arg(fixture("progress.p12"))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("invalid cadence '5d'"));
+    }
+}